@@ -3,6 +3,7 @@ use std::{
     hash::Hash,
     path::PathBuf,
     sync::atomic::{AtomicUsize, Ordering},
+    time::Duration,
 };
 
 // Mutable Key safety: mutable AtomicUsize is not used in Hash nor Eq
@@ -17,6 +18,24 @@ pub struct Agent {
     pub compile: bool,
     pub args: Option<Vec<String>>,
     pub error_message: Option<String>,
+    /// Team of the agent (`team:` key of its config file). Teammates never play each other in a
+    /// [`RoundRobinTournament`](crate::tournament_strategy::RoundRobinTournament).
+    pub team: Option<String>,
+    /// Launch command template (`command:` key of its config file), split into words. See
+    /// [`expand_command`](Agent::expand_command).
+    pub command: Option<Vec<String>>,
+    /// Played by an in-process agent instead of an external process (see
+    /// [`Evaluator::with_reference_agent`](crate::server::Evaluator::with_reference_agent)).
+    pub in_process: bool,
+    /// Factor applied to the action timeout and time budget of the agent (`time_multiplier:` key
+    /// of its config file), `1.0` by default. See [`scale_time`](Agent::scale_time).
+    pub time_multiplier: f32,
+    /// Arguments given after the arguments of its config (see
+    /// [`Configuration::with_extra_agent_args`](crate::configuration::Configuration::with_extra_agent_args)).
+    pub extra_args: Vec<String>,
+    /// Directory the agent is launched from (its agent directory when its config file declares a
+    /// `run:` command), the directory of the evaluator if `None`.
+    pub working_dir: Option<PathBuf>,
     // pub scores: Vec<f32>,
 }
 
@@ -53,6 +72,21 @@ impl Agent {
             id, // scores: vec![],
             args,
             error_message: None,
+            team: None,
+            command: None,
+            in_process: false,
+            time_multiplier: 1.0,
+            extra_args: vec![],
+            working_dir: None,
+        }
+    }
+
+    /// Agent played in-process, by the reference agent of the evaluator
+    pub fn in_process(name: String, id: u32) -> Agent {
+        Agent {
+            compile: true,
+            in_process: true,
+            ..Agent::new(name, None, None, id, None)
         }
     }
 
@@ -66,7 +100,96 @@ impl Agent {
             compile: false,
             args: None,
             error_message: Some(msg),
+            team: None,
+            command: None,
+            in_process: false,
+            time_multiplier: 1.0,
+            extra_args: vec![],
+            working_dir: None,
+        }
+    }
+
+    pub fn with_team(mut self, team: Option<String>) -> Agent {
+        self.team = team;
+        self
+    }
+
+    pub fn with_command(mut self, command: Option<Vec<String>>) -> Agent {
+        self.command = command;
+        self
+    }
+
+    pub fn with_time_multiplier(mut self, time_multiplier: f32) -> Agent {
+        self.time_multiplier = time_multiplier;
+        self
+    }
+
+    pub fn with_extra_args(mut self, extra_args: Vec<String>) -> Agent {
+        self.extra_args = extra_args;
+        self
+    }
+
+    pub fn with_working_dir(mut self, working_dir: Option<PathBuf>) -> Agent {
+        self.working_dir = working_dir;
+        self
+    }
+
+    /// `duration` (action timeout or time budget) scaled by the time multiplier of the agent.
+    ///
+    /// Only this agent's clocks are scaled: its opponents keep their own. Saturates at
+    /// [`Duration::MAX`] (unlimited).
+    pub fn scale_time(&self, duration: Duration) -> Duration {
+        if self.time_multiplier == 1.0 {
+            return duration;
         }
+        Duration::try_from_secs_f64(duration.as_secs_f64() * self.time_multiplier as f64)
+            .unwrap_or(Duration::MAX)
+    }
+
+    /// Command line (program and arguments) launching the agent.
+    ///
+    /// Without a command template, this is `<exe> <port> <budget_us> <action_us> [seed] <args...>`.
+    /// Otherwise, placeholders of the template are substituted: `{exe}`, `{port}`, `{budget_us}`,
+    /// `{action_us}`, `{seed}` (removed without a seed), and `{args}` which expands to the agent's
+    /// arguments as separate words.
+    ///
+    /// In both cases, the arguments are the arguments of its config followed by its extra
+    /// arguments.
+    pub fn expand_command(
+        &self,
+        exe: &str,
+        port: &str,
+        budget_us: &str,
+        action_us: &str,
+        seed: Option<&str>,
+    ) -> Vec<String> {
+        let Some(template) = &self.command else {
+            let mut command = vec![exe, port, budget_us, action_us]
+                .into_iter()
+                .chain(seed)
+                .map(String::from)
+                .collect::<Vec<_>>();
+            command.extend_from_slice(self.args());
+            command.extend_from_slice(&self.extra_args);
+            return command;
+        };
+        let mut command = vec![];
+        for word in template {
+            if word == "{args}" {
+                command.extend_from_slice(self.args());
+                command.extend_from_slice(&self.extra_args);
+            } else if word == "{seed}" {
+                command.extend(seed.map(String::from));
+            } else {
+                command.push(
+                    word.replace("{exe}", exe)
+                        .replace("{port}", port)
+                        .replace("{budget_us}", budget_us)
+                        .replace("{action_us}", action_us),
+                );
+            }
+        }
+        command
     }
 
     pub fn create_new_match_log_file(&self) -> File {
@@ -82,6 +205,11 @@ impl Agent {
         File::create_new(&path).unwrap_or_else(|_| panic!("file {} already exists", path.display()))
     }
 
+    /// Arguments from the agent's config file, given after the protocol arguments
+    pub fn args(&self) -> &[String] {
+        self.args.as_deref().unwrap_or_default()
+    }
+
     pub fn should_be_logged(&self) -> bool {
         self.path_to_log_dir.is_some()
     }
@@ -1,51 +1,399 @@
 // use crate::agent::Agent;
 
-use std::path::{Path, PathBuf};
+use std::{
+    io::Read,
+    path::{Path, PathBuf},
+    process::{Child, Command, ExitStatus, Stdio},
+    sync::mpsc,
+    thread,
+    time::{Duration, Instant},
+};
 
+use anyhow::{bail, Context};
 use tracing::{error, instrument};
 
+/// How agent crates are compiled (see [`Configuration`](crate::configuration::Configuration))
+#[derive(Debug, Clone, Default)]
+pub struct CompileOptions {
+    /// Time limit of a compilation
+    pub timeout: Option<Duration>,
+    /// Compile with `--offline --locked`
+    pub offline: bool,
+    /// Crates agents may depend on, any crate if `None`
+    pub allowed_crates: Option<Vec<String>>,
+}
+
+/// Builds the binary `bin_name` of the agent crate in `dir`, in release mode
 #[instrument(parent = None)]
-pub fn compile_single_agent(dir: &Path) -> (anyhow::Result<PathBuf>, String) {
-    const BIN_NAME: &str = "eval";
-    //TODO: check crates used ? (list "abnormal" crates)
-    //TODO: --offline to prevent using other crates than expected ?
-    let args = vec![
+pub fn compile_single_agent(
+    dir: &Path,
+    bin_name: &str,
+    options: &CompileOptions,
+) -> (anyhow::Result<PathBuf>, String) {
+    if let Some(allowed) = &options.allowed_crates {
+        if let Err(e) = check_dependencies(dir, allowed) {
+            error!("compilation error: {e:#}");
+            return (Err(e), String::new());
+        }
+    }
+    let timeout = options.timeout;
+    let mut args = vec![
         "build",
         "--release",
         "--bin",
-        BIN_NAME,
+        bin_name,
         // "--message-format",
         // "short",
     ];
+    if options.offline {
+        args.push("--offline");
+    }
+    // the checked dependencies must be the ones compiled
+    if options.offline || options.allowed_crates.is_some() {
+        args.push("--locked");
+    }
+
+    let mut command = Command::new("cargo");
+    command.args(args).current_dir(dir.canonicalize().unwrap());
+    let (status, stdout, stderr) =
+        match run_with_timeout(command, timeout).context("could not run cargo") {
+            Ok(output) => output,
+            Err(e) => {
+                error!("compilation error: {e:#}");
+                return (Err(e), String::new());
+            }
+        };
+    let result = stdout + "\n" + &stderr;
+    match status {
+        Some(status) if status.success() => {
+            let path = dir.join("target/release/").join(bin_name); // works even on windows since '.exe' is optional in commands
+            (Ok(path), result)
+        }
+        Some(_) => {
+            let output = stderr.trim();
+            error!("compilation error: {output}");
+
+            (
+                Err(anyhow::anyhow!(
+                    "Compilation error: {}",
+                    output.trim().split("\n").next().unwrap_or_default(),
+                )),
+                result,
+            )
+        }
+        None => {
+            // Safety: there is no status only after a timeout
+            let timeout = timeout.unwrap();
+            error!("compilation timed out after {}s", timeout.as_secs_f32());
+            (
+                Err(anyhow::anyhow!(
+                    "Compilation timed out after {}s",
+                    timeout.as_secs_f32()
+                )),
+                result,
+            )
+        }
+    }
+}
+
+/// Checks that the agent crate in `dir` only depends on `allowed` crates, according to its
+/// `Cargo.lock`
+fn check_dependencies(dir: &Path, allowed: &[String]) -> anyhow::Result<()> {
+    let normalize = |name: &str| name.replace('-', "_");
+    let lock = std::fs::read_to_string(dir.join("Cargo.lock"))
+        .context("Cargo.lock is required to check dependencies")?;
+    let allowed = allowed
+        .iter()
+        .map(|name| normalize(name))
+        .collect::<Vec<_>>();
+    let mut forbidden = locked_dependencies(&lock)
+        .into_iter()
+        .filter(|name| !allowed.contains(&normalize(name)))
+        .collect::<Vec<_>>();
+    forbidden.sort();
+    forbidden.dedup();
+    if !forbidden.is_empty() {
+        bail!("Forbidden dependencies: {}", forbidden.join(", "));
+    }
+    // local packages have no source in `Cargo.lock`: they must be part of the agent
+    check_path_dependencies(dir)
+}
+
+/// Checks that the paths of the manifests in `dir` (path dependencies, patches...) do not lead
+/// outside of `dir`
+fn check_path_dependencies(dir: &Path) -> anyhow::Result<()> {
+    let dir = dir.canonicalize().context("could not resolve directory")?;
+    for manifest in find_manifests(&dir) {
+        let content = std::fs::read_to_string(&manifest)
+            .with_context(|| format!("could not read {}", manifest.display()))?;
+        // Safety: manifests are files of `dir`
+        let manifest_dir = manifest.parent().unwrap();
+        for path in manifest_paths(&content) {
+            let inside = manifest_dir
+                .join(&path)
+                .canonicalize()
+                .is_ok_and(|path| path.starts_with(&dir));
+            if !inside {
+                bail!(
+                    "Forbidden path dependency: '{path}' (in {}) is not in the agent directory",
+                    manifest.strip_prefix(&dir).unwrap_or(&manifest).display()
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Paths to the `Cargo.toml` files in `dir` and its subdirectories, except build directories
+fn find_manifests(dir: &Path) -> Vec<PathBuf> {
+    let mut manifests = vec![];
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return manifests;
+    };
+    for entry in entries.flatten() {
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        // symbolic links to directories are not followed
+        if file_type.is_dir() && entry.file_name() != "target" {
+            manifests.extend(find_manifests(&entry.path()));
+        } else if entry.file_name() == "Cargo.toml" {
+            manifests.push(entry.path());
+        }
+    }
+    manifests
+}
+
+/// Values of the `path` keys of a `Cargo.toml`, e.g. `"../utils"` in
+/// `utils = { path = "../utils" }`
+fn manifest_paths(manifest: &str) -> Vec<String> {
+    let mut paths = vec![];
+    for line in manifest.lines() {
+        let mut rest = line;
+        while let Some(start) = rest.find("path") {
+            let is_key =
+                !rest[..start].ends_with(|c: char| c.is_alphanumeric() || c == '_' || c == '-');
+            rest = &rest[start + "path".len()..];
+            let value = rest.trim_start();
+            let Some(value) = value.strip_prefix('=').filter(|_| is_key) else {
+                continue;
+            };
+            let value = value.trim_start();
+            let Some(quote) = value.chars().next().filter(|&c| c == '"' || c == '\'') else {
+                continue;
+            };
+            if let Some(end) = value[1..].find(quote) {
+                paths.push(value[1..=end].to_string());
+            }
+        }
+    }
+    paths
+}
+
+/// Names of the packages of a `Cargo.lock` fetched from a registry or a git repository. Local
+/// packages, such as the agent crate itself, have no source.
+fn locked_dependencies(lock: &str) -> Vec<String> {
+    let mut dependencies = vec![];
+    let mut name = None;
+    let mut has_source = false;
+    for line in lock.lines().map(str::trim).chain(["[[package]]"]) {
+        if line == "[[package]]" {
+            if let Some(name) = name.take().filter(|_| has_source) {
+                dependencies.push(name);
+            }
+            has_source = false;
+        } else if let Some(value) = line.strip_prefix("name = ") {
+            name = Some(value.trim_matches('"').to_string());
+        } else if line.starts_with("source = ") {
+            has_source = true;
+        }
+    }
+    dependencies
+}
 
-    let proc = std::process::Command::new("cargo")
-        .args(args)
-        .current_dir(dir.canonicalize().unwrap())
-        .stderr(std::process::Stdio::piped())
-        .stdout(std::process::Stdio::piped())
+/// Runs the `build:` command of the agent in `dir`, within `timeout`
+#[instrument(parent = None)]
+pub fn build_with_command(
+    dir: &Path,
+    build: &[String],
+    timeout: Option<Duration>,
+) -> (anyhow::Result<()>, String) {
+    let mut command = Command::new(&build[0]);
+    command.args(&build[1..]).current_dir(dir);
+    let (status, stdout, stderr) = match run_with_timeout(command, timeout)
+        .with_context(|| format!("could not run build command '{}'", build[0]))
+    {
+        Ok(output) => output,
+        Err(e) => {
+            error!("build error: {e:#}");
+            return (Err(e), String::new());
+        }
+    };
+    let result = stdout + "\n" + &stderr;
+    match status {
+        Some(status) if status.success() => (Ok(()), result),
+        Some(status) => {
+            error!("build error ({status}): {}", stderr.trim());
+            (
+                Err(anyhow::anyhow!(
+                    "Build error ({status}): {}",
+                    stderr.trim().lines().next().unwrap_or_default(),
+                )),
+                result,
+            )
+        }
+        None => {
+            // Safety: there is no status only after a timeout
+            let timeout = timeout.unwrap();
+            error!("build timed out after {}s", timeout.as_secs_f32());
+            (
+                Err(anyhow::anyhow!(
+                    "Build timed out after {}s",
+                    timeout.as_secs_f32()
+                )),
+                result,
+            )
+        }
+    }
+}
+
+/// Runs `command` to completion, and returns its exit status (`None` if it was killed after
+/// `timeout`), stdout and stderr.
+///
+/// With a timeout, the command is run in its own process group on unix, so that the processes it
+/// started are killed with it.
+fn run_with_timeout(
+    mut command: Command,
+    timeout: Option<Duration>,
+) -> anyhow::Result<(Option<ExitStatus>, String, String)> {
+    #[cfg(unix)]
+    if timeout.is_some() {
+        std::os::unix::process::CommandExt::process_group(&mut command, 0);
+    }
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
         .spawn()
-        .expect("could not launch command 'cargo'");
-
-    let output = proc
-        .wait_with_output()
-        .expect("failed to wait for end of compilation");
-    let result = std::str::from_utf8(&output.stdout).unwrap().to_owned()
-        + "\n"
-        + std::str::from_utf8(&output.stderr).unwrap();
-    if output.status.success() {
-        let path = dir.join("target/release/").join(BIN_NAME); // works even on windows since '.exe' is optional in commands
-        (Ok(path), result)
-    } else {
-        let output = &output.stderr;
-        let output = std::str::from_utf8(output).unwrap().trim();
-        error!("compilation error: {output}");
-
-        (
-            Err(anyhow::anyhow!(
-                "Compilation error: {}",
-                output.trim().split("\n").next().unwrap_or_default(),
-            )),
-            result,
-        )
+        .context("could not launch command")?;
+    // read while the command runs, so that it never blocks on a full pipe
+    let stdout = read_in_background(child.stdout.take());
+    let stderr = read_in_background(child.stderr.take());
+
+    let status = match timeout {
+        None => Some(
+            child
+                .wait()
+                .context("failed to wait for end of compilation")?,
+        ),
+        Some(timeout) => {
+            let start = Instant::now();
+            loop {
+                if let Some(status) = child
+                    .try_wait()
+                    .context("failed to wait for end of compilation")?
+                {
+                    break Some(status);
+                }
+                if start.elapsed() >= timeout {
+                    kill_with_children(&mut child);
+                    break None;
+                }
+                thread::sleep(Duration::from_millis(50));
+            }
+        }
+    };
+
+    // processes started by the command may keep the pipes open after it exited
+    let output = |rx: mpsc::Receiver<Vec<u8>>| {
+        rx.recv_timeout(Duration::from_secs(1))
+            .map(|bytes| String::from_utf8_lossy(&bytes).into_owned())
+            .unwrap_or_default()
+    };
+    Ok((status, output(stdout), output(stderr)))
+}
+
+/// Reads `pipe` to the end on another thread
+fn read_in_background(pipe: Option<impl Read + Send + 'static>) -> mpsc::Receiver<Vec<u8>> {
+    let (tx, rx) = mpsc::channel();
+    if let Some(mut pipe) = pipe {
+        thread::spawn(move || {
+            let mut bytes = vec![];
+            let _ = pipe.read_to_end(&mut bytes);
+            let _ = tx.send(bytes);
+        });
+    }
+    rx
+}
+
+/// Kills `child` and, on unix, the other processes of its process group
+fn kill_with_children(child: &mut Child) {
+    #[cfg(unix)]
+    let _ = Command::new("kill")
+        .args(["-KILL", "--", &format!("-{}", child.id())])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status();
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
+#[cfg(test)]
+mod agent_compiler_tests {
+    use super::*;
+
+    #[test]
+    fn test_locked_dependencies() {
+        let lock = r#"# This file is automatically @generated by Cargo.
+version = 4
+
+[[package]]
+name = "agent"
+version = "0.1.0"
+dependencies = [
+ "game",
+ "rand",
+]
+
+[[package]]
+name = "game"
+version = "0.1.0"
+
+[[package]]
+name = "rand"
+version = "0.8.5"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+checksum = "34af8d1a0e25924bc5b7c43c079c942339d8f0a8b57c39049bef581b46327404"
+
+[[package]]
+name = "fast-math"
+version = "0.1.0"
+source = "git+https://example.com/fast-math#0123abc"
+"#;
+        assert_eq!(locked_dependencies(lock), ["rand", "fast-math"]);
+    }
+
+    #[test]
+    fn test_manifest_paths() {
+        let manifest = r#"[package]
+name = "agent"
+
+[[bin]]
+name = "eval"
+path = "src/main.rs"
+
+[dependencies]
+game = { path = "../game", version = "0.1" }
+rand = "0.8"
+utils = {path='utils'}
+classpath = "not a path key"
+
+[dependencies.engine]
+path  =  "../../engine"
+"#;
+        assert_eq!(
+            manifest_paths(manifest),
+            ["src/main.rs", "../game", "utils", "../../engine"]
+        );
     }
 }
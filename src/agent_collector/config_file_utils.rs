@@ -1,102 +1,305 @@
-use std::{
-    collections::HashMap,
-    path::{Path, PathBuf},
-};
+use std::path::{Path, PathBuf};
 
 use anyhow::{bail, Context};
+use indexmap::IndexMap;
+use serde::Deserialize;
 use tracing::{error, instrument};
 
-pub fn get_all_configs(dir: &Path) -> anyhow::Result<HashMap<String, String>> {
+/// Arguments of each config, by name, in the order of the config file
+pub fn get_all_configs(dir: &Path) -> anyhow::Result<IndexMap<String, String>> {
     let config_file = collect_yaml(dir)?;
     let yaml = std::fs::read_to_string(config_file)?;
     let full_config = parse_yaml(&yaml)?;
-    Ok(full_config.configs)
+    Ok(full_config.configs.into_map())
 }
 
-pub fn get_eval_config(dir: &Path) -> anyhow::Result<String> {
+/// Config to evaluate: `config_name` if given, otherwise the one of the `eval:` key
+pub fn get_eval_config(dir: &Path, config_name: Option<&str>) -> anyhow::Result<String> {
     let config_file = collect_yaml(dir)?;
     let yaml = std::fs::read_to_string(config_file)?;
     let full_config = parse_yaml(&yaml)?;
-    let config_name = &full_config.eval;
+    let config_name = config_name.unwrap_or(&full_config.eval);
     let config = full_config
         .configs
-        .get(config_name)
-        .context("unknown config '{config_name}'")?;
-    Ok(config.clone())
+        .into_map()
+        .swap_remove(config_name)
+        .with_context(|| format!("unknown config '{config_name}'"))?;
+    Ok(config)
 }
 
-pub fn get_args_from_config(config: &str) -> anyhow::Result<Vec<String>> {
-    if config.contains("\"") || config.contains("'") || config.contains("`") {
-        bail!("arguments should not contain any quote")
+/// Team of the agent (`team:` key), if any
+pub fn get_team(dir: &Path) -> anyhow::Result<Option<String>> {
+    let config_file = collect_yaml(dir)?;
+    let yaml = std::fs::read_to_string(config_file)?;
+    let full_config = parse_yaml(&yaml)?;
+    Ok(full_config.team)
+}
+
+/// Binary of the agent crate to build (`bin:` key), [`DEFAULT_BIN`] if not set
+pub fn get_bin(dir: &Path) -> anyhow::Result<String> {
+    let config_file = collect_yaml(dir)?;
+    let yaml = std::fs::read_to_string(config_file)?;
+    let full_config = parse_yaml(&yaml)?;
+    Ok(full_config.bin.unwrap_or_else(|| DEFAULT_BIN.to_string()))
+}
+
+/// Time multiplier of the agent (`time_multiplier:` key), `1.0` if not set
+pub fn get_time_multiplier(dir: &Path) -> anyhow::Result<f32> {
+    let config_file = collect_yaml(dir)?;
+    let yaml = std::fs::read_to_string(config_file)?;
+    let full_config = parse_yaml(&yaml)?;
+    Ok(full_config.time_multiplier.unwrap_or(1.0))
+}
+
+/// Binary built when an agent crate does not set the `bin:` key
+pub const DEFAULT_BIN: &str = "eval";
+
+/// Launch command template of the agent (`command:` key), if any, split into words
+pub fn get_command(dir: &Path) -> anyhow::Result<Option<Vec<String>>> {
+    let config_file = collect_yaml(dir)?;
+    let yaml = std::fs::read_to_string(config_file)?;
+    let full_config = parse_yaml(&yaml)?;
+    full_config
+        .command
+        .map(|command| parse_command_template(&command))
+        .transpose()
+}
+
+/// Command running the agent (`run:` key), if any, as a launch command template: the words of
+/// the command, followed by the protocol arguments (see [`parse_run_command`])
+pub fn get_run(dir: &Path) -> anyhow::Result<Option<Vec<String>>> {
+    let config_file = collect_yaml(dir)?;
+    let yaml = std::fs::read_to_string(config_file)?;
+    let full_config = parse_yaml(&yaml)?;
+    full_config
+        .run
+        .map(|run| parse_run_command(&run))
+        .transpose()
+}
+
+/// Command building the agent (`build:` key), if any, split into words
+pub fn get_build(dir: &Path) -> anyhow::Result<Option<Vec<String>>> {
+    let config_file = collect_yaml(dir)?;
+    let yaml = std::fs::read_to_string(config_file)?;
+    let full_config = parse_yaml(&yaml)?;
+    full_config
+        .build
+        .map(|build| split_command(&build))
+        .transpose()
+}
+
+/// Splits a command into words, without placeholders
+fn split_command(command: &str) -> anyhow::Result<Vec<String>> {
+    if command.contains('\'') || command.contains('"') || command.contains('`') {
+        bail!("command should not contain any quote")
+    }
+    if command.contains('{') {
+        bail!("placeholders are only supported by the 'command' key");
     }
-    if config.is_empty() {
-        // "".split(" ") == vec![""] : we should allow having an empty config
-        return Ok(vec![]);
+    let words = command
+        .split_whitespace()
+        .map(String::from)
+        .collect::<Vec<_>>();
+    if words.is_empty() {
+        bail!("empty command");
     }
-    Ok(config.split(" ").map(String::from).collect())
+    Ok(words)
 }
 
-struct ConfigFile {
-    eval: String,
-    configs: HashMap<String, String>,
+/// Launch command template of a `run:` command: the agent is given the same arguments as a
+/// binary, after the words of the command (`<run...> <port> <budget_us> <action_us> [seed]
+/// <args...>`)
+pub fn parse_run_command(run: &str) -> anyhow::Result<Vec<String>> {
+    let mut words = split_command(run)?;
+    words.extend(["{port}", "{budget_us}", "{action_us}", "{seed}", "{args}"].map(String::from));
+    Ok(words)
 }
 
-fn parse_yaml(yaml: &str) -> anyhow::Result<ConfigFile> {
-    let mut eval = None;
-    let mut configs = HashMap::new();
-    let mut in_configs = false;
+/// Placeholders that can be used in a launch command template
+const COMMAND_PLACEHOLDERS: [&str; 6] = [
+    "{exe}",
+    "{port}",
+    "{budget_us}",
+    "{action_us}",
+    "{seed}",
+    "{args}",
+];
 
-    // .peekable() ? (to 'exit' in_config)
-    for (i, line) in yaml.lines().enumerate() {
-        let line = line.trim_end();
-
-        // Skip empty lines and comments
-        if line.trim().is_empty() || line.trim().starts_with("#") {
-            continue;
+/// Splits a command template into words, and checks its placeholders
+pub fn parse_command_template(command: &str) -> anyhow::Result<Vec<String>> {
+    if command.contains('\'') || command.contains('`') {
+        bail!("command should not contain any quote")
+    }
+    let words = command
+        .split_whitespace()
+        .map(String::from)
+        .collect::<Vec<_>>();
+    if words.is_empty() {
+        bail!("empty command");
+    }
+    for word in &words {
+        let mut rest = word.as_str();
+        while let Some(start) = rest.find('{') {
+            let end = rest[start..]
+                .find('}')
+                .with_context(|| format!("unclosed placeholder in '{word}'"))?;
+            let placeholder = &rest[start..start + end + 1];
+            if !COMMAND_PLACEHOLDERS.contains(&placeholder) {
+                bail!(
+                    "unknown placeholder {placeholder} (expected one of {})",
+                    COMMAND_PLACEHOLDERS.join(", ")
+                );
+            }
+            if (placeholder == "{args}" || placeholder == "{seed}") && word != placeholder {
+                bail!("{placeholder} must be a separate word");
+            }
+            rest = &rest[start + end + 1..];
         }
+    }
+    if !words.iter().any(|word| word.contains("{port}")) {
+        bail!("command must contain the {{port}} placeholder");
+    }
+    Ok(words)
+}
 
-        if !in_configs {
-            if let Some(value) = line.strip_prefix("eval:") {
-                let value = value.trim();
-                if value.is_empty() {
-                    bail!("Line {}: 'eval' value missing", i + 1);
+/// Splits the arguments of a config like a shell would, without expansions: arguments are
+/// separated by whitespace, unless quoted.
+///
+/// Single quotes keep everything literally. In double quotes, `\` only escapes `"` and `\`.
+/// Outside quotes, `\` escapes any character. An unclosed quote or a trailing `\` is an error.
+pub fn get_args_from_config(config: &str) -> anyhow::Result<Vec<String>> {
+    let mut args = vec![];
+    // `None` between arguments, so that `""` gives an empty argument
+    let mut arg: Option<String> = None;
+    let mut chars = config.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => args.extend(arg.take()),
+            '\'' => {
+                let arg = arg.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => arg.push(c),
+                        None => bail!("unclosed single quote"),
+                    }
                 }
-                eval = Some(value.to_string());
-            } else if line.starts_with("configs:") {
-                in_configs = true;
-            } else {
-                bail!("Line {}: Expected 'eval:' or 'configs:' key", i + 1);
             }
-        } else {
-            // Inside configs list, expect lines like: '- key: "value"'
-            let line = line.trim_start();
-            if !line.starts_with('-') {
-                bail!("Line {}: Expected list item starting with '-'", i + 1);
+            '"' => {
+                let arg = arg.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c @ ('"' | '\\')) => arg.push(c),
+                            Some(c) => {
+                                arg.push('\\');
+                                arg.push(c);
+                            }
+                            None => bail!("unclosed double quote"),
+                        },
+                        Some(c) => arg.push(c),
+                        None => bail!("unclosed double quote"),
+                    }
+                }
             }
-            let rest = line[1..].trim_start();
-            // Now expect key: "value"
-            let colon_pos = rest
-                .find(':')
-                .context(format!("Line {}: Missing ':' in config item", i + 1))?;
-            let key = rest[..colon_pos].trim();
-            let value_part = rest[colon_pos + 1..].trim();
-
-            // Value should start and end with double quotes
-            if !value_part.starts_with('"') || value_part[1..].find('"').is_none() {
-                bail!("Line {}: Value must be quoted with double quotes", i + 1);
+            '\\' => {
+                let escaped = chars.next().context("trailing backslash")?;
+                arg.get_or_insert_with(String::new).push(escaped);
             }
+            c => arg.get_or_insert_with(String::new).push(c),
+        }
+    }
+    args.extend(arg);
+    Ok(args)
+}
 
-            // Remove surrounding quotes (does not handle escaped quotes inside)
-            let second_quote = 1 + value_part[1..].find('"').unwrap();
-            let value = &value_part[1..second_quote];
+/// Content of an agent's YAML config file
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ConfigFile {
+    eval: String,
+    #[serde(default)]
+    configs: Configs,
+    team: Option<String>,
+    command: Option<String>,
+    run: Option<String>,
+    build: Option<String>,
+    bin: Option<String>,
+    time_multiplier: Option<f32>,
+}
 
-            configs.insert(key.to_string(), value.to_string());
-        }
+/// Named configs: a list of `name: "args"` items, or a mapping of names to arguments
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Configs {
+    List(Vec<IndexMap<String, String>>),
+    Map(IndexMap<String, String>),
+}
+
+impl Default for Configs {
+    fn default() -> Self {
+        Configs::Map(IndexMap::new())
+    }
+}
+
+impl Configs {
+    /// Arguments of each config, by name in declaration order, trimmed (folded values end with a
+    /// line break). In a list, a later config overrides an earlier one with the same name.
+    fn into_map(self) -> IndexMap<String, String> {
+        let configs: IndexMap<String, String> = match self {
+            Configs::List(items) => items.into_iter().flatten().collect(),
+            Configs::Map(configs) => configs,
+        };
+        configs
+            .into_iter()
+            .map(|(name, args)| (name, args.trim().to_string()))
+            .collect()
     }
+}
 
-    let eval = eval.ok_or_else(|| anyhow::anyhow!("Missing 'eval' key"))?;
+/// Parses and checks a config file. Values are trimmed.
+fn parse_yaml(yaml: &str) -> anyhow::Result<ConfigFile> {
+    let mut config: ConfigFile = serde_yaml::from_str(yaml).context("invalid YAML config")?;
+    fn non_empty(key: &str, value: &mut Option<String>) -> anyhow::Result<()> {
+        if let Some(value) = value {
+            *value = value.trim().to_string();
+            if value.is_empty() {
+                bail!("'{key}' value missing");
+            }
+        }
+        Ok(())
+    }
 
-    Ok(ConfigFile { eval, configs })
+    config.eval = config.eval.trim().to_string();
+    if config.eval.is_empty() {
+        bail!("'eval' value missing");
+    }
+    non_empty("team", &mut config.team)?;
+    non_empty("command", &mut config.command)?;
+    non_empty("run", &mut config.run)?;
+    non_empty("build", &mut config.build)?;
+    non_empty("bin", &mut config.bin)?;
+    if let Some(bin) = config
+        .bin
+        .as_ref()
+        .filter(|bin| bin.contains(|c: char| c.is_whitespace() || c == '/' || c == '\\'))
+    {
+        bail!("invalid binary name '{bin}'");
+    }
+    if let Some(multiplier) = config
+        .time_multiplier
+        .filter(|m| !m.is_finite() || *m <= 0.0)
+    {
+        bail!("'time_multiplier' must be a positive number, got '{multiplier}'");
+    }
+    if config.run.is_some() && config.command.is_some() {
+        bail!("'run' and 'command' keys cannot be used together");
+    }
+    if config.build.is_some() && config.run.is_none() {
+        bail!("'build' key requires a 'run' key");
+    }
+    Ok(config)
 }
 
 #[instrument]
@@ -119,10 +322,10 @@ pub(super) fn check_dir_integrity(dir: &Path) -> anyhow::Result<()> {
     Ok(())
 }
 
-fn collect_yaml(dir: &Path) -> anyhow::Result<PathBuf> {
+pub(super) fn collect_yaml(dir: &Path) -> anyhow::Result<PathBuf> {
     check_dir_integrity(dir)?;
 
-    let mut result: Option<PathBuf> = None;
+    let mut found: Vec<PathBuf> = vec![];
     // Safety: `check_dir_integrity` tested that read_dir is ok
     for entry in std::fs::read_dir(dir).unwrap() {
         let Ok(entry) = entry else {
@@ -138,14 +341,89 @@ fn collect_yaml(dir: &Path) -> anyhow::Result<PathBuf> {
             bail!("name error: {:?}", entry.file_name());
         };
         if name.ends_with(".yml") || name.ends_with(".yaml") {
-            if result.is_some() {
-                bail!(
-                    "two YAML files found: {} and {name}",
-                    result.unwrap().to_str().unwrap()
-                );
-            }
-            result = Some(entry.path());
+            found.push(entry.path());
         }
     }
-    result.context("YAML not found")
+    if found.len() > 1 {
+        // sorted: the order of read_dir is not specified
+        let mut names = found
+            .iter()
+            .map(|path| path.file_name().unwrap().to_string_lossy().into_owned())
+            .collect::<Vec<_>>();
+        names.sort();
+        bail!(
+            "several YAML config files found ({}), keep only one",
+            names.join(", ")
+        );
+    }
+    found.pop().context("YAML not found")
+}
+
+#[cfg(test)]
+mod config_file_utils_tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_yaml() {
+        let yaml = r#"
+# comment
+eval: folded   # trailing comment
+configs:
+  - folded: >
+      --depth 3
+      --mode fast
+  - special: "--path=a#b: c"
+  - plain: --verbose
+"#;
+        let configs = parse_yaml(yaml).unwrap().configs.into_map();
+        assert_eq!(configs["folded"], "--depth 3 --mode fast");
+        assert_eq!(configs["special"], "--path=a#b: c");
+        assert_eq!(configs["plain"], "--verbose");
+        // in declaration order
+        assert!(configs.keys().eq(["folded", "special", "plain"]));
+
+        // configs can also be a mapping
+        let config =
+            parse_yaml("eval: a\nconfigs:\n  z: \"\"\n  a: \"-x\"\ntime_multiplier: 3\n").unwrap();
+        let configs = config.configs.into_map();
+        assert_eq!(configs["a"], "-x");
+        assert!(configs.keys().eq(["z", "a"]));
+        assert_eq!(config.time_multiplier, Some(3.0));
+
+        for invalid in [
+            "configs:\n  - a: \"\"\n",
+            "eval: a\nunknown: 1\n",
+            "eval: a\nrun: ./agent\ncommand: \"{exe} {port}\"\n",
+            "eval: a\ntime_multiplier: 0\n",
+            "eval: a\nbin: ../eval\n",
+            "eval: [a\n",
+        ] {
+            assert!(parse_yaml(invalid).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn test_args_from_config() {
+        assert!(get_args_from_config("").unwrap().is_empty());
+        assert_eq!(
+            get_args_from_config("  --fast   -v ").unwrap(),
+            ["--fast", "-v"]
+        );
+        assert_eq!(
+            get_args_from_config(r#"--name "My Bot" --path='C:\My Files' --x="" a\ b"#).unwrap(),
+            ["--name", "My Bot", r"--path=C:\My Files", "--x=", "a b"]
+        );
+        assert_eq!(
+            get_args_from_config(r#""say \"hi\" \n" '' it's"#)
+                .unwrap_err()
+                .to_string(),
+            "unclosed single quote"
+        );
+        assert_eq!(
+            get_args_from_config(r#""say \"hi\" \n" ''"#).unwrap(),
+            [r#"say "hi" \n"#, ""]
+        );
+        assert!(get_args_from_config("\"unclosed").is_err());
+        assert!(get_args_from_config("trailing\\").is_err());
+    }
 }
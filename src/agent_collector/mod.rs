@@ -1,15 +1,24 @@
 use std::{
+    collections::{HashMap, VecDeque},
     fs,
+    hash::{DefaultHasher, Hasher},
     io::Write,
     path::{Path, PathBuf},
-    sync::Arc,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc, Mutex,
+    },
+    thread::JoinHandle,
+    time::Duration,
 };
 
-use anyhow::bail;
+use anyhow::{bail, Context};
+use time::{format_description::parse, OffsetDateTime};
 use tracing::{error, info, instrument, warn};
 
 use crate::{
-    agent::Agent, agent_collector::config_file_utils::check_dir_integrity,
+    agent::Agent,
+    agent_collector::{agent_compiler::CompileOptions, config_file_utils::check_dir_integrity},
     configuration::Configuration,
 };
 
@@ -26,6 +35,7 @@ pub fn collect_agents(
     let compile = config.compile_agents;
     let self_test = config.self_test;
     let all_configs = config.test_all_configs;
+    let discovery = config.binary_discovery.as_ref().filter(|_| !compile);
 
     let directory = directory.as_ref();
 
@@ -39,10 +49,15 @@ pub fn collect_agents(
     const YELLOW: &str = "\x1b[33m";
     const RESET: &str = "\x1b[0m";
 
+    // read the directory once: it may become unreadable (or change) between two reads
+    let entries = std::fs::read_dir(directory)
+        .with_context(|| format!("could not read directory '{directory:?}'"))?
+        .collect::<Result<Vec<_>, _>>()
+        .with_context(|| format!("could not read an entry of directory '{directory:?}'"))?;
+
     // get longest subdir name for printing
-    let longest_name = std::fs::read_dir(directory)
-        .unwrap()
-        .filter_map(|res| res.ok())
+    let longest_name = entries
+        .iter()
         .fold(0, |acu, entry| acu.max(entry.file_name().len()))
         + 3; // at least 3 dots
 
@@ -54,25 +69,57 @@ pub fn collect_agents(
         }
     }
 
+    let extra_args = &config.extra_agent_args;
     let mut ids = 1;
+    // fingerprint of the code of each working agent (by id), to detect duplicates
+    let mut fingerprints = HashMap::new();
     let subdirs = if self_test {
-        // hacky way of only checking cwd when self_test is set
-        vec![std::env::current_dir().unwrap()]
+        // only check the self-test directory (cwd by default) when self_test is set
+        let path = match &config.self_test_path {
+            Some(path) => path.clone(),
+            None => std::env::current_dir().context("could not get current directory")?,
+        };
+        vec![path]
     } else {
-        std::fs::read_dir(directory)
-            .unwrap()
-            .filter_map(|item| {
-                if let Ok(item) = item {
-                    Some(item.path())
-                } else {
-                    None
-                }
-            })
-            .collect::<Vec<_>>()
+        entries.iter().map(fs::DirEntry::path).collect::<Vec<_>>()
     };
     info!(agent_directories=?subdirs);
 
-    for subdir in subdirs {
+    if subdirs.is_empty() {
+        if !config.allow_empty_agent_dir {
+            error!("No agent found in '{directory:?}'");
+            bail!("no agent found in '{directory:?}'");
+        }
+        warn!("No agent found in '{directory:?}'");
+        if verbose {
+            println!("{YELLOW}No agent found in '{directory:?}'{RESET}");
+        }
+    }
+
+    // agents are compiled in the background, in parallel; their results are collected below in
+    // order, so that progress is printed as agents are compiled
+    let jobs = if compile {
+        subdirs
+            .iter()
+            .enumerate()
+            .filter(|(_, subdir)| {
+                !subdir.is_file()
+                    && config_file_utils::collect_yaml(subdir).is_ok()
+                    && config_file_utils::get_run(subdir).is_ok()
+            })
+            .map(|(i, subdir)| (i, subdir.clone()))
+            .collect()
+    } else {
+        vec![]
+    };
+    let options = CompileOptions {
+        timeout: config.compile_timeout,
+        offline: config.offline_compile,
+        allowed_crates: config.allowed_crates.clone(),
+    };
+    let mut compilations = Compilations::start(jobs, options);
+
+    for (i, subdir) in subdirs.into_iter().enumerate() {
         let name = subdir
             .file_name()
             .unwrap()
@@ -96,7 +143,7 @@ pub fn collect_agents(
             let _ = std::io::stdout().flush(); // try to flush stdout
         }
 
-        if subdir.metadata().unwrap().is_file() {
+        if subdir.is_file() {
             warn!("Not a directory: '{name}'");
             if verbose {
                 println!("{RED}Not a directory{RESET}");
@@ -110,9 +157,61 @@ pub fn collect_agents(
             continue;
         }
 
+        // check the config file first (unless replaced by the discovery function): there is no
+        // need to compile an agent that cannot be used
+        let config_check = match discovery {
+            Some(_) => Ok(()),
+            None => config_file_utils::collect_yaml(&subdir).map(|_| ()),
+        };
+        if let Err(e) = config_check {
+            error!("Invalid config in '{name}': {e}");
+            if verbose {
+                println!("{RED}Invalid config: {e}{RESET}");
+            }
+            vec.push(Arc::new(Agent::with_error(
+                name,
+                ids,
+                format!("Invalid config: {e}"),
+            )));
+            ids += 1;
+            continue;
+        }
+
+        // agents declaring a `run:` command are launched from their directory, whatever their
+        // language: they are built with their `build:` command (if any) instead of cargo
+        let run = match discovery {
+            Some(_) => Ok(None),
+            None => config_file_utils::get_run(&subdir),
+        };
+        let run = match run {
+            Ok(run) => run,
+            Err(e) => {
+                error!("Invalid config in '{name}': {e:#}");
+                if verbose {
+                    println!("{RED}Invalid config: {e:#}{RESET}");
+                }
+                vec.push(Arc::new(Agent::with_error(
+                    name,
+                    ids,
+                    format!("Invalid config: {e:#}"),
+                )));
+                ids += 1;
+                continue;
+            }
+        };
+
         // collect path to executable and compilation result (empty if we are not compiling)
+        let mut discovered_args = None;
         let (res, compilation_output) = if compile {
-            agent_compiler::compile_single_agent(&subdir)
+            compilations.wait(i)
+        } else if run.is_some() {
+            build_run_agent(&subdir, false, None)
+        } else if let Some(discovery) = discovery {
+            let res = (discovery.0)(&subdir).map(|(binary, args)| {
+                discovered_args = Some(args);
+                binary
+            });
+            (res, "".to_owned())
         } else {
             (collect_binary(&subdir), "".to_owned())
         };
@@ -134,6 +233,12 @@ pub fn collect_agents(
             if verbose {
                 println!("{RED}{}{RESET}", res.as_ref().unwrap_err());
             }
+            if compile && config.fail_on_compile_error {
+                bail!(
+                    "agent '{name}' failed to compile: {}",
+                    res.as_ref().unwrap_err()
+                );
+            }
             vec.push(Arc::new(Agent::with_error(
                 name,
                 ids,
@@ -143,6 +248,72 @@ pub fn collect_agents(
             continue;
         };
 
+        // agents with a `run:` command have no single binary to keep
+        if let (true, None, Some(output_dir)) = (compile, &run, &config.keep_binaries) {
+            if let Err(e) = keep_binary(output_dir, &name, &res, &subdir) {
+                warn!("Could not keep binary of '{name}': {e:#}");
+                if verbose {
+                    print!("{YELLOW}could not keep binary ({e}), {RESET}");
+                }
+            }
+        }
+
+        // compiled binaries depend on the crate location: compare sources instead
+        let fingerprint = if config.detect_duplicates {
+            let code = if compile && run.is_none() {
+                subdir.join("src")
+            } else {
+                res.clone()
+            };
+            fingerprint(&code)
+                .inspect_err(|e| warn!("Could not fingerprint '{name}': {e:#}"))
+                .ok()
+        } else {
+            None
+        };
+        let first_new_agent = vec.len();
+
+        if let Some(args) = discovered_args {
+            // replaces the config file
+            if let Some(fingerprint) = fingerprint {
+                fingerprints.insert(ids, fingerprint);
+            }
+            vec.push(Arc::new(
+                Agent::new(name, Some(res), log_path, ids, Some(args))
+                    .with_extra_args(extra_args.clone()),
+            ));
+            ids += 1;
+            if verbose {
+                println!("{GREEN}Ok{RESET}");
+            }
+            continue;
+        }
+
+        // parsing errors are reported below, when collecting the configs
+        let time_multiplier = config_file_utils::get_time_multiplier(&subdir).unwrap_or(1.0);
+        let working_dir = run.is_some().then(|| res.clone());
+        let team_and_command = config_file_utils::get_team(&subdir).and_then(|team| {
+            let command = match run {
+                Some(run) => Some(run),
+                None => config_file_utils::get_command(&subdir)?,
+            };
+            Ok((team, command))
+        });
+        let Ok((team, command)) = team_and_command else {
+            let e = team_and_command.unwrap_err();
+            error!("Invalid config: {e:#}");
+            if verbose {
+                println!("{RED}Invalid config: {e:#}{RESET}");
+            }
+            vec.push(Arc::new(Agent::with_error(
+                name,
+                ids,
+                format!("Invalid config: {e:#}"),
+            )));
+            ids += 1;
+            continue;
+        };
+
         if all_configs {
             let configs = config_file_utils::get_all_configs(&subdir);
             let Ok(configs) = configs else {
@@ -187,17 +358,24 @@ pub fn collect_agents(
                     None
                 };
 
-                vec.push(Arc::new(Agent::new(
-                    agent_name,
-                    Some(res.clone()),
-                    config_log_path,
-                    ids,
-                    Some(args),
-                )));
+                vec.push(Arc::new(
+                    Agent::new(
+                        agent_name,
+                        Some(res.clone()),
+                        config_log_path,
+                        ids,
+                        Some(args),
+                    )
+                    .with_team(team.clone())
+                    .with_command(command.clone())
+                    .with_time_multiplier(time_multiplier)
+                    .with_extra_args(extra_args.clone())
+                    .with_working_dir(working_dir.clone()),
+                ));
                 ids += 1;
             }
         } else {
-            let config = config_file_utils::get_eval_config(&subdir);
+            let config = config_file_utils::get_eval_config(&subdir, config.eval_config.as_deref());
             let Ok(config) = config else {
                 error!("No 'eval' config: {}", config.as_ref().unwrap_err());
                 if verbose {
@@ -235,26 +413,117 @@ pub fn collect_agents(
                 continue;
             };
 
-            vec.push(Arc::new(Agent::new(
-                name,
-                Some(res),
-                log_path,
-                ids,
-                Some(args),
-            )));
+            vec.push(Arc::new(
+                Agent::new(name, Some(res), log_path, ids, Some(args))
+                    .with_team(team)
+                    .with_command(command)
+                    .with_time_multiplier(time_multiplier)
+                    .with_extra_args(extra_args.clone())
+                    .with_working_dir(working_dir),
+            ));
             ids += 1;
         }
 
+        if let Some(fingerprint) = fingerprint {
+            for agent in vec[first_new_agent..].iter().filter(|a| a.compile) {
+                fingerprints.insert(agent.id, fingerprint);
+            }
+        }
+
         if verbose {
             println!("{GREEN}Ok{RESET}");
         }
     }
 
+    if config.detect_duplicates {
+        for (duplicate, original) in find_duplicates(&vec, &fingerprints) {
+            let agent = &vec[duplicate];
+            warn!("'{}' is a duplicate of '{original}'", agent.name);
+            if verbose {
+                println!(
+                    "{YELLOW}'{}' is a duplicate of '{original}'{RESET}",
+                    agent.name
+                );
+            }
+            vec[duplicate] = Arc::new(Agent::with_error(
+                agent.name.clone(),
+                agent.id,
+                format!("duplicate of '{original}'"),
+            ));
+        }
+    }
+
     Ok(vec)
 }
 
+/// Returns the index of each duplicate agent in `agents`, with the name of the agent it
+/// duplicates.
+///
+/// Agents are duplicates when they have the same fingerprint and the same arguments (configs of
+/// the same agent share a fingerprint). In each group of duplicates, the agent with the smallest
+/// name is the original.
+fn find_duplicates(
+    agents: &[Arc<Agent>],
+    fingerprints: &HashMap<u32, u64>,
+) -> Vec<(usize, String)> {
+    let mut groups: HashMap<_, Vec<usize>> = HashMap::new();
+    for (i, agent) in agents.iter().enumerate() {
+        if let Some(fingerprint) = fingerprints.get(&agent.id) {
+            groups
+                .entry((fingerprint, &agent.args))
+                .or_default()
+                .push(i);
+        }
+    }
+
+    let mut duplicates = vec![];
+    for mut group in groups.into_values() {
+        group.sort_by(|a, b| agents[*a].name.cmp(&agents[*b].name));
+        let original = &agents[group[0]].name;
+        duplicates.extend(group[1..].iter().map(|&i| (i, original.clone())));
+    }
+    duplicates
+}
+
+/// Hash of the content of a file, or of all the files of a directory (with their relative paths)
+fn fingerprint(path: &Path) -> anyhow::Result<u64> {
+    fn hash_dir(hasher: &mut DefaultHasher, root: &Path, dir: &Path) -> anyhow::Result<()> {
+        let mut entries = fs::read_dir(dir)
+            .with_context(|| format!("could not read directory {}", dir.display()))?
+            .collect::<Result<Vec<_>, _>>()?;
+        entries.sort_by_key(|entry| entry.path());
+        for entry in entries {
+            let path = entry.path();
+            if path.is_dir() {
+                hash_dir(hasher, root, &path)?;
+            } else {
+                // Safety: `path` is inside `root`
+                let relative = path.strip_prefix(root).unwrap();
+                hasher.write(relative.as_os_str().as_encoded_bytes());
+                hasher.write(
+                    &fs::read(&path)
+                        .with_context(|| format!("could not read {}", path.display()))?,
+                );
+            }
+        }
+        Ok(())
+    }
+
+    let mut hasher = DefaultHasher::new();
+    if path.is_dir() {
+        hash_dir(&mut hasher, path, path)?;
+    } else {
+        hasher
+            .write(&fs::read(path).with_context(|| format!("could not read {}", path.display()))?);
+    }
+    Ok(hasher.finish())
+}
+
 fn create_log_subdir(config: &Configuration, name: &str) -> PathBuf {
     let path = config.log_dir.as_ref().unwrap().join(name);
+    if config.append_logs {
+        return create_run_subdir(&path);
+    }
 
     if path.exists() {
         if !path.is_dir() {
@@ -287,6 +556,183 @@ fn create_log_subdir(config: &Configuration, name: &str) -> PathBuf {
     path
 }
 
+/// Creates a new subdirectory of `path` named after the current time, with a `_<n>` suffix when
+/// several runs start within the same second
+fn create_run_subdir(path: &Path) -> PathBuf {
+    fs::create_dir_all(path)
+        .unwrap_or_else(|e| panic!("Failed to create directory '{}': {}", path.display(), e));
+
+    let format = parse("[year]-[month]-[day]_[hour]-[minute]-[second]").unwrap();
+    let now = OffsetDateTime::now_local().unwrap_or_else(|_| OffsetDateTime::now_utc());
+    let timestamp = now.format(&format).unwrap();
+
+    let mut run_path = path.join(&timestamp);
+    let mut n = 1;
+    loop {
+        match fs::create_dir(&run_path) {
+            Ok(()) => return run_path,
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                n += 1;
+                run_path = path.join(format!("{timestamp}_{n}"));
+            }
+            Err(e) => panic!("Failed to create directory '{}': {}", run_path.display(), e),
+        }
+    }
+}
+
+/// Copy a compiled agent and its config file to `<output_dir>/<name>/`, in the layout expected
+/// when agents are not compiled.
+fn keep_binary(
+    output_dir: &Path,
+    name: &str,
+    binary: &Path,
+    agent_dir: &Path,
+) -> anyhow::Result<()> {
+    let target_dir = output_dir.join(name);
+    fs::create_dir_all(&target_dir)
+        .with_context(|| format!("could not create directory {}", target_dir.display()))?;
+
+    fs::copy(binary, target_dir.join(name))
+        .with_context(|| format!("could not copy {}", binary.display()))?;
+
+    let config_file = config_file_utils::collect_yaml(agent_dir)?;
+    // Safety: `collect_yaml` only returns files
+    fs::copy(
+        &config_file,
+        target_dir.join(config_file.file_name().unwrap()),
+    )
+    .with_context(|| format!("could not copy {}", config_file.display()))?;
+    info!("binary of '{name}' kept in {}", target_dir.display());
+    Ok(())
+}
+
+/// Result of the compilation of an agent: path to its executable, and compilation output
+type CompilationResult = (anyhow::Result<PathBuf>, String);
+
+/// Agents compiled in parallel, on at most one thread per CPU
+struct Compilations {
+    results: mpsc::Receiver<(usize, CompilationResult)>,
+    /// Results received before they were waited for, by job index
+    received: HashMap<usize, CompilationResult>,
+    cancelled: Arc<AtomicBool>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+impl Compilations {
+    /// Starts compiling the agents of `jobs` (directories, with their index)
+    fn start(jobs: Vec<(usize, PathBuf)>, options: CompileOptions) -> Self {
+        let options = Arc::new(options);
+        let threads = num_cpus::get().clamp(1, jobs.len().max(1));
+        let queue = Arc::new(Mutex::new(VecDeque::from(jobs)));
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let (tx, results) = mpsc::channel();
+        let workers = (0..threads)
+            .map(|_| {
+                let queue = queue.clone();
+                let cancelled = cancelled.clone();
+                let tx = tx.clone();
+                let options = options.clone();
+                std::thread::spawn(move || {
+                    while !cancelled.load(Ordering::Relaxed) {
+                        let Some((i, dir)) = queue.lock().unwrap().pop_front() else {
+                            break;
+                        };
+                        if tx.send((i, compile_agent(&dir, &options))).is_err() {
+                            break;
+                        }
+                    }
+                })
+            })
+            .collect();
+        Self {
+            results,
+            received: HashMap::new(),
+            cancelled,
+            workers,
+        }
+    }
+
+    /// Waits for the compilation of the job of index `i`
+    fn wait(&mut self, i: usize) -> CompilationResult {
+        loop {
+            if let Some(result) = self.received.remove(&i) {
+                return result;
+            }
+            match self.results.recv() {
+                Ok((j, result)) => {
+                    self.received.insert(j, result);
+                }
+                Err(_) => {
+                    return (
+                        Err(anyhow::anyhow!("agent was not compiled")),
+                        String::new(),
+                    )
+                }
+            }
+        }
+    }
+}
+
+impl Drop for Compilations {
+    /// Compilations not started yet are cancelled, e.g. when the evaluation is aborted. Running
+    /// ones are waited for.
+    fn drop(&mut self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+/// Compiles the agent in `dir`: with its `build:` command if it declares a `run:` command, with
+/// cargo otherwise
+fn compile_agent(dir: &Path, options: &CompileOptions) -> CompilationResult {
+    if config_file_utils::get_run(dir).is_ok_and(|run| run.is_some()) {
+        // their build and run commands could download or use anything
+        if options.offline || options.allowed_crates.is_some() {
+            let e = anyhow::anyhow!(
+                "agents declaring a `run:` command are not allowed with offline compilation or allowed crates"
+            );
+            error!("compilation error: {e}");
+            return (Err(e), String::new());
+        }
+        return build_run_agent(dir, true, options.timeout);
+    }
+    // parsing errors are reported when collecting the configs
+    let bin = config_file_utils::get_bin(dir)
+        .unwrap_or_else(|_| config_file_utils::DEFAULT_BIN.to_string());
+    agent_compiler::compile_single_agent(dir, &bin, options)
+}
+
+/// Builds an agent declaring a `run:` command with its `build:` command (if any, and only if
+/// `compile`) within `timeout`, and returns its directory, which it is launched from
+fn build_run_agent(
+    dir: &Path,
+    compile: bool,
+    timeout: Option<Duration>,
+) -> (anyhow::Result<PathBuf>, String) {
+    let dir = match dir.canonicalize() {
+        Ok(dir) => dir,
+        Err(e) => return (Err(e).context("could not resolve directory"), String::new()),
+    };
+    let build = if compile {
+        config_file_utils::get_build(&dir)
+    } else {
+        Ok(None)
+    };
+    match build {
+        Ok(Some(build)) => {
+            let (res, output) = agent_compiler::build_with_command(&dir, &build, timeout);
+            (res.map(|()| dir), output)
+        }
+        Ok(None) => (Ok(dir), String::new()),
+        Err(e) => (
+            Err(anyhow::anyhow!("invalid build command: {e}")),
+            String::new(),
+        ),
+    }
+}
+
 #[instrument]
 fn collect_binary(dir: &Path) -> anyhow::Result<PathBuf> {
     check_dir_integrity(dir)?;
@@ -317,3 +763,680 @@ fn collect_binary(dir: &Path) -> anyhow::Result<PathBuf> {
     }
     bail!("binary not found")
 }
+
+#[cfg(test)]
+mod agent_collector_tests {
+    use super::*;
+
+    fn make_temp_dir(name: &str) -> PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("ai_tournament_{name}_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_empty_directory() {
+        let dir = make_temp_dir("empty_agent_dir");
+        let config = Configuration::new().with_verbose(false);
+        assert!(collect_agents(&dir, &config).is_err());
+
+        let config = config.with_allow_empty_agent_dir(true);
+        assert!(collect_agents(&dir, &config).unwrap().is_empty());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_append_logs() {
+        let dir = make_temp_dir("append_logs");
+        fs::write(dir.join("previous_run.txt"), "").unwrap();
+        // the log directory is only emptied once the configuration is complete
+        let config = Configuration::new()
+            .with_verbose(false)
+            .with_log(&dir)
+            .with_append_logs(true);
+        config.clear_log_dir();
+        assert!(dir.join("previous_run.txt").exists());
+        fs::remove_file(dir.join("previous_run.txt")).unwrap();
+
+        let first = create_log_subdir(&config, "agent");
+        fs::write(first.join("match_1.txt"), "first run").unwrap();
+        let second = create_log_subdir(&config, "agent");
+        fs::write(second.join("match_1.txt"), "second run").unwrap();
+
+        assert_ne!(first, second);
+        assert_eq!(first.parent(), Some(dir.join("agent").as_path()));
+        assert_eq!(second.parent(), first.parent());
+        assert_eq!(
+            fs::read_to_string(first.join("match_1.txt")).unwrap(),
+            "first run"
+        );
+        assert_eq!(fs::read_dir(dir.join("agent")).unwrap().count(), 2);
+
+        // without the option, the agent directory is emptied
+        let config = config.with_append_logs(false);
+        assert_eq!(create_log_subdir(&config, "agent"), dir.join("agent"));
+        assert_eq!(fs::read_dir(dir.join("agent")).unwrap().count(), 0);
+        config.clear_log_dir();
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 0);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_missing_directory() {
+        let dir = std::env::temp_dir().join("ai_tournament_this_directory_does_not_exist");
+        let config = Configuration::new()
+            .with_verbose(false)
+            .with_allow_empty_agent_dir(true);
+        assert!(collect_agents(&dir, &config).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_unreadable_directory() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = make_temp_dir("unreadable_agent_dir");
+        fs::create_dir(dir.join("some_agent")).unwrap();
+        fs::set_permissions(&dir, fs::Permissions::from_mode(0o000)).unwrap();
+
+        // permissions are not enforced for privileged users (e.g. root in CI containers)
+        if fs::read_dir(&dir).is_err() {
+            let config = Configuration::new().with_verbose(false);
+            assert!(collect_agents(&dir, &config).is_err());
+        }
+
+        fs::set_permissions(&dir, fs::Permissions::from_mode(0o755)).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_keep_binary() {
+        let dir = make_temp_dir("keep_binary");
+        let agent_dir = dir.join("my_agent");
+        fs::create_dir_all(agent_dir.join("target/release")).unwrap();
+        fs::write(agent_dir.join("target/release/eval"), "binary").unwrap();
+        fs::write(agent_dir.join("config.yml"), "eval: default\n").unwrap();
+
+        let output_dir = dir.join("output");
+        keep_binary(
+            &output_dir,
+            "my_agent",
+            &agent_dir.join("target/release/eval"),
+            &agent_dir,
+        )
+        .unwrap();
+
+        let kept = output_dir.join("my_agent");
+        assert_eq!(fs::read_to_string(kept.join("my_agent")).unwrap(), "binary");
+        assert_eq!(
+            fs::read_to_string(kept.join("config.yml")).unwrap(),
+            "eval: default\n"
+        );
+        // the kept directory can be collected without compiling
+        assert_eq!(collect_binary(&kept).unwrap(), kept.join("my_agent"));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_self_test_path() {
+        let dir = make_temp_dir("self_test_path");
+        let agent_dir = dir.join("my_agent");
+        fs::create_dir(&agent_dir).unwrap();
+        fs::write(agent_dir.join("agent_binary"), "").unwrap();
+        fs::write(
+            agent_dir.join("config.yaml"),
+            "eval: default\nconfigs:\n  - default: \"--fast\"\n",
+        )
+        .unwrap();
+
+        let config = Configuration::new()
+            .with_verbose(false)
+            .with_compile_agents(false)
+            .with_self_test(true)
+            .with_self_test_path(&agent_dir);
+        // `directory` is ignored in self-test mode, except for display purposes
+        let agents = collect_agents(&dir, &config).unwrap();
+        assert_eq!(agents.len(), 1);
+        assert_eq!(agents[0].name, "my_agent");
+        assert_eq!(agents[0].path_to_exe, Some(agent_dir.join("agent_binary")));
+        assert_eq!(agents[0].args, Some(vec!["--fast".to_string()]));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_binary_discovery() {
+        let dir = make_temp_dir("binary_discovery");
+        // binary in a subdirectory, arguments in a text file, no config file
+        fs::create_dir_all(dir.join("bot").join("bin")).unwrap();
+        fs::write(dir.join("bot").join("bin").join("run"), "").unwrap();
+        fs::write(dir.join("bot").join("args.txt"), "--depth 3").unwrap();
+        fs::create_dir(dir.join("broken")).unwrap();
+
+        let config = Configuration::new()
+            .with_verbose(false)
+            .with_compile_agents(false)
+            .with_binary_discovery(|dir| {
+                let args = fs::read_to_string(dir.join("args.txt")).context("no args.txt")?;
+                let args = args.split_whitespace().map(String::from).collect();
+                Ok((dir.join("bin").join("run"), args))
+            });
+        let mut agents = collect_agents(&dir, &config).unwrap();
+        agents.sort_by(|a, b| a.name.cmp(&b.name));
+        assert_eq!(agents.len(), 2);
+
+        assert_eq!(agents[0].name, "bot");
+        assert!(agents[0].error_message.is_none());
+        assert_eq!(
+            agents[0].path_to_exe,
+            Some(dir.join("bot").join("bin").join("run"))
+        );
+        assert_eq!(agents[0].args(), ["--depth", "3"]);
+
+        assert_eq!(agents[1].name, "broken");
+        assert_eq!(
+            agents[1].error_message.as_deref(),
+            Some("agent collection failed: no args.txt")
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_two_yaml_files() {
+        let dir = make_temp_dir("two_yaml");
+        let agent_dir = dir.join("bot");
+        fs::create_dir(&agent_dir).unwrap();
+        fs::write(agent_dir.join("agent_binary"), "").unwrap();
+        fs::write(agent_dir.join("config.yaml"), "eval: default\n").unwrap();
+        fs::write(agent_dir.join("old.yml"), "eval: default\n").unwrap();
+
+        // same error in both modes, found before compiling
+        for compile in [false, true] {
+            let config = Configuration::new()
+                .with_verbose(false)
+                .with_compile_agents(compile);
+            let agents = collect_agents(&dir, &config).unwrap();
+            assert_eq!(agents.len(), 1);
+            assert!(!agents[0].compile);
+            assert_eq!(
+                agents[0].error_message.as_deref(),
+                Some(
+                    "Invalid config: several YAML config files found (config.yaml, old.yml), keep only one"
+                )
+            );
+        }
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_bin_name() {
+        if std::process::Command::new("cargo")
+            .arg("--version")
+            .output()
+            .is_err()
+        {
+            return;
+        }
+        let dir = make_temp_dir("bin_name");
+        let agent_dir = dir.join("bot_agent");
+        fs::create_dir_all(agent_dir.join("src")).unwrap();
+        fs::write(
+            agent_dir.join("Cargo.toml"),
+            "[package]\nname = \"bot_agent\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n\
+             [[bin]]\nname = \"bot\"\npath = \"src/main.rs\"\n",
+        )
+        .unwrap();
+        fs::write(agent_dir.join("src/main.rs"), "fn main() {}\n").unwrap();
+        fs::write(
+            agent_dir.join("config.yaml"),
+            "bin: bot\neval: default\nconfigs:\n  - default: \"\"\n",
+        )
+        .unwrap();
+
+        let config = Configuration::new().with_verbose(false);
+        let agents = collect_agents(&dir, &config).unwrap();
+        assert_eq!(agents.len(), 1);
+        assert!(agents[0].compile, "{:?}", agents[0].error_message);
+        assert_eq!(
+            agents[0].path_to_exe,
+            Some(agent_dir.join("target/release/bot"))
+        );
+        assert!(agent_dir.join("target/release/bot").is_file());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_fail_on_compile_error() {
+        if std::process::Command::new("cargo")
+            .arg("--version")
+            .output()
+            .is_err()
+        {
+            return;
+        }
+        let dir = make_temp_dir("fail_on_compile_error");
+        let agent_dir = dir.join("broken");
+        fs::create_dir_all(agent_dir.join("src")).unwrap();
+        fs::write(
+            agent_dir.join("Cargo.toml"),
+            "[package]\nname = \"broken\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n\
+             [[bin]]\nname = \"eval\"\npath = \"src/main.rs\"\n",
+        )
+        .unwrap();
+        fs::write(agent_dir.join("src/main.rs"), "fn main() {\n").unwrap();
+        fs::write(
+            agent_dir.join("config.yaml"),
+            "eval: default\nconfigs:\n  - default: \"\"\n",
+        )
+        .unwrap();
+
+        // lenient by default: the agent is reported, and skipped
+        let config = Configuration::new().with_verbose(false);
+        let agents = collect_agents(&dir, &config).unwrap();
+        assert_eq!(agents.len(), 1);
+        assert!(!agents[0].compile);
+
+        let config = config.with_fail_on_compile_error(true);
+        let err = collect_agents(&dir, &config).unwrap_err();
+        assert!(
+            err.to_string().contains("'broken' failed to compile"),
+            "{err}"
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_team() {
+        let dir = make_temp_dir("team");
+        for (name, yaml) in [
+            ("no_team", "eval: default\nconfigs:\n  - default: \"\"\n"),
+            (
+                "after_configs",
+                "eval: default\nconfigs:\n  - default: \"\"\nteam: red\n",
+            ),
+            (
+                "before_eval",
+                "team:  blue \neval: default\nconfigs:\n  - default: \"\"\n",
+            ),
+            (
+                "malformed",
+                "eval: default\nconfigs:\n  - default: \"\"\nteam: [red, blue]\n",
+            ),
+        ] {
+            fs::create_dir(dir.join(name)).unwrap();
+            fs::write(dir.join(name).join("agent_binary"), "").unwrap();
+            fs::write(dir.join(name).join("config.yaml"), yaml).unwrap();
+        }
+
+        let config = Configuration::new()
+            .with_verbose(false)
+            .with_compile_agents(false);
+        let agents = collect_agents(&dir, &config).unwrap();
+        let agent = |name: &str| agents.iter().find(|a| a.name == name).unwrap();
+        let team = |name: &str| {
+            let agent = agent(name);
+            assert!(agent.error_message.is_none());
+            agent.team.clone()
+        };
+        assert_eq!(team("no_team"), None);
+        assert_eq!(team("after_configs"), Some("red".to_string()));
+        assert_eq!(team("before_eval"), Some("blue".to_string()));
+        let error = agent("malformed").error_message.as_deref().unwrap();
+        assert!(error.starts_with("Invalid config"), "{error}");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_time_multiplier() {
+        let dir = make_temp_dir("time_multiplier");
+        for (name, yaml) in [
+            ("default", "eval: default\nconfigs:\n  - default: \"\"\n"),
+            (
+                "python",
+                "eval: default\nconfigs:\n  - default: \"\"\ntime_multiplier: 2\n",
+            ),
+            (
+                "negative",
+                "eval: default\nconfigs:\n  - default: \"\"\ntime_multiplier: -1\n",
+            ),
+        ] {
+            fs::create_dir(dir.join(name)).unwrap();
+            fs::write(dir.join(name).join("agent_binary"), "").unwrap();
+            fs::write(dir.join(name).join("config.yaml"), yaml).unwrap();
+        }
+
+        let config = Configuration::new()
+            .with_verbose(false)
+            .with_compile_agents(false);
+        let agents = collect_agents(&dir, &config).unwrap();
+        let agent = |name: &str| agents.iter().find(|a| a.name == name).unwrap();
+        assert_eq!(agent("default").time_multiplier, 1.0);
+        assert_eq!(agent("python").time_multiplier, 2.0);
+        assert_eq!(
+            agent("python").scale_time(std::time::Duration::from_millis(150)),
+            std::time::Duration::from_millis(300)
+        );
+        assert!(agent("negative").error_message.is_some());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_eval_config_override() {
+        let dir = make_temp_dir("eval_config");
+        fs::create_dir(dir.join("bot")).unwrap();
+        fs::write(dir.join("bot").join("agent_binary"), "").unwrap();
+        fs::write(
+            dir.join("bot").join("config.yaml"),
+            "eval: default\nconfigs:\n  - default: \"--slow\"\n  - fast: \"--fast\"\n",
+        )
+        .unwrap();
+        let args = |config: Configuration| {
+            let config = config.with_verbose(false).with_compile_agents(false);
+            let agents = collect_agents(&dir, &config).unwrap();
+            assert!(agents[0].error_message.is_none());
+            agents[0].args().to_vec()
+        };
+
+        assert_eq!(args(Configuration::new()), ["--slow"]);
+        // override > `eval:` key
+        assert_eq!(
+            args(Configuration::new().with_eval_config("fast")),
+            ["--fast"]
+        );
+        assert_eq!(
+            args(Configuration::new().with_eval_config("default")),
+            ["--slow"]
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_detect_duplicates() {
+        let dir = make_temp_dir("duplicates");
+        for (name, binary) in [("bot_a", "same"), ("bot_b", "same"), ("bot_c", "other")] {
+            fs::create_dir(dir.join(name)).unwrap();
+            fs::write(dir.join(name).join("agent_binary"), binary).unwrap();
+            fs::write(
+                dir.join(name).join("config.yaml"),
+                "eval: default\nconfigs:\n  - default: \"\"\n",
+            )
+            .unwrap();
+        }
+
+        let config = Configuration::new()
+            .with_verbose(false)
+            .with_compile_agents(false);
+        let agents = collect_agents(&dir, &config).unwrap();
+        assert!(agents.iter().all(|a| a.compile));
+
+        let agents = collect_agents(&dir, &config.with_detect_duplicates(true)).unwrap();
+        let agent = |name: &str| agents.iter().find(|a| a.name == name).unwrap();
+        assert!(agent("bot_a").compile);
+        assert!(!agent("bot_b").compile);
+        assert_eq!(
+            agent("bot_b").error_message.as_deref(),
+            Some("duplicate of 'bot_a'")
+        );
+        assert!(agent("bot_c").compile);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_command_template() {
+        let dir = make_temp_dir("command_template");
+        for (name, command) in [
+            (
+                "valid",
+                "command: python3 {exe} --port={port} {budget_us} {args}",
+            ),
+            ("no_port", "command: \"python3 {exe}\""),
+            // YAML values starting with `{` must be quoted
+            ("unknown", "command: \"{python} {exe} {port}\""),
+            ("glued_args", "command: \"{exe} {port} --x{args}\""),
+            ("seeded", "command: \"{exe} {port} {seed} {args}\""),
+            ("glued_seed", "command: \"{exe} {port} --seed={seed}\""),
+        ] {
+            fs::create_dir(dir.join(name)).unwrap();
+            fs::write(dir.join(name).join("agent.py"), "").unwrap();
+            fs::write(
+                dir.join(name).join("config.yaml"),
+                format!("{command}\neval: default\nconfigs:\n  - default: \"--fast -v\"\n"),
+            )
+            .unwrap();
+        }
+
+        let config = Configuration::new()
+            .with_verbose(false)
+            .with_compile_agents(false);
+        let agents = collect_agents(&dir, &config).unwrap();
+        let agent = |name: &str| agents.iter().find(|a| a.name == name).unwrap();
+
+        assert_eq!(
+            agent("valid").expand_command("agent.py", "1234", "5000", "100", None),
+            vec!["python3", "agent.py", "--port=1234", "5000", "--fast", "-v"]
+        );
+        assert_eq!(
+            agent("seeded").expand_command("agent.py", "1234", "5000", "100", Some("7")),
+            vec!["agent.py", "1234", "7", "--fast", "-v"]
+        );
+        assert_eq!(
+            agent("seeded").expand_command("agent.py", "1234", "5000", "100", None),
+            vec!["agent.py", "1234", "--fast", "-v"]
+        );
+        for name in ["no_port", "unknown", "glued_args", "glued_seed"] {
+            assert!(!agent(name).compile, "{name} should be rejected");
+        }
+
+        // without template, the command keeps the historical argument positions
+        let legacy = Agent::new("legacy".into(), None, None, 0, Some(vec!["-v".into()]));
+        assert_eq!(
+            legacy.expand_command("bin", "1234", "5000", "100", None),
+            vec!["bin", "1234", "5000", "100", "-v"]
+        );
+        // the seed of the match comes right after the action timeout
+        assert_eq!(
+            legacy.expand_command("bin", "1234", "5000", "100", Some("42")),
+            vec!["bin", "1234", "5000", "100", "42", "-v"]
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_run_command() {
+        let dir = make_temp_dir("run_command");
+        for (name, keys) in [
+            ("python", "run: python3 agent.py\nbuild: touch built"),
+            ("unbuilt", "run: \"python3 agent.py\""),
+            ("failed_build", "run: ./agent\nbuild: false"),
+            ("placeholder", "run: python3 agent.py {port}"),
+            (
+                "both",
+                "run: python3 agent.py\ncommand: python3 {exe} {port}",
+            ),
+            ("build_only", "build: make"),
+        ] {
+            fs::create_dir(dir.join(name)).unwrap();
+            fs::write(dir.join(name).join("agent.py"), "").unwrap();
+            fs::write(dir.join(name).join("helper.py"), "").unwrap();
+            fs::write(
+                dir.join(name).join("config.yaml"),
+                format!("{keys}\neval: default\nconfigs:\n  - default: \"--fast\"\n"),
+            )
+            .unwrap();
+        }
+
+        let config = Configuration::new().with_verbose(false);
+        let agents = collect_agents(&dir, &config).unwrap();
+        let agent = |name: &str| agents.iter().find(|a| a.name == name).unwrap();
+
+        let python = agent("python");
+        assert!(python.compile);
+        // built in its directory, without cargo
+        assert!(dir.join("python").join("built").exists());
+        let agent_dir = dir.join("python").canonicalize().unwrap();
+        assert_eq!(python.working_dir.as_ref(), Some(&agent_dir));
+        assert_eq!(
+            python.expand_command("unused", "1234", "5000", "100", Some("7")),
+            vec!["python3", "agent.py", "1234", "5000", "100", "7", "--fast"]
+        );
+        assert!(agent("unbuilt").compile);
+        assert!(agent("failed_build")
+            .error_message
+            .as_ref()
+            .unwrap()
+            .contains("Build error"));
+        for name in ["placeholder", "both", "build_only"] {
+            assert!(!agent(name).compile, "{name} should be rejected");
+        }
+
+        // without compilation, the directory is collected as is: the build command is not run
+        fs::remove_file(dir.join("python").join("built")).unwrap();
+        let config = config.with_compile_agents(false);
+        let agents = collect_agents(&dir, &config).unwrap();
+        let python = agents.iter().find(|a| a.name == "python").unwrap();
+        assert!(python.compile);
+        assert!(!dir.join("python").join("built").exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_compile_timeout() {
+        let dir = make_temp_dir("compile_timeout");
+        fs::create_dir(dir.join("hanging")).unwrap();
+        fs::write(
+            dir.join("hanging").join("config.yaml"),
+            "run: ./agent\nbuild: sleep 30\neval: default\nconfigs:\n  - default: \"\"\n",
+        )
+        .unwrap();
+
+        let config = Configuration::new()
+            .with_verbose(false)
+            .with_compile_timeout(Duration::from_millis(300));
+        let start = std::time::Instant::now();
+        let agents = collect_agents(&dir, &config).unwrap();
+        assert!(start.elapsed() < Duration::from_secs(10));
+        assert!(!agents[0].compile);
+        assert!(agents[0]
+            .error_message
+            .as_ref()
+            .unwrap()
+            .contains("timed out after 0.3s"));
+
+        let config = config.with_fail_on_compile_error(true);
+        assert!(collect_agents(&dir, &config).is_err());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_allowed_crates() {
+        let dir = make_temp_dir("allowed_crates");
+        for name in ["locked", "unlocked"] {
+            let agent_dir = dir.join(name);
+            fs::create_dir_all(agent_dir.join("src")).unwrap();
+            fs::write(agent_dir.join("src/main.rs"), "fn main() {}\n").unwrap();
+            fs::write(
+                agent_dir.join("config.yaml"),
+                "eval: default\nconfigs:\n  - default: \"\"\n",
+            )
+            .unwrap();
+        }
+        fs::write(
+            dir.join("locked").join("Cargo.lock"),
+            "[[package]]\nname = \"locked\"\nversion = \"0.1.0\"\n\n\
+             [[package]]\nname = \"rand\"\nversion = \"0.8.5\"\nsource = \"registry+https://github.com/rust-lang/crates.io-index\"\n",
+        )
+        .unwrap();
+        // local packages have no source: only their manifest tells where they are
+        let outside = dir.join("outside");
+        fs::create_dir_all(outside.join("src")).unwrap();
+        fs::write(outside.join("src/main.rs"), "fn main() {}\n").unwrap();
+        fs::write(
+            outside.join("config.yaml"),
+            "eval: default\nconfigs:\n  - default: \"\"\n",
+        )
+        .unwrap();
+        fs::write(
+            outside.join("Cargo.toml"),
+            "[package]\nname = \"outside\"\n\n[dependencies]\nother = { path = \"../locked\" }\n",
+        )
+        .unwrap();
+        fs::write(
+            outside.join("Cargo.lock"),
+            "[[package]]\nname = \"outside\"\nversion = \"0.1.0\"\n\n\
+             [[package]]\nname = \"other\"\nversion = \"0.1.0\"\n",
+        )
+        .unwrap();
+        // its commands are not checked
+        let script = dir.join("script");
+        fs::create_dir_all(&script).unwrap();
+        fs::write(
+            script.join("config.yaml"),
+            "run: ./agent\nbuild: touch built\neval: default\nconfigs:\n  - default: \"\"\n",
+        )
+        .unwrap();
+
+        // rejected before cargo is run
+        let config = Configuration::new()
+            .with_verbose(false)
+            .with_allowed_crates(vec!["serde".into()]);
+        let agents = collect_agents(&dir, &config).unwrap();
+        let error = |name: &str| {
+            let agent = agents.iter().find(|a| a.name == name).unwrap();
+            agent.error_message.clone().unwrap()
+        };
+        assert!(error("locked").contains("Forbidden dependencies: rand"));
+        assert!(error("unlocked").contains("Cargo.lock is required"));
+        assert!(error("outside").contains("'../locked'"));
+        assert!(error("script").contains("`run:` command"));
+        assert!(!script.join("built").exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_parallel_compilation() {
+        let dir = make_temp_dir("parallel_compilation");
+        let names = ["a", "b", "c", "d", "e", "f"];
+        for name in names {
+            fs::create_dir(dir.join(name)).unwrap();
+            let build = if name == "c" { "false" } else { "touch built" };
+            fs::write(
+                dir.join(name).join("config.yaml"),
+                format!(
+                    "run: ./agent\nbuild: {build}\neval: default\nconfigs:\n  - default: \"\"\n"
+                ),
+            )
+            .unwrap();
+        }
+
+        let config = Configuration::new().with_verbose(false);
+        let agents = collect_agents(&dir, &config).unwrap();
+        assert_eq!(agents.len(), names.len());
+        // ids follow the order of the returned agents
+        for (i, agent) in agents.iter().enumerate() {
+            assert_eq!(agent.id, i as u32 + 1);
+            assert_eq!(agent.compile, agent.name != "c", "{}", agent.name);
+            if agent.compile {
+                assert!(dir.join(&agent.name).join("built").exists());
+            }
+        }
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,13 +1,16 @@
 use std::{
+    collections::HashSet,
     fs::File,
+    path::Path,
     process::Child,
+    sync::OnceLock,
     time::{Duration, Instant},
 };
 
-use anyhow::{self, Context};
+use anyhow::{self, bail, Context};
 use cgroups_rs::Cgroup;
 
-use super::create_process;
+use super::{create_process, StderrMode};
 
 pub fn get_current_user_id() -> anyhow::Result<String> {
     let output = std::process::Command::new("id")
@@ -23,6 +26,90 @@ pub fn get_cgroup_path(user_id: &str, group_name: &str) -> String {
     format!("user.slice/user-{user_id}.slice/user@{user_id}.service/{group_name}")
 }
 
+/// Parent of the cgroups created for agents, relative to the cgroup root.
+///
+/// Defaults to the systemd user service of the current user. If it does not exist (containers,
+/// non-systemd systems), falls back to the cgroup of the current process, after moving the process
+/// into a leaf child cgroup (see [`move_into_leaf_cgroup`]).
+pub fn get_cgroup_parent(custom_parent: Option<&str>) -> anyhow::Result<String> {
+    if let Some(parent) = custom_parent {
+        return Ok(parent.trim_matches('/').to_string());
+    }
+    let user_id = get_current_user_id().context("could not get user id")?;
+    let systemd_parent = get_cgroup_path(&user_id, "");
+    let systemd_parent = systemd_parent.trim_end_matches('/');
+    if std::path::Path::new("/sys/fs/cgroup")
+        .join(systemd_parent)
+        .is_dir()
+    {
+        return Ok(systemd_parent.to_string());
+    }
+    // the process leaves its cgroup on the first call: later calls must not see the leaf
+    static OWN_CGROUP: OnceLock<Result<Option<String>, String>> = OnceLock::new();
+    let own_cgroup = OWN_CGROUP.get_or_init(|| {
+        let own_cgroup = std::fs::read_to_string("/proc/self/cgroup")
+            .ok()
+            .and_then(|content| parse_own_cgroup(&content));
+        if let Some(own_cgroup) = &own_cgroup {
+            tracing::debug!("{systemd_parent} not found, creating cgroups in {own_cgroup} instead");
+            move_into_leaf_cgroup(Path::new("/sys/fs/cgroup"), own_cgroup)
+                .map_err(|e| format!("{e:#}"))?;
+        }
+        Ok(own_cgroup)
+    });
+    match own_cgroup {
+        Ok(Some(own_cgroup)) => Ok(own_cgroup.clone()),
+        Ok(None) => Ok(systemd_parent.to_string()),
+        Err(e) => Err(anyhow::anyhow!("{e}")),
+    }
+}
+
+/// Name of the leaf cgroup the evaluator moves into, see [`move_into_leaf_cgroup`]
+const EVALUATOR_CGROUP: &str = "ai_tournament_evaluator";
+
+/// Moves the current process from `own_cgroup` (relative to the cgroup `root`) into its child
+/// [`EVALUATOR_CGROUP`], so that the
+/// agents' cgroups can be created in `own_cgroup`: cgroup v2 forbids a (non-root) cgroup to both
+/// contain processes and give resource controllers to its children.
+///
+/// Fails if other processes remain in `own_cgroup` (e.g. the shell that started the evaluator): a
+/// cgroup parent must then be given with `EVAL_CGROUP_PARENT` or
+/// [`Configuration::with_cgroup_parent`](crate::configuration::Configuration::with_cgroup_parent).
+fn move_into_leaf_cgroup(root: &Path, own_cgroup: &str) -> anyhow::Result<()> {
+    // cgroup v1 hierarchies have no such rule, and neither has the root cgroup
+    if own_cgroup.is_empty() || !root.join("cgroup.controllers").exists() {
+        return Ok(());
+    }
+    let own_dir = root.join(own_cgroup);
+    let leaf_dir = own_dir.join(EVALUATOR_CGROUP);
+    std::fs::create_dir_all(&leaf_dir)
+        .with_context(|| format!("could not create cgroup {}", leaf_dir.display()))?;
+    std::fs::write(
+        leaf_dir.join("cgroup.procs"),
+        std::process::id().to_string(),
+    )
+    .with_context(|| format!("could not move the evaluator to {}", leaf_dir.display()))?;
+    let remaining = std::fs::read_to_string(own_dir.join("cgroup.procs"))
+        .with_context(|| format!("could not read processes of {}", own_dir.display()))?;
+    if !remaining.trim().is_empty() {
+        bail!(
+            "agents cannot be contained in cgroup {own_cgroup}, which has other processes than the \
+            evaluator ({}): set a cgroup parent with EVAL_CGROUP_PARENT or \
+            Configuration::with_cgroup_parent",
+            remaining.split_whitespace().collect::<Vec<_>>().join(", ")
+        );
+    }
+    Ok(())
+}
+
+/// Path of the cgroup v2 of a process, from the content of `/proc/<pid>/cgroup`
+fn parse_own_cgroup(content: &str) -> Option<String> {
+    content
+        .lines()
+        .find_map(|line| line.strip_prefix("0::"))
+        .map(|path| path.trim().trim_matches('/').to_string())
+}
+
 /// Create a cgroup at `path`.
 ///
 /// The cgroup will have the provided limitations.
@@ -30,6 +117,7 @@ pub fn get_cgroup_path(user_id: &str, group_name: &str) -> String {
 /// * `max_memory` - Maximum available memory in Bytes. Non-positive means no restriction.
 /// * `max_pids` - Maximum number of PIDS inside the cgroup at any time. Non-positive means no restriction.
 /// * `cpus` - which cpus the members can run one. Uses comma separated cpu ranges ("1-5,7", "1,3,4", ...). Empty string means no restriction.
+/// * `max_write_bps` - Maximum write rate in Bytes per second to the device backing the working directory. Non-positive means no restriction.
 ///
 /// # Errors
 ///
@@ -39,6 +127,7 @@ pub fn create_cgroup(
     max_memory: i64,
     max_pids: i64,
     cpus: &str,
+    max_write_bps: i64,
 ) -> anyhow::Result<cgroups_rs::Cgroup> {
     let mut builder = cgroups_rs::cgroup_builder::CgroupBuilder::new(path);
     if max_memory > 0 {
@@ -53,11 +142,115 @@ pub fn create_cgroup(
     if !cpus.is_empty() {
         builder = builder.cpu().cpus(cpus.to_string()).done();
     }
+    if max_write_bps > 0 {
+        let (major, minor) =
+            working_dir_device().context("could not find device of working directory")?;
+        builder = builder
+            .blkio()
+            .throttle_bps()
+            .write(major, minor, max_write_bps as u64)
+            .done();
+    }
     builder
         .build(cgroups_rs::hierarchies::auto())
         .context("could not create cgroup")
 }
 
+/// Creates a cgroup with a new name under `parent` (see [`create_cgroup`] for the limits).
+///
+/// Names contain the PID of the evaluator, but a cgroup of the same name may have been left by a
+/// previous run whose PID was recycled (e.g. after a crash that skipped cleanup). Such a stale
+/// cgroup is deleted if it is empty, and skipped otherwise: its processes must not share the
+/// limits of the new agent. Another name is also tried when the creation fails.
+fn create_unique_cgroup(
+    parent: &str,
+    max_memory: i64,
+    max_pids: i64,
+    cpus: &str,
+    max_write_bps: i64,
+) -> anyhow::Result<Cgroup> {
+    const MAX_ATTEMPTS: usize = 3;
+    static COUNTER: std::sync::atomic::AtomicU32 = std::sync::atomic::AtomicU32::new(1);
+
+    let mut last_error = None;
+    for _ in 0..MAX_ATTEMPTS {
+        // generate a new cgroup name for each Limited Process
+        let group_name = format!(
+            "CGROUP_MANAGER_{}_{}",
+            std::process::id(),
+            COUNTER.fetch_add(1, std::sync::atomic::Ordering::Relaxed)
+        );
+        let path = if parent.is_empty() {
+            group_name
+        } else {
+            format!("{parent}/{group_name}")
+        };
+        if !reclaim_stale_cgroup(&std::path::Path::new("/sys/fs/cgroup").join(&path)) {
+            continue;
+        }
+        match create_cgroup(&path, max_memory, max_pids, cpus, max_write_bps) {
+            Ok(group) => return Ok(group),
+            Err(e) => {
+                tracing::warn!("could not create cgroup {path}, trying another name: {e:#}");
+                last_error = Some(e);
+            }
+        }
+    }
+    match last_error {
+        Some(e) => Err(e),
+        None => bail!("could not find a free cgroup name in {MAX_ATTEMPTS} attempts"),
+    }
+}
+
+/// Deletes the cgroup directory `dir` if it exists and is empty. Returns false if it is still
+/// there (processes or child cgroups remain).
+fn reclaim_stale_cgroup(dir: &std::path::Path) -> bool {
+    if !dir.exists() {
+        return true;
+    }
+    // `rmdir` on a cgroup only succeeds when it has no process and no child cgroup
+    match std::fs::remove_dir(dir) {
+        Ok(()) => {
+            tracing::info!("stale cgroup {} reclaimed", dir.display());
+            true
+        }
+        Err(e) => {
+            tracing::warn!("stale cgroup {} is still in use: {e}", dir.display());
+            false
+        }
+    }
+}
+
+/// Major and minor numbers of the disk backing the current working directory (inherited by agents).
+///
+/// `io.max` only accepts whole disks, so partitions are resolved to their parent disk.
+fn working_dir_device() -> anyhow::Result<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+
+    let dir = std::env::current_dir().context("could not get current directory")?;
+    let dev = std::fs::metadata(&dir)
+        .with_context(|| format!("could not read metadata of {dir:?}"))?
+        .dev();
+    // glibc encoding of dev_t
+    let major = ((dev >> 32) & 0xffff_f000) | ((dev >> 8) & 0xfff);
+    let minor = ((dev >> 12) & 0xffff_ff00) | (dev & 0xff);
+    if major == 0 {
+        bail!("{dir:?} is not backed by a block device (virtual or network filesystem)");
+    }
+
+    let sys_path = format!("/sys/dev/block/{major}:{minor}");
+    if !std::path::Path::new(&sys_path).join("partition").exists() {
+        return Ok((major, minor));
+    }
+    let parent = std::fs::read_to_string(format!("{sys_path}/../dev"))
+        .with_context(|| format!("could not find parent disk of partition {major}:{minor}"))?;
+    let (major, minor) = parent
+        .trim()
+        .split_once(':')
+        .context("invalid device number")?;
+    Ok((major.parse()?, minor.parse()?))
+}
+
 #[derive(Debug)]
 pub struct TimeoutError {}
 
@@ -69,6 +262,22 @@ impl std::fmt::Display for TimeoutError {
 
 impl std::error::Error for TimeoutError {}
 
+/// Sends `SIGKILL` to `pids`
+fn kill_processes(pids: &[cgroups_rs::CgroupPid]) -> anyhow::Result<()> {
+    if pids.is_empty() {
+        return Ok(());
+    }
+    std::process::Command::new("kill")
+        .arg("-KILL")
+        .args(pids.iter().map(|pid| pid.pid.to_string()))
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .status()
+        .context("could not run kill")?;
+    // processes may have exited in the meantime: only the cleanup wait tells if they are gone
+    Ok(())
+}
+
 pub fn wait_for_process_cleanup(
     cgroup: &cgroups_rs::Cgroup,
     pid: u64,
@@ -88,11 +297,13 @@ pub fn wait_for_process_cleanup(
 pub fn create_process_in_cgroup(
     command: &str,
     args: &[String],
+    envs: &[(&str, &str)],
+    current_dir: Option<&Path>,
     group: &cgroups_rs::Cgroup,
-    allow_stderr: bool,
+    stderr: StderrMode,
     log_file: &Option<File>,
 ) -> anyhow::Result<std::process::Child> {
-    let mut child = create_process(command, args, allow_stderr, log_file)?;
+    let mut child = create_process(command, args, envs, current_dir, stderr, log_file)?;
 
     let pid = child.id() as u64;
     let addition = group.add_task_by_tgid(cgroups_rs::CgroupPid { pid });
@@ -120,29 +331,29 @@ pub struct LimitedProcess {
 }
 
 impl LimitedProcess {
+    /// Launches a process in a new cgroup, created under `cgroup_parent` (see
+    /// [`get_cgroup_parent`]), from `current_dir` if given.
+    #[allow(clippy::too_many_arguments)]
     pub fn launch(
         command: &str,
         args: &[String],
+        envs: &[(&str, &str)],
+        current_dir: Option<&Path>,
         max_memory: i64,
         cpus: &str,
-        allow_stderr: bool,
+        max_write_bps: i64,
+        stderr: StderrMode,
         log_file: &Option<File>,
+        cgroup_parent: Option<&str>,
     ) -> anyhow::Result<LimitedProcess> {
-        static COUNTER: std::sync::atomic::AtomicU32 = std::sync::atomic::AtomicU32::new(1); // lazy cell ? (if multiple evaluations at the same time !)
-        let user_id = get_current_user_id().context("could not get user id")?;
-        // generate a new cgroup name for each Limited Process
-        let group_name = "CGROUP_MANAGER_".to_owned()
-            + &COUNTER
-                .fetch_add(1, std::sync::atomic::Ordering::Relaxed)
-                .to_string();
-        let path = get_cgroup_path(&user_id, &group_name);
-        let group =
-            create_cgroup(&path, max_memory, 100, cpus).context("could not create cgroup")?;
-        let child = create_process_in_cgroup(command, args, &group, allow_stderr, log_file)
-            .with_context(|| {
-                let _ = group.delete();
-                "could not create process in cgroup"
-            })?;
+        let parent = get_cgroup_parent(cgroup_parent)?;
+        let group = create_unique_cgroup(&parent, max_memory, 100, cpus, max_write_bps)?;
+        let child =
+            create_process_in_cgroup(command, args, envs, current_dir, &group, stderr, log_file)
+                .with_context(|| {
+                    let _ = group.delete();
+                    "could not create process in cgroup"
+                })?;
 
         Ok(LimitedProcess {
             child,
@@ -155,7 +366,11 @@ impl LimitedProcess {
         match &mut self.cgroup {
             Some(cgroup) => {
                 self.child.kill().context("could not kill child process")?; // start with (blocking) process kill
-                cgroup.kill().context("could not kill process")?;
+                if cgroup.kill().is_err() {
+                    // `cgroup.kill` only exists on cgroups v2 (Linux 5.14+): kill the remaining
+                    // processes one by one
+                    kill_processes(&cgroup.procs()).context("could not kill process")?;
+                }
                 wait_for_process_cleanup(cgroup, self.child.id() as u64, max_duration)
                     .context("process cleanup timed out")?;
                 // at this point, the process is killed. Even so the cgroup cleanup fail, it is
@@ -178,10 +393,12 @@ impl LimitedProcess {
     pub fn launch_without_container(
         command: &str,
         args: &[String],
-        allow_stderr: bool,
+        envs: &[(&str, &str)],
+        current_dir: Option<&Path>,
+        stderr: StderrMode,
         log_file: &Option<File>,
     ) -> anyhow::Result<LimitedProcess> {
-        let child = create_process(command, args, allow_stderr, log_file)
+        let child = create_process(command, args, envs, current_dir, stderr, log_file)
             .context("could not create process")?;
 
         Ok(LimitedProcess {
@@ -191,6 +408,79 @@ impl LimitedProcess {
         })
     }
 
+    /// Set of cpus the process is actually allowed to run on.
+    ///
+    /// Reads `Cpus_allowed_list` from `/proc/<pid>/status`, or `cpuset.cpus.effective` of the
+    /// process's cgroup if the former is unavailable. Returns `None` if neither could be read.
+    pub fn effective_cpus(&self) -> Option<HashSet<u8>> {
+        let from_status = std::fs::read_to_string(format!("/proc/{}/status", self.child.id()))
+            .ok()
+            .and_then(|status| {
+                status.lines().find_map(|line| {
+                    line.strip_prefix("Cpus_allowed_list:")
+                        .and_then(parse_cpu_list)
+                })
+            });
+        from_status.or_else(|| {
+            let cgroup = self.cgroup.as_ref()?;
+            let path = format!("/sys/fs/cgroup/{}/cpuset.cpus.effective", cgroup.path());
+            parse_cpu_list(&std::fs::read_to_string(path).ok()?)
+        })
+    }
+
+    /// Peak memory usage of the process (and its children) in bytes, read from `memory.peak` of
+    /// its cgroup (Linux 5.19 or later).
+    ///
+    /// Returns `None` if the process runs without container. Must be called before the process
+    /// is killed, which removes its cgroup.
+    pub fn peak_memory_bytes(&self) -> anyhow::Result<Option<u64>> {
+        let Some(cgroup) = &self.cgroup else {
+            return Ok(None);
+        };
+        let path = format!("/sys/fs/cgroup/{}/memory.peak", cgroup.path());
+        let content =
+            std::fs::read_to_string(&path).with_context(|| format!("could not read {path}"))?;
+        let peak = content
+            .trim()
+            .parse()
+            .with_context(|| format!("invalid memory peak '{}' in {path}", content.trim()))?;
+        Ok(Some(peak))
+    }
+
+    /// Number of processes of the cgroup killed by the kernel for exceeding its memory limit,
+    /// read from `memory.events`.
+    ///
+    /// Returns `None` if the process runs without container. Must be called before the process
+    /// is killed, which removes its cgroup.
+    pub fn oom_kills(&self) -> anyhow::Result<Option<u64>> {
+        let Some(cgroup) = &self.cgroup else {
+            return Ok(None);
+        };
+        let path = format!("/sys/fs/cgroup/{}/memory.events", cgroup.path());
+        let content =
+            std::fs::read_to_string(&path).with_context(|| format!("could not read {path}"))?;
+        let kills =
+            parse_oom_kills(&content).with_context(|| format!("no oom_kill counter in {path}"))?;
+        Ok(Some(kills))
+    }
+
+    /// CPU time used by the process (and its children) since its launch, read from `cpu.stat` of
+    /// its cgroup.
+    ///
+    /// Returns `None` if the process runs without container. Must be called before the process
+    /// is killed, which removes its cgroup.
+    pub fn cpu_time_used(&self) -> anyhow::Result<Option<Duration>> {
+        let Some(cgroup) = &self.cgroup else {
+            return Ok(None);
+        };
+        let path = format!("/sys/fs/cgroup/{}/cpu.stat", cgroup.path());
+        let content =
+            std::fs::read_to_string(&path).with_context(|| format!("could not read {path}"))?;
+        let usage =
+            parse_cpu_usage(&content).with_context(|| format!("no usage_usec in {path}"))?;
+        Ok(Some(Duration::from_micros(usage)))
+    }
+
     /// Will print out as much info as possible
     #[allow(dead_code)]
     pub(crate) fn try_debug_cgroup(&mut self) {
@@ -252,6 +542,38 @@ impl Drop for LimitedProcess {
     }
 }
 
+/// `oom_kill` counter of a `memory.events` file
+fn parse_oom_kills(events: &str) -> Option<u64> {
+    events
+        .lines()
+        .find_map(|line| line.strip_prefix("oom_kill "))
+        .and_then(|count| count.trim().parse().ok())
+}
+
+/// `usage_usec` counter of a `cpu.stat` file
+fn parse_cpu_usage(stat: &str) -> Option<u64> {
+    stat.lines()
+        .find_map(|line| line.strip_prefix("usage_usec "))
+        .and_then(|usage| usage.trim().parse().ok())
+}
+
+/// Parse a cpu list as written by the kernel ("0-3,5,7-8").
+fn parse_cpu_list(list: &str) -> Option<HashSet<u8>> {
+    let mut cpus = HashSet::new();
+    for range in list.trim().split(',').filter(|r| !r.is_empty()) {
+        match range.split_once('-') {
+            Some((start, end)) => {
+                let (start, end) = (start.parse::<u8>().ok()?, end.parse::<u8>().ok()?);
+                cpus.extend(start..=end);
+            }
+            None => {
+                cpus.insert(range.parse().ok()?);
+            }
+        }
+    }
+    Some(cpus)
+}
+
 #[cfg(test)]
 mod cgroup_manager_tests {
 
@@ -259,6 +581,22 @@ mod cgroup_manager_tests {
 
     use super::*;
 
+    #[test]
+    fn test_reclaim_stale_cgroup() {
+        // plain directories behave like cgroups for `rmdir`: only empty ones can be removed
+        let dir = std::env::temp_dir().join(format!("ai_tournament_stale_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        assert!(reclaim_stale_cgroup(&dir));
+
+        std::fs::create_dir_all(dir.join("child")).unwrap();
+        assert!(!reclaim_stale_cgroup(&dir));
+        assert!(dir.exists());
+
+        std::fs::remove_dir(dir.join("child")).unwrap();
+        assert!(reclaim_stale_cgroup(&dir));
+        assert!(!dir.exists());
+    }
+
     #[test]
     fn launch_something() {
         use std::process;
@@ -278,6 +616,88 @@ mod cgroup_manager_tests {
         println!("{buffer}");
     }
 
+    #[test]
+    fn test_parse_cpu_list() {
+        assert_eq!(
+            parse_cpu_list("0-2,5,7-8\n"),
+            Some(HashSet::from([0, 1, 2, 5, 7, 8]))
+        );
+        assert_eq!(parse_cpu_list("3"), Some(HashSet::from([3])));
+        assert_eq!(parse_cpu_list(""), Some(HashSet::new()));
+        assert_eq!(parse_cpu_list("0-a"), None);
+    }
+
+    #[test]
+    fn test_parse_oom_kills() {
+        let events = "low 0\nhigh 0\nmax 12\noom 1\noom_kill 1\noom_group_kill 0\n";
+        assert_eq!(parse_oom_kills(events), Some(1));
+        assert_eq!(parse_oom_kills("oom_group_kill 0\n"), None);
+    }
+
+    #[test]
+    fn test_parse_cpu_usage() {
+        let stat = "usage_usec 1520\nuser_usec 1000\nsystem_usec 520\nnr_periods 0\n";
+        assert_eq!(parse_cpu_usage(stat), Some(1520));
+        assert_eq!(parse_cpu_usage("user_usec 1000\n"), None);
+    }
+
+    #[test]
+    fn test_move_into_leaf_cgroup() {
+        // plain directories stand for cgroups: writing `cgroup.procs` does not move anything
+        let root = std::env::temp_dir().join(format!("ai_tournament_leaf_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(root.join("container")).unwrap();
+        std::fs::write(root.join("cgroup.controllers"), "cpu memory").unwrap();
+
+        std::fs::write(root.join("container/cgroup.procs"), "").unwrap();
+        move_into_leaf_cgroup(&root, "container").unwrap();
+        let leaf_procs = root
+            .join("container")
+            .join(EVALUATOR_CGROUP)
+            .join("cgroup.procs");
+        assert_eq!(
+            std::fs::read_to_string(leaf_procs).unwrap(),
+            std::process::id().to_string()
+        );
+
+        std::fs::write(root.join("container/cgroup.procs"), "1\n").unwrap();
+        let error = move_into_leaf_cgroup(&root, "container").unwrap_err();
+        assert!(error.to_string().contains("EVAL_CGROUP_PARENT"), "{error}");
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_parse_own_cgroup() {
+        let content = "12:pids:/user.slice\n0::/system.slice/docker-abc.scope\n";
+        assert_eq!(
+            parse_own_cgroup(content).as_deref(),
+            Some("system.slice/docker-abc.scope")
+        );
+        assert_eq!(parse_own_cgroup("0::/\n").as_deref(), Some(""));
+        assert_eq!(parse_own_cgroup("1:name=systemd:/init.scope\n"), None);
+        assert_eq!(
+            get_cgroup_parent(Some("/my.slice/")).unwrap(),
+            "my.slice".to_string()
+        );
+    }
+
+    #[test]
+    fn test_effective_cpus() {
+        let mut process = LimitedProcess::launch_without_container(
+            "sleep",
+            &["10".to_string()],
+            &[],
+            None,
+            StderrMode::Log,
+            &None,
+        )
+        .expect("could not launch process");
+        let cpus = process.effective_cpus().expect("could not read cpus");
+        assert!(!cpus.is_empty());
+        process.try_kill(Duration::from_secs(1)).unwrap();
+    }
+
     #[test]
     fn test_create_cgroup() {
         //NOTE: future work: implement the Windows equivalent: "Job Object"
@@ -304,7 +724,7 @@ mod cgroup_manager_tests {
 
         println!("Future new group path: {new_group_path}");
 
-        let my_group = create_cgroup(&new_group_path, 1024 * 1024, 3, "1-3,5")
+        let my_group = create_cgroup(&new_group_path, 1024 * 1024, 3, "1-3,5", 0)
             .expect("Could not create cgroup...");
         println!("path: {}", my_group.path());
 
@@ -315,7 +735,7 @@ mod cgroup_manager_tests {
     fn test_create_process_in_cgroup() {
         let id = get_current_user_id().unwrap();
         let path = get_cgroup_path(&id, "rust_group");
-        let group = create_cgroup(&path, 1024 * 1024, 0, "").unwrap();
+        let group = create_cgroup(&path, 1024 * 1024, 0, "", 0).unwrap();
         println!("Cgroup created");
         let process = std::process::Command::new("sleep").arg("10").spawn();
         if let Ok(mut child) = process {
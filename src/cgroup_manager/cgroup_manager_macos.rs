@@ -0,0 +1,254 @@
+//! macOS has no cgroups nor CPU affinity: contained processes are only limited in memory.
+//!
+//! The kernel does not enforce `RLIMIT_AS` nor `RLIMIT_DATA` for memory allocated with `mmap`
+//! (which `malloc` uses for large blocks), so the limit is enforced by a watchdog thread instead:
+//! it polls the memory footprint of the process with `proc_pid_rusage` and kills the process once
+//! the footprint exceeds the limit. The limit is best-effort: a process may briefly go past it
+//! between two polls. CPU affinity and disk I/O limits are not enforced.
+
+use std::{
+    collections::HashSet,
+    fs::File,
+    os::unix::process::CommandExt,
+    path::Path,
+    process::Child,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+use anyhow::{self, Context};
+
+use super::{build_command, create_process, StderrMode};
+
+/// Interval between two measures of the memory footprint of a limited process
+const MEMORY_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+#[derive(Debug)]
+pub struct LimitedProcess {
+    pub child: Child,
+    cleaned_up: bool,
+    memory_watch: Option<Arc<MemoryWatch>>,
+}
+
+/// State shared with the watchdog thread enforcing the memory limit of a process
+#[derive(Debug, Default)]
+struct MemoryWatch {
+    /// Set when the process is killed, to stop the watchdog before its pid can be reused
+    stopped: AtomicBool,
+    peak: AtomicU64,
+    oom_kills: AtomicU64,
+}
+
+impl MemoryWatch {
+    /// Polls the memory footprint of `pid` until the process exits or exceeds `max_memory` bytes,
+    /// in which case it is killed.
+    fn watch(&self, pid: libc::pid_t, max_memory: u64) {
+        while !self.stopped.load(Ordering::Acquire) {
+            let Some(footprint) = memory_footprint(pid) else {
+                return;
+            };
+            self.peak.fetch_max(footprint, Ordering::Relaxed);
+            if footprint > max_memory {
+                // Safety: `kill` has no memory safety requirement; the process is not reaped
+                // before `stopped` is set, so `pid` still designates it
+                unsafe { libc::kill(pid, libc::SIGKILL) };
+                self.oom_kills.fetch_add(1, Ordering::Relaxed);
+                return;
+            }
+            thread::sleep(MEMORY_POLL_INTERVAL);
+        }
+    }
+}
+
+/// Physical memory footprint of a process in bytes, as reported by Activity Monitor, or `None` if
+/// the process has exited.
+fn memory_footprint(pid: libc::pid_t) -> Option<u64> {
+    // Safety: `rusage_info_v2` is plain data, for which zeroed memory is valid
+    let mut info: libc::rusage_info_v2 = unsafe { std::mem::zeroed() };
+    // Safety: `info` is large enough for the `RUSAGE_INFO_V2` flavor
+    let result = unsafe {
+        libc::proc_pid_rusage(
+            pid,
+            libc::RUSAGE_INFO_V2,
+            &mut info as *mut libc::rusage_info_v2 as *mut libc::rusage_info_t,
+        )
+    };
+    (result == 0).then_some(info.ri_phys_footprint)
+}
+
+impl LimitedProcess {
+    /// Launches a process limited to `max_memory` bytes of memory (see the
+    /// [module documentation](self)), from `current_dir` if given. `cpus`, `max_write_bps` and
+    /// `cgroup_parent` are ignored on this platform.
+    #[allow(clippy::too_many_arguments)]
+    pub fn launch(
+        command: &str,
+        args: &[String],
+        envs: &[(&str, &str)],
+        current_dir: Option<&Path>,
+        max_memory: i64,
+        _cpus: &str,
+        _max_write_bps: i64,
+        stderr: StderrMode,
+        log_file: &Option<File>,
+        _cgroup_parent: Option<&str>,
+    ) -> anyhow::Result<LimitedProcess> {
+        let mut cmd = build_command(command, args, envs, current_dir, stderr, log_file)?;
+        if max_memory > 0 {
+            let limit = libc::rlimit {
+                rlim_cur: max_memory as libc::rlim_t,
+                rlim_max: max_memory as libc::rlim_t,
+            };
+            // `setrlimit` still makes small allocations fail, before the watchdog notices them.
+            // Safety: only calls `setrlimit`, which is async-signal-safe, between fork and exec
+            unsafe {
+                cmd.pre_exec(move || {
+                    for resource in [libc::RLIMIT_AS, libc::RLIMIT_DATA] {
+                        if libc::setrlimit(resource, &limit) != 0 {
+                            return Err(std::io::Error::last_os_error());
+                        }
+                    }
+                    Ok(())
+                });
+            }
+        }
+        let child = cmd
+            .spawn()
+            .with_context(|| format!("could not launch '{command}' with a memory limit"))?;
+
+        let memory_watch = (max_memory > 0).then(|| {
+            let watch = Arc::new(MemoryWatch::default());
+            let pid = child.id() as libc::pid_t;
+            let watcher = Arc::clone(&watch);
+            thread::spawn(move || watcher.watch(pid, max_memory as u64));
+            watch
+        });
+
+        Ok(LimitedProcess {
+            child,
+            cleaned_up: false,
+            memory_watch,
+        })
+    }
+
+    pub fn try_kill(&mut self, _max_duration: Duration) -> anyhow::Result<()> {
+        if let Some(watch) = &self.memory_watch {
+            watch.stopped.store(true, Ordering::Release);
+        }
+        self.child.kill().context("could not kill process")?;
+        // reap the process, so that it does not remain as a zombie
+        let _ = self.child.wait();
+        self.cleaned_up = true;
+        Ok(())
+    }
+
+    pub fn launch_without_container(
+        command: &str,
+        args: &[String],
+        envs: &[(&str, &str)],
+        current_dir: Option<&Path>,
+        stderr: StderrMode,
+        log_file: &Option<File>,
+    ) -> anyhow::Result<LimitedProcess> {
+        let child = create_process(command, args, envs, current_dir, stderr, log_file)
+            .context("could not create process")?;
+
+        Ok(LimitedProcess {
+            child,
+            cleaned_up: false,
+            memory_watch: None,
+        })
+    }
+
+    /// Cpu affinity is not available on this platform.
+    pub fn effective_cpus(&self) -> Option<HashSet<u8>> {
+        None
+    }
+
+    /// Highest memory footprint of the process seen by the watchdog.
+    ///
+    /// Returns `None` if the process runs without memory limit.
+    pub fn peak_memory_bytes(&self) -> anyhow::Result<Option<u64>> {
+        Ok(self
+            .memory_watch
+            .as_ref()
+            .map(|watch| watch.peak.load(Ordering::Relaxed)))
+    }
+
+    /// Number of times the watchdog killed the process for exceeding its memory limit.
+    ///
+    /// Returns `None` if the process runs without memory limit.
+    pub fn oom_kills(&self) -> anyhow::Result<Option<u64>> {
+        Ok(self
+            .memory_watch
+            .as_ref()
+            .map(|watch| watch.oom_kills.load(Ordering::Relaxed)))
+    }
+
+    /// CPU time is not measured on this platform.
+    pub fn cpu_time_used(&self) -> anyhow::Result<Option<Duration>> {
+        Ok(None)
+    }
+
+    /// Will print out as much info as possible
+    #[allow(dead_code)]
+    pub(crate) fn try_debug_cgroup(&mut self) {}
+}
+
+impl Drop for LimitedProcess {
+    fn drop(&mut self) {
+        static CLEANUP_DURATION: Duration = Duration::from_secs(1);
+        if !self.cleaned_up {
+            if let Err(e) = self.try_kill(CLEANUP_DURATION) {
+                panic!("could not kill process on LimitedProcess::drop: {e}");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod cgroup_manager_tests {
+    use std::time::Instant;
+
+    use super::*;
+
+    #[test]
+    fn test_memory_limit_kills_process() {
+        // allocates and fills 1GiB, way past the limit of 64MiB
+        let args = [
+            "-e".to_string(),
+            "my $x = 'a' x (1024 * 1024 * 1024); sleep 10".to_string(),
+        ];
+        let mut process = LimitedProcess::launch(
+            "perl",
+            &args,
+            &[],
+            None,
+            64 * 1024 * 1024,
+            "",
+            0,
+            StderrMode::Log,
+            &None,
+            None,
+        )
+        .unwrap();
+
+        let start = Instant::now();
+        let status = loop {
+            if let Some(status) = process.child.try_wait().unwrap() {
+                break status;
+            }
+            assert!(
+                start.elapsed() < Duration::from_secs(5),
+                "process exceeding its memory limit was not killed"
+            );
+            thread::sleep(Duration::from_millis(10));
+        };
+        assert!(!status.success());
+        process.try_kill(Duration::from_secs(1)).unwrap();
+    }
+}
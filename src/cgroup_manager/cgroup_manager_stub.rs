@@ -1,8 +1,8 @@
-use std::{fs::File, process::Child, time::Duration};
+use std::{collections::HashSet, fs::File, path::Path, process::Child, time::Duration};
 
 use anyhow::{self, bail, Context};
 
-use super::create_process;
+use super::{create_process, StderrMode};
 
 #[derive(Debug)]
 pub struct LimitedProcess {
@@ -11,13 +11,18 @@ pub struct LimitedProcess {
 }
 
 impl LimitedProcess {
+    #[allow(clippy::too_many_arguments)]
     pub fn launch(
         _command: &str,
         _args: &[String],
+        _envs: &[(&str, &str)],
+        _current_dir: Option<&Path>,
         _max_memory: i64,
         _cpus: &str,
-        _allow_stderr: bool,
+        _max_write_bps: i64,
+        _stderr: StderrMode,
         _log_file: &Option<File>,
+        _cgroup_parent: Option<&str>,
     ) -> anyhow::Result<LimitedProcess> {
         bail!("cgroups only available on linux")
     }
@@ -31,10 +36,12 @@ impl LimitedProcess {
     pub fn launch_without_container(
         command: &str,
         args: &[String],
-        allow_stderr: bool,
+        envs: &[(&str, &str)],
+        current_dir: Option<&Path>,
+        stderr: StderrMode,
         log_file: &Option<File>,
     ) -> anyhow::Result<LimitedProcess> {
-        let child = create_process(command, args, allow_stderr, log_file)
+        let child = create_process(command, args, envs, current_dir, stderr, log_file)
             .context("could not create process")?;
 
         Ok(LimitedProcess {
@@ -43,6 +50,26 @@ impl LimitedProcess {
         })
     }
 
+    /// Cpu affinity is not available on this platform.
+    pub fn effective_cpus(&self) -> Option<HashSet<u8>> {
+        None
+    }
+
+    /// Memory usage is not measured on this platform.
+    pub fn peak_memory_bytes(&self) -> anyhow::Result<Option<u64>> {
+        Ok(None)
+    }
+
+    /// Memory limits are not enforced on this platform.
+    pub fn oom_kills(&self) -> anyhow::Result<Option<u64>> {
+        Ok(None)
+    }
+
+    /// CPU time is not measured on this platform.
+    pub fn cpu_time_used(&self) -> anyhow::Result<Option<Duration>> {
+        Ok(None)
+    }
+
     /// Will print out as much info as possible
     #[allow(dead_code)]
     pub(crate) fn try_debug_cgroup(&mut self) {}
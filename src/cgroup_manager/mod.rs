@@ -1,52 +1,93 @@
 #[cfg(target_os = "linux")]
 mod cgroup_manager_linux;
 
-#[cfg(not(target_os = "linux"))]
+#[cfg(target_os = "macos")]
+mod cgroup_manager_macos;
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
 mod cgroup_manager_stub;
 
 use std::{
     fs::File,
-    process::{Child, Stdio},
+    path::Path,
+    process::{Child, Command, Stdio},
 };
 
 use anyhow::Context;
 #[cfg(target_os = "linux")]
 pub use cgroup_manager_linux::*;
 
-#[cfg(not(target_os = "linux"))]
+#[cfg(target_os = "macos")]
+pub use cgroup_manager_macos::*;
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
 pub use cgroup_manager_stub::*;
 
+/// Where the stderr of a launched process goes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StderrMode {
+    /// Written to the log file if there is one, discarded otherwise
+    Log,
+    /// Printed to the evaluator's stderr
+    Inherit,
+    /// Captured in a pipe, to be read from `child.stderr`
+    Piped,
+}
+
 fn create_process(
     command: &str,
     args: &[String],
-    allow_stderr: bool,
+    envs: &[(&str, &str)],
+    current_dir: Option<&Path>,
+    stderr: StderrMode,
     log_file: &Option<File>,
 ) -> anyhow::Result<Child> {
-    let mut cmd = std::process::Command::new(command);
-    cmd.args(args).stdin(Stdio::null());
+    build_command(command, args, envs, current_dir, stderr, log_file)?
+        .spawn()
+        .with_context(|| format!("command '{command}' not found"))
+}
 
-    match (log_file, allow_stderr) {
-        (Some(file), false) => {
-            let stdout = file.try_clone().context("log file error")?;
-            let stderr = file.try_clone().context("log file error")?;
-            cmd.stdout(Stdio::from(stdout));
-            cmd.stderr(Stdio::from(stderr));
-        }
-        (Some(file), true) => {
+/// Command launching a process, with its stdio redirected
+fn build_command(
+    command: &str,
+    args: &[String],
+    envs: &[(&str, &str)],
+    current_dir: Option<&Path>,
+    stderr: StderrMode,
+    log_file: &Option<File>,
+) -> anyhow::Result<Command> {
+    let mut cmd = Command::new(command);
+    cmd.args(args)
+        .envs(envs.iter().copied())
+        .stdin(Stdio::null());
+    if let Some(dir) = current_dir {
+        cmd.current_dir(dir);
+    }
+
+    match log_file {
+        Some(file) => {
             let stdout = file.try_clone().context("log file error")?;
             cmd.stdout(Stdio::from(stdout));
-            cmd.stderr(Stdio::inherit());
         }
-        (None, false) => {
+        None => {
             cmd.stdout(Stdio::null());
+        }
+    }
+    match (log_file, stderr) {
+        (Some(file), StderrMode::Log) => {
+            let stderr = file.try_clone().context("log file error")?;
+            cmd.stderr(Stdio::from(stderr));
+        }
+        (None, StderrMode::Log) => {
             cmd.stderr(Stdio::null());
         }
-        (None, true) => {
-            cmd.stdout(Stdio::null());
+        (_, StderrMode::Inherit) => {
             cmd.stderr(Stdio::inherit());
         }
+        (_, StderrMode::Piped) => {
+            cmd.stderr(Stdio::piped());
+        }
     }
 
-    cmd.spawn()
-        .with_context(|| format!("command '{command}' not found"))
+    Ok(cmd)
 }
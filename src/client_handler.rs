@@ -1,50 +1,133 @@
-use std::io::{Read, Write};
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read, Write};
 use std::net::{TcpListener, TcpStream};
-use std::sync::Arc;
+use std::ops::Range;
+use std::sync::{Arc, Mutex};
 use std::thread;
 use std::time::{Duration, Instant};
 
 use anyhow::{anyhow, bail, Context};
-use tracing::{error, instrument};
+use tracing::{error, instrument, trace, warn};
 
 use crate::agent::Agent;
-use crate::cgroup_manager::LimitedProcess;
+use crate::cgroup_manager::{LimitedProcess, StderrMode};
+use crate::configuration::{StderrCallback, StderrCallbackFn};
 use crate::constraints::Constraints;
 
 #[derive(Debug)]
 pub struct ClientHandler {
     stream: TcpStream,
     process: LimitedProcess,
+    startup_duration: Duration,
+    /// The agent asked for the rules of the game during the handshake
+    rules_requested: bool,
+    /// The agent did not acknowledge the protocol and has not responded yet: its first response
+    /// may be an `ACK` sent after the handshake grace period
+    unacknowledged: bool,
+    /// The CPU time of the agent could not be read: it is charged wall-clock time
+    cpu_time_unavailable: bool,
     // config: Configuration,
 }
 
+/// Environment variable through which the protocol is announced to agents
+pub(crate) const PROTOCOL_ENV_VAR: &str = "AI_TOURNAMENT_PROTOCOL";
+/// Protocol mode and version announced to agents
+pub(crate) const PROTOCOL: &str = "text/1";
+/// Protocol announced to agents when messages are length-prefixed (see
+/// [`Configuration::with_framed_protocol`](crate::configuration::Configuration::with_framed_protocol))
+pub(crate) const FRAMED_PROTOCOL: &str = "framed/1";
+/// Message sent to agents when a match ends (see
+/// [`Configuration::with_end_notification`](crate::configuration::Configuration::with_end_notification))
+pub(crate) const GAME_OVER: &str = "GAME_OVER";
+
+/// Outcome of a successful protocol handshake
+#[derive(Debug, PartialEq, Eq)]
+enum Handshake {
+    /// The agent sent nothing during the grace period: it uses the default protocol
+    Legacy,
+    /// The agent acknowledged the announced protocol
+    Acknowledged {
+        /// The agent asked for the rules of the game
+        rules_requested: bool,
+    },
+}
+
 impl ClientHandler {
-    const RESPONSE_TIMEOUT_DURATION: Duration = Duration::from_secs(1);
+    const MAX_HANDSHAKE_LENGTH: usize = 64;
+    /// Longest response accepted from an agent in framed mode, so that a bogus length does not
+    /// allocate unbounded memory
+    const MAX_FRAME_LENGTH: usize = 16 * 1024 * 1024;
+    /// How long agents have to exit after being told the match is over, before being killed
+    pub(crate) const END_GRACE_PERIOD: Duration = Duration::from_millis(500);
 
     /// launch a child process running agent with given constraints.
     ///
+    /// The agent must connect (and optionally complete the handshake) within
+    /// `resources.startup_timeout`.
+    ///
+    /// With a `seed`, the agent is given the seed of the match after the action timeout (see
+    /// [`Agent::expand_command`]). With `framed`, the agent is announced the framed protocol, and
+    /// messages must be exchanged with [`Self::send_and_recv_framed`].
+    ///
+    /// With a `stderr_callback`, the agent's stderr is read in a separate thread (see
+    /// [`Self::forward_stderr`]). With a `port_range`, the server listens for the agent on a port of
+    /// this range (see [`Self::bind_listener`]).
+    ///
     /// Child process is killed on drop. Child process's cgroup is cleaned up on drop.
+    ///
+    /// # Panics
+    ///
+    /// In debug builds, panics if `resources` are not the resources of a single agent. Release
+    /// builds return an error instead, failing only this match.
     #[instrument(skip_all,fields(Agent=agent.name))]
+    #[allow(clippy::too_many_arguments)]
     pub fn init(
         agent: Arc<Agent>,
         resources: &Constraints,
+        seed: Option<u64>,
+        framed: bool,
         allow_uncontained: bool,
         debug_process_stderr: bool,
+        stderr_callback: Option<&StderrCallback>,
+        port_range: Option<&Range<u16>>,
+        cgroup_parent: Option<&str>,
+        handshake_grace_period: Duration,
     ) -> anyhow::Result<ClientHandler> {
-        assert_eq!(
+        debug_assert_eq!(
             resources.total_ram, resources.agent_ram,
             "incorrect ram to launch agent"
         );
-        assert_eq!(
+        debug_assert_eq!(
             resources.cpus.len(),
             resources.cpus_per_agent,
             "incorrect cpus to launch agents"
         );
+        if resources.total_ram != resources.agent_ram {
+            bail!(
+                "incorrect ram to launch agent: {} given instead of {}",
+                resources.total_ram,
+                resources.agent_ram
+            );
+        }
+        if resources.cpus.len() != resources.cpus_per_agent {
+            bail!(
+                "incorrect cpus to launch agent: {} given instead of {}",
+                resources.cpus.len(),
+                resources.cpus_per_agent
+            );
+        }
 
         static HAVE_TASKSET: std::sync::LazyLock<bool> =
             std::sync::LazyLock::new(ClientHandler::test_taskset);
-        static HAVE_CGROUPS_V2: std::sync::LazyLock<bool> =
-            std::sync::LazyLock::new(ClientHandler::test_cgroups);
+        // availability depends on the cgroup parent
+        static HAVE_CGROUPS_V2: std::sync::LazyLock<Mutex<HashMap<Option<String>, bool>>> =
+            std::sync::LazyLock::new(Default::default);
+        let have_cgroups_v2 = *HAVE_CGROUPS_V2
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .entry(cgroup_parent.map(String::from))
+            .or_insert_with(|| ClientHandler::test_cgroups(cgroup_parent));
 
         // return early if agent has no binary
         let path = agent
@@ -55,8 +138,7 @@ impl ClientHandler {
             .into_string()
             .map_err(|_| anyhow!("path is not a valid string"))?;
 
-        let listener = TcpListener::bind("127.0.0.1:0")
-            .context("server error: could not create TcpListener")?;
+        let listener = Self::bind_listener(port_range, resources.startup_timeout)?;
         let port_arg = listener.local_addr()?.port().to_string();
         let time_budget_arg = (resources.time_budget.as_micros() as u64).to_string();
         let action_timeout_arg = (resources.action_timeout.as_micros() as u64).to_string();
@@ -69,10 +151,11 @@ impl ClientHandler {
             .collect::<Vec<_>>()
             .join(",");
 
-        if !*HAVE_TASKSET && !allow_uncontained {
+        // macOS has no CPU affinity: agents are only limited in memory there
+        if !*HAVE_TASKSET && !allow_uncontained && !cfg!(target_os = "macos") {
             bail!(
                 "taskset {}unavailable. Consider setting allow_uncontained to true.",
-                if *HAVE_CGROUPS_V2 {
+                if have_cgroups_v2 {
                     ""
                 } else {
                     "and cgroups v2 "
@@ -80,28 +163,24 @@ impl ClientHandler {
             );
         }
 
-        if !*HAVE_CGROUPS_V2 && !allow_uncontained {
-            bail!("cgroups v2 unavailable. Consider setting allow_uncontained to true");
+        if !have_cgroups_v2 && !allow_uncontained {
+            bail!("cgroups v2 unavailable. Consider setting allow_uncontained to true, or setting the cgroup parent (see Configuration::with_cgroup_parent)");
         }
 
+        let seed_arg = seed.map(|seed| seed.to_string());
+        let agent_command = agent.expand_command(
+            &path,
+            &port_arg,
+            &time_budget_arg,
+            &action_timeout_arg,
+            seed_arg.as_deref(),
+        );
         let mut full_command = if *HAVE_TASKSET {
-            vec![
-                "taskset".to_string(),
-                "-c".to_string(),
-                cpus.clone(),
-                path,
-                port_arg,
-                time_budget_arg,
-                action_timeout_arg,
-            ]
+            vec!["taskset".to_string(), "-c".to_string(), cpus.clone()]
         } else {
-            vec![path, port_arg, time_budget_arg, action_timeout_arg]
+            vec![]
         };
-
-        // append agent's arguments (from config file) to the args
-        if let Some(args) = &agent.args {
-            full_command.extend_from_slice(args);
-        }
+        full_command.extend(agent_command);
         let mut full_command = full_command.into_iter();
 
         let command = full_command.next().unwrap();
@@ -113,94 +192,524 @@ impl ClientHandler {
             None
         };
 
-        let mut process = if *HAVE_CGROUPS_V2 {
+        let stderr_mode = if stderr_callback.is_some() {
+            StderrMode::Piped
+        } else if debug_process_stderr {
+            StderrMode::Inherit
+        } else {
+            StderrMode::Log
+        };
+
+        let protocol = if framed { FRAMED_PROTOCOL } else { PROTOCOL };
+        let launch_time = Instant::now();
+        let mut process = if have_cgroups_v2 {
             LimitedProcess::launch(
                 &command,
                 &args,
+                &[(PROTOCOL_ENV_VAR, protocol)],
+                agent.working_dir.as_deref(),
                 max_memory as i64,
                 &cpus,
-                debug_process_stderr,
+                resources.disk_io_limit.map_or(0, |bps| bps as i64),
+                stderr_mode,
                 &log_file,
+                cgroup_parent,
             )
             .context("server error: child + cgroup creation failed")?
         } else {
             LimitedProcess::launch_without_container(
                 &command,
                 &args,
-                debug_process_stderr,
+                &[(PROTOCOL_ENV_VAR, protocol)],
+                agent.working_dir.as_deref(),
+                stderr_mode,
                 &log_file,
             )?
         };
 
+        if let (Some(callback), Some(stderr)) = (stderr_callback, process.child.stderr.take()) {
+            let log_file = log_file
+                .as_ref()
+                .map(File::try_clone)
+                .transpose()
+                .context("log file error")?;
+            Self::forward_stderr(
+                agent.name.clone(),
+                stderr,
+                log_file,
+                debug_process_stderr,
+                callback.0.clone(),
+            );
+        }
+
         listener
             .set_nonblocking(true)
             .context("server error: setting non-blocking to true")?;
 
-        let response_timeout = Instant::now() + Self::RESPONSE_TIMEOUT_DURATION;
-        while Instant::now() < response_timeout {
-            if let Ok((stream, _addr)) = listener.accept() {
+        let startup_deadline = launch_time + resources.startup_timeout;
+        while Instant::now() < startup_deadline {
+            if let Ok((mut stream, _addr)) = listener.accept() {
+                let startup_duration = launch_time.elapsed();
+                // only one agent connects: free the port now rather than after the handshake
+                drop(listener);
+                let handshake = Self::handshake(&mut stream, protocol, handshake_grace_period)
+                    .context("protocol handshake failed")?;
+                let rules_requested = matches!(
+                    handshake,
+                    Handshake::Acknowledged {
+                        rules_requested: true
+                    }
+                );
+                // the agent is running at this point: taskset/cgroup affinity must have been applied
+                if *HAVE_TASKSET || have_cgroups_v2 {
+                    Self::check_pinning(&process, &resources.cpus);
+                }
                 return Ok(ClientHandler {
                     stream,
                     process,
+                    startup_duration,
+                    rules_requested,
+                    unacknowledged: handshake == Handshake::Legacy,
+                    cpu_time_unavailable: false,
                     // config,
                 });
             }
             // at least 10 tries
-            thread::sleep(Duration::from_millis(10).min(Self::RESPONSE_TIMEOUT_DURATION / 10));
+            thread::sleep(Duration::from_millis(10).min(resources.startup_timeout / 10));
         }
 
         //FIXME: panic
         process.try_kill(Duration::from_secs(1)).unwrap();
-        Err(anyhow!("no connection made to server"))
+        Err(anyhow!(
+            "no connection made to server within startup timeout ({}ms)",
+            resources.startup_timeout.as_millis()
+        ))
     }
 
+    /// Creates the listener the agent connects to.
+    ///
+    /// Without `port_range`, the port is chosen by the OS among ephemeral ports. Otherwise, the
+    /// first free port of the range is used. If all ports of the range are in use (by agents
+    /// starting in other matches), the launch waits for one to be released, for up to `max_wait`.
+    /// A port is only held until the agent connects.
+    fn bind_listener(
+        port_range: Option<&Range<u16>>,
+        max_wait: Duration,
+    ) -> anyhow::Result<TcpListener> {
+        let Some(range) = port_range else {
+            // the I/O error is kept as the source, to tell transient failures apart
+            return TcpListener::bind("127.0.0.1:0").map_err(|e| match e.kind() {
+                std::io::ErrorKind::AddrInUse | std::io::ErrorKind::AddrNotAvailable => {
+                    let message = format!(
+                        "server error: no ephemeral port available ({e}). Too many concurrent matches? Consider setting a port range (see Configuration::with_port_range)"
+                    );
+                    anyhow!(e).context(message)
+                }
+                _ => anyhow!(e).context("server error: could not create TcpListener"),
+            });
+        };
+        if range.is_empty() {
+            bail!("server error: empty port range {range:?}");
+        }
+
+        let deadline = Instant::now() + max_wait;
+        loop {
+            for port in range.clone() {
+                if let Ok(listener) = TcpListener::bind(("127.0.0.1", port)) {
+                    return Ok(listener);
+                }
+            }
+            if Instant::now() > deadline {
+                return Err(
+                    anyhow!(std::io::Error::from(std::io::ErrorKind::AddrInUse)).context(format!(
+                        "server error: all ports of range {range:?} are in use (waited {}ms)",
+                        max_wait.as_millis()
+                    )),
+                );
+            }
+            trace!("all ports of range {range:?} are in use, waiting");
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    /// Reads `stderr` line by line in a new thread, until the process closes it (usually when it
+    /// exits). Each line is given to `callback`, and also written to the log file, or printed if
+    /// `print` is set, as it would have been without the callback.
+    fn forward_stderr(
+        name: String,
+        stderr: impl Read + Send + 'static,
+        mut log_file: Option<File>,
+        print: bool,
+        callback: Arc<StderrCallbackFn>,
+    ) -> thread::JoinHandle<()> {
+        thread::spawn(move || {
+            let mut reader = BufReader::new(stderr);
+            let mut line = vec![];
+            // lines are read as bytes: an invalid UTF-8 line must not stop the reading, or the
+            // agent would block once the pipe is full
+            while reader.read_until(b'\n', &mut line).is_ok_and(|n| n > 0) {
+                if let Some(file) = &mut log_file {
+                    let _ = file.write_all(&line);
+                }
+                let text = String::from_utf8_lossy(&line);
+                let text = text.trim_end_matches(['\n', '\r']);
+                if print {
+                    eprintln!("{text}");
+                }
+                callback(&name, text);
+                line.clear();
+            }
+        })
+    }
+
+    /// Time from process launch to connection to the server
+    pub fn startup_duration(&self) -> Duration {
+        self.startup_duration
+    }
+
+    /// Peak memory usage of the agent in bytes so far, `None` if it runs without container or
+    /// if it could not be read
+    pub fn peak_memory_bytes(&self) -> Option<u64> {
+        self.process.peak_memory_bytes().unwrap_or_else(|e| {
+            warn!("Could not read peak memory usage: {e:#}");
+            None
+        })
+    }
+
+    /// CPU time used by the agent since its launch, `None` if it runs without container or if it
+    /// could not be read (e.g. on cgroups v1). A read failure is only logged once: the agent is
+    /// then charged wall-clock time for the rest of the match
+    pub fn cpu_time_used(&mut self) -> Option<Duration> {
+        if self.cpu_time_unavailable {
+            return None;
+        }
+        self.process.cpu_time_used().unwrap_or_else(|e| {
+            warn!("Could not read cpu time, falling back to wall-clock time: {e:#}");
+            self.cpu_time_unavailable = true;
+            None
+        })
+    }
+
+    /// The kernel killed a process of the agent for exceeding its memory limit. Always false when
+    /// running without container
+    pub fn oom_killed(&self) -> bool {
+        match self.process.oom_kills() {
+            Ok(kills) => kills.is_some_and(|kills| kills > 0),
+            Err(e) => {
+                warn!("Could not read memory events: {e:#}");
+                false
+            }
+        }
+    }
+
+    /// The agent asked for the rules of the game during the handshake, see [`Self::send_rules`]
+    pub fn rules_requested(&self) -> bool {
+        self.rules_requested
+    }
+
+    /// True if `response`, the first one of an agent which did not acknowledge the protocol, is
+    /// an `ACK` sent after the handshake grace period rather than an action
+    pub fn is_late_ack(&mut self, response: &[u8]) -> bool {
+        std::mem::take(&mut self.unacknowledged) && response.starts_with(b"ACK ")
+    }
+
+    /// Sends the rules of the game, as `RULES <length>\n` followed by `length` bytes of
+    /// description (none if the game has no description). See [`Self::write_rules`].
+    pub fn send_rules(
+        &mut self,
+        rules: Option<&str>,
+        io_timeout_margin: Duration,
+    ) -> anyhow::Result<()> {
+        Self::write_rules(&mut self.stream, rules, io_timeout_margin)
+    }
+
+    fn write_rules(
+        stream: &mut TcpStream,
+        rules: Option<&str>,
+        io_timeout: Duration,
+    ) -> anyhow::Result<()> {
+        let rules = rules.unwrap_or_default();
+        stream
+            .set_write_timeout(Some(io_timeout.max(Duration::from_millis(1))))
+            .context("server error: setting write timeout")?;
+        stream
+            .write_all(format!("RULES {}\n{rules}", rules.len()).as_bytes())
+            .context("I/O error while sending rules")
+    }
+
+    /// Tells the agent the match is over, with a `GAME_OVER\n` line, or a `GAME_OVER` frame if
+    /// `framed` (see [`Self::exchange_framed`]). No response is expected.
+    pub fn send_game_over(&mut self, framed: bool, io_timeout: Duration) -> anyhow::Result<()> {
+        let msg = if framed {
+            let mut frame = (GAME_OVER.len() as u32).to_be_bytes().to_vec();
+            frame.extend_from_slice(GAME_OVER.as_bytes());
+            frame
+        } else {
+            format!("{GAME_OVER}\n").into_bytes()
+        };
+        self.stream
+            .set_write_timeout(Some(io_timeout.max(Duration::from_millis(1))))
+            .context("server error: setting write timeout")?;
+        self.stream
+            .write_all(&msg)
+            .context("I/O error while sending end of match")
+    }
+
+    /// Waits until the agent process exits, at most until `deadline`. Returns whether it exited.
+    pub fn wait_for_exit(&mut self, deadline: Instant) -> bool {
+        loop {
+            match self.process.child.try_wait() {
+                Ok(Some(_)) => return true,
+                Ok(None) if Instant::now() < deadline => thread::sleep(Duration::from_millis(10)),
+                Ok(None) => return false,
+                Err(e) => {
+                    warn!("Could not check whether the agent exited: {e}");
+                    return false;
+                }
+            }
+        }
+    }
+
+    /// Sends `msg` and waits for the response, which must arrive within `max_duration` (the action
+    /// deadline). See [`Self::exchange`].
     #[instrument]
     pub fn send_and_recv(
         &mut self,
         msg: &[u8],
         buf: &mut [u8],
         max_duration: Duration,
+        io_timeout_margin: Duration,
     ) -> anyhow::Result<usize> {
-        self.stream
-            .set_nonblocking(true)
-            .context("server error: setting non-blocking for 'write'")?;
+        Self::exchange(&mut self.stream, msg, buf, max_duration, io_timeout_margin)
+    }
+
+    /// Same as [`Self::send_and_recv`], with length-prefixed messages (see
+    /// [`Self::exchange_framed`]).
+    #[instrument]
+    pub fn send_and_recv_framed(
+        &mut self,
+        msg: &[u8],
+        max_duration: Duration,
+        io_timeout_margin: Duration,
+    ) -> anyhow::Result<Vec<u8>> {
+        Self::exchange_framed(&mut self.stream, msg, max_duration, io_timeout_margin)
+    }
+
+    /// Sends `msg`, then reads the response into `buf`.
+    ///
+    /// The action deadline `max_duration` is measured from the start of the exchange: a response
+    /// read after it is an error. Socket operations themselves time out `io_timeout_margin` after
+    /// the deadline. This is only a safety net, so that a write to an agent that does not read its
+    /// socket, or a stalled connection, cannot block the match forever.
+    fn exchange(
+        stream: &mut TcpStream,
+        msg: &[u8],
+        buf: &mut [u8],
+        max_duration: Duration,
+        io_timeout_margin: Duration,
+    ) -> anyhow::Result<usize> {
+        let start = Instant::now();
+        let io_timeout = max_duration.saturating_add(io_timeout_margin);
+        // a zero timeout is rejected by the socket API
+        let min_timeout = Duration::from_micros(1);
+
+        stream
+            .set_nonblocking(false)
+            .context("server error: setting blocking I/O")?;
+        stream
+            .set_write_timeout(Some(io_timeout.max(min_timeout)))
+            .context("server error: setting write timeout")?;
+        stream
+            .write_all(msg)
+            .context("I/O error while sending msg")?;
+
+        stream
+            .set_read_timeout(Some(
+                io_timeout.saturating_sub(start.elapsed()).max(min_timeout),
+            ))
+            .context("server error: setting read timeout")?;
+        let n = stream.read(buf).context("server could not read stream")?;
 
-        match self.stream.write(msg) {
-            Ok(0) => {
-                return Err(anyhow!("connection closed by client"));
+        let elapsed = start.elapsed();
+        if elapsed > max_duration {
+            bail!(
+                "response received {}ms after the deadline",
+                (elapsed - max_duration).as_millis()
+            );
+        }
+        Ok(n)
+    }
+
+    /// Same as [`Self::exchange`], but both messages are preceded by their length in bytes, as a
+    /// 4-byte big-endian integer, and the response is read until complete, whatever its size.
+    ///
+    /// A connection closed before the response is returned as an empty response, as in
+    /// [`Self::exchange`]. A connection closed in the middle of a response, or a response longer
+    /// than [`Self::MAX_FRAME_LENGTH`], is an error.
+    fn exchange_framed(
+        stream: &mut TcpStream,
+        msg: &[u8],
+        max_duration: Duration,
+        io_timeout_margin: Duration,
+    ) -> anyhow::Result<Vec<u8>> {
+        let start = Instant::now();
+        let io_timeout = max_duration.saturating_add(io_timeout_margin);
+        // a zero timeout is rejected by the socket API
+        let min_timeout = Duration::from_micros(1);
+
+        stream
+            .set_nonblocking(false)
+            .context("server error: setting blocking I/O")?;
+        stream
+            .set_write_timeout(Some(io_timeout.max(min_timeout)))
+            .context("server error: setting write timeout")?;
+        let length = u32::try_from(msg.len()).context("message too long for a frame")?;
+        // a single write, so that the agent does not wait for the rest of the frame
+        let mut frame = Vec::with_capacity(4 + msg.len());
+        frame.extend_from_slice(&length.to_be_bytes());
+        frame.extend_from_slice(msg);
+        stream
+            .write_all(&frame)
+            .context("I/O error while sending msg")?;
+
+        let mut header = [0; 4];
+        match Self::read_until_full(stream, &mut header, start, io_timeout)? {
+            0 => return Ok(vec![]),
+            4 => {}
+            n => bail!("connection closed after {n} bytes of the response length"),
+        }
+        let length = u32::from_be_bytes(header) as usize;
+        if length > Self::MAX_FRAME_LENGTH {
+            bail!(
+                "response of {length} bytes exceeds the maximum of {} bytes",
+                Self::MAX_FRAME_LENGTH
+            );
+        }
+        let mut response = vec![0; length];
+        let n = Self::read_until_full(stream, &mut response, start, io_timeout)?;
+        if n < length {
+            bail!("connection closed after {n} of {length} bytes of response");
+        }
+
+        let elapsed = start.elapsed();
+        if elapsed > max_duration {
+            bail!(
+                "response received {}ms after the deadline",
+                (elapsed - max_duration).as_millis()
+            );
+        }
+        Ok(response)
+    }
+
+    /// Reads until `buf` is full or the connection is closed, and returns the number of bytes
+    /// read. Reads time out `io_timeout` after `start`.
+    fn read_until_full(
+        stream: &mut TcpStream,
+        buf: &mut [u8],
+        start: Instant,
+        io_timeout: Duration,
+    ) -> anyhow::Result<usize> {
+        let mut filled = 0;
+        while filled < buf.len() {
+            stream
+                .set_read_timeout(Some(
+                    io_timeout
+                        .saturating_sub(start.elapsed())
+                        .max(Duration::from_micros(1)),
+                ))
+                .context("server error: setting read timeout")?;
+            match stream
+                .read(&mut buf[filled..])
+                .context("server could not read stream")?
+            {
+                0 => break,
+                n => filled += n,
             }
-            Ok(n) => {
-                if n < msg.len() {
-                    error!(
-                        "only {}/{} bytes of {} were sent",
-                        n,
-                        msg.len(),
-                        std::str::from_utf8(msg).unwrap_or("NON_VALID_UTF8")
-                    );
-                    return Err(anyhow!(
-                        "msg transmission error: only {}/{} bytes sent",
-                        n,
-                        msg.len()
-                    ));
+        }
+        Ok(filled)
+    }
+
+    /// Wait for the agent to acknowledge the announced `protocol` (`ACK <mode>/<version>\n`, or
+    /// `ACK <mode>/<version> RULES\n` to ask for the rules of the game). Returns whether the rules
+    /// were asked for.
+    ///
+    /// Agents that send nothing during `grace_period` are legacy agents, and use the default
+    /// protocol (all agents are, if it is zero). Agents acknowledging another protocol are
+    /// rejected.
+    fn handshake(
+        stream: &mut TcpStream,
+        protocol: &str,
+        grace_period: Duration,
+    ) -> anyhow::Result<Handshake> {
+        stream
+            .set_nonblocking(false)
+            .context("server error: setting blocking for handshake")?;
+        if grace_period.is_zero() {
+            return Ok(Handshake::Legacy);
+        }
+        stream
+            .set_read_timeout(Some(grace_period))
+            .context("server error: setting handshake timeout")?;
+
+        let mut line = vec![];
+        let mut byte = [0];
+        let deadline = Instant::now() + grace_period;
+        while !line.ends_with(b"\n") {
+            match stream.read(&mut byte) {
+                Ok(0) => break,
+                Ok(_) => line.push(byte[0]),
+                Err(e)
+                    if matches!(
+                        e.kind(),
+                        std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+                    ) =>
+                {
+                    break
                 }
+                Err(e) => return Err(e).context("I/O error during handshake"),
             }
-            Err(e) => {
-                return Err(e).context("I/O error while sending msg");
+            if line.len() > Self::MAX_HANDSHAKE_LENGTH || Instant::now() > deadline {
+                break;
             }
         }
-        self.stream
-            .set_nonblocking(false)
-            .context("server error: setting blocking for 'read'")?;
 
-        self.stream
-            .set_read_timeout(Some(max_duration))
-            .context("server error: setting read timeout")?;
+        if line.is_empty() {
+            trace!("no handshake: legacy agent");
+            return Ok(Handshake::Legacy);
+        }
 
-        let n = self
-            .stream
-            .read(buf)
-            .context("server could not read stream")?;
-        Ok(n)
+        let line = String::from_utf8_lossy(&line);
+        let ack = line.trim().strip_prefix("ACK ");
+        let (ack, rules_requested) = match ack.map(|ack| ack.strip_suffix(" RULES")) {
+            Some(Some(protocol)) => (Some(protocol), true),
+            _ => (ack, false),
+        };
+        let result = match ack {
+            Some(ack) if ack == protocol => Ok(Handshake::Acknowledged { rules_requested }),
+            Some(ack) => Err(anyhow!(
+                "agent expects protocol '{ack}' but server uses '{protocol}'"
+            )),
+            None if line.ends_with('\n') => Err(anyhow!("invalid handshake '{}'", line.trim())),
+            None => Err(anyhow!("incomplete handshake '{line}'")),
+        };
+        if let Err(e) = &result {
+            // best effort: let the agent know why it is rejected
+            let _ = stream.write_all(format!("REJECT {e}\n").as_bytes());
+        }
+        result
+    }
+
+    /// Warn if the process is not pinned to the expected cpus (e.g. if affinity silently failed).
+    fn check_pinning(process: &LimitedProcess, expected: &HashSet<u8>) {
+        match process.effective_cpus() {
+            Some(cpus) => {
+                trace!(?cpus, "effective cpus");
+                if cpus != *expected {
+                    warn!("process is not pinned to the expected cpus: expected {expected:?}, got {cpus:?}");
+                }
+            }
+            None => trace!("could not read effective cpus"),
+        }
     }
 
     fn kill_child_process(&mut self) -> anyhow::Result<()> {
@@ -214,9 +723,20 @@ impl ClientHandler {
             .is_ok()
     }
 
-    #[cfg(unix)]
-    fn test_cgroups() -> bool {
-        match LimitedProcess::launch("pwd", &[], 1000, "0", false, &None) {
+    #[cfg(all(unix, not(target_os = "macos")))]
+    fn test_cgroups(cgroup_parent: Option<&str>) -> bool {
+        match LimitedProcess::launch(
+            "pwd",
+            &[],
+            &[],
+            None,
+            1000,
+            "0",
+            0,
+            StderrMode::Log,
+            &None,
+            cgroup_parent,
+        ) {
             Ok(mut p) => {
                 let _ = p.child.wait();
                 let _ = p.try_kill(Duration::from_secs(1));
@@ -226,8 +746,14 @@ impl ClientHandler {
         }
     }
 
+    /// Memory limits are set with `setrlimit`, which needs no setup
+    #[cfg(target_os = "macos")]
+    fn test_cgroups(_cgroup_parent: Option<&str>) -> bool {
+        true
+    }
+
     #[cfg(not(unix))]
-    fn test_cgroups() -> bool {
+    fn test_cgroups(_cgroup_parent: Option<&str>) -> bool {
         false
     }
 }
@@ -245,3 +771,238 @@ impl Drop for ClientHandler {
         }
     }
 }
+
+#[cfg(test)]
+mod client_handler_tests {
+    use super::*;
+
+    fn connected_pair() -> (TcpStream, TcpStream) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let agent = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (server, _addr) = listener.accept().unwrap();
+        (server, agent)
+    }
+
+    const GRACE_PERIOD: Duration = Duration::from_millis(100);
+
+    #[test]
+    fn test_legacy_agent() {
+        let (mut server, _agent) = connected_pair();
+        assert_eq!(
+            ClientHandler::handshake(&mut server, PROTOCOL, GRACE_PERIOD).unwrap(),
+            Handshake::Legacy
+        );
+    }
+
+    #[test]
+    fn test_acknowledged_protocol() {
+        let (mut server, mut agent) = connected_pair();
+        agent
+            .write_all(format!("ACK {PROTOCOL}\n").as_bytes())
+            .unwrap();
+        assert_eq!(
+            ClientHandler::handshake(&mut server, PROTOCOL, GRACE_PERIOD).unwrap(),
+            Handshake::Acknowledged {
+                rules_requested: false
+            }
+        );
+
+        // without grace period, nothing is read
+        let (mut server, mut agent) = connected_pair();
+        agent
+            .write_all(format!("ACK {PROTOCOL}\n").as_bytes())
+            .unwrap();
+        assert_eq!(
+            ClientHandler::handshake(&mut server, PROTOCOL, Duration::ZERO).unwrap(),
+            Handshake::Legacy
+        );
+        let mut buf = [0; 16];
+        let n = server.read(&mut buf).unwrap();
+        assert!(buf[..n].starts_with(b"ACK "));
+    }
+
+    #[test]
+    fn test_rules_request() {
+        let (mut server, mut agent) = connected_pair();
+        agent
+            .write_all(format!("ACK {PROTOCOL} RULES\n").as_bytes())
+            .unwrap();
+        assert_eq!(
+            ClientHandler::handshake(&mut server, PROTOCOL, GRACE_PERIOD).unwrap(),
+            Handshake::Acknowledged {
+                rules_requested: true
+            }
+        );
+
+        let timeout = Duration::from_secs(1);
+        ClientHandler::write_rules(&mut server, Some("9x9 board\nno ko"), timeout).unwrap();
+        ClientHandler::write_rules(&mut server, None, timeout).unwrap();
+        drop(server);
+        let mut received = String::new();
+        agent.read_to_string(&mut received).unwrap();
+        assert_eq!(received, "RULES 15\n9x9 board\nno koRULES 0\n");
+
+        let (mut server, mut agent) = connected_pair();
+        agent.write_all(b"ACK text/1 HELLO\n").unwrap();
+        assert!(ClientHandler::handshake(&mut server, PROTOCOL, GRACE_PERIOD).is_err());
+    }
+
+    #[test]
+    fn test_rejected_protocol() {
+        let (mut server, mut agent) = connected_pair();
+        agent.write_all(b"ACK json/2\n").unwrap();
+        let err = ClientHandler::handshake(&mut server, PROTOCOL, GRACE_PERIOD).unwrap_err();
+        assert!(err.to_string().contains("json/2"));
+
+        let mut buf = [0; 128];
+        let n = agent.read(&mut buf).unwrap();
+        assert!(std::str::from_utf8(&buf[..n])
+            .unwrap()
+            .starts_with("REJECT"));
+    }
+
+    #[test]
+    fn test_invalid_handshake() {
+        let (mut server, mut agent) = connected_pair();
+        agent.write_all(b"hello\n").unwrap();
+        assert!(ClientHandler::handshake(&mut server, PROTOCOL, GRACE_PERIOD).is_err());
+    }
+
+    #[test]
+    fn test_port_range_queuing() {
+        // two consecutive free ports
+        let start = (0..100)
+            .filter_map(|_| {
+                let port = TcpListener::bind("127.0.0.1:0")
+                    .ok()?
+                    .local_addr()
+                    .ok()?
+                    .port();
+                let _next = TcpListener::bind(("127.0.0.1", port.checked_add(1)?)).ok()?;
+                Some(port)
+            })
+            .next()
+            .unwrap();
+        let range = start..start + 2;
+        let wait = Duration::from_secs(2);
+
+        let first = ClientHandler::bind_listener(Some(&range), wait).unwrap();
+        let second = ClientHandler::bind_listener(Some(&range), wait).unwrap();
+        assert_ne!(
+            first.local_addr().unwrap().port(),
+            second.local_addr().unwrap().port()
+        );
+        let err = ClientHandler::bind_listener(Some(&range), Duration::ZERO).unwrap_err();
+        assert!(err.to_string().contains("are in use"));
+
+        // a third launch waits for a port to be released
+        let range_clone = range.clone();
+        let third = thread::spawn(move || ClientHandler::bind_listener(Some(&range_clone), wait));
+        thread::sleep(Duration::from_millis(100));
+        let released = first.local_addr().unwrap().port();
+        drop(first);
+        let third = third.join().unwrap().unwrap();
+        assert_eq!(third.local_addr().unwrap().port(), released);
+    }
+
+    #[test]
+    fn test_forward_stderr() {
+        let mut child = std::process::Command::new("sh")
+            .args(["-c", "echo one >&2; printf 'two\\377' >&2"])
+            .stderr(std::process::Stdio::piped())
+            .spawn()
+            .unwrap();
+        let lines = Arc::new(Mutex::new(vec![]));
+        let lines_clone = lines.clone();
+        let callback = Arc::new(move |name: &str, line: &str| {
+            lines_clone.lock().unwrap().push(format!("{name}: {line}"));
+        });
+        ClientHandler::forward_stderr(
+            "agent".into(),
+            child.stderr.take().unwrap(),
+            None,
+            false,
+            callback,
+        )
+        .join()
+        .unwrap();
+        child.wait().unwrap();
+        assert_eq!(*lines.lock().unwrap(), ["agent: one", "agent: two\u{FFFD}"]);
+    }
+
+    #[test]
+    fn test_exchange_deadline() {
+        let (mut server, mut agent) = connected_pair();
+        let responder = thread::spawn(move || {
+            let mut buf = [0; 16];
+            for delay in [0, 300] {
+                let _ = agent.read(&mut buf).unwrap();
+                thread::sleep(Duration::from_millis(delay));
+                agent.write_all(b"1\n").unwrap();
+            }
+            // never answer the last message
+            let _ = agent.read(&mut buf).unwrap();
+            agent
+        });
+        let deadline = Duration::from_millis(200);
+        let margin = Duration::from_millis(200);
+        let mut buf = [0; 16];
+
+        let n = ClientHandler::exchange(&mut server, b"a\n", &mut buf, deadline, margin).unwrap();
+        assert_eq!(&buf[..n], b"1\n");
+
+        // answered before the safety net, but after the deadline
+        let err = ClientHandler::exchange(&mut server, b"b\n", &mut buf, deadline, margin);
+        assert!(err.unwrap_err().to_string().contains("after the deadline"));
+
+        let start = Instant::now();
+        assert!(ClientHandler::exchange(&mut server, b"c\n", &mut buf, deadline, margin).is_err());
+        assert!(start.elapsed() < deadline + margin + Duration::from_millis(200));
+        let _agent = responder.join().unwrap();
+    }
+
+    #[test]
+    fn test_exchange_framed() {
+        let (mut server, mut agent) = connected_pair();
+        let action = vec![b'x'; 10_000];
+        let responder = thread::spawn({
+            let action = action.clone();
+            move || {
+                let mut header = [0; 4];
+                agent.read_exact(&mut header).unwrap();
+                let mut state = vec![0; u32::from_be_bytes(header) as usize];
+                agent.read_exact(&mut state).unwrap();
+                assert_eq!(state, b"state");
+                // longer than a single read, split in several writes
+                agent
+                    .write_all(&(action.len() as u32).to_be_bytes())
+                    .unwrap();
+                for chunk in action.chunks(3000) {
+                    agent.write_all(chunk).unwrap();
+                    thread::sleep(Duration::from_millis(10));
+                }
+                agent.read_exact(&mut header).unwrap();
+                agent.read_exact(&mut [0; 1]).unwrap();
+                // truncated response
+                agent.write_all(&5u32.to_be_bytes()).unwrap();
+                agent.write_all(b"ab").unwrap();
+            }
+        });
+        let deadline = Duration::from_secs(5);
+        let margin = Duration::from_millis(200);
+
+        let response =
+            ClientHandler::exchange_framed(&mut server, b"state", deadline, margin).unwrap();
+        assert_eq!(response, action);
+
+        let err = ClientHandler::exchange_framed(&mut server, b"s", deadline, margin);
+        responder.join().unwrap();
+        assert!(err
+            .unwrap_err()
+            .to_string()
+            .contains("connection closed after 2 of 5 bytes"));
+        // closed before the response: empty response, as in text mode
+        let response = ClientHandler::exchange_framed(&mut server, b"s", deadline, margin);
+        assert!(response.map_or(true, |r| r.is_empty()));
+    }
+}
@@ -12,24 +12,146 @@
 //!
 //! - `EVAL_VERBOSE` — Enable verbose output (default: `true`)
 //! - `EVAL_LOG_DIR` — Enable logging to a directory (must be a valid directory path)
+//! - `EVAL_APPEND_LOGS` — Keep the logs of previous runs, in one subdirectory per run (default: `false`)
 //! - `EVAL_ALLOW_UNCONTAINED` — Allow unsafe fallbacks (e.g., skipping `taskset`, `cgroup`) (default: `false`)
 //! - `EVAL_COMPILE_AGENTS` — Compile agents before evaluation (default: `true`)
 //! - `EVAL_SELF_TEST` — Enable self-test mode (for single-agent evaluation) (default: `false`)
+//! - `EVAL_SELF_TEST_PATH` — Agent directory used in self-test mode (default: current directory)
+//! - `EVAL_CONFIG_NAME` — Name of the config to evaluate, instead of the `eval:` key of each agent (default: unset)
 //! - `EVAL_TEST_ALL_CONFIGS` — Test all available configurations instead of just `eval` (default: `false`)
 //! - `EVAL_DEBUG_AGENT_STDERR` — Print agent stderr for debugging (default: `false`)
+//! - `EVAL_MAX_MESSAGE_SIZE` — Maximum size in bytes of an action read in a single packet (default: `4096`)
+//! - `EVAL_FRAMED_PROTOCOL` — Prefix messages with their length instead of reading a single packet (default: `false`)
+//! - `EVAL_END_NOTIFICATION` — Send `GAME_OVER` to agents at the end of a match and let them exit (default: `false`)
+//! - `EVAL_CPU_TIME_BUDGET` — Charge the CPU time of agents to their time budget instead of wall-clock time (default: `false`)
+//! - `EVAL_ALLOW_EMPTY_AGENT_DIR` — Only warn when no agent is found instead of failing (default: `false`)
+//! - `EVAL_KEEP_BINARIES` — Copy compiled agents to this directory (default: unset)
+//! - `EVAL_SHUFFLE_SEED` — Shuffle the matches of each round with this seed (default: unset)
+//! - `EVAL_SEED` — Give each agent a seed derived from this one for each match (default: unset)
+//! - `EVAL_MATCH_RETRIES` — Number of times a match failing for transient reasons is replayed (default: `0`)
+//! - `EVAL_MAX_CONCURRENT_MATCHES` — Maximum number of matches running at the same time (default: unset)
+//! - `EVAL_DECISION_LOG_DIR` — Write the `(state, action)` pairs of each agent to this directory (default: unset)
+//! - `EVAL_EVENT_LOG` — Append the events of the tournament to this JSON Lines file (default: unset)
+//! - `EVAL_REPLAY_DIR` — Write the turns of each match to this directory (default: unset)
+//! - `EVAL_DETECT_DUPLICATES` — Exclude agents identical to another agent (default: `false`)
+//! - `EVAL_CGROUP_PARENT` — Parent of the agents' cgroups, relative to the cgroup root (default: systemd user service)
+//! - `EVAL_VERIFY_GAME_STATE` — Call `Game::verify` after every action (debug purposes only) (default: `false`)
+//! - `EVAL_END_ON_ELIMINATION` — End two-player matches as soon as a player is eliminated (default: `false`)
+//! - `EVAL_ENFORCE_MAX_TURNS_HINT` — Stop matches reaching the game's `max_turns_hint` (default: `false`)
+//! - `EVAL_PORT_RANGE` — Ports agents connect to, as `start..end` (default: ephemeral ports)
+//! - `EVAL_EXTRA_AGENT_ARGS` — Arguments given to every agent after its own, separated by whitespace (default: none)
+//! - `EVAL_FAIL_ON_COMPILE_ERROR` — Abort the evaluation when an agent fails to compile (default: `false`)
+//! - `EVAL_COMPILE_TIMEOUT_SECS` — Time limit of the compilation of each agent, in seconds (default: unset)
+//! - `EVAL_OFFLINE_COMPILE` — Compile agents with `cargo build --offline --locked` (default: `false`)
+//! - `EVAL_ALLOWED_CRATES` — Only compile agents depending on these crates, separated by commas or whitespace (default: unset)
+//! - `EVAL_HANDSHAKE_GRACE_MS` — How long agents have to acknowledge the protocol after connecting, in milliseconds (default: `100`)
 
-use std::path::{Path, PathBuf};
+use std::{
+    ops::Range,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
 
 /// Configuration for evaluator behaviors.
 #[derive(Debug, Clone)]
 pub struct Configuration {
     pub(crate) verbose: bool,
     pub(crate) log_dir: Option<PathBuf>,
+    pub(crate) append_logs: bool,
     pub(crate) allow_uncontained: bool,
     pub(crate) compile_agents: bool,
     pub(crate) self_test: bool,
+    pub(crate) self_test_path: Option<PathBuf>,
+    pub(crate) eval_config: Option<String>,
     pub(crate) test_all_configs: bool,
     pub(crate) debug_agent_stderr: bool,
+    pub(crate) max_message_size: usize,
+    pub(crate) framed_protocol: bool,
+    pub(crate) end_notification: bool,
+    pub(crate) cpu_time_budget: bool,
+    pub(crate) allow_empty_agent_dir: bool,
+    pub(crate) verify_game_state: bool,
+    pub(crate) end_on_elimination: bool,
+    pub(crate) enforce_max_turns_hint: bool,
+    pub(crate) keep_binaries: Option<PathBuf>,
+    pub(crate) shuffle_seed: Option<u64>,
+    pub(crate) seed: Option<u64>,
+    /// Seed given to the agents instead of the derived one (see `Evaluator::rerun_match`)
+    pub(crate) match_seed: Option<u64>,
+    pub(crate) match_retries: usize,
+    pub(crate) max_concurrent_matches: Option<usize>,
+    pub(crate) decision_log_dir: Option<PathBuf>,
+    pub(crate) event_log: Option<PathBuf>,
+    pub(crate) replay_dir: Option<PathBuf>,
+    pub(crate) detect_duplicates: bool,
+    pub(crate) penalties: Penalties,
+    pub(crate) eliminated_score: EliminatedScore,
+    pub(crate) cgroup_parent: Option<String>,
+    pub(crate) binary_discovery: Option<BinaryDiscovery>,
+    pub(crate) stderr_callback: Option<StderrCallback>,
+    pub(crate) port_range: Option<Range<u16>>,
+    pub(crate) extra_agent_args: Vec<String>,
+    pub(crate) fail_on_compile_error: bool,
+    pub(crate) compile_timeout: Option<Duration>,
+    pub(crate) offline_compile: bool,
+    pub(crate) allowed_crates: Option<Vec<String>>,
+    pub(crate) handshake_grace_period: Duration,
+}
+
+/// Finds the binary of an agent and its arguments in an agent directory, see
+/// [`Configuration::with_binary_discovery`].
+pub type BinaryDiscoveryFn = dyn Fn(&Path) -> anyhow::Result<(PathBuf, Vec<String>)> + Send + Sync;
+
+#[derive(Clone)]
+pub(crate) struct BinaryDiscovery(pub(crate) Arc<BinaryDiscoveryFn>);
+
+impl std::fmt::Debug for BinaryDiscovery {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("BinaryDiscovery")
+    }
+}
+
+/// Receives the stderr of agents line by line, see [`Configuration::with_stderr_callback`].
+pub type StderrCallbackFn = dyn Fn(&str, &str) + Send + Sync;
+
+#[derive(Clone)]
+pub(crate) struct StderrCallback(pub(crate) Arc<StderrCallbackFn>);
+
+impl std::fmt::Debug for StderrCallback {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("StderrCallback")
+    }
+}
+
+/// Penalty points given to an agent for each rule violation (see
+/// [`Configuration::with_penalties`]).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Penalties {
+    /// Action that could not be parsed, was not valid UTF-8, or was rejected by the game.
+    pub illegal_action: u32,
+    /// No response within the action timeout or the remaining time budget.
+    pub timeout: u32,
+    /// Connection closed by the agent during the match, or agent killed for exceeding its memory
+    /// limit.
+    pub crash: u32,
+}
+
+/// Score recorded for agents eliminated from a match (see
+/// [`Configuration::with_eliminated_score`]).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EliminatedScore {
+    /// The score given by the game, which is trusted to model the elimination.
+    #[default]
+    Game,
+    /// The worst score of the match, among the scores given by the game to all players: an
+    /// eliminated agent ties at best with the worst player. This is the lowest score, or the
+    /// highest one when the strategy ranks lower scores first (see
+    /// [`TournamentStrategy::higher_is_better`](crate::tournament_strategy::TournamentStrategy::higher_is_better)).
+    Minimum,
+    /// A fixed number of points, converted with
+    /// [`MatchScore::from_points`](crate::game_interface::MatchScore::from_points).
+    Fixed(i64),
 }
 
 impl Configuration {
@@ -41,17 +163,66 @@ impl Configuration {
     /// - Unsafe fallbacks (e.g. skipping taskset or cgroup checks) are not allowed.
     /// - Agents will be compiled before execution.
     /// - Self-test mode is disabled (expects multiple agents).
-    /// - Only the 'eval' configuration will be tested.
+    /// - Only the 'eval' configuration of each agent will be tested.
     /// - Agent stderr output is disabled.
+    /// - Agent stderr is not delivered to any callback.
+    /// - An agent directory without any agent is an error.
+    /// - Game state verification is disabled.
+    /// - Matches go on until the game ends when a player is eliminated.
+    /// - Matches are not stopped at the game's max turns hint.
+    /// - Compiled binaries are not copied anywhere.
+    /// - Matches run in the order generated by the tournament strategy.
+    /// - Matches failing for transient reasons are not replayed.
+    /// - Decisions are not written to a dataset.
+    /// - Duplicate agents are not detected.
+    /// - Rule violations give no penalty.
+    /// - Eliminated agents get the score given by the game.
+    /// - Agents' cgroups are created under the systemd user service of the current user.
+    /// - Agents connect to ephemeral ports chosen by the OS.
+    /// - Agents only get the arguments of their config.
+    /// - Agents failing to compile are skipped.
     pub fn new() -> Self {
         Self {
             verbose: true,
             log_dir: None,
+            append_logs: false,
             allow_uncontained: false,
             compile_agents: true,
             self_test: false,
+            self_test_path: None,
+            eval_config: None,
             test_all_configs: false,
             debug_agent_stderr: false,
+            max_message_size: 4096,
+            framed_protocol: false,
+            end_notification: false,
+            cpu_time_budget: false,
+            allow_empty_agent_dir: false,
+            verify_game_state: false,
+            end_on_elimination: false,
+            enforce_max_turns_hint: false,
+            keep_binaries: None,
+            shuffle_seed: None,
+            seed: None,
+            match_seed: None,
+            match_retries: 0,
+            max_concurrent_matches: None,
+            decision_log_dir: None,
+            event_log: None,
+            replay_dir: None,
+            detect_duplicates: false,
+            penalties: Penalties::default(),
+            eliminated_score: EliminatedScore::Game,
+            cgroup_parent: None,
+            binary_discovery: None,
+            stderr_callback: None,
+            port_range: None,
+            extra_agent_args: vec![],
+            fail_on_compile_error: false,
+            compile_timeout: None,
+            offline_compile: false,
+            allowed_crates: None,
+            handshake_grace_period: Duration::from_millis(100),
         }
     }
 
@@ -60,11 +231,39 @@ impl Configuration {
     /// The following environment variables are recognized:
     /// - `EVAL_VERBOSE`: if set to `"true"`, enables verbose output (default: `true`)
     /// - `EVAL_LOG_DIR`: if set, enables logging to the given directory path (must be valid)
+    /// - `EVAL_APPEND_LOGS`: if set to `"true"`, keeps the logs of previous runs (default: `false`)
     /// - `EVAL_ALLOW_UNCONTAINED`: if set to `"true"`, allows unsafe fallbacks (default: `false`)
     /// - `EVAL_COMPILE_AGENTS`: if set to `"true"`, enables agent compilation (default: `true`)
     /// - `EVAL_SELF_TEST`: if set to `"true"`, enables self-test mode (default: `false`)
+    /// - `EVAL_SELF_TEST_PATH`: if set, the agent directory used in self-test mode (default: current directory)
+    /// - `EVAL_CONFIG_NAME`: if set, the config with this name is evaluated instead of the `eval:` key of each agent
     /// - `EVAL_TEST_ALL_CONFIGS`: if set to `"true"`, enables testing all configurations (default: `false`)
     /// - `EVAL_DEBUG_AGENT_STDERR`: if set to `"true"`, enables agent stderr debug output (default: `false`)
+    /// - `EVAL_MAX_MESSAGE_SIZE`: if set to an integer, actions of up to this many bytes are read (default: `4096`)
+    /// - `EVAL_FRAMED_PROTOCOL`: if set to `"true"`, messages are prefixed with their length (default: `false`)
+    /// - `EVAL_END_NOTIFICATION`: if set to `"true"`, agents are sent `GAME_OVER` at the end of a match (default: `false`)
+    /// - `EVAL_CPU_TIME_BUDGET`: if set to `"true"`, the CPU time of agents is charged to their time budget (default: `false`)
+    /// - `EVAL_ALLOW_EMPTY_AGENT_DIR`: if set to `"true"`, an empty agent directory only emits a warning (default: `false`)
+    /// - `EVAL_KEEP_BINARIES`: if set, compiled agents are copied to the given directory path
+    /// - `EVAL_SHUFFLE_SEED`: if set to an integer, shuffles the matches of each round with this seed
+    /// - `EVAL_SEED`: if set to an integer, each agent is given a seed derived from it for each match
+    /// - `EVAL_MATCH_RETRIES`: if set to an integer, replays matches failing for transient reasons up to this many times (default: `0`)
+    /// - `EVAL_MAX_CONCURRENT_MATCHES`: if set to an integer, at most this many matches run at the same time
+    /// - `EVAL_DECISION_LOG_DIR`: if set, the `(state, action)` pairs of each agent are written to the given directory path
+    /// - `EVAL_EVENT_LOG`: if set, the events of the tournament are appended to the given file path
+    /// - `EVAL_REPLAY_DIR`: if set, the turns of each match are written to the given directory path
+    /// - `EVAL_DETECT_DUPLICATES`: if set to `"true"`, agents identical to another agent are excluded (default: `false`)
+    /// - `EVAL_CGROUP_PARENT`: if set, the agents' cgroups are created under this cgroup (relative to the cgroup root)
+    /// - `EVAL_VERIFY_GAME_STATE`: if set to `"true"`, calls `Game::verify` after every action (default: `false`)
+    /// - `EVAL_END_ON_ELIMINATION`: if set to `"true"`, two-player matches end as soon as a player is eliminated (default: `false`)
+    /// - `EVAL_ENFORCE_MAX_TURNS_HINT`: if set to `"true"`, matches are stopped when they reach the game's max turns hint (default: `false`)
+    /// - `EVAL_PORT_RANGE`: if set to `start..end` (e.g. `20000..20100`), agents connect to ports of this range
+    /// - `EVAL_EXTRA_AGENT_ARGS`: if set, arguments (separated by whitespace) given to every agent after its own
+    /// - `EVAL_FAIL_ON_COMPILE_ERROR`: if set to `"true"`, the evaluation is aborted when an agent fails to compile (default: `false`)
+    /// - `EVAL_COMPILE_TIMEOUT_SECS`: if set to an integer, the compilation of an agent taking longer than this many seconds fails
+    /// - `EVAL_OFFLINE_COMPILE`: if set to `"true"`, agents are compiled with `--offline --locked` (default: `false`)
+    /// - `EVAL_ALLOWED_CRATES`: if set, agents depending on other crates (separated by commas or whitespace) fail to compile
+    /// - `EVAL_HANDSHAKE_GRACE_MS`: if set to an integer, agents have this many milliseconds to acknowledge the protocol (default: `100`)
     ///
     /// Any other value (including unset) will result in using the default value for each field.
     pub fn from_env() -> Self {
@@ -83,11 +282,71 @@ impl Configuration {
         Self {
             verbose: get_env_flag("EVAL_VERBOSE", true),
             log_dir,
+            append_logs: get_env_flag("EVAL_APPEND_LOGS", false),
             allow_uncontained: get_env_flag("EVAL_ALLOW_UNCONTAINED", false),
             compile_agents: get_env_flag("EVAL_COMPILE_AGENTS", true),
             self_test: get_env_flag("EVAL_SELF_TEST", false),
+            self_test_path: std::env::var("EVAL_SELF_TEST_PATH").ok().map(PathBuf::from),
+            eval_config: std::env::var("EVAL_CONFIG_NAME").ok(),
             test_all_configs: get_env_flag("EVAL_TEST_ALL_CONFIGS", false),
             debug_agent_stderr: get_env_flag("EVAL_DEBUG_AGENT_STDERR", false),
+            max_message_size: std::env::var("EVAL_MAX_MESSAGE_SIZE")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(4096),
+            framed_protocol: get_env_flag("EVAL_FRAMED_PROTOCOL", false),
+            end_notification: get_env_flag("EVAL_END_NOTIFICATION", false),
+            cpu_time_budget: get_env_flag("EVAL_CPU_TIME_BUDGET", false),
+            allow_empty_agent_dir: get_env_flag("EVAL_ALLOW_EMPTY_AGENT_DIR", false),
+            verify_game_state: get_env_flag("EVAL_VERIFY_GAME_STATE", false),
+            end_on_elimination: get_env_flag("EVAL_END_ON_ELIMINATION", false),
+            enforce_max_turns_hint: get_env_flag("EVAL_ENFORCE_MAX_TURNS_HINT", false),
+            keep_binaries: std::env::var("EVAL_KEEP_BINARIES").ok().map(PathBuf::from),
+            shuffle_seed: std::env::var("EVAL_SHUFFLE_SEED")
+                .ok()
+                .and_then(|s| s.parse().ok()),
+            seed: std::env::var("EVAL_SEED").ok().and_then(|s| s.parse().ok()),
+            match_seed: None,
+            match_retries: std::env::var("EVAL_MATCH_RETRIES")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(0),
+            max_concurrent_matches: std::env::var("EVAL_MAX_CONCURRENT_MATCHES")
+                .ok()
+                .and_then(|s| s.parse().ok()),
+            decision_log_dir: std::env::var("EVAL_DECISION_LOG_DIR")
+                .ok()
+                .map(PathBuf::from),
+            event_log: std::env::var("EVAL_EVENT_LOG").ok().map(PathBuf::from),
+            replay_dir: std::env::var("EVAL_REPLAY_DIR").ok().map(PathBuf::from),
+            detect_duplicates: get_env_flag("EVAL_DETECT_DUPLICATES", false),
+            penalties: Penalties::default(),
+            eliminated_score: EliminatedScore::Game,
+            cgroup_parent: std::env::var("EVAL_CGROUP_PARENT").ok(),
+            binary_discovery: None,
+            stderr_callback: None,
+            port_range: std::env::var("EVAL_PORT_RANGE")
+                .ok()
+                .and_then(|s| parse_port_range(&s)),
+            extra_agent_args: std::env::var("EVAL_EXTRA_AGENT_ARGS")
+                .map(|s| s.split_whitespace().map(String::from).collect())
+                .unwrap_or_default(),
+            fail_on_compile_error: get_env_flag("EVAL_FAIL_ON_COMPILE_ERROR", false),
+            compile_timeout: std::env::var("EVAL_COMPILE_TIMEOUT_SECS")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .map(Duration::from_secs),
+            offline_compile: get_env_flag("EVAL_OFFLINE_COMPILE", false),
+            allowed_crates: std::env::var("EVAL_ALLOWED_CRATES").ok().map(|s| {
+                s.split(|c: char| c == ',' || c.is_whitespace())
+                    .filter(|name| !name.is_empty())
+                    .map(String::from)
+                    .collect()
+            }),
+            handshake_grace_period: std::env::var("EVAL_HANDSHAKE_GRACE_MS")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .map_or(Duration::from_millis(100), Duration::from_millis),
         }
     }
 
@@ -101,14 +360,16 @@ impl Configuration {
     ///
     /// # Warning
     ///
-    /// **This method will permanently delete all contents (files and subdirectories)**
-    /// in the specified directory before using it for logging. Make absolutely sure
+    /// **Creating the [`Evaluator`](crate::server::Evaluator) will permanently delete all contents
+    /// (files and subdirectories)** in the specified directory before using it for logging, unless
+    /// [`with_append_logs`](Self::with_append_logs) is enabled. Make absolutely sure
     /// that the provided path does not point to a directory containing important data.
     ///
     /// # Panics
     ///
     /// - Panics if the provided path is not a valid directory and cannot be created.
-    /// - Panics if it fails to delete any contents in the directory.
+    /// - [`Evaluator::new`](crate::server::Evaluator::new) panics if it fails to delete any
+    ///   contents in the directory.
     pub fn with_log<P: AsRef<Path>>(mut self, path: P) -> Self {
         let path = path.as_ref();
 
@@ -121,6 +382,17 @@ impl Configuration {
             panic!("Logging path must be a valid directory: {}", path.display());
         }
 
+        self.log_dir = Some(path.to_path_buf());
+        self
+    }
+
+    /// Deletes the contents of the log directory, unless logs are appended (see
+    /// [`with_log`](Self::with_log)).
+    pub(crate) fn clear_log_dir(&self) {
+        let Some(path) = self.log_dir.as_deref().filter(|_| !self.append_logs) else {
+            return;
+        };
+
         // WARNING: Delete all contents inside the directory
         for entry in std::fs::read_dir(path).unwrap_or_else(|e| {
             panic!(
@@ -146,8 +418,18 @@ impl Configuration {
                 });
             }
         }
+    }
 
-        self.log_dir = Some(path.to_path_buf());
+    /// Keep the logs of previous runs instead of deleting them.
+    ///
+    /// By default, the log directory of each agent (`<log dir>/<agent name>/`) is emptied when the
+    /// agent is collected. When enabled, match logs of each run go to a new subdirectory named
+    /// after the time of the run instead (e.g. `<log dir>/<agent name>/2025-01-31_14-05-00/`), to
+    /// compare runs or investigate past failures. Old runs are never cleaned up, and the log
+    /// directory is not emptied when the [`Evaluator`](crate::server::Evaluator) is created (see
+    /// [`with_log`](Self::with_log)).
+    pub fn with_append_logs(mut self, value: bool) -> Self {
+        self.append_logs = value;
         self
     }
 
@@ -158,20 +440,62 @@ impl Configuration {
     }
 
     /// Enable or disable agent compilation.
+    ///
+    /// Agents are compiled in parallel, on at most one thread per CPU.
     pub fn with_compile_agents(mut self, value: bool) -> Self {
         self.compile_agents = value;
         self
     }
 
+    /// Replace the built-in collection of agent binaries, for agent directories with an unusual
+    /// layout. Only used when agents are not compiled.
+    ///
+    /// By default, an agent directory must contain exactly two files: a YAML config file, and the
+    /// binary. With a discovery function, `discovery` is called with each candidate agent
+    /// directory (each subdirectory of the agent directory, or the self-test directory), and
+    /// returns the path to the binary and the arguments to launch it with. Returning an error
+    /// marks the agent as failed to collect, with that error.
+    ///
+    /// The config file is then not read: the agent has no team nor launch command template, and
+    /// [`with_test_all_configs`](Self::with_test_all_configs) is ignored.
+    pub fn with_binary_discovery(
+        mut self,
+        discovery: impl Fn(&Path) -> anyhow::Result<(PathBuf, Vec<String>)> + Send + Sync + 'static,
+    ) -> Self {
+        self.binary_discovery = Some(BinaryDiscovery(Arc::new(discovery)));
+        self
+    }
+
     /// Enable or disable self-test mode.
     ///
-    /// When enabled, evaluates a single agent in the CURRENT directory.
+    /// When enabled, evaluates a single agent in the CURRENT directory (or in the directory set
+    /// with [`with_self_test_path`](Self::with_self_test_path)).
     /// When disabled, expects multiple agents in the given directory.
     pub fn with_self_test(mut self, value: bool) -> Self {
         self.self_test = value;
         self
     }
 
+    /// Set the agent directory evaluated in self-test mode.
+    ///
+    /// Defaults to the current directory. Has no effect unless self-test mode is enabled.
+    pub fn with_self_test_path<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.self_test_path = Some(path.into());
+        self
+    }
+
+    /// Evaluate the config named `name` of each agent, instead of the one of its `eval:` key.
+    ///
+    /// Precedence, from highest to lowest: this method, the `EVAL_CONFIG_NAME` environment
+    /// variable (read by [`from_env`](Self::from_env)), the `eval:` key of the agent's config
+    /// file. Agents without a config of that name are reported like agents that failed to
+    /// compile. Ignored when testing all configs (see
+    /// [`with_test_all_configs`](Self::with_test_all_configs)).
+    pub fn with_eval_config(mut self, name: impl Into<String>) -> Self {
+        self.eval_config = Some(name.into());
+        self
+    }
+
     /// Enable or disable testing all configurations.
     ///
     /// When enabled, tests every available configuration.
@@ -188,6 +512,450 @@ impl Configuration {
         self
     }
 
+    /// Read actions of up to `size` bytes (at least one), `4096` by default.
+    ///
+    /// Without [framing](Self::with_framed_protocol), each action is read with a single read into
+    /// a buffer of this size: longer actions are truncated, and usually fail to parse. States are
+    /// always sent entirely, whatever their size.
+    pub fn with_max_message_size(mut self, size: usize) -> Self {
+        self.max_message_size = size.max(1);
+        self
+    }
+
+    /// Enable or disable length-prefixed messages.
+    ///
+    /// By default, states are sent as is, and each action is read with a single read of at most
+    /// [`max_message_size`](Self::with_max_message_size) bytes, so longer actions are truncated. When enabled, the `framed/1` protocol is
+    /// announced to agents instead of `text/1`, and states and actions are both preceded by their
+    /// length in bytes, as a 4-byte big-endian integer, so that messages of any size are read
+    /// entirely (see the crate documentation about the protocol handshake).
+    pub fn with_framed_protocol(mut self, value: bool) -> Self {
+        self.framed_protocol = value;
+        self
+    }
+
+    /// How long agents have to acknowledge the protocol after connecting (100ms by default), see
+    /// the crate documentation about the protocol handshake.
+    ///
+    /// Agents sending nothing during this period are legacy agents: each of their launches waits
+    /// for the whole period, so a shorter one speeds up matches between legacy agents, and a
+    /// longer one suits loaded machines. An agent acknowledging after it fails to start (the
+    /// match is retried with [`with_match_retries`](Self::with_match_retries)), rather than
+    /// having its `ACK` read as its first action. When zero, the handshake is skipped and agents
+    /// must not acknowledge.
+    pub fn with_handshake_grace_period(mut self, period: Duration) -> Self {
+        self.handshake_grace_period = period;
+        self
+    }
+
+    /// Enable or disable the end of match notification.
+    ///
+    /// By default, agents still playing at the end of a match are killed right away. When enabled,
+    /// they are first sent a `GAME_OVER` message instead of a state, and are given a short grace
+    /// period (500ms) to exit cleanly, e.g. to save learned data, before being killed. Agents
+    /// eliminated during the match are still killed right away.
+    pub fn with_end_notification(mut self, value: bool) -> Self {
+        self.end_notification = value;
+        self
+    }
+
+    /// Measure the time budget of agents in CPU time instead of wall-clock time.
+    ///
+    /// By default, each turn is charged to the time budget of the agent by the clock of the
+    /// server, which includes scheduling delays and so depends on the load of the machine. When
+    /// enabled, the CPU time used by the agent during its turn is charged instead, read from the
+    /// `cpu.stat` of its cgroup, so that results do not depend on how many matches run at the same
+    /// time. The CPU time of all the threads of the agent is counted: an agent running on several
+    /// cpus uses up its budget faster than the clock.
+    ///
+    /// The action timeout is still measured in wall-clock time: an agent is waited for until its
+    /// action timeout even if its remaining budget is shorter, and its action is rejected as a
+    /// timeout if it used more CPU time than its remaining budget (plus the time margin). Agents
+    /// running without container, or whose CPU time cannot be read (on cgroups v1, or on platforms
+    /// without cgroups), are still charged wall-clock time.
+    pub fn with_cpu_time_budget(mut self, value: bool) -> Self {
+        self.cpu_time_budget = value;
+        self
+    }
+
+    /// Deliver the stderr of agents to `callback`, line by line, while matches are played (e.g. to
+    /// show agent warnings on a live dashboard). `callback` is called with the agent name and the
+    /// line, without its line break.
+    ///
+    /// Lines are still written to the agent's log file, and printed if
+    /// [`with_debug_agent_stderr`](Self::with_debug_agent_stderr) is enabled.
+    ///
+    /// # Performance
+    ///
+    /// Stderr is captured in a pipe, read by one thread per agent and per match, on which
+    /// `callback` is called: it must be fast. An agent writing to stderr faster than the lines are
+    /// read blocks once the pipe is full, which counts against its time.
+    pub fn with_stderr_callback(
+        mut self,
+        callback: impl Fn(&str, &str) + Send + Sync + 'static,
+    ) -> Self {
+        self.stderr_callback = Some(StderrCallback(Arc::new(callback)));
+        self
+    }
+
+    /// Listen for agents on ports of `range` (e.g. `20000..20100`) instead of ephemeral ports
+    /// chosen by the OS, e.g. if only some ports are allowed, or if ephemeral ports run out under
+    /// high match concurrency.
+    ///
+    /// A port is only used while an agent starts, until it connects. When all ports of the range
+    /// are in use, agents wait for a port to be released, for up to the startup timeout.
+    pub fn with_port_range(mut self, range: Range<u16>) -> Self {
+        self.port_range = Some(range);
+        self
+    }
+
+    /// Give `args` to every agent, after the arguments of its config, e.g. for quick experiments
+    /// without editing YAML files.
+    ///
+    /// Agents are launched as `<binary> <port> <time budget> <action timeout> <config args...>
+    /// <extra args...>`. With a `command:` template, extra arguments follow the config arguments
+    /// where `{args}` is expanded. Agents which do not expect them should ignore unknown arguments.
+    pub fn with_extra_agent_args(mut self, args: Vec<String>) -> Self {
+        self.extra_agent_args = args;
+        self
+    }
+
+    /// Abort the evaluation on the first agent failing to compile, instead of skipping it and
+    /// running the tournament without it, e.g. for a CI check that all submissions build.
+    ///
+    /// Agent collection then returns an error naming the agent, with the compilation error: no
+    /// match is played. Other collection errors (invalid config file, duplicates...) still only
+    /// skip the agent.
+    pub fn with_fail_on_compile_error(mut self, value: bool) -> Self {
+        self.fail_on_compile_error = value;
+        self
+    }
+
+    /// Time limit of the compilation of each agent (cargo, or the `build:` command of agents
+    /// declaring a `run:` command), unlimited by default.
+    ///
+    /// A compilation exceeding it is killed, along with the processes it started (build scripts,
+    /// compilers...) on unix, and the agent fails to compile, e.g. when a build script hangs.
+    pub fn with_compile_timeout(mut self, timeout: Duration) -> Self {
+        self.compile_timeout = Some(timeout);
+        self
+    }
+
+    /// Enable or disable offline compilation.
+    ///
+    /// When enabled, agents are compiled with `cargo build --offline --locked`: nothing is
+    /// downloaded, so agents can only use the crates already in the local cargo cache, and their
+    /// `Cargo.lock` must be up to date. Agents declaring a `run:` command, whose commands could
+    /// download anything, then fail to compile.
+    pub fn with_offline_compile(mut self, value: bool) -> Self {
+        self.offline_compile = value;
+        self
+    }
+
+    /// Only compile agents whose dependencies are all in `crates` (by crate name, `-` and `_`
+    /// being equivalent).
+    ///
+    /// Dependencies are read from the `Cargo.lock` of each agent, which is then required, before
+    /// compiling it: an agent depending on another crate fetched from a registry or a git
+    /// repository fails to compile, without running any build script. Crates local to the agent
+    /// (path dependencies) are always allowed, but the paths of its manifests (`Cargo.toml`
+    /// files) must not lead outside of the agent directory. Agents are compiled with `--locked`, so that cargo
+    /// cannot add dependencies to the checked `Cargo.lock`. Agents declaring a `run:` command,
+    /// whose commands could use anything, then fail to compile.
+    pub fn with_allowed_crates(mut self, crates: Vec<String>) -> Self {
+        self.allowed_crates = Some(crates);
+        self
+    }
+
+    /// Keep compiled agents in the given output directory.
+    ///
+    /// After a successful compilation, each agent's `eval` binary is copied to
+    /// `<path>/<agent name>/<agent name>`, along with its YAML config file. The output directory
+    /// can then be evaluated again without compiling (see
+    /// [`with_compile_agents`](Self::with_compile_agents)). The directory is created if needed, and
+    /// existing files with the same names are overwritten.
+    ///
+    /// Has no effect when agents are not compiled, nor on agents declaring a `run:` command.
+    pub fn with_keep_binaries<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.keep_binaries = Some(path.into());
+        self
+    }
+
+    /// Run the matches of each round in a randomized order, shuffled with the given seed.
+    ///
+    /// When resources are limited, matches run in the order they are generated by the tournament
+    /// strategy, so the same matchups always run first. Shuffling reduces this systematic
+    /// ordering bias (e.g. for timing measurements sensitive to shared caches). The order only
+    /// depends on the seed and the round, not on thread timings: the same seed gives the same
+    /// order.
+    pub fn with_shuffled_matches(mut self, seed: u64) -> Self {
+        self.shuffle_seed = Some(seed);
+        self
+    }
+
+    /// Give each agent a seed for its random choices, derived from `seed`, to make tournaments
+    /// reproducible (e.g. to replay the match an agent lost, with the same random draws).
+    ///
+    /// The seed of a match is derived from `seed`, the round and the index of the match: each
+    /// match gets its own seed, and a match gets the same seed in every run with the same `seed`
+    /// (and when it is replayed after a transient failure). All players of a match get the same
+    /// seed, as an extra argument right after the action timeout:
+    /// `<binary> <port> <budget_us> <action_us> <seed> <args...>`. Agents using a command
+    /// template get it with the `{seed}` placeholder.
+    ///
+    /// Without a seed (the default), no argument is added. Agents which do not use randomness can
+    /// ignore the argument, but agents reading their own arguments by position must expect it.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Replay a match up to `retries` times when it fails for transient reasons.
+    ///
+    /// A match fails transiently when an agent could not be started or when the connection with
+    /// an agent broke for reasons outside of its control (e.g. connection refused, interrupted
+    /// system call). Timeouts, crashes during the game and invalid actions are legitimate losses
+    /// and are never replayed.
+    /// Only the last attempt is recorded, once it succeeded or no retry remains.
+    pub fn with_match_retries(mut self, retries: usize) -> Self {
+        self.match_retries = retries;
+        self
+    }
+
+    /// Run at most `max` matches at the same time (at least one).
+    ///
+    /// By default, the number of concurrent matches is only bounded by the CPUs and RAM of the
+    /// [`Constraints`](crate::constraints::Constraints): each match takes the CPUs and RAM of its
+    /// agents. This cap applies on top of them, e.g. to throttle an uncontained evaluation on a
+    /// big machine, where agents are not actually restricted to their CPUs.
+    pub fn with_max_concurrent_matches(mut self, max: usize) -> Self {
+        self.max_concurrent_matches = Some(max);
+        self
+    }
+
+    /// Write every decision of the agents to a dataset in the given directory (e.g. to train
+    /// models by imitation).
+    ///
+    /// Each agent gets its own [JSON Lines](https://jsonlines.org) file, `<path>/<agent name>.jsonl`,
+    /// with one object per accepted action:
+    ///
+    /// ```json
+    /// {"agent":"my_agent","match":"1-3","player":0,"turn":5,"state":"...","action":"..."}
+    /// ```
+    ///
+    /// - `agent`: agent name (including the config name when testing all configs)
+    /// - `match`: `<round>-<index>` of the match, unique within a tournament
+    /// - `player`: player number of the agent in the match
+    /// - `turn`: turn number in the match, starting at 1
+    /// - `state`: state sent to the agent, as sent on the wire
+    /// - `action`: action played, as formatted by the game
+    ///
+    /// Rejected or invalid actions are not written, nor are matches that failed for transient
+    /// reasons (see [`with_match_retries`](Self::with_match_retries)). Files are appended to: clear
+    /// the directory between tournaments. The directory is created if needed.
+    pub fn with_decision_log<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.decision_log_dir = Some(path.into());
+        self
+    }
+
+    /// Write a replay of each match to the given directory, to debug the moves of the agents.
+    ///
+    /// Like logs, replays are sorted in one subdirectory per agent: each player of a match gets a
+    /// copy of its replay, `<path>/<agent name>/match_<round>-<index>.jsonl`. Each line is a turn,
+    /// in the [JSON Lines](https://jsonlines.org) format:
+    ///
+    /// ```json
+    /// {"turn":5,"player":0,"state":"...","action":"...","error":null}
+    /// ```
+    ///
+    /// - `turn`: turn number, starting at 1
+    /// - `player`: player number of the player to play
+    /// - `state`: state sent to the player, as sent on the wire
+    /// - `action`: action played, as formatted by the game (`null` if the player sent no valid
+    ///   action, e.g. it timed out or was already eliminated)
+    /// - `error`: what went wrong during the turn (timeout, invalid or rejected action...), `null`
+    ///   if nothing did
+    ///
+    /// A replayed match (see [`with_match_retries`](Self::with_match_retries)) overwrites the
+    /// replay of its failed attempt. The directory is created if needed.
+    pub fn with_replays<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.replay_dir = Some(path.into());
+        self
+    }
+
+    /// Append the events of the tournament to the given file, to replay or audit it afterwards.
+    ///
+    /// The file is in the [JSON Lines](https://jsonlines.org) format: one object per event, in the
+    /// order they happened, each with its `event` kind and the `elapsed_ms` since the start of the
+    /// tournament:
+    ///
+    /// ```json
+    /// {"event":"tournament_start","elapsed_ms":0,"directory":"agents","shuffle_seed":7,"cpus":[0,1,2,3],"total_ram":8589934592}
+    /// {"event":"agent","elapsed_ms":812,"name":"my_agent","id":0,"args":[],"working":true,"error":null,"late":false}
+    /// {"event":"match_start","elapsed_ms":815,"round":1,"index":0,"players":["my_agent","other"],"cpus":[0,1],"ram":1073741824,"weight":1.0,"seed":null}
+    /// {"event":"match_result","elapsed_ms":2410,"round":1,"index":0,"players":["my_agent","other"],"args":[[],[]],"scores":["1","0"],"errors":"","startup_ms":[12,15],"penalties":[0,0],"turns":42,"weight":1.0,"retried":false,"progress":{"matches_done":1,"matches_total_estimate":2,"round":1}}
+    /// {"event":"standings","elapsed_ms":4020,"ranking":[{"place":1,"agents":["my_agent"]},{"place":2,"agents":["other"]}]}
+    /// {"event":"tournament_end","elapsed_ms":4021,"total_matches":2,"rounds":1,"byes":0}
+    /// ```
+    ///
+    /// - `tournament_start`: agent directory, [shuffle seed](Self::with_shuffled_matches) (`null`
+    ///   if unset), CPUs and RAM (in bytes) available to the tournament
+    /// - `agent`: one per collected agent, working or not (`error` is then its error message);
+    ///   `late` for agents added during the tournament
+    /// - `match_start`: players in play order, the CPUs and RAM allocated to the match, and the
+    ///   seed given to its agents (see [`with_seed`](Self::with_seed); `null` if unset)
+    /// - `match_result`: the fields of [`MatchRecord`](crate::results::MatchRecord), scores being
+    ///   formatted by the game, `retried` for matches failing for transient reasons and played
+    ///   again, and the [`Progress`](crate::server::Progress) of the tournament after the match
+    /// - `standings`: final places, best first, tied agents sharing a place
+    /// - `tournament_end`: number of matches (retries excluded), rounds and byes
+    ///
+    /// Each event is appended with a single write as soon as it happens, and the file is never
+    /// rewritten: if the evaluator crashes, the log holds every event until the crash (a reader
+    /// should skip an incomplete last line). Events of successive tournaments follow each other
+    /// in the same file, each starting with `tournament_start`.
+    pub fn with_event_log<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.event_log = Some(path.into());
+        self
+    }
+
+    /// Enable or disable the detection of duplicate agents (e.g. the same bot submitted twice under
+    /// different names).
+    ///
+    /// Agents are compared by content: the files of their `src` directory when agents are compiled
+    /// (compiled binaries depend on the crate location), their binary otherwise. Agents with the
+    /// same content and the same arguments are duplicates. In each group of duplicates, only the
+    /// agent whose name comes first alphabetically plays; the others are reported like agents that
+    /// failed to compile, with a `duplicate of '<name>'` message.
+    pub fn with_detect_duplicates(mut self, value: bool) -> Self {
+        self.detect_duplicates = value;
+        self
+    }
+
+    /// Give penalty points to agents violating the rules, instead of only eliminating them.
+    ///
+    /// Penalties are counted per match, in play order, in
+    /// [`MatchContext::penalties`](crate::game_interface::MatchContext::penalties) and
+    /// [`MatchRecord::penalties`](crate::results::MatchRecord::penalties), and summed per agent in
+    /// [`EvaluationReport::penalties`](crate::results::EvaluationReport::penalties). They are
+    /// deducted from the score given by the game (added to it when the strategy ranks lower scores
+    /// first, see
+    /// [`TournamentStrategy::higher_is_better`](crate::tournament_strategy::TournamentStrategy::higher_is_better)),
+    /// so that they affect the standings. Integer scores do not go past their bounds: an unsigned
+    /// score of 0 cannot be lowered.
+    ///
+    /// Scores computed by a scoring function (see
+    /// [`Evaluator::with_scoring`](crate::server::Evaluator::with_scoring)) are not adjusted: deduct
+    /// [`MatchContext::penalties`](crate::game_interface::MatchContext::penalties) there.
+    ///
+    /// An agent violating the rules is still removed from the match: it gets at most one penalty
+    /// per match. Silenced timeouts (when almost no time budget remains) are not penalized.
+    pub fn with_penalties(mut self, penalties: Penalties) -> Self {
+        self.penalties = penalties;
+        self
+    }
+
+    /// Choose the score recorded for agents eliminated from a match, instead of trusting the game.
+    ///
+    /// An agent is eliminated when the evaluator removes it from the match: crash, timeout,
+    /// illegal action, disqualification, or failed startup. The game only sees its actions become
+    /// `None`, and may not model this as a loss: e.g. a game scoring the pieces left on the board
+    /// may still give the eliminated agent a good score. With [`EliminatedScore::Minimum`], it gets
+    /// the worst score of the match instead, and with [`EliminatedScore::Fixed`] a given score.
+    ///
+    /// In multiplayer games, elimination is not always a total loss: the points scored before
+    /// being eliminated may count, and the eliminated agent may rightly finish ahead of other
+    /// players. Keep [`EliminatedScore::Game`] for such games. Several agents eliminated from the
+    /// same match all get the same score, and tie. Disqualified agents forfeit their matches even
+    /// with [`EliminatedScore::Game`]: they get the worst score (see
+    /// [disqualification](crate::server#disqualification)).
+    ///
+    /// Scores computed by a scoring function (see
+    /// [`Evaluator::with_scoring`](crate::server::Evaluator::with_scoring)) are not overridden: a
+    /// fixed penalty can be given there, with
+    /// [`MatchContext::eliminated`](crate::game_interface::MatchContext::eliminated).
+    pub fn with_eliminated_score(mut self, policy: EliminatedScore) -> Self {
+        self.eliminated_score = policy;
+        self
+    }
+
+    /// Create the agents' cgroups under `parent`, a cgroup path relative to the cgroup root
+    /// (`/sys/fs/cgroup`), e.g. `user.slice/user-1000.slice/user@1000.service`.
+    ///
+    /// By default, cgroups are created under the systemd user service of the current user
+    /// (`user.slice/user-<uid>.slice/user@<uid>.service`), where an unprivileged user is allowed to
+    /// create them. When it does not exist (containers, non-systemd systems), the evaluator falls
+    /// back to the cgroup of its own process, after moving itself into a child cgroup
+    /// (`ai_tournament_evaluator`). This fails if other processes share its cgroup (e.g. the shell
+    /// that started it): a parent must then be given explicitly.
+    ///
+    /// To find a suitable parent on other systems:
+    /// - `cat /proc/self/cgroup` gives the cgroup of the current shell (the `0::` line).
+    /// - The parent must be writable by the user running the evaluator, and have the `cpuset`,
+    ///   `memory` and `pids` controllers available (listed in its `cgroup.controllers` file).
+    /// - Cgroups v2 forbid processes in a cgroup whose controllers are delegated to children: in a
+    ///   container, move the evaluator process to a child cgroup first (e.g. `init`), and use the
+    ///   container's root cgroup as parent.
+    ///
+    /// If cgroups still cannot be created, agents are only launched with
+    /// [`with_allow_uncontained`](Self::with_allow_uncontained).
+    pub fn with_cgroup_parent(mut self, parent: impl Into<String>) -> Self {
+        self.cgroup_parent = Some(parent.into());
+        self
+    }
+
+    /// Allow or forbid evaluating an agent directory that contains no agent.
+    ///
+    /// When disabled (default), [`Evaluator::evaluate`](crate::server::Evaluator::evaluate)
+    /// returns an error if no agent is found. When enabled, a warning is emitted and the
+    /// tournament runs with no agent (empty results).
+    pub fn with_allow_empty_agent_dir(mut self, value: bool) -> Self {
+        self.allow_empty_agent_dir = value;
+        self
+    }
+
+    /// Enable or disable game state verification (debug purposes only).
+    ///
+    /// When enabled, the state given to the current player is kept until its action was applied,
+    /// then [`Game::verify`](crate::game_interface::Game::verify) is called with it. Failures are
+    /// reported in the match errors. This adds overhead to every turn and is meant to debug game
+    /// implementations and agents, not for regular evaluations.
+    pub fn with_verify_game_state(mut self, value: bool) -> Self {
+        self.verify_game_state = value;
+        self
+    }
+
+    /// End a match as soon as a player is eliminated (crash, timeout, invalid action,
+    /// disqualification...), instead of playing `None` for it until the game ends.
+    ///
+    /// The game is still given the `None` action (or the rejected action) of the eliminated
+    /// player, then the match stops. As the game did not finish by itself, the match is cut off:
+    /// scores come from [`Game::heuristic_score`](crate::game_interface::Game::heuristic_score)
+    /// when it gives one. The remaining player then wins whatever these scores are: the eliminated
+    /// player gets the worst score of the match, and the remaining player one point more
+    /// (see [`MatchScore::offset`](crate::game_interface::MatchScore::offset)) unless its score is
+    /// already better. Scores computed by a scoring function (see
+    /// [`Evaluator::with_scoring`](crate::server::Evaluator::with_scoring)) are not adjusted.
+    ///
+    /// This only applies to matches of one or two players, where a single remaining player has
+    /// nobody left to play against. In matches of three players or more, the remaining players
+    /// keep playing as usual.
+    pub fn with_end_on_elimination(mut self, value: bool) -> Self {
+        self.end_on_elimination = value;
+        self
+    }
+
+    /// Stop matches once they reach the number of turns given by
+    /// [`Game::max_turns_hint`](crate::game_interface::Game::max_turns_hint), as a guard against
+    /// runaway games. Games without a hint are not limited.
+    ///
+    /// The match is cut off: scores come from
+    /// [`Game::heuristic_score`](crate::game_interface::Game::heuristic_score) when it gives one.
+    pub fn with_enforce_max_turns_hint(mut self, value: bool) -> Self {
+        self.enforce_max_turns_hint = value;
+        self
+    }
+
     /// Returns true if logging is enabled (i.e., a log directory is set).
     pub(crate) fn is_logging_enabled(&self) -> bool {
         self.log_dir.is_some()
@@ -199,3 +967,9 @@ impl Default for Configuration {
         Self::new()
     }
 }
+
+/// Parses a port range written `start..end`
+fn parse_port_range(s: &str) -> Option<Range<u16>> {
+    let (start, end) = s.trim().split_once("..")?;
+    Some(start.parse().ok()?..end.parse().ok()?)
+}
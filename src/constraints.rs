@@ -11,10 +11,16 @@
 //!
 //! - **Memory constraints**: max total RAM and per-agent RAM limits
 //! - **CPU constraints**: total CPU count, CPU affinity via list/range, CPUs per agent
+//! - **Disk constraints**: per-agent write rate limit (cgroups v2 only, see
+//!   [`ConstraintsBuilder::with_disk_io_limit()`])
 //! - **Timing constraints**:
 //!   * Per-action timeout
 //!   * Total think time ("time budget") per agent across a match
 //!   * *Invisible time margin* to absorb small scheduling delays
+//!   * Startup timeout: time allowed to an agent to connect after being launched
+//!   * Match timeout: wall-clock limit of a whole match, against stuck games
+//! - **Turn constraints**: maximum number of turns of a match (see
+//!   [`ConstraintsBuilder::with_max_turns()`])
 //!
 //! Once built, a [`Constraints`] object can be passed to the evaluator to enforce limits
 //! at runtime.
@@ -37,6 +43,9 @@
 //! using [`ConstraintsBuilder::with_time_margin()`]. This margin is **not visible to agents**
 //! and is intended to prevent unfair timeouts caused by minor scheduling delays or system load spikes.
 //!
+//! Independently, socket reads and writes time out shortly after the action deadline (see
+//! [`ConstraintsBuilder::with_io_timeout_margin()`]), as a safety net against stalled connections.
+//!
 //! # Example
 //!
 //! ```no_run
@@ -57,6 +66,25 @@
 //!
 //! You may also construct constraints from environment variables using
 //! [`ConstraintsBuilder::from_env()`] for runtime configurability.
+//!
+//! # Presets
+//!
+//! Pre-filled builders are available for common scenarios. They can be chained with any `with_*`
+//! method to override a value:
+//!
+//! - [`ConstraintsBuilder::fast_iteration()`]: loose limits and short games, for development
+//! - [`ConstraintsBuilder::contest_strict()`]: tight RAM and time limits, for competitions
+//! - [`ConstraintsBuilder::benchmark()`]: dedicated cores and long time budgets, for measurements
+//!
+//! ```no_run
+//! use std::time::Duration;
+//! use ai_tournament::constraints::ConstraintsBuilder;
+//!
+//! let constraints = ConstraintsBuilder::contest_strict()
+//!     .with_action_timeout(Duration::from_millis(500))
+//!     .build()
+//!     .unwrap();
+//! ```
 
 use std::{collections::HashSet, env, time::Duration};
 
@@ -105,6 +133,12 @@ pub struct ConstraintsBuilder {
     time_budget: Option<Duration>,
     action_timeout: Option<Duration>,
     time_margin: Duration,
+    io_timeout_margin: Option<Duration>,
+    disk_io_limit: Option<usize>,
+    startup_timeout: Option<Duration>,
+    startup_in_time_budget: bool,
+    match_timeout: Option<Duration>,
+    max_turns: Option<usize>,
 }
 
 impl ConstraintsBuilder {
@@ -115,6 +149,55 @@ impl ConstraintsBuilder {
         Self::default()
     }
 
+    /// Preset for quick development iterations: games are short and memory is not limited.
+    ///
+    /// - 100ms per action, 10s time budget per agent and per match, 20ms time margin
+    /// - No RAM limit per agent
+    /// - One CPU per agent, among all physical CPUs
+    #[must_use]
+    pub fn fast_iteration() -> Self {
+        Self::new()
+            .with_action_timeout(Duration::from_millis(100))
+            .with_time_budget(Duration::from_secs(10))
+            .with_time_margin(Duration::from_millis(20))
+    }
+
+    /// Preset for competitions: tight limits, identical for every agent.
+    ///
+    /// - 1s per action, 60s time budget per agent and per match, 10ms time margin
+    /// - 512MB of RAM per agent
+    /// - One CPU per agent, each agent being pinned to its own CPU
+    /// - 1s to connect after launch, deducted from the time budget
+    #[must_use]
+    pub fn contest_strict() -> Self {
+        Self::new()
+            .with_action_timeout(Duration::from_secs(1))
+            .with_time_budget(Duration::from_secs(60))
+            .with_time_margin(Duration::from_millis(10))
+            .with_ram_per_agent(512)
+            .with_cpus_per_agent(1)
+            .with_startup_timeout(Duration::from_secs(1))
+            .with_startup_in_time_budget(true)
+    }
+
+    /// Preset for benchmarks: agents get dedicated cores and plenty of time, so that measurements
+    /// are not biased by the evaluation itself.
+    ///
+    /// - 10s per action, 30min time budget per agent and per match, 50ms time margin
+    /// - 4GB of RAM per agent
+    /// - Two CPUs per agent, among physical CPUs only (no core is shared between agents)
+    /// - 10s to connect after launch, not deducted from the time budget
+    #[must_use]
+    pub fn benchmark() -> Self {
+        Self::new()
+            .with_action_timeout(Duration::from_secs(10))
+            .with_time_budget(Duration::from_secs(30 * 60))
+            .with_time_margin(Duration::from_millis(50))
+            .with_ram_per_agent(4_000)
+            .with_cpus_per_agent(2)
+            .with_startup_timeout(Duration::from_secs(10))
+    }
+
     /// Creates a new `ConstraintsBuilder` configured from environment variables,
     /// with no limits except for total CPU count defaulting to the number of physical CPUs and one CPU per agent.
     ///
@@ -127,6 +210,12 @@ impl ConstraintsBuilder {
     /// - `TIME_BUDGET_SECS` (u64): total time budget per agent in seconds
     /// - `ACTION_TIMEOUT_MS` (u64): timeout per action in milliseconds
     /// - `TIME_MARGIN_MS` (u64): invisible margin in milliseconds added to all timeouts to prevent false timeouts
+    /// - `IO_TIMEOUT_MARGIN_MS` (u64): time in milliseconds after the action deadline before socket operations are aborted
+    /// - `DISK_IO_LIMIT` (usize): maximum disk write rate per agent in MB per second
+    /// - `STARTUP_TIMEOUT_MS` (u64): time allowed to an agent to connect after launch in milliseconds
+    /// - `STARTUP_IN_TIME_BUDGET` (bool): if `"true"`, startup time is deducted from the time budget
+    /// - `MATCH_TIMEOUT_SECS` (u64): wall-clock limit of a whole match in seconds
+    /// - `MAX_TURNS` (usize): maximum number of turns of a match
     #[must_use]
     pub fn from_env() -> Self {
         fn parse_usize(var: &str) -> Option<usize> {
@@ -161,6 +250,13 @@ impl ConstraintsBuilder {
             .and_then(|s| s.parse::<u64>().ok())
             .map(Duration::from_millis)
             .unwrap_or(Duration::ZERO);
+        let io_timeout_margin = parse_duration_millis("IO_TIMEOUT_MARGIN_MS");
+        let disk_io_limit = parse_usize("DISK_IO_LIMIT");
+        let startup_timeout = parse_duration_millis("STARTUP_TIMEOUT_MS");
+        let startup_in_time_budget =
+            env::var("STARTUP_IN_TIME_BUDGET").is_ok_and(|s| s.eq_ignore_ascii_case("true"));
+        let match_timeout = parse_duration_secs("MATCH_TIMEOUT_SECS");
+        let max_turns = parse_usize("MAX_TURNS");
 
         let cpus = if let Some(cpus_str) = cpu_list {
             AutoCpus::List(cpus_str)
@@ -178,6 +274,12 @@ impl ConstraintsBuilder {
             time_budget,
             action_timeout,
             time_margin,
+            io_timeout_margin,
+            disk_io_limit,
+            startup_timeout,
+            startup_in_time_budget,
+            match_timeout,
+            max_turns,
         }
     }
 
@@ -274,6 +376,101 @@ impl ConstraintsBuilder {
         }
     }
 
+    /// Sets how long socket operations may last past the action deadline before being aborted.
+    ///
+    /// The action deadline (action timeout or remaining time budget, plus the time margin) is
+    /// measured by the server: a response received after it is a timeout. Sending a state and
+    /// reading a response also have a socket-level timeout, this margin after the deadline, so
+    /// that a stalled connection cannot block a match. It only delays the detection of such
+    /// stalls, and never gives agents more time.
+    ///
+    /// Default is 100 milliseconds.
+    #[must_use]
+    pub fn with_io_timeout_margin(self, duration: Duration) -> Self {
+        Self {
+            io_timeout_margin: Some(duration),
+            ..self
+        }
+    }
+
+    /// Sets the maximum duration allowed for an agent to connect to the server after being launched.
+    ///
+    /// Agents that load big resources (e.g. models) should do it before connecting, or be given a
+    /// larger startup timeout. Agents that do not connect in time fail to start.
+    ///
+    /// Default is one second.
+    #[must_use]
+    pub fn with_startup_timeout(self, duration: Duration) -> Self {
+        Self {
+            startup_timeout: Some(duration),
+            ..self
+        }
+    }
+
+    /// Sets whether the startup time of an agent is deducted from its time budget.
+    ///
+    /// Agents still receive the full time budget as argument, as it is not known yet when they are
+    /// launched.
+    ///
+    /// Default is false: startup time is not counted in the time budget.
+    #[must_use]
+    pub fn with_startup_in_time_budget(self, value: bool) -> Self {
+        Self {
+            startup_in_time_budget: value,
+            ..self
+        }
+    }
+
+    /// Sets the maximum wall-clock duration of a whole match, from the launch of its agents.
+    ///
+    /// Protects the evaluation against matches that never end, e.g. a game whose `is_finished`
+    /// never returns true, or agents that keep playing valid actions forever. The limit is
+    /// checked between turns: a match going over it is stopped, its agents are killed, the error
+    /// `match exceeded wall-clock limit` is recorded, and scores are collected from the game as
+    /// it is.
+    ///
+    /// Default is no limit.
+    #[must_use]
+    pub fn with_match_timeout(self, duration: Duration) -> Self {
+        Self {
+            match_timeout: Some(duration),
+            ..self
+        }
+    }
+
+    /// Sets the maximum number of turns of a match.
+    ///
+    /// Caps pathological matches of naturally unbounded games independently of the load of the
+    /// machine, unlike [`with_match_timeout`](Self::with_match_timeout). A match reaching the
+    /// limit is stopped, the error `max turns reached` is recorded, and scores are collected from
+    /// the game as it is.
+    ///
+    /// Default is no limit.
+    #[must_use]
+    pub fn with_max_turns(self, max: usize) -> Self {
+        Self {
+            max_turns: Some(max),
+            ..self
+        }
+    }
+
+    /// Sets the maximum disk write rate per agent (in MB per second).
+    ///
+    /// Applied through the cgroup v2 `io.max` controller on the disk backing the agents' working
+    /// directory. Agents currently inherit the evaluator's working directory, so writes to other
+    /// disks (e.g. a `tmpfs` or another mount) are not limited. The limit is skipped when running
+    /// uncontained, and agent startup fails if the working directory is not backed by a block
+    /// device (e.g. `overlayfs` in containers, network filesystems).
+    ///
+    /// Note that this limits the write **rate**, not the total amount of data written.
+    #[must_use]
+    pub fn with_disk_io_limit(self, max: usize) -> Self {
+        Self {
+            disk_io_limit: Some(max),
+            ..self
+        }
+    }
+
     /// Consumes the builder and returns the constructed `Constraints`.
     ///
     /// # Returns
@@ -282,15 +479,39 @@ impl ConstraintsBuilder {
     ///
     /// # Errors
     ///
-    /// Returns Error (String) when Constraints are impossible, e.g. total RAM < agent RAM
+    /// Returns Error (String) when Constraints are impossible, e.g. total RAM < agent RAM, or when
+    /// the available memory or CPUs could not be detected (some containers report 0): set them
+    /// explicitly in that case.
     pub fn build(self) -> anyhow::Result<Constraints> {
-        let mut sys = sysinfo::System::new();
+        self.build_with(
+            || {
+                let mut sys = sysinfo::System::new();
+                sys.refresh_memory();
+                //REVIEW: sys.total_memory() ?
+                sys.available_memory() as usize
+            },
+            num_cpus::get_physical,
+        )
+    }
 
-        let total_ram = self.total_ram.map(|i| i * 1_000_000).unwrap_or_else(|| {
-            sys.refresh_memory();
-            //REVIEW: sys.total_memory() ?
-            sys.available_memory() as usize
-        });
+    /// [`build`](Self::build), with the detection of the available memory (in bytes) and of the
+    /// number of physical CPUs given as parameters. They are only called when the corresponding
+    /// value was not set explicitly.
+    fn build_with(
+        self,
+        detect_memory: impl FnOnce() -> usize,
+        detect_cpus: impl FnOnce() -> usize,
+    ) -> anyhow::Result<Constraints> {
+        let total_ram = match self.total_ram {
+            Some(total_ram) => total_ram * 1_000_000,
+            None => match detect_memory() {
+                // some containers report no memory at all
+                0 => bail!(
+                    "could not detect available memory, set it with `with_max_total_ram` (or `MAX_TOTAL_RAM`)"
+                ),
+                total_ram => total_ram,
+            },
+        };
 
         if total_ram < (self.agent_ram.unwrap_or(0) * 1_000_000) {
             bail!(
@@ -304,8 +525,12 @@ impl ConstraintsBuilder {
         // cuts agent performance in half (at least on the machine I tested).
         let cpus = match self.cpus {
             AutoCpus::Auto => {
-                sys.refresh_cpu_all();
-                let num_cpus = num_cpus::get_physical() as u8;
+                let num_cpus = detect_cpus().min(u8::MAX as usize) as u8;
+                if num_cpus == 0 {
+                    bail!(
+                        "could not detect the number of CPUs, set them with `with_total_cpu_count` or `with_cpu_list`"
+                    );
+                }
                 (0..num_cpus).collect::<HashSet<u8>>()
             }
             AutoCpus::Count(num_cpus) => (0..(num_cpus as u8)).collect::<HashSet<u8>>(),
@@ -314,10 +539,19 @@ impl ConstraintsBuilder {
             }
         };
         let cpus_per_agent = self.cpus_per_agent.unwrap_or(1);
+        if cpus_per_agent == 0 || cpus.len() < cpus_per_agent {
+            bail!(
+                "{} CPU(s) available, cannot give {cpus_per_agent} CPU(s) to each agent",
+                cpus.len()
+            );
+        }
         let agent_ram = self
             .agent_ram
             .map(|i| i * 1_000_000)
             .unwrap_or_else(|| total_ram / (cpus.len() / cpus_per_agent));
+        if agent_ram == 0 {
+            bail!("no RAM per agent, set it with `with_ram_per_agent` (or `RAM_PER_AGENT`)");
+        }
         let time_budget = self.time_budget.unwrap_or(Duration::MAX);
         let action_timeout = self.action_timeout.unwrap_or(Duration::MAX);
         let time_margin = if self.time_budget.is_none() && self.action_timeout.is_none() {
@@ -325,6 +559,9 @@ impl ConstraintsBuilder {
         } else {
             self.time_margin
         };
+        let disk_io_limit = self.disk_io_limit.map(|i| i as u64 * 1_000_000);
+        let io_timeout_margin = self.io_timeout_margin.unwrap_or(Duration::from_millis(100));
+        let startup_timeout = self.startup_timeout.unwrap_or(Duration::from_secs(1));
 
         Ok(Constraints {
             total_ram,
@@ -334,6 +571,12 @@ impl ConstraintsBuilder {
             time_budget,
             action_timeout,
             time_margin,
+            io_timeout_margin,
+            disk_io_limit,
+            startup_timeout,
+            startup_in_time_budget: self.startup_in_time_budget,
+            match_timeout: self.match_timeout,
+            max_turns: self.max_turns,
         })
     }
 }
@@ -378,6 +621,16 @@ fn cpu_list_to_hashset(s: &str) -> anyhow::Result<HashSet<u8>> {
     Ok(set)
 }
 
+/// Upper bound of the CPUs and RAM used by all the matches running at the same time, see
+/// [`TournamentControl::set_resource_cap`](crate::server::TournamentControl::set_resource_cap).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ResourceCap {
+    /// Number of CPUs.
+    pub cpus: usize,
+    /// RAM, in bytes.
+    pub ram: usize,
+}
+
 /// Obtained using `ConstraintsBuilder`
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Constraints {
@@ -388,6 +641,12 @@ pub struct Constraints {
     pub(crate) time_budget: Duration,
     pub(crate) action_timeout: Duration,
     pub(crate) time_margin: Duration,
+    pub(crate) io_timeout_margin: Duration,
+    pub(crate) disk_io_limit: Option<u64>,
+    pub(crate) startup_timeout: Duration,
+    pub(crate) startup_in_time_budget: bool,
+    pub(crate) match_timeout: Option<Duration>,
+    pub(crate) max_turns: Option<usize>,
 }
 
 impl Constraints {
@@ -427,3 +686,83 @@ impl Constraints {
         self.cpus.take(&cpu).unwrap()
     }
 }
+
+#[cfg(test)]
+mod constraints_tests {
+    use super::*;
+
+    #[test]
+    fn test_presets() {
+        let fast = ConstraintsBuilder::fast_iteration();
+        assert_eq!(fast.action_timeout, Some(Duration::from_millis(100)));
+        assert_eq!(fast.time_budget, Some(Duration::from_secs(10)));
+        assert_eq!(fast.time_margin, Duration::from_millis(20));
+        assert_eq!(fast.agent_ram, None);
+
+        let strict = ConstraintsBuilder::contest_strict();
+        assert_eq!(strict.action_timeout, Some(Duration::from_secs(1)));
+        assert_eq!(strict.time_budget, Some(Duration::from_secs(60)));
+        assert_eq!(strict.agent_ram, Some(512));
+        assert_eq!(strict.cpus_per_agent, Some(1));
+        assert!(strict.startup_in_time_budget);
+
+        let bench = ConstraintsBuilder::benchmark();
+        assert_eq!(bench.action_timeout, Some(Duration::from_secs(10)));
+        assert_eq!(bench.time_budget, Some(Duration::from_secs(1800)));
+        assert_eq!(bench.agent_ram, Some(4_000));
+        assert_eq!(bench.cpus_per_agent, Some(2));
+        assert_eq!(bench.startup_timeout, Some(Duration::from_secs(10)));
+        assert!(!bench.startup_in_time_budget);
+    }
+
+    #[test]
+    fn test_degenerate_detection() {
+        let unreachable = || -> usize { panic!("detection should not be called") };
+
+        let err = ConstraintsBuilder::new()
+            .build_with(|| 0, || 4)
+            .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("could not detect available memory"));
+        let err = ConstraintsBuilder::new()
+            .build_with(|| 8_000_000_000, || 0)
+            .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("could not detect the number of CPUs"));
+
+        // explicit values skip the detection
+        let constraints = ConstraintsBuilder::new()
+            .with_max_total_ram(4_000)
+            .with_total_cpu_count(2)
+            .build_with(unreachable, unreachable)
+            .unwrap();
+        assert_eq!(constraints.agent_ram, 2_000 * 1_000_000);
+
+        let err = ConstraintsBuilder::new()
+            .with_cpus_per_agent(4)
+            .build_with(|| 8_000_000_000, || 2)
+            .unwrap_err();
+        assert!(err.to_string().contains("2 CPU(s) available"));
+        // less than 1 byte per agent
+        let err = ConstraintsBuilder::new()
+            .build_with(|| 3, || 4)
+            .unwrap_err();
+        assert!(err.to_string().contains("no RAM per agent"));
+    }
+
+    #[test]
+    fn test_presets_can_be_overridden() {
+        let constraints = ConstraintsBuilder::contest_strict()
+            .with_action_timeout(Duration::from_millis(300))
+            .with_total_cpu_count(4)
+            .with_max_total_ram(4_000)
+            .build()
+            .unwrap();
+        assert_eq!(constraints.action_timeout, Duration::from_millis(300));
+        assert_eq!(constraints.time_budget, Duration::from_secs(60));
+        assert_eq!(constraints.agent_ram, 512 * 1_000_000);
+        assert_eq!(constraints.cpus.len(), 4);
+    }
+}
@@ -0,0 +1,130 @@
+use std::{
+    fmt::Write as _,
+    fs::OpenOptions,
+    io::Write as _,
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+use anyhow::Context;
+
+use crate::agent::Agent;
+
+/// Serializes appends: an agent may play several matches at the same time
+static DATASET_LOCK: Mutex<()> = Mutex::new(());
+
+/// `(state, action)` pairs played during a match, written to one JSONL file per agent
+/// (see [`Configuration::with_decision_log`](crate::configuration::Configuration::with_decision_log))
+#[derive(Debug, Default)]
+pub(crate) struct DecisionLog {
+    /// (player number, turn, state, action)
+    decisions: Vec<(usize, usize, String, String)>,
+}
+
+impl DecisionLog {
+    pub(crate) fn with_capacity(capacity: usize) -> Self {
+        Self {
+            decisions: Vec::with_capacity(capacity),
+        }
+    }
+
+    pub(crate) fn record(&mut self, player: usize, turn: usize, state: &str, action: String) {
+        self.decisions
+            .push((player, turn, state.to_string(), action));
+    }
+
+    /// Appends the decisions of each player to `<dir>/<agent name>.jsonl`
+    pub(crate) fn write(
+        &self,
+        dir: &Path,
+        players: &[Arc<Agent>],
+        round: usize,
+        index: usize,
+    ) -> anyhow::Result<()> {
+        let _lock = DATASET_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("could not create directory {}", dir.display()))?;
+        for (player, agent) in players.iter().enumerate() {
+            let mut lines = String::new();
+            for (_, turn, state, action) in self.decisions.iter().filter(|d| d.0 == player) {
+                writeln!(
+                    lines,
+                    "{{\"agent\":\"{}\",\"match\":\"{round}-{index}\",\"player\":{player},\"turn\":{turn},\"state\":\"{}\",\"action\":\"{}\"}}",
+                    escape_json(&agent.name),
+                    escape_json(state),
+                    escape_json(action)
+                )
+                .unwrap();
+            }
+            if lines.is_empty() {
+                continue;
+            }
+            let path = dir.join(format!("{}.jsonl", agent.name));
+            OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&path)
+                .and_then(|mut file| file.write_all(lines.as_bytes()))
+                .with_context(|| format!("could not write {}", path.display()))?;
+        }
+        Ok(())
+    }
+}
+
+pub(crate) fn escape_json(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 => write!(escaped, "\\u{:04x}", c as u32).unwrap(),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod decision_log_tests {
+    use super::*;
+
+    #[test]
+    fn test_escape_json() {
+        assert_eq!(escape_json("a\"b\\c"), "a\\\"b\\\\c");
+        assert_eq!(escape_json("line 1\nline 2\u{1}"), "line 1\\nline 2\\u0001");
+    }
+
+    #[test]
+    fn test_write_per_agent() {
+        let dir =
+            std::env::temp_dir().join(format!("ai_tournament_decision_log_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let players = [
+            Arc::new(Agent::new("a".into(), None, None, 0, None)),
+            Arc::new(Agent::new("b".into(), None, None, 1, None)),
+        ];
+
+        let mut log = DecisionLog::default();
+        log.record(0, 1, "x\ny", "3".into());
+        log.record(1, 2, "z", "4".into());
+        log.record(0, 3, "w", "5".into());
+        log.write(&dir, &players, 2, 7).unwrap();
+        log.write(&dir, &players, 2, 8).unwrap();
+
+        let a = std::fs::read_to_string(dir.join("a.jsonl")).unwrap();
+        let a = a.lines().collect::<Vec<_>>();
+        assert_eq!(a.len(), 4);
+        assert_eq!(
+            a[0],
+            r#"{"agent":"a","match":"2-7","player":0,"turn":1,"state":"x\ny","action":"3"}"#
+        );
+        assert!(a[3].contains(r#""match":"2-8""#));
+        let b = std::fs::read_to_string(dir.join("b.jsonl")).unwrap();
+        assert_eq!(b.lines().count(), 2);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,281 @@
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    fs::{File, OpenOptions},
+    io::Write as _,
+    path::Path,
+    time::Instant,
+};
+
+use anyhow::Context;
+use tracing::warn;
+
+use crate::{
+    agent::Agent,
+    constraints::Constraints,
+    decision_log::escape_json,
+    match_runner::{MatchSettings, RunnerResult},
+    results::final_ranking_ordered,
+    rng::match_seed,
+    tournament_control::Progress,
+};
+
+/// Events of a tournament, appended to a JSONL file as they happen
+/// (see [`Configuration::with_event_log`](crate::configuration::Configuration::with_event_log))
+#[derive(Debug)]
+pub(crate) struct EventLog {
+    file: File,
+    start: Instant,
+}
+
+impl EventLog {
+    /// Opens `path` for appending, creating it (and its parent directory) if needed
+    pub(crate) fn open(path: &Path) -> anyhow::Result<Self> {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("could not create {}", parent.display()))?;
+        }
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("could not open event log {}", path.display()))?;
+        Ok(Self {
+            file,
+            start: Instant::now(),
+        })
+    }
+
+    /// Appends one line. A failed write only loses this event: the tournament goes on.
+    fn write(&self, event: &str, fields: &str) {
+        let line = format!(
+            "{{\"event\":\"{event}\",\"elapsed_ms\":{},{fields}}}\n",
+            self.start.elapsed().as_millis()
+        );
+        // a single write per event, so that a crash can only truncate the last line
+        if let Err(e) = (&self.file).write_all(line.as_bytes()) {
+            warn!("Could not write {event} event to the event log: {e}");
+        }
+    }
+
+    pub(crate) fn tournament_start(
+        &self,
+        directory: &Path,
+        shuffle_seed: Option<u64>,
+        pool: &Constraints,
+    ) {
+        let seed = shuffle_seed.map_or("null".to_string(), |seed| seed.to_string());
+        self.write(
+            "tournament_start",
+            &format!(
+                "\"directory\":\"{}\",\"shuffle_seed\":{seed},\"cpus\":{},\"total_ram\":{}",
+                escape_json(&directory.to_string_lossy()),
+                json_cpus(pool),
+                pool.total_ram
+            ),
+        );
+    }
+
+    pub(crate) fn agent(&self, agent: &Agent, late: bool) {
+        let error = agent
+            .error_message
+            .as_ref()
+            .map_or("null".to_string(), |e| format!("\"{}\"", escape_json(e)));
+        self.write(
+            "agent",
+            &format!(
+                "\"name\":\"{}\",\"id\":{},\"args\":{},\"working\":{},\"error\":{error},\"late\":{late}",
+                escape_json(&agent.name),
+                agent.id,
+                json_strings(agent.args()),
+                agent.compile
+            ),
+        );
+    }
+
+    pub(crate) fn match_start(&self, settings: &MatchSettings, seed: Option<u64>) {
+        let seed = seed.map_or("null".to_string(), |seed| {
+            match_seed(seed, settings.round, settings.index).to_string()
+        });
+        let players = settings
+            .ordered_player
+            .iter()
+            .map(|agent| agent.name.as_str());
+        self.write(
+            "match_start",
+            &format!(
+                "\"round\":{},\"index\":{},\"players\":{},\"cpus\":{},\"ram\":{},\"weight\":{:?},\"seed\":{seed}",
+                settings.round,
+                settings.index,
+                json_strings(players),
+                json_cpus(&settings.resources),
+                settings.resources.total_ram,
+                settings.weight
+            ),
+        );
+    }
+
+    pub(crate) fn match_result<S: PartialOrd + Display>(
+        &self,
+        result: &RunnerResult<S>,
+        retried: bool,
+        progress: Progress,
+    ) {
+        let players = result.results.iter().map(|(agent, _)| agent.name.as_str());
+        let args = result
+            .results
+            .iter()
+            .map(|(agent, _)| json_strings(agent.args()))
+            .collect::<Vec<_>>();
+        let scores = result
+            .results
+            .iter()
+            .map(|(_, score)| score.to_string())
+            .collect::<Vec<_>>();
+        let startup = result
+            .startup_durations
+            .iter()
+            .map(|d| d.map_or("null".to_string(), |d| d.as_millis().to_string()))
+            .collect::<Vec<_>>();
+        let penalties = result
+            .penalties
+            .iter()
+            .map(u32::to_string)
+            .collect::<Vec<_>>();
+        self.write(
+            "match_result",
+            &format!(
+                "\"round\":{},\"index\":{},\"players\":{},\"args\":[{}],\"scores\":{},\"errors\":\"{}\",\
+                 \"startup_ms\":[{}],\"penalties\":[{}],\"turns\":{},\"weight\":{:?},\"retried\":{retried},\
+                 \"progress\":{{\"matches_done\":{},\"matches_total_estimate\":{},\"round\":{}}}",
+                result.round,
+                result.index,
+                json_strings(players),
+                args.join(","),
+                json_strings(&scores),
+                escape_json(&result.errors),
+                startup.join(","),
+                penalties.join(","),
+                result.turns,
+                result.weight,
+                progress.matches_done,
+                progress.matches_total_estimate,
+                progress.round
+            ),
+        );
+    }
+
+    pub(crate) fn standings<S: Ord>(&self, scores: &HashMap<String, S>, higher_is_better: bool) {
+        let scores = scores
+            .iter()
+            .map(|(name, score)| (name.clone(), score))
+            .collect();
+        let ranking = final_ranking_ordered(&scores, higher_is_better)
+            .into_iter()
+            .map(|rank| {
+                format!(
+                    "{{\"place\":{},\"agents\":{}}}",
+                    rank.place,
+                    json_strings(&rank.agents)
+                )
+            })
+            .collect::<Vec<_>>();
+        self.write("standings", &format!("\"ranking\":[{}]", ranking.join(",")));
+    }
+
+    pub(crate) fn tournament_end(&self, total_matches: usize, rounds: usize, byes: usize) {
+        self.write(
+            "tournament_end",
+            &format!("\"total_matches\":{total_matches},\"rounds\":{rounds},\"byes\":{byes}"),
+        );
+    }
+}
+
+fn json_strings<I: IntoIterator<Item = T>, T: AsRef<str>>(strings: I) -> String {
+    let strings = strings
+        .into_iter()
+        .map(|s| format!("\"{}\"", escape_json(s.as_ref())))
+        .collect::<Vec<_>>();
+    format!("[{}]", strings.join(","))
+}
+
+/// CPU ids, sorted
+fn json_cpus(resources: &Constraints) -> String {
+    let mut cpus = resources.cpus.iter().copied().collect::<Vec<_>>();
+    cpus.sort();
+    let cpus = cpus.iter().map(u8::to_string).collect::<Vec<_>>();
+    format!("[{}]", cpus.join(","))
+}
+
+#[cfg(test)]
+mod event_log_tests {
+    use std::{sync::Arc, time::Duration};
+
+    use super::*;
+    use crate::constraints::ConstraintsBuilder;
+
+    #[test]
+    fn test_append_events() {
+        let path = std::env::temp_dir()
+            .join(format!("ai_tournament_event_log_{}", std::process::id()))
+            .join("events.jsonl");
+        let _ = std::fs::remove_file(&path);
+        let players = vec![
+            Arc::new(Agent::new(
+                "a".into(),
+                None,
+                None,
+                0,
+                Some(vec!["-x".into()]),
+            )),
+            Arc::new(Agent::with_error("b\"".into(), 1, "no main".into())),
+        ];
+        let result = RunnerResult {
+            results: players.iter().cloned().zip([3, 1]).collect(),
+            resources_freed: ConstraintsBuilder::new().build().unwrap(),
+            errors: "line\n".into(),
+            error_details: vec![],
+            peak_memory: vec![],
+            round: 2,
+            index: 1,
+            startup_durations: vec![Some(Duration::from_millis(20)), None],
+            transient: false,
+            penalties: vec![0, 1],
+            turns: 7,
+            timings: vec![],
+            duration: Duration::ZERO,
+            weight: 1.0,
+            seed: None,
+        };
+
+        let log = EventLog::open(&path).unwrap();
+        log.agent(&players[1], true);
+        let progress = Progress {
+            matches_done: 1,
+            matches_total_estimate: 4,
+            round: 2,
+        };
+        log.match_result(&result, false, progress);
+        let scores = HashMap::from([("a".to_string(), 1), ("b".to_string(), 2)]);
+        log.standings(&scores, false);
+        drop(log);
+        // reopening appends
+        EventLog::open(&path).unwrap().tournament_end(1, 2, 0);
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        let lines = content.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 4);
+        assert!(lines[0].starts_with(r#"{"event":"agent","elapsed_ms":"#));
+        assert!(lines[0].ends_with(
+            r#""name":"b\"","id":1,"args":[],"working":false,"error":"no main","late":true}"#
+        ));
+        assert!(lines[1].ends_with(
+            r#""round":2,"index":1,"players":["a","b\""],"args":[["-x"],[]],"scores":["3","1"],"errors":"line\n","startup_ms":[20,null],"penalties":[0,1],"turns":7,"weight":1.0,"retried":false,"progress":{"matches_done":1,"matches_total_estimate":4,"round":2}}"#
+        ));
+        assert!(lines[2]
+            .ends_with(r#""ranking":[{"place":1,"agents":["a"]},{"place":2,"agents":["b"]}]}"#));
+        assert!(lines[3].ends_with(r#""total_matches":1,"rounds":2,"byes":0}"#));
+
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+}
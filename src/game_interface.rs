@@ -8,10 +8,39 @@ pub trait Game {
     type State: ToString;
     /// What should be returned by players to make the game progress.
     ///
-    /// Warning: assert_equal!(from_str(&action.to_string()).unwrap(), action)
-    type Action: ToString + FromStr;
+    /// Warning: assert_equal!(game.parse_action(&action.to_string()).unwrap(), action)
+    type Action: ToString;
     /// The score of an player
-    type Score: PartialOrd + Display + Send;
+    type Score: MatchScore;
+
+    /// Parse the text sent by the current player into an action.
+    ///
+    /// This is what the evaluator uses to read actions, so the action type does not have to
+    /// implement [`FromStr`]. It may parse with game context (legal moves, current player, ...).
+    /// `text` is already trimmed. Returning `None` rejects the response as "not an action",
+    /// eliminating the player.
+    ///
+    /// Games whose actions implement [`FromStr`] can delegate to
+    /// [`parse_from_str`](Game::parse_from_str):
+    ///
+    /// ```ignore
+    /// fn parse_action(&self, text: &str) -> Option<Self::Action> {
+    ///     Self::parse_from_str(text)
+    /// }
+    /// ```
+    fn parse_action(&self, text: &str) -> Option<Self::Action>;
+
+    /// Parse `text` with the [`FromStr`] implementation of the action, `None` if it fails.
+    ///
+    /// Helper for [`parse_action`](Game::parse_action), for games whose actions implement
+    /// [`FromStr`].
+    fn parse_from_str(text: &str) -> Option<Self::Action>
+    where
+        Self: Sized,
+        Self::Action: FromStr,
+    {
+        Self::Action::from_str(text).ok()
+    }
 
     /// Apply an optional action to the game.
     ///
@@ -25,6 +54,28 @@ pub trait Game {
     /// Even if `action` is not valid, `current_player` should be updated!
     fn apply_action(&mut self, action: &Option<Self::Action>) -> anyhow::Result<()>;
 
+    /// Apply an optional action to the game, and tell whether it ended the game.
+    ///
+    /// This is what the evaluator calls on each turn. Override it when the end of the game is
+    /// naturally detected while applying the action: [`is_finished`](Game::is_finished) is then
+    /// only called before the first turn and after rejected actions. Overriding games can implement
+    /// `apply_action` with `self.step(action).map(|_| ())`.
+    ///
+    /// # Error
+    /// Same as [`apply_action`](Game::apply_action). The evaluator then calls
+    /// [`is_finished`](Game::is_finished).
+    ///
+    /// Default implementation calls [`apply_action`](Game::apply_action), then
+    /// [`is_finished`](Game::is_finished).
+    fn step(&mut self, action: &Option<Self::Action>) -> anyhow::Result<ActionOutcome> {
+        self.apply_action(action)?;
+        Ok(if self.is_finished() {
+            ActionOutcome::Finished
+        } else {
+            ActionOutcome::Continue
+        })
+    }
+
     /// The current state that will be given to the current player
     ///
     /// Does not returns &State because of annoying lifetime to deal with.
@@ -38,15 +89,195 @@ pub trait Game {
 
     /// Used at the end of the game to collect players score
     fn get_player_score(&self, player_number: u32) -> Self::Score;
+
+    /// Score of a player when the match is cut off before the game is finished.
+    ///
+    /// A match is cut off when the evaluator stops it early (e.g. cancellation), not when the game
+    /// ends by itself or all players are eliminated. The score of an unfinished game may be
+    /// meaningless: this lets the game estimate it instead (e.g. material count), or declare a
+    /// draw by giving every player the same score.
+    ///
+    /// Returning `None` falls back to [`get_player_score`](Game::get_player_score), which is the
+    /// default.
+    fn heuristic_score(&self, player_number: u32) -> Option<Self::Score> {
+        let _ = player_number;
+        None
+    }
+
+    /// Expected maximum number of turns of a match, if known (e.g. `9` for tic-tac-toe).
+    ///
+    /// Advisory only: the evaluator uses it to pre-size its buffers, and warns when a match goes
+    /// far beyond it, which is likely a bug in the game. Matches are only stopped when they reach
+    /// it if [`Configuration::with_enforce_max_turns_hint`](crate::configuration::Configuration::with_enforce_max_turns_hint)
+    /// is enabled.
+    ///
+    /// Called once, before the first turn. Default is `None`.
+    fn max_turns_hint(&self) -> Option<usize> {
+        None
+    }
+
+    /// Description of the rules of this match (e.g. board size, variant, scoring), for generic
+    /// agents adapting to variant rules. Sent to the agents that ask for it during the protocol
+    /// handshake, before the first state (see [Game rules](crate#game-rules)). The format is up to
+    /// the game.
+    ///
+    /// Called once per match, before the agents are launched. Default is `None`.
+    fn rules_description(&self) -> Option<String> {
+        None
+    }
+
+    /// Optional invariant check, for debugging game implementations and agents.
+    ///
+    /// Only called when [`Configuration::with_verify_game_state`](crate::configuration::Configuration::with_verify_game_state)
+    /// is enabled, right after `action` was applied. `before` is the state returned by
+    /// [`get_state`](Game::get_state) before the action, so no `Clone` is required: games that want
+    /// to compare full states should make `get_state` return a complete snapshot.
+    ///
+    /// # Error
+    /// Returned when an invariant does not hold (e.g. the action changed more than it should).
+    ///
+    /// Default implementation accepts everything.
+    fn verify(&self, before: &Self::State, action: &Option<Self::Action>) -> anyhow::Result<()> {
+        let _ = (before, action);
+        Ok(())
+    }
+}
+
+/// Effect of an accepted action on the game, returned by [`Game::step`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ActionOutcome {
+    /// The game goes on.
+    Continue,
+    /// The action ended the game.
+    Finished,
 }
 
-/// What the agent should implement. Not used yet, be could allow to launch agent without creating
-/// processes
-#[allow(dead_code)]
-#[doc(hidden)]
+/// What happened during a match, given to the scoring function of
+/// [`Evaluator::with_scoring`](crate::server::Evaluator::with_scoring).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MatchContext {
+    /// Agent names, in play order.
+    pub players: Vec<String>,
+    /// Number of turns played.
+    pub turns: usize,
+    /// Errors reported during the match (crashes, timeouts, invalid actions). Empty if none.
+    pub errors: String,
+    /// True if the match was stopped before the game finished by itself (see
+    /// [`Game::heuristic_score`]).
+    pub cut_off: bool,
+    /// Penalty points of each player, in play order (see
+    /// [`Configuration::with_penalties`](crate::configuration::Configuration::with_penalties)).
+    pub penalties: Vec<u32>,
+    /// Whether each player, in play order, was eliminated from the match by the evaluator (crash,
+    /// timeout, illegal action, disqualification, or failed startup).
+    pub eliminated: Vec<bool>,
+}
+
+/// Score of a player in a match, see [`Game::Score`].
+///
+/// Scores are given by the game, but the evaluator adjusts them itself in some cases, e.g. to
+/// deduct [penalties](crate::configuration::Configuration::with_penalties). Implemented for the
+/// primitive numeric types. Other score types implement it as a number of points:
+///
+/// ```
+/// use std::fmt;
+///
+/// use ai_tournament::game_interface::MatchScore;
+///
+/// /// Chips won, in cents
+/// #[derive(Debug, PartialEq, PartialOrd)]
+/// struct Chips(i64);
+///
+/// impl fmt::Display for Chips {
+///     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+///         write!(f, "{}.{:02}", self.0 / 100, self.0.abs() % 100)
+///     }
+/// }
+///
+/// impl MatchScore for Chips {
+///     fn offset(&self, points: i64) -> Chips {
+///         Chips(self.0.saturating_add(points))
+///     }
+///
+///     fn from_points(points: i64) -> Chips {
+///         Chips(points)
+///     }
+/// }
+///
+/// assert_eq!(Chips(150).offset(-200), Chips(-50));
+/// ```
+pub trait MatchScore: PartialOrd + Display + Send + Sized {
+    /// This score moved by `points`, negative to lower it. Integers saturate at their bounds.
+    fn offset(&self, points: i64) -> Self;
+
+    /// The score worth `points`.
+    fn from_points(points: i64) -> Self;
+}
+
+macro_rules! impl_integer_match_score {
+    ($($t:ty),*) => {
+        $(
+            impl MatchScore for $t {
+                fn offset(&self, points: i64) -> Self {
+                    (*self as i128 + points as i128).clamp(<$t>::MIN as i128, <$t>::MAX as i128)
+                        as $t
+                }
+
+                fn from_points(points: i64) -> Self {
+                    (points as i128).clamp(<$t>::MIN as i128, <$t>::MAX as i128) as $t
+                }
+            }
+        )*
+    };
+}
+
+impl_integer_match_score!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+
+impl MatchScore for i128 {
+    fn offset(&self, points: i64) -> Self {
+        self.saturating_add(points as i128)
+    }
+
+    fn from_points(points: i64) -> Self {
+        points as i128
+    }
+}
+
+impl MatchScore for u128 {
+    fn offset(&self, points: i64) -> Self {
+        self.saturating_add_signed(points as i128)
+    }
+
+    fn from_points(points: i64) -> Self {
+        points.max(0) as u128
+    }
+}
+
+macro_rules! impl_float_match_score {
+    ($($t:ty),*) => {
+        $(
+            impl MatchScore for $t {
+                fn offset(&self, points: i64) -> Self {
+                    self + points as $t
+                }
+
+                fn from_points(points: i64) -> Self {
+                    points as $t
+                }
+            }
+        )*
+    };
+}
+
+impl_float_match_score!(f32, f64);
+
+/// An agent running inside the evaluator process, without a crate, a binary nor a socket. Used
+/// as a reference opponent, see [`Evaluator::with_reference_agent`](crate::server::Evaluator::with_reference_agent).
 pub trait Agent<G: Game> {
+    /// Called once before the match starts.
     fn init(&mut self);
 
+    /// Returns the action to play in `state`, ideally before `deadline` (not enforced).
     fn select_action(&mut self, state: G::State, deadline: SystemTime) -> G::Action;
 }
 
@@ -69,6 +300,10 @@ mod interface_tests {
         type Action = u32;
         type Score = u32;
 
+        fn parse_action(&self, text: &str) -> Option<u32> {
+            Self::parse_from_str(text)
+        }
+
         fn apply_action(&mut self, _action: &Option<u32>) -> anyhow::Result<()> {
             Ok(())
         }
@@ -99,6 +334,147 @@ mod interface_tests {
         assert_eq!(game.get_state(), "");
     }
 
+    #[test]
+    fn test_parse_from_str() {
+        let game = DummyGame {};
+        assert_eq!(game.parse_action("42"), Some(42));
+        assert_eq!(game.parse_action("forty-two"), None);
+    }
+
+    #[test]
+    fn test_match_score() {
+        assert_eq!(10u32.offset(-3), 7);
+        assert_eq!(2u32.offset(-3), 0);
+        assert_eq!(250u8.offset(10), u8::MAX);
+        assert_eq!(i64::MIN.offset(-1), i64::MIN);
+        assert_eq!(0u128.offset(-1), 0);
+        assert_eq!(1.5f32.offset(-2), -0.5);
+        assert_eq!(u8::from_points(-4), 0);
+        assert_eq!(i8::from_points(1000), i8::MAX);
+        assert_eq!(f64::from_points(-4), -4.0);
+    }
+
+    #[test]
+    fn test_action_without_from_str() {
+        #[derive(Debug, PartialEq)]
+        enum Move {
+            Left,
+            Right,
+        }
+
+        impl std::fmt::Display for Move {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                write!(f, "{self:?}")
+            }
+        }
+
+        /// Only accepts the moves allowed by the current position
+        struct Corridor(u32);
+
+        impl Game for Corridor {
+            type State = u32;
+            type Action = Move;
+            type Score = u32;
+
+            fn parse_action(&self, text: &str) -> Option<Move> {
+                match text {
+                    "Left" if self.0 > 0 => Some(Move::Left),
+                    "Right" => Some(Move::Right),
+                    _ => None,
+                }
+            }
+
+            fn apply_action(&mut self, action: &Option<Move>) -> anyhow::Result<()> {
+                match action {
+                    Some(Move::Left) => self.0 -= 1,
+                    Some(Move::Right) => self.0 += 1,
+                    None => anyhow::bail!("no action"),
+                }
+                Ok(())
+            }
+
+            fn is_finished(&self) -> bool {
+                self.0 == 3
+            }
+
+            fn get_state(&self) -> u32 {
+                self.0
+            }
+
+            fn get_player_score(&self, _player_number: u32) -> Self::Score {
+                self.0
+            }
+
+            fn get_current_player_number(&self) -> usize {
+                0
+            }
+        }
+
+        let mut game = Corridor(0);
+        assert_eq!(game.parse_action("Left"), None);
+        let action = game.parse_action(&Move::Right.to_string());
+        assert_eq!(action, Some(Move::Right));
+        game.apply_action(&action).unwrap();
+        assert_eq!(game.parse_action("Left"), Some(Move::Left));
+        assert_eq!(game.parse_action("Up"), None);
+    }
+
+    #[test]
+    fn test_default_heuristic_score() {
+        let game = DummyGame {};
+        assert_eq!(game.heuristic_score(0), None);
+    }
+
+    #[test]
+    fn test_default_verify() {
+        let mut game = DummyGame {};
+        let before = game.get_state();
+        game.apply_action(&Some(0)).unwrap();
+        assert!(game.verify(&before, &Some(0)).is_ok());
+    }
+
+    #[test]
+    fn test_default_step() {
+        struct Countdown(u32);
+
+        impl Game for Countdown {
+            type State = u32;
+            type Action = u32;
+            type Score = u32;
+
+            fn parse_action(&self, text: &str) -> Option<u32> {
+                Self::parse_from_str(text)
+            }
+
+            fn apply_action(&mut self, action: &Option<u32>) -> anyhow::Result<()> {
+                self.0 -= 1;
+                anyhow::ensure!(action.is_some(), "no action");
+                Ok(())
+            }
+
+            fn is_finished(&self) -> bool {
+                self.0 == 0
+            }
+
+            fn get_state(&self) -> u32 {
+                self.0
+            }
+
+            fn get_player_score(&self, _player_number: u32) -> Self::Score {
+                0
+            }
+
+            fn get_current_player_number(&self) -> usize {
+                0
+            }
+        }
+
+        let mut game = Countdown(3);
+        assert_eq!(game.step(&Some(0)).unwrap(), ActionOutcome::Continue);
+        assert!(game.step(&None).is_err());
+        assert_eq!(game.step(&Some(0)).unwrap(), ActionOutcome::Finished);
+    }
+
     struct DummyAgent {}
 
     impl Agent<DummyGame> for DummyAgent {
@@ -6,7 +6,8 @@
 //! - Match scheduling and execution (`Evaluator`)
 //! - Tournament logic via the `TournamentStrategy` trait
 //! - Built-in strategies like `SinglePlayerTournament`, `SwissTournament` and `RoundRobin`
-//! - Resource constraints enforced through Linux cgroups v2 and `taskset`
+//! - Resource constraints enforced through Linux cgroups v2 and `taskset` (memory only, with
+//!   `setrlimit`, on macOS)
 //!
 //! Each match consists of one or more agents, each running as a separate OS process.
 //! Process-level isolation applies constraints such as CPU affinity, memory limits, and timeouts.
@@ -17,6 +18,7 @@
 //! - For configuring evaluation behavior, resource limits, and execution environment,
 //!   see [`Configuration`](crate::configuration::Configuration) and [`constraints`].
 //! - To understand tournament formats and match scheduling, see the [`TournamentStrategy`](crate::tournament_strategy::TournamentStrategy) trait and its implementations.
+//! - To get a summary of a whole tournament, or keep a record of every match played and recompute or merge standings, see the [`results`] module.
 //! - For implementing custom games and agents, check out the [`Game`] and [`GameFactory`] traits.
 //!
 //! This crate is designed to be modular and extensible, allowing you to customize agent compilation, match execution, and resource management.
@@ -40,6 +42,7 @@
 //! #     type State = u32;
 //! #     type Action = u32;
 //! #     type Score = f32;
+//! #     fn parse_action(&self, text: &str) -> Option<u32> { Self::parse_from_str(text) }
 //! #     fn apply_action(&mut self, _action: &Option<Self::Action>) -> anyhow::Result<()> { Ok(()) }
 //! #     fn get_state(&self) -> Self::State { 0 }
 //! #     fn get_current_player_number(&self) -> usize { 0 }
@@ -52,6 +55,7 @@
 //! use std::{collections::HashMap, time::Duration};
 //! use anyhow;
 //! use ai_tournament::prelude::*;
+//! use ai_tournament::results::final_ranking;
 //!
 //! fn main() -> anyhow::Result<()> {
 //!     // Define per-agent constraints
@@ -72,11 +76,9 @@
 //!     let (results, errors): (HashMap<String, SinglePlayerScore<_>>, _) =
 //!         evaluator.evaluate("path_to_agents_directory", tournament)?;
 //!
-//!     // Sort and display scores
-//!     let mut sorted = results.iter().collect::<Vec<_>>();
-//!     sorted.sort_by(|a, b| b.1.cmp(a.1));
-//!     for (agent_name, score) in sorted {
-//!         println!("{agent_name}: {score:?}");
+//!     // Display the ranking, tied agents sharing the same place
+//!     for rank in final_ranking(&results) {
+//!         println!("{}. {}: {:?}", rank.place, rank.agents.join(", "), rank.score);
 //!     }
 //!     // Print non-compiling agents and the associated error
 //!     println!("\nNon-compiling agents:");
@@ -103,6 +105,7 @@
 //! #     type State = u32;
 //! #     type Action = u32;
 //! #     type Score = f32;
+//! #     fn parse_action(&self, text: &str) -> Option<u32> { Self::parse_from_str(text) }
 //! #     fn apply_action(&mut self, _action: &Option<Self::Action>) -> anyhow::Result<()> { Ok(()) }
 //! #     fn get_state(&self) -> Self::State { 0 }
 //! #     fn get_current_player_number(&self) -> usize { 0 }
@@ -134,7 +137,8 @@
 //!     let total_time_budget = Duration::from_micros(args.next().unwrap().parse()?);
 //!     let action_timeout = Duration::from_micros(args.next().unwrap().parse()?);
 //!     // After the four first arguments (binary name, port number, time budget, and action
-//!     // timeout) will follow your arguments defined in your config file
+//!     // timeout) will follow your arguments defined in your config file, then the extra
+//!     // arguments given to all agents by the evaluator, if any
 //!
 //!     let mut agent = YourAgent::new();
 //!
@@ -154,11 +158,61 @@
 //!
 //! ## Agent Requirements
 //!
-//! - `Game::State` and `Game::Action` must implement `ToString` and `FromStr`
+//! - `Game::State` must implement `ToString` (and `FromStr` for agents to parse it)
+//! - `Game::Action` must implement `ToString`, and `Game::parse_action` must read it back (e.g.
+//!   with `FromStr`, through `Game::parse_from_str`)
 //! - Agent logic must terminate within the configured timeout
 //! - Communication is done over TCP using a basic protocol:
 //!  * Server -> Agent : string of Game::State
 //!  * Agent -> Server : string of Game::Action
+//!
+//! ## Protocol Handshake
+//!
+//! The server announces its protocol to agents through the `AI_TOURNAMENT_PROTOCOL` environment
+//! variable, formatted as `<mode>/<version>` (currently `text/1`, the protocol described above).
+//!
+//! Right after connecting, an agent may acknowledge it by sending a single line
+//! `ACK <mode>/<version>\n`, echoing the protocol it implements. If it does not match the
+//! announced protocol, the server replies `REJECT <reason>\n`, closes the connection and reports
+//! the agent startup as failed.
+//!
+//! Agents that send nothing during a short grace period (100ms by default, see
+//! [`Configuration::with_handshake_grace_period`](crate::configuration::Configuration::with_handshake_grace_period))
+//! after connecting are considered legacy agents and use the default protocol, so acknowledging
+//! is optional. Agents that do acknowledge must do it immediately after connecting, before any
+//! slow initialization: an `ACK` received after the grace period is not taken for an action, the
+//! agent startup is reported as failed instead.
+//!
+//! ### Game rules
+//!
+//! Agents adapting to variant rules can ask for the rules of the match, as described by
+//! [`Game::rules_description`](crate::game_interface::Game::rules_description), by acknowledging
+//! with `ACK <mode>/<version> RULES\n`. Rules can only be asked for once, in the handshake.
+//!
+//! The server then sends `RULES <length>\n`, followed by the `length` bytes (not characters) of
+//! the UTF-8 description, with no trailing newline. The length is `0` if the game has no
+//! description. This message is sent right after the handshake, **before the first state**: the
+//! agent must read it first, using the length to separate it from the first state if both arrive
+//! in the same read. Agents that do not ask for the rules never receive it.
+//!
+//! ### Framed messages
+//!
+//! In `text/1`, each action is read with a single read of at most 4096 bytes (see
+//! [`Configuration::with_max_message_size`](crate::configuration::Configuration::with_max_message_size)).
+//! Games with larger actions can enable
+//! [`Configuration::with_framed_protocol`](crate::configuration::Configuration::with_framed_protocol),
+//! which announces `framed/1` instead: every state and every action is preceded by its length in
+//! bytes, as a 4-byte big-endian integer, and is read until complete. The handshake and the
+//! `RULES` message are unchanged. Agents that do not acknowledge are assumed to implement the
+//! announced protocol, so framed games should be played by agents reading
+//! `AI_TOURNAMENT_PROTOCOL`.
+//!
+//! ### End of match
+//!
+//! Agents are killed when their match ends. With
+//! [`Configuration::with_end_notification`](crate::configuration::Configuration::with_end_notification),
+//! agents still playing are first sent `GAME_OVER\n` (a `GAME_OVER` frame in `framed/1`) in
+//! place of a state, and should exit within 500ms. No action is expected.
 #![warn(missing_docs)]
 
 mod cgroup_manager;
@@ -169,9 +223,16 @@ mod agent_collector;
 mod client_handler;
 pub mod configuration;
 pub mod constraints;
+mod decision_log;
+mod event_log;
 mod logger;
 mod match_runner;
+mod replay;
+pub mod results;
+mod rng;
 pub mod server;
+pub mod test_support;
+pub mod tournament_control;
 mod tournament_scheduler;
 pub mod tournament_strategy;
 
@@ -190,8 +251,11 @@ pub mod tournament_strategy;
 pub mod prelude {
     pub use crate::configuration::Configuration;
     pub use crate::constraints::ConstraintsBuilder;
+    pub use crate::game_interface::ActionOutcome;
     pub use crate::game_interface::Game;
     pub use crate::game_interface::GameFactory;
+    pub use crate::game_interface::MatchContext;
+    pub use crate::game_interface::MatchScore;
     pub use crate::server::Evaluator;
     pub use crate::tournament_strategy::*;
 }
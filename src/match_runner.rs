@@ -1,17 +1,34 @@
-use std::{collections::HashMap, fmt::Display, str::FromStr, sync::Arc, time::Duration};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::{collections::HashMap, fmt::Display, sync::Arc, time::Duration};
 
-use tracing::{error, info, instrument, trace, warn};
+use tracing::{error, field, info, instrument, trace, trace_span, warn, Span};
 
 use crate::agent::Agent;
 use crate::client_handler::ClientHandler;
-use crate::configuration::Configuration;
+use crate::configuration::{Configuration, EliminatedScore};
 use crate::constraints::Constraints;
-use crate::game_interface::Game;
+use crate::decision_log::DecisionLog;
+use crate::game_interface::{ActionOutcome, Game, MatchContext, MatchScore};
+use crate::replay::{Replay, TurnRecord};
+use crate::results::{AgentTiming, MatchError};
+use crate::rng::match_seed;
+use crate::tournament_control::{ReferenceFactory, ScoringFn, TournamentControl};
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct MatchSettings {
     pub ordered_player: Vec<Arc<Agent>>,
     pub resources: Constraints,
+    /// Round in which the match was scheduled (starting at 1)
+    pub round: usize,
+    /// Position of the match in the list returned by the strategy for that round
+    pub index: usize,
+    /// Weight given to the match by the strategy (see
+    /// [`TournamentStrategy::match_weight`](crate::tournament_strategy::TournamentStrategy::match_weight))
+    pub weight: f64,
+    /// Whether the strategy ranks higher scores first (see
+    /// [`TournamentStrategy::higher_is_better`](crate::tournament_strategy::TournamentStrategy::higher_is_better)),
+    /// to tell which way penalties move the scores
+    pub higher_is_better: bool,
 }
 
 impl Display for MatchSettings {
@@ -36,66 +53,371 @@ where
     pub results: MatchResult<S>,
     pub resources_freed: Constraints,
     pub errors: String,
-    // pub duration: Duration,
+    /// Errors of the players and cut-offs of the match, also reported in `errors`
+    pub error_details: Vec<MatchError>,
+    /// Peak memory usage of each player in bytes, in play order (`None` if it did not start, ran
+    /// without container, or could not be measured)
+    pub peak_memory: Vec<Option<u64>>,
+    pub round: usize,
+    pub index: usize,
+    /// Time each player took to connect after launch, in play order (`None` if it did not start)
+    pub startup_durations: Vec<Option<Duration>>,
+    /// The match failed for reasons unrelated to the agents' play (the host could not start an
+    /// agent, or a transport error occurred). Such a match may be replayed.
+    pub transient: bool,
+    /// Penalty points of each player, in play order
+    pub penalties: Vec<u32>,
+    /// Number of turns played
+    pub turns: usize,
+    /// Time spent by each player on its turns, in play order
+    pub timings: Vec<AgentTiming>,
+    /// Duration of the whole match, from the launch of the players
+    pub duration: Duration,
+    /// Weight of the match, from its settings
+    pub weight: f64,
+    /// Seed given to the players, if any
+    pub seed: Option<u64>,
 }
 
-#[instrument(skip_all,fields(%settings,cpus=?settings.resources.cpus))]
+/// Appends `error` to the error string and to the structured errors of a match
+/// Whether `score` is strictly worse than `other`
+fn is_worse<S: PartialOrd>(score: &S, other: &S, higher_is_better: bool) -> bool {
+    if higher_is_better {
+        score < other
+    } else {
+        score > other
+    }
+}
+
+/// Index of the worst of `scores`: the lowest one, or the highest when lower scores are better
+fn worst_index<S: PartialOrd>(scores: &[S], higher_is_better: bool) -> usize {
+    (1..scores.len()).fold(0, |worst, i| {
+        if is_worse(&scores[i], &scores[worst], higher_is_better) {
+            i
+        } else {
+            worst
+        }
+    })
+}
+
+fn push_error(errors_string: &mut String, error_details: &mut Vec<MatchError>, error: MatchError) {
+    *errors_string += &format!("{error}, ");
+    error_details.push(error);
+}
+
+/// Removes (and kills) the client of player `i`, keeping its peak memory usage
+fn remove_client(
+    clients: &mut HashMap<usize, ClientHandler>,
+    i: usize,
+    peak_memory: &mut [Option<u64>],
+) {
+    if let Some(client) = clients.remove(&i) {
+        peak_memory[i] = client.peak_memory_bytes();
+    }
+}
+
+/// Tells the remaining agents the match is over, and gives them
+/// [`ClientHandler::END_GRACE_PERIOD`] to exit cleanly before they are killed
+fn notify_game_over(
+    clients: &mut HashMap<usize, ClientHandler>,
+    players: &[Arc<Agent>],
+    framed: bool,
+    io_timeout: Duration,
+) {
+    for (&i, client) in clients.iter_mut() {
+        if let Err(e) = client.send_game_over(framed, io_timeout) {
+            warn!(
+                "Could not tell agent {} the match is over: {e:#}",
+                players[i].name
+            );
+        }
+    }
+    let deadline = std::time::Instant::now() + ClientHandler::END_GRACE_PERIOD;
+    for (&i, client) in clients.iter_mut() {
+        if !client.wait_for_exit(deadline) {
+            info!(
+                "Agent {} did not exit after the end of the match: killed",
+                players[i].name
+            );
+        }
+    }
+}
+
+#[instrument(skip_all,fields(
+    %settings,
+    round=settings.round,
+    index=settings.index,
+    cpus=?settings.resources.cpus,
+    agent_cpus=field::Empty,
+    agent_ram_mb=settings.resources.agent_ram / 1_000_000,
+))]
 pub fn run_match<G: Game>(
     settings: MatchSettings,
     config: &Configuration,
     mut game: G,
+    cancelled: &AtomicBool,
+    control: &TournamentControl,
+    scoring: Option<&ScoringFn<G>>,
+    reference: Option<&ReferenceFactory<G>>,
 ) -> RunnerResult<G::Score> {
     trace!("game started");
     let MatchSettings {
         ordered_player,
         resources,
+        round,
+        index,
+        weight,
+        higher_is_better,
     } = settings;
     let mut errors_string = String::new();
+    let mut error_details = vec![];
+    let mut transient = false;
+    let match_start = std::time::Instant::now();
+    let seed = config
+        .match_seed
+        .or_else(|| config.seed.map(|seed| match_seed(seed, round, index)));
+    if let Some(seed) = seed {
+        info!("match seed: {seed}");
+    }
 
-    let max_turn_duration = resources.action_timeout;
-    const MAX_BUFFER_SIZE: usize = 4096;
+    // clocks of each player, scaled by its time multiplier
+    let max_turn_durations = ordered_player
+        .iter()
+        .map(|agent| agent.scale_time(resources.action_timeout))
+        .collect::<Vec<_>>();
+    // larger read buffers are allocated on the heap
+    const MAX_STACK_BUFFER_SIZE: usize = 4096;
+    let mut stack_buffer = [0; MAX_STACK_BUFFER_SIZE];
+    let mut heap_buffer = vec![];
+    let buf: &mut [u8] = if config.max_message_size <= MAX_STACK_BUFFER_SIZE {
+        &mut stack_buffer[..config.max_message_size]
+    } else {
+        heap_buffer.resize(config.max_message_size, 0);
+        &mut heap_buffer
+    };
 
+    let rules = game.rules_description();
     let mut clients: HashMap<usize, ClientHandler> = HashMap::new();
+    // players played by the in-process reference agent
+    let mut references = HashMap::new();
+    let mut startup_durations = vec![None; ordered_player.len()];
+    let mut penalties = vec![0; ordered_player.len()];
+    let mut peak_memory = vec![None; ordered_player.len()];
+    let mut timings = vec![AgentTiming::default(); ordered_player.len()];
     // Start client processes
     {
         let num_cpus = resources.cpus_per_agent;
         let ram = resources.agent_ram;
         let mut avail_res = resources.clone();
+        // CPUs given to each launched agent, in play order
+        let mut agent_cpus = vec![];
         for (i, agent) in ordered_player.iter().enumerate() {
+            if control.is_disqualified(&agent.name) {
+                push_error(
+                    &mut errors_string,
+                    &mut error_details,
+                    MatchError::Disqualified {
+                        agent: agent.name.clone(),
+                        turn: None,
+                    },
+                );
+                continue;
+            }
+            if agent.in_process {
+                if let Some(factory) = reference {
+                    let mut reference = factory();
+                    reference.init();
+                    references.insert(i, reference);
+                }
+                continue;
+            }
+            let mut agent_res = avail_res.take(num_cpus, ram);
+            // the agent is told its own clocks
+            agent_res.action_timeout = max_turn_durations[i];
+            agent_res.time_budget = agent.scale_time(resources.time_budget);
+            let mut cpus = agent_res.cpus.iter().copied().collect::<Vec<_>>();
+            cpus.sort_unstable();
+            agent_cpus.push(format!("{}={cpus:?}", agent.name));
             match ClientHandler::init(
                 agent.clone(),
-                &avail_res.take(num_cpus, ram),
+                &agent_res,
+                seed,
+                config.framed_protocol,
                 config.allow_uncontained,
                 config.debug_agent_stderr,
+                config.stderr_callback.as_ref(),
+                config.port_range.as_ref(),
+                config.cgroup_parent.as_deref(),
+                config.handshake_grace_period,
             ) {
-                Ok(client) => {
+                Ok(mut client) => {
+                    startup_durations[i] = Some(client.startup_duration());
+                    if client.rules_requested() {
+                        if let Err(e) =
+                            client.send_rules(rules.as_deref(), resources.io_timeout_margin)
+                        {
+                            push_error(
+                                &mut errors_string,
+                                &mut error_details,
+                                MatchError::RulesNotSent {
+                                    agent: agent.name.clone(),
+                                    reason: e.to_string(),
+                                },
+                            );
+                            warn!("Failed to send rules to agent {}: {e}", agent.name);
+                        }
+                    }
                     clients.insert(i, client);
                 }
                 Err(e) => {
-                    errors_string += &format!("{} startup failed ({e}), ", agent.name);
+                    // an agent crashing or never connecting would fail again
+                    transient |= is_transient_startup_error(&e);
+                    push_error(
+                        &mut errors_string,
+                        &mut error_details,
+                        MatchError::StartupFailed {
+                            agent: agent.name.clone(),
+                            reason: e.to_string(),
+                        },
+                    );
                     warn!("Failed to start client for agent {}: {e}", agent.name);
                 }
             }
         }
+        Span::current().record("agent_cpus", agent_cpus.join(" "));
     }
 
     // Init clocks (time budget)
-    let mut time_budgets = vec![resources.time_budget; ordered_player.len()];
+    let mut time_budgets = ordered_player
+        .iter()
+        .map(|agent| agent.scale_time(resources.time_budget))
+        .collect::<Vec<_>>();
+    if resources.startup_in_time_budget {
+        for (budget, startup) in time_budgets.iter_mut().zip(&startup_durations) {
+            *budget = budget.saturating_sub(startup.unwrap_or_default());
+        }
+    }
+
+    let max_turns_hint = game.max_turns_hint();
+    // a game going this many times beyond its hint is likely stuck
+    const RUNAWAY_FACTOR: usize = 10;
+    // the hint comes from the game: do not trust it to size the buffers
+    const MAX_PREALLOCATED_TURNS: usize = 1024;
+    let preallocated_turns = max_turns_hint
+        .unwrap_or_default()
+        .min(MAX_PREALLOCATED_TURNS);
+
+    let mut decisions = config
+        .decision_log_dir
+        .is_some()
+        .then(|| DecisionLog::with_capacity(preallocated_turns));
+    let mut replay = config
+        .replay_dir
+        .is_some()
+        .then(|| Replay::with_capacity(preallocated_turns));
 
     let mut turn = 0;
+    // true if the match was stopped before the game finished by itself
+    let mut cut_off = false;
+    // true if the match was stopped as a player was eliminated (see `end_on_elimination`)
+    let mut ended_on_elimination = false;
 
-    while !game.is_finished() && !clients.is_empty() {
+    let mut finished = game.is_finished();
+    while !finished && !clients.is_empty() {
+        if cancelled.load(Ordering::Relaxed) {
+            info!("match cancelled (turn {turn})");
+            push_error(
+                &mut errors_string,
+                &mut error_details,
+                MatchError::Cancelled,
+            );
+            cut_off = true;
+            break;
+        }
+        if let Some(match_timeout) = resources.match_timeout {
+            if match_start.elapsed() > match_timeout {
+                info!(
+                    "match stopped: wall-clock limit of {match_timeout:?} exceeded (turn {turn})"
+                );
+                push_error(
+                    &mut errors_string,
+                    &mut error_details,
+                    MatchError::WallClockLimit,
+                );
+                cut_off = true;
+                break;
+            }
+        }
+        for (i, agent) in ordered_player.iter().enumerate() {
+            if (clients.contains_key(&i) || references.contains_key(&i))
+                && control.is_disqualified(&agent.name)
+            {
+                info!("Agent {} disqualified (turn {turn})", agent.name);
+                push_error(
+                    &mut errors_string,
+                    &mut error_details,
+                    MatchError::Disqualified {
+                        agent: agent.name.clone(),
+                        turn: Some(turn),
+                    },
+                );
+                remove_client(&mut clients, i, &mut peak_memory);
+                references.remove(&i);
+            }
+        }
+        if clients.is_empty() {
+            break;
+        }
+        if let Some(max_turns) = resources.max_turns.filter(|&max| turn >= max) {
+            info!("match stopped: max turns reached ({max_turns} turns)");
+            push_error(
+                &mut errors_string,
+                &mut error_details,
+                MatchError::MaxTurns { turns: max_turns },
+            );
+            cut_off = true;
+            break;
+        }
+        if let Some(hint) = max_turns_hint {
+            if config.enforce_max_turns_hint && turn >= hint {
+                info!("match stopped: max turns hint reached ({hint} turns)");
+                push_error(
+                    &mut errors_string,
+                    &mut error_details,
+                    MatchError::MaxTurnsHint { turns: hint },
+                );
+                cut_off = true;
+                break;
+            }
+            if hint > 0 && turn == hint.saturating_mul(RUNAWAY_FACTOR) {
+                warn!("match reached {turn} turns, far beyond the game's max turns hint ({hint}): is the game stuck?");
+            }
+        }
         turn += 1;
+        // errors of this turn are appended after this length
+        let errors_before = errors_string.len();
         let current = game.get_current_player_number();
+        let _turn_span = trace_span!("turn", turn, player = current).entered();
 
-        let state_str = game.get_state().to_string();
+        let state = game.get_state();
+        let state_str = state.to_string();
+        // keep the state given to the player only if it will be verified
+        let state_before = config.verify_game_state.then_some(state);
 
         // If player is missing, action is none
         let action = if let Some(client) = clients.get_mut(&current) {
-            let mut buf = [0; MAX_BUFFER_SIZE];
-
             let time_budget = time_budgets[current];
-            let mut max_duration = Duration::min(max_turn_duration, time_budget);
+            let cpu_start = config
+                .cpu_time_budget
+                .then(|| client.cpu_time_used())
+                .flatten();
+            // an agent charged CPU time may use more wall-clock time than its remaining budget:
+            // its budget is checked once it answered
+            let mut max_duration = if cpu_start.is_some() && !time_budget.is_zero() {
+                max_turn_durations[current]
+            } else {
+                Duration::min(max_turn_durations[current], time_budget)
+            };
             // always add margin except when no time remains. Otherwise, we could play indefinitely
             // if each action takes less time than margin
             if !max_duration.is_zero() {
@@ -103,20 +425,73 @@ pub fn run_match<G: Game>(
             }
             let timer_start = std::time::Instant::now();
 
-            let response = client.send_and_recv(state_str.as_bytes(), &mut buf, max_duration);
+            let response = if config.framed_protocol {
+                client.send_and_recv_framed(
+                    state_str.as_bytes(),
+                    max_duration,
+                    resources.io_timeout_margin,
+                )
+            } else {
+                client
+                    .send_and_recv(
+                        state_str.as_bytes(),
+                        buf,
+                        max_duration,
+                        resources.io_timeout_margin,
+                    )
+                    .map(|n| buf[..n].to_vec())
+            };
 
-            let elapsed = timer_start.elapsed();
+            let mut elapsed = timer_start.elapsed();
+            // falls back to wall-clock time if the cpu time could not be read
+            if let Some(start) = cpu_start {
+                if let Some(end) = client.cpu_time_used() {
+                    elapsed = end.saturating_sub(start);
+                }
+            }
+            let response = match response {
+                Ok(_) if cpu_start.is_some() && elapsed > time_budget + resources.time_margin => {
+                    Err(anyhow::anyhow!(
+                        "time budget exceeded ({}ms used)",
+                        elapsed.as_millis()
+                    ))
+                }
+                response => response,
+            };
             time_budgets[current] = time_budgets[current]
                 .checked_sub(elapsed)
                 .unwrap_or(Duration::ZERO);
+            timings[current].record_turn(elapsed, time_budgets[current]);
 
             match response {
-                Ok(received) => {
-                    let response = std::str::from_utf8(&buf[..received]);
+                // the agent is not the legacy agent it seemed to be: it is not running as expected
+                Ok(bytes) if client.is_late_ack(&bytes) => {
+                    transient = true;
+                    push_error(
+                        &mut errors_string,
+                        &mut error_details,
+                        MatchError::StartupFailed {
+                            agent: ordered_player[current].name.clone(),
+                            reason: format!(
+                                "protocol acknowledged after the handshake grace period ({}ms)",
+                                config.handshake_grace_period.as_millis()
+                            ),
+                        },
+                    );
+                    warn!(
+                        "Agent {} acknowledged the protocol too late: its ACK is not an action",
+                        ordered_player[current].name
+                    );
+                    remove_client(&mut clients, current, &mut peak_memory);
+                    None
+                }
+                Ok(bytes) => {
+                    let received = bytes.len();
+                    let response = std::str::from_utf8(&bytes);
                     match response {
-                        Ok(text) => match G::Action::from_str(text.trim()) {
-                            Ok(action) => Some(action),
-                            Err(_) => {
+                        Ok(text) => match game.parse_action(text.trim()) {
+                            Some(action) => Some(action),
+                            None => {
                                 info!(
                                     "Agent {} sent invalid action: '{text}' {}",
                                     ordered_player[current].name,
@@ -126,18 +501,22 @@ pub fn run_match<G: Game>(
                                         ""
                                     }
                                 );
-                                if received == 0 {
-                                    errors_string += &format!(
-                                        "{} empty string received (player probably crashed), ",
-                                        ordered_player[current].name
-                                    );
+                                let agent = ordered_player[current].name.clone();
+                                let error = if received == 0 && client.oom_killed() {
+                                    penalties[current] += config.penalties.crash;
+                                    MatchError::OutOfMemory { agent, turn }
+                                } else if received == 0 {
+                                    penalties[current] += config.penalties.crash;
+                                    MatchError::EmptyResponse { agent }
                                 } else {
-                                    errors_string += &format!(
-                                        "{} not an action: '{text}', ",
-                                        ordered_player[current].name
-                                    );
-                                }
-                                clients.remove(&current);
+                                    penalties[current] += config.penalties.illegal_action;
+                                    MatchError::InvalidAction {
+                                        agent,
+                                        text: text.to_string(),
+                                    }
+                                };
+                                push_error(&mut errors_string, &mut error_details, error);
+                                remove_client(&mut clients, current, &mut peak_memory);
                                 None
                             }
                         },
@@ -146,22 +525,71 @@ pub fn run_match<G: Game>(
                                 "Agent {} sent non-UTF8 response",
                                 ordered_player[current].name
                             );
-                            errors_string +=
-                                &format!("{} non-utf8 response, ", ordered_player[current].name);
-                            clients.remove(&current);
+                            penalties[current] += config.penalties.illegal_action;
+                            push_error(
+                                &mut errors_string,
+                                &mut error_details,
+                                MatchError::NonUtf8 {
+                                    agent: ordered_player[current].name.clone(),
+                                },
+                            );
+                            remove_client(&mut clients, current, &mut peak_memory);
                             None
                         }
                     }
                 }
+                // the connection of an agent killed by the kernel may be reset: not transient
+                Err(e) if is_transport_error(&e) && client.oom_killed() => {
+                    penalties[current] += config.penalties.crash;
+                    push_error(
+                        &mut errors_string,
+                        &mut error_details,
+                        MatchError::OutOfMemory {
+                            agent: ordered_player[current].name.clone(),
+                            turn,
+                        },
+                    );
+                    warn!(
+                        "Agent {} was killed for exceeding its memory limit: {e:#}",
+                        ordered_player[current].name
+                    );
+                    remove_client(&mut clients, current, &mut peak_memory);
+                    None
+                }
+                Err(e) if is_transport_error(&e) => {
+                    transient = true;
+                    push_error(
+                        &mut errors_string,
+                        &mut error_details,
+                        MatchError::TransportError {
+                            agent: ordered_player[current].name.clone(),
+                            turn,
+                            reason: format!("{e:#}"),
+                        },
+                    );
+                    warn!(
+                        "Transport error with agent {}: {e:#}",
+                        ordered_player[current].name
+                    );
+                    remove_client(&mut clients, current, &mut peak_memory);
+                    None
+                }
                 Err(e) => {
                     // timeout is silenced when duration is small (time budget exceeded is normal behaviour (must happen))
-                    if max_duration >= resources.action_timeout
-                        || max_duration >= (resources.time_budget / 10)
+                    if max_duration >= max_turn_durations[current]
+                        || max_duration
+                            >= (ordered_player[current].scale_time(resources.time_budget) / 10)
                     {
-                        errors_string += &format!(
-                            "{}: {e} response timeout ({}ms) (turn {turn}), ",
-                            ordered_player[current].name,
-                            max_duration.as_millis()
+                        penalties[current] += config.penalties.timeout;
+                        push_error(
+                            &mut errors_string,
+                            &mut error_details,
+                            MatchError::Timeout {
+                                agent: ordered_player[current].name.clone(),
+                                turn,
+                                limit: max_duration,
+                                reason: e.to_string(),
+                            },
                         );
                         warn!(
                             "Agent {} did not respond in time (min(action_timeout, time_budget) + margin = {}ms): state={state_str}, error={e}",
@@ -175,10 +603,14 @@ pub fn run_match<G: Game>(
                             max_duration.as_millis()
                         );
                     }
-                    clients.remove(&current);
+                    remove_client(&mut clients, current, &mut peak_memory);
                     None
                 }
             }
+        } else if let Some(reference) = references.get_mut(&current) {
+            let deadline = std::time::SystemTime::now()
+                + Duration::min(max_turn_durations[current], time_budgets[current]);
+            Some(reference.select_action(game.get_state(), deadline))
         } else {
             // Agent was already eliminated/killed/did not start
             None
@@ -186,38 +618,227 @@ pub fn run_match<G: Game>(
 
         // Apply action (even if it's None, Game is supposed to handle elimination logic)
         // Only warn when a non-None action is rejected
-        if let Err(e) = game.apply_action(&action) {
-            if action.is_some() {
+        match game.step(&action) {
+            Ok(outcome) => {
+                finished = outcome == ActionOutcome::Finished;
+                if let (Some(decisions), Some(action)) = (&mut decisions, &action) {
+                    decisions.record(current, turn, &state_str, action.to_string());
+                }
+            }
+            Err(e) => {
+                finished = game.is_finished();
+                if action.is_some() {
+                    warn!(
+                        "player {current}'s action ({}) rejected by Game (State={state_str})",
+                        action.as_ref().unwrap().to_string(),
+                    );
+                    penalties[current] += config.penalties.illegal_action;
+                    push_error(
+                        &mut errors_string,
+                        &mut error_details,
+                        MatchError::ActionRejected {
+                            agent: ordered_player[current].name.clone(),
+                            action: action.as_ref().unwrap().to_string(),
+                            reason: e.to_string(),
+                        },
+                    );
+                    remove_client(&mut clients, current, &mut peak_memory);
+                }
+            }
+        }
+
+        if let Some(before) = state_before {
+            if let Err(e) = game.verify(&before, &action) {
                 warn!(
-                    "player {current}'s action ({}) rejected by Game (State={state_str})",
-                    action.as_ref().unwrap().to_string(),
+                    "game state verification failed after player {current}'s action (State={state_str}): {e}"
                 );
-                errors_string += &format!(
-                    "{}'s action '{}' was rejected: {e}, ",
-                    ordered_player[current].name,
-                    action.unwrap().to_string()
+                push_error(
+                    &mut errors_string,
+                    &mut error_details,
+                    MatchError::VerificationFailed {
+                        agent: ordered_player[current].name.clone(),
+                        reason: e.to_string(),
+                    },
                 );
-                clients.remove(&current);
             }
         }
+
+        if let Some(replay) = &mut replay {
+            let error = errors_string[errors_before..].trim_end_matches(", ");
+            replay.record(TurnRecord {
+                turn,
+                player: current,
+                state: state_str.clone(),
+                action: action.as_ref().map(ToString::to_string),
+                error: (!error.is_empty()).then(|| error.to_string()),
+            });
+        }
+
+        // the remaining player has nobody to play against
+        if config.end_on_elimination
+            && ordered_player.len() <= 2
+            && !finished
+            && !clients.contains_key(&current)
+            && !references.contains_key(&current)
+        {
+            info!(
+                "match ended: {} was eliminated (turn {turn})",
+                ordered_player[current].name
+            );
+            cut_off = true;
+            ended_on_elimination = true;
+            break;
+        }
+    }
+    let eliminated = (0..ordered_player.len())
+        .map(|i| !clients.contains_key(&i) && !references.contains_key(&i))
+        .collect::<Vec<_>>();
+    if config.end_notification {
+        notify_game_over(
+            &mut clients,
+            &ordered_player,
+            config.framed_protocol,
+            resources.io_timeout_margin,
+        );
     }
     // Kill remaining processes
-    drop(clients);
+    for (i, client) in clients.drain() {
+        peak_memory[i] = client.peak_memory_bytes();
+    }
+    for (agent, peak) in ordered_player.iter().zip(&peak_memory) {
+        if let Some(peak) = peak.filter(|&peak| peak >= resources.agent_ram as u64) {
+            warn!(
+                "Agent {} reached its memory limit ({}MB used): it may have been OOM-killed",
+                agent.name,
+                peak / 1_000_000
+            );
+        }
+    }
 
     // Collect final scores
+    let context = scoring.map(|_| MatchContext {
+        players: ordered_player.iter().map(|a| a.name.clone()).collect(),
+        turns: turn,
+        errors: errors_string.clone(),
+        cut_off,
+        penalties: penalties.clone(),
+        eliminated: eliminated.clone(),
+    });
+    let game_score = |i: usize| {
+        // the score of an unfinished game may be meaningless: prefer the game's estimate
+        cut_off
+            .then(|| game.heuristic_score(i as u32))
+            .flatten()
+            .unwrap_or_else(|| game.get_player_score(i as u32))
+    };
+    let mut scores = (0..ordered_player.len())
+        .map(|i| match (scoring, &context) {
+            (Some(scoring), Some(context)) => scoring(&game, context, i as u32),
+            _ => game_score(i),
+        })
+        .collect::<Vec<_>>();
+    if scoring.is_none() && eliminated.contains(&true) {
+        // disqualified players forfeit the match, whatever the game made of it
+        let forfeited = |i: usize| {
+            error_details.iter().any(|error| {
+                matches!(error, MatchError::Disqualified { agent, .. }
+                    if *agent == ordered_player[i].name)
+            })
+        };
+        let worst = worst_index(&scores, higher_is_better);
+        for i in (0..scores.len()).filter(|&i| eliminated[i]) {
+            match config.eliminated_score {
+                EliminatedScore::Game if forfeited(i) => scores[i] = game_score(worst),
+                EliminatedScore::Game => {}
+                EliminatedScore::Minimum => scores[i] = game_score(worst),
+                EliminatedScore::Fixed(points) => scores[i] = G::Score::from_points(points),
+            }
+        }
+    }
+    if scoring.is_none() && ended_on_elimination {
+        // the remaining player wins, whatever the game made of the elimination. Scores are not
+        // `Clone`: `offset(0)` copies them
+        let lost = scores[worst_index(&scores, higher_is_better)].offset(0);
+        let won = lost.offset(if higher_is_better { 1 } else { -1 });
+        for (score, &eliminated) in scores.iter_mut().zip(&eliminated) {
+            if eliminated {
+                *score = lost.offset(0);
+            } else if !is_worse(&lost, score, higher_is_better) {
+                *score = won.offset(0);
+            }
+        }
+    }
+    if scoring.is_none() {
+        // penalty points count against the player
+        let sign = if higher_is_better { -1 } else { 1 };
+        for (score, &points) in scores.iter_mut().zip(&penalties) {
+            if points > 0 {
+                *score = score.offset(sign * i64::from(points));
+            }
+        }
+    }
     let mut result_str = vec![];
     let mut results = vec![];
-    for (i, agent) in ordered_player.iter().enumerate() {
-        let score = game.get_player_score(i as u32);
+    for (agent, score) in ordered_player.iter().zip(scores) {
         result_str.push(score.to_string());
         results.push((agent.clone(), score));
     }
 
+    if let (Some(decisions), Some(dir), false) = (decisions, &config.decision_log_dir, transient) {
+        if let Err(e) = decisions.write(dir, &ordered_player, round, index) {
+            error!("Could not write decision log: {e:#}");
+        }
+    }
+    if let (Some(replay), Some(dir)) = (replay, &config.replay_dir) {
+        if let Err(e) = replay.write(dir, &ordered_player, round, index) {
+            error!("Could not write replay: {e:#}");
+        }
+    }
+
     let result_str = result_str.join("-");
     trace!("match end: {result_str}, {errors_string}");
     RunnerResult {
         results,
         resources_freed: resources,
         errors: errors_string,
+        error_details,
+        peak_memory,
+        round,
+        index,
+        startup_durations,
+        transient,
+        penalties,
+        turns: turn,
+        timings,
+        duration: match_start.elapsed(),
+        weight,
+        seed,
     }
 }
+
+/// Errors caused by the OS or the network rather than by the agent (which can only time out,
+/// close the connection or crash).
+fn is_transport_error(e: &anyhow::Error) -> bool {
+    use std::io::ErrorKind;
+    e.chain()
+        .filter_map(|cause| cause.downcast_ref::<std::io::Error>())
+        .any(|e| {
+            matches!(
+                e.kind(),
+                ErrorKind::ConnectionRefused
+                    | ErrorKind::AddrInUse
+                    | ErrorKind::AddrNotAvailable
+                    | ErrorKind::Interrupted
+            )
+        })
+}
+
+/// Startup errors caused by the host rather than by the agent: a transport error, no free port,
+/// or no resources left to spawn the process.
+fn is_transient_startup_error(e: &anyhow::Error) -> bool {
+    use std::io::ErrorKind;
+    is_transport_error(e)
+        || e.chain()
+            .filter_map(|cause| cause.downcast_ref::<std::io::Error>())
+            .any(|e| matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::OutOfMemory))
+}
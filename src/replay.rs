@@ -0,0 +1,124 @@
+use std::{fmt::Write as _, path::Path, sync::Arc};
+
+use anyhow::Context;
+
+use crate::{agent::Agent, decision_log::escape_json};
+
+/// One turn of a match, as seen by the evaluator
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct TurnRecord {
+    /// Turn number, starting at 1
+    pub(crate) turn: usize,
+    /// Player number of the player to play
+    pub(crate) player: usize,
+    /// State sent to the player, as sent on the wire
+    pub(crate) state: String,
+    /// Action played, as formatted by the game. `None` if the player sent no valid action
+    pub(crate) action: Option<String>,
+    /// Errors of the turn (invalid or rejected action, timeout, crash...)
+    pub(crate) error: Option<String>,
+}
+
+impl TurnRecord {
+    fn to_json(&self) -> String {
+        let optional = |s: &Option<String>| {
+            s.as_ref()
+                .map_or("null".to_string(), |s| format!("\"{}\"", escape_json(s)))
+        };
+        format!(
+            "{{\"turn\":{},\"player\":{},\"state\":\"{}\",\"action\":{},\"error\":{}}}",
+            self.turn,
+            self.player,
+            escape_json(&self.state),
+            optional(&self.action),
+            optional(&self.error)
+        )
+    }
+}
+
+/// Turns of a match, written to one JSONL file per player of the match
+/// (see [`Configuration::with_replays`](crate::configuration::Configuration::with_replays))
+#[derive(Debug, Default)]
+pub(crate) struct Replay {
+    turns: Vec<TurnRecord>,
+}
+
+impl Replay {
+    pub(crate) fn with_capacity(capacity: usize) -> Self {
+        Self {
+            turns: Vec::with_capacity(capacity),
+        }
+    }
+
+    pub(crate) fn record(&mut self, turn: TurnRecord) {
+        self.turns.push(turn);
+    }
+
+    /// Writes the replay to `<dir>/<agent name>/match_<round>-<index>.jsonl` for each player
+    pub(crate) fn write(
+        &self,
+        dir: &Path,
+        players: &[Arc<Agent>],
+        round: usize,
+        index: usize,
+    ) -> anyhow::Result<()> {
+        let mut lines = String::new();
+        for turn in &self.turns {
+            writeln!(lines, "{}", turn.to_json()).unwrap();
+        }
+        for agent in players {
+            let agent_dir = dir.join(&agent.name);
+            std::fs::create_dir_all(&agent_dir)
+                .with_context(|| format!("could not create directory {}", agent_dir.display()))?;
+            let path = agent_dir.join(format!("match_{round}-{index}.jsonl"));
+            std::fs::write(&path, &lines)
+                .with_context(|| format!("could not write {}", path.display()))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod replay_tests {
+    use super::*;
+
+    #[test]
+    fn test_write_per_player() {
+        let dir = std::env::temp_dir().join(format!("ai_tournament_replay_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let players = [
+            Arc::new(Agent::new("a".into(), None, None, 0, None)),
+            Arc::new(Agent::new("b".into(), None, None, 1, None)),
+        ];
+
+        let mut replay = Replay::default();
+        replay.record(TurnRecord {
+            turn: 1,
+            player: 0,
+            state: "x\ny".into(),
+            action: Some("3".into()),
+            error: None,
+        });
+        replay.record(TurnRecord {
+            turn: 2,
+            player: 1,
+            state: "z".into(),
+            action: None,
+            error: Some("b not an action: 'q'".into()),
+        });
+        replay.write(&dir, &players, 2, 7).unwrap();
+
+        let a = std::fs::read_to_string(dir.join("a").join("match_2-7.jsonl")).unwrap();
+        assert_eq!(
+            a.lines().collect::<Vec<_>>(),
+            [
+                r#"{"turn":1,"player":0,"state":"x\ny","action":"3","error":null}"#,
+                r#"{"turn":2,"player":1,"state":"z","action":null,"error":"b not an action: 'q'"}"#
+            ]
+        );
+        let b = std::fs::read_to_string(dir.join("b").join("match_2-7.jsonl")).unwrap();
+        assert_eq!(a, b);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,1038 @@
+//! Match history, result aggregation and rankings.
+//!
+//! An [`EvaluationReport`] summarizes a whole tournament (scores, failures, number of matches,
+//! ...). It is returned by [`Evaluator::evaluate_full`](crate::server::Evaluator::evaluate_full).
+//!
+//! [`final_ranking`] turns the final scores returned by
+//! [`Evaluator::evaluate`](crate::server::Evaluator::evaluate) into a ranking with places, tied
+//! agents sharing the same place, which [`to_markdown`] formats as a Markdown table.
+//! [`export_results_json`] writes the final scores to a JSON file instead, e.g. for a dashboard,
+//! and [`export_leaderboard_csv`] writes the ranking to a CSV file, e.g. as a CI artifact.
+//!
+//! Rankings put the greatest scores first, unless the strategy ranks the lowest scores first (see
+//! [`TournamentStrategy::higher_is_better`](crate::tournament_strategy::TournamentStrategy::higher_is_better)):
+//! [`EvaluationReport::ranking`] and [`final_ranking_ordered`] take care of it, without reversing
+//! the scores by hand.
+//!
+//! A [`MatchRecord`] describes one finished match: who played (in play order), what each player
+//! scored, and where the match sat in the schedule. Records are obtained with
+//! [`Evaluator::evaluate_with_history`](crate::server::Evaluator::evaluate_with_history).
+//!
+//! Records are the canonical source of a tournament outcome: standings can be recomputed from them
+//! at any time. This makes it possible to split a deterministic tournament (e.g.
+//! [`RoundRobinTournament`](crate::tournament_strategy::RoundRobinTournament) or
+//! [`SinglePlayerTournament`](crate::tournament_strategy::SinglePlayerTournament)) across several
+//! machines and stitch the partial runs together with [`merge`] or [`merge_single_player`].
+//!
+//! # Duplicate matches
+//!
+//! Two records are considered to describe **the same match** when they have the same `round`,
+//! the same `index` within that round, and the same `players` in the same order. Scores and errors
+//! are not part of the identity: when the same match was played on several machines, only the
+//! first record encountered (in the order of the `runs` argument, then in the order of each run)
+//! is kept.
+//!
+//! This identification is only meaningful for strategies that schedule matches deterministically
+//! given the same set of agents. Adaptive strategies (such as Swiss) produce different schedules on
+//! each run and their records should not be merged.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::Path;
+use std::time::Duration;
+
+use anyhow::Context;
+use serde::Serialize;
+
+use crate::match_runner::RunnerResult;
+use crate::tournament_strategy::{PlacementScore, SinglePlayerScore, TwoPlayersGameScore};
+
+/// Record of a single finished match.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MatchRecord<S> {
+    /// Round in which the match was scheduled (starting at 1).
+    pub round: usize,
+    /// Position of the match in the list of matches generated for that round.
+    pub index: usize,
+    /// Agent names, in play order. When the strategy declares
+    /// [roles](crate::tournament_strategy::TournamentStrategy::roles), the player at position `i`
+    /// played the `i`-th role.
+    pub players: Vec<String>,
+    /// Arguments each player was launched with (from its config file), in the same order as
+    /// `players`. Useful to tell apart configurations of the same agent when `test_all_configs`
+    /// is enabled.
+    pub args: Vec<Vec<String>>,
+    /// Final score of each player, in the same order as `players`.
+    pub scores: Vec<S>,
+    /// Errors reported during the match (crashes, timeouts, invalid actions): the
+    /// [`error_details`](Self::error_details), each displayed and followed by `, `. Empty if none.
+    pub errors: String,
+    /// Errors reported in `errors`, in the order they happened, to tell timeouts from crashes or
+    /// invalid actions, and why the match was cut off (cancellation, turn limits...).
+    pub error_details: Vec<MatchError>,
+    /// Time each player took to connect after being launched, in the same order as `players`.
+    /// `None` if the player failed to start.
+    pub startup_durations: Vec<Option<Duration>>,
+    /// Penalty points of each player, in the same order as `players` (see
+    /// [`Configuration::with_penalties`](crate::configuration::Configuration::with_penalties)).
+    pub penalties: Vec<u32>,
+    /// Peak memory usage of each player in bytes, in the same order as `players`, to compare with
+    /// its RAM limit. `None` if the player did not start, or ran without container (see
+    /// [`Configuration::with_allow_uncontained`](crate::configuration::Configuration::with_allow_uncontained)).
+    pub peak_memory: Vec<Option<u64>>,
+    /// Number of turns played.
+    pub turns: usize,
+    /// Time spent by each player on its turns, in the same order as `players`. Empty if the
+    /// match was not played.
+    pub timings: Vec<AgentTiming>,
+    /// Time from the start of the match (before launching the players) to its end.
+    pub duration: Duration,
+    /// Weight given to the match by the strategy (see
+    /// [`TournamentStrategy::match_weight`](crate::tournament_strategy::TournamentStrategy::match_weight)).
+    pub weight: f64,
+    /// Seed given to the players (see
+    /// [`Configuration::with_seed`](crate::configuration::Configuration::with_seed)), `None` if
+    /// unset. Give it to [`Evaluator::rerun_match`](crate::server::Evaluator::rerun_match) to play
+    /// the match again.
+    pub seed: Option<u64>,
+}
+
+impl<S: PartialOrd + Clone> MatchRecord<S> {
+    pub(crate) fn from_runner_result(result: &RunnerResult<S>) -> Self {
+        let (players, scores) = result
+            .results
+            .iter()
+            .map(|(agent, score)| (agent.name.clone(), score.clone()))
+            .unzip();
+        let args = result
+            .results
+            .iter()
+            .map(|(agent, _)| agent.args().to_vec())
+            .collect();
+        MatchRecord {
+            round: result.round,
+            index: result.index,
+            players,
+            args,
+            scores,
+            errors: result.errors.clone(),
+            error_details: result.error_details.clone(),
+            peak_memory: result.peak_memory.clone(),
+            startup_durations: result.startup_durations.clone(),
+            penalties: result.penalties.clone(),
+            turns: result.turns,
+            timings: result.timings.clone(),
+            duration: result.duration,
+            weight: result.weight,
+            seed: result.seed,
+        }
+    }
+}
+
+/// Time spent by a player on its turns during a match (see [`MatchRecord::timings`]).
+///
+/// Think time is what was charged to the time budget of the player: wall-clock time, or CPU time
+/// with [`Configuration::with_cpu_time_budget`](crate::configuration::Configuration::with_cpu_time_budget).
+/// Players played in-process are not timed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AgentTiming {
+    /// Total think time over the match.
+    pub think_time: Duration,
+    /// Number of turns the player was asked to play, including a turn it failed to answer.
+    pub turns: usize,
+    /// Longest think time of a single turn.
+    pub max_turn_time: Duration,
+    /// The player used up its whole time budget.
+    pub budget_exhausted: bool,
+}
+
+impl AgentTiming {
+    /// Adds a turn of `think_time`, after which `remaining_budget` was left.
+    pub(crate) fn record_turn(&mut self, think_time: Duration, remaining_budget: Duration) {
+        self.think_time += think_time;
+        self.turns += 1;
+        self.max_turn_time = self.max_turn_time.max(think_time);
+        self.budget_exhausted = remaining_budget.is_zero();
+    }
+}
+
+/// Error of a player during a match, or reason why the match was cut off (see
+/// [`MatchRecord::error_details`]). Players are given by their name.
+///
+/// Displayed as in [`MatchRecord::errors`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum MatchError {
+    /// The agent could not be started, did not connect in time, or acknowledged the protocol
+    /// after the handshake grace period.
+    StartupFailed {
+        /// Name of the agent.
+        agent: String,
+        /// Why the startup failed.
+        reason: String,
+    },
+    /// The agent did not respond in time.
+    Timeout {
+        /// Name of the agent.
+        agent: String,
+        /// Turn of the match, starting at 1.
+        turn: usize,
+        /// Time it was given to respond, margin included.
+        limit: Duration,
+        /// What exceeded the limit (no response, or the CPU time used).
+        reason: String,
+    },
+    /// The response of the agent could not be parsed as an action.
+    InvalidAction {
+        /// Name of the agent.
+        agent: String,
+        /// Response of the agent.
+        text: String,
+    },
+    /// The response of the agent was not valid UTF-8.
+    NonUtf8 {
+        /// Name of the agent.
+        agent: String,
+    },
+    /// The game rejected the action of the agent.
+    ActionRejected {
+        /// Name of the agent.
+        agent: String,
+        /// The rejected action.
+        action: String,
+        /// Error returned by the game.
+        reason: String,
+    },
+    /// The agent closed the connection without responding (it probably crashed).
+    EmptyResponse {
+        /// Name of the agent.
+        agent: String,
+    },
+    /// The kernel killed the agent for exceeding its memory limit. Only detected when agents run
+    /// in containers.
+    OutOfMemory {
+        /// Name of the agent.
+        agent: String,
+        /// Turn of the match, starting at 1.
+        turn: usize,
+    },
+    /// The connection with the agent failed (reset, broken pipe...), e.g. because it crashed.
+    /// The match is retried if retries are enabled (see
+    /// [`Configuration::with_match_retries`](crate::configuration::Configuration::with_match_retries)).
+    TransportError {
+        /// Name of the agent.
+        agent: String,
+        /// Turn of the match, starting at 1.
+        turn: usize,
+        /// The I/O error.
+        reason: String,
+    },
+    /// The agent was disqualified (see
+    /// [`TournamentControl::disqualify`](crate::server::TournamentControl::disqualify)).
+    Disqualified {
+        /// Name of the agent.
+        agent: String,
+        /// Turn of the match at which it was removed, `None` if it was not launched.
+        turn: Option<usize>,
+    },
+    /// [`Game::verify`](crate::game_interface::Game::verify) failed after the action of the
+    /// agent.
+    VerificationFailed {
+        /// Name of the agent.
+        agent: String,
+        /// Error returned by the game.
+        reason: String,
+    },
+    /// The rules of the game could not be sent to the agent, which asked for them.
+    RulesNotSent {
+        /// Name of the agent.
+        agent: String,
+        /// Why sending failed.
+        reason: String,
+    },
+    /// The match was cancelled (e.g. the tournament was stopped).
+    Cancelled,
+    /// The match exceeded its wall-clock limit.
+    WallClockLimit,
+    /// The match reached the maximum number of turns of the constraints.
+    MaxTurns {
+        /// The maximum number of turns.
+        turns: usize,
+    },
+    /// The match reached the maximum number of turns hinted by the game (see
+    /// [`Game::max_turns_hint`](crate::game_interface::Game::max_turns_hint)).
+    MaxTurnsHint {
+        /// The hinted number of turns.
+        turns: usize,
+    },
+}
+
+impl MatchError {
+    /// Name of the agent at fault, `None` if the match was cut off.
+    pub fn agent(&self) -> Option<&str> {
+        match self {
+            MatchError::StartupFailed { agent, .. }
+            | MatchError::Timeout { agent, .. }
+            | MatchError::InvalidAction { agent, .. }
+            | MatchError::NonUtf8 { agent }
+            | MatchError::ActionRejected { agent, .. }
+            | MatchError::EmptyResponse { agent }
+            | MatchError::OutOfMemory { agent, .. }
+            | MatchError::TransportError { agent, .. }
+            | MatchError::Disqualified { agent, .. }
+            | MatchError::VerificationFailed { agent, .. }
+            | MatchError::RulesNotSent { agent, .. } => Some(agent),
+            MatchError::Cancelled
+            | MatchError::WallClockLimit
+            | MatchError::MaxTurns { .. }
+            | MatchError::MaxTurnsHint { .. } => None,
+        }
+    }
+}
+
+impl std::fmt::Display for MatchError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MatchError::StartupFailed { agent, reason } => {
+                write!(f, "{agent} startup failed ({reason})")
+            }
+            MatchError::Timeout {
+                agent,
+                turn,
+                limit,
+                reason,
+            } => write!(
+                f,
+                "{agent}: response timeout after {}ms ({reason}) (turn {turn})",
+                limit.as_millis()
+            ),
+            MatchError::InvalidAction { agent, text } => {
+                write!(f, "{agent} not an action: '{text}'")
+            }
+            MatchError::NonUtf8 { agent } => write!(f, "{agent} non-utf8 response"),
+            MatchError::ActionRejected {
+                agent,
+                action,
+                reason,
+            } => write!(f, "{agent}'s action '{action}' was rejected: {reason}"),
+            MatchError::EmptyResponse { agent } => {
+                write!(f, "{agent} empty string received (player probably crashed)")
+            }
+            MatchError::OutOfMemory { agent, turn } => {
+                write!(f, "{agent} killed: out of memory (turn {turn})")
+            }
+            MatchError::TransportError {
+                agent,
+                turn,
+                reason,
+            } => write!(f, "{agent}: transport error ({reason}) (turn {turn})"),
+            MatchError::Disqualified { agent, turn: None } => write!(f, "{agent} disqualified"),
+            MatchError::Disqualified {
+                agent,
+                turn: Some(turn),
+            } => write!(f, "{agent} disqualified (turn {turn})"),
+            MatchError::VerificationFailed { agent, reason } => {
+                write!(
+                    f,
+                    "state verification failed after {agent}'s action: {reason}"
+                )
+            }
+            MatchError::RulesNotSent { agent, reason } => write!(f, "{agent}: {reason}"),
+            MatchError::Cancelled => write!(f, "match cancelled"),
+            MatchError::WallClockLimit => write!(f, "match exceeded wall-clock limit"),
+            MatchError::MaxTurns { turns } => write!(f, "max turns reached ({turns} turns)"),
+            MatchError::MaxTurnsHint { turns } => {
+                write!(f, "max turns hint reached ({turns} turns)")
+            }
+        }
+    }
+}
+
+/// Summary of a tournament, returned by
+/// [`Evaluator::evaluate_full`](crate::server::Evaluator::evaluate_full).
+#[derive(Debug, Clone, PartialEq)]
+pub struct EvaluationReport<S> {
+    /// Final score of each working agent, by agent name.
+    pub scores: HashMap<String, S>,
+    /// Error message of each agent that could not take part (compilation error, failed self
+    /// test, duplicate, ...), by agent name.
+    pub failures: HashMap<String, String>,
+    /// Number of matches played. Replayed attempts (see
+    /// [`Configuration::with_match_retries`](crate::configuration::Configuration::with_match_retries))
+    /// are counted once.
+    pub total_matches: usize,
+    /// Number of rounds in which matches were played.
+    pub round_count: usize,
+    /// Total penalty points of each agent that played, by agent name (see
+    /// [`Configuration::with_penalties`](crate::configuration::Configuration::with_penalties)).
+    pub penalties: HashMap<String, u32>,
+    /// Number of byes given by the strategy (see
+    /// [`TournamentStrategy::num_byes`](crate::tournament_strategy::TournamentStrategy::num_byes)).
+    pub byes: usize,
+    /// Whether greater scores are better (see
+    /// [`TournamentStrategy::higher_is_better`](crate::tournament_strategy::TournamentStrategy::higher_is_better)).
+    pub higher_is_better: bool,
+    /// Duration of the whole evaluation, agent collection (and compilation) included.
+    pub duration: Duration,
+}
+
+impl<S: Ord + Clone> EvaluationReport<S> {
+    /// Ranks the agents from best to worst, in the direction given by the strategy (see
+    /// [`final_ranking_ordered`]).
+    pub fn ranking(&self) -> Vec<RankedAgent<S>> {
+        final_ranking_ordered(&self.scores, self.higher_is_better)
+    }
+}
+
+/// A place in a ranking (see [`final_ranking`]).
+#[derive(Debug, Clone, PartialEq)]
+pub struct RankedAgent<S> {
+    /// 1-based place. Tied agents share the same place, and the following places are skipped
+    /// (e.g. two agents tied for 2nd place, next one is 4th).
+    pub place: usize,
+    /// Names of the agents at this place, sorted alphabetically.
+    pub agents: Vec<String>,
+    /// Score of the agents at this place.
+    pub score: S,
+}
+
+/// Ranks agents from best to worst score, grouping tied agents.
+///
+/// Scores are compared with their [`Ord`] implementation, e.g. the final scores of any built-in
+/// strategy ([`TwoPlayersGameScore`], [`SinglePlayerScore`], ...). The greatest score is the best:
+/// use [`final_ranking_ordered`] for strategies where it is the lowest.
+pub fn final_ranking<S: Ord + Clone>(scores: &HashMap<String, S>) -> Vec<RankedAgent<S>> {
+    final_ranking_ordered(scores, true)
+}
+
+/// Same as [`final_ranking`], but the best score is the lowest one if `higher_is_better` is false
+/// (e.g. golf-style tournaments). Pass the strategy's
+/// [`higher_is_better`](crate::tournament_strategy::TournamentStrategy::higher_is_better).
+pub fn final_ranking_ordered<S: Ord + Clone>(
+    scores: &HashMap<String, S>,
+    higher_is_better: bool,
+) -> Vec<RankedAgent<S>> {
+    let mut sorted = scores.iter().collect::<Vec<_>>();
+    sorted.sort_by(|a, b| {
+        let order = if higher_is_better {
+            b.1.cmp(a.1)
+        } else {
+            a.1.cmp(b.1)
+        };
+        order.then(a.0.cmp(b.0))
+    });
+
+    let mut ranking: Vec<RankedAgent<S>> = vec![];
+    for (i, (name, score)) in sorted.into_iter().enumerate() {
+        match ranking.last_mut() {
+            Some(last) if last.score == *score => last.agents.push(name.clone()),
+            _ => ranking.push(RankedAgent {
+                place: i + 1,
+                agents: vec![name.clone()],
+                score: score.clone(),
+            }),
+        }
+    }
+    ranking
+}
+
+/// Sums the penalty points of each agent over all `records`, by agent name.
+pub fn total_penalties<S>(records: &[MatchRecord<S>]) -> HashMap<String, u32> {
+    let mut totals: HashMap<String, u32> = HashMap::new();
+    for record in records {
+        for (name, penalty) in record.players.iter().zip(&record.penalties) {
+            *totals.entry(name.clone()).or_default() += penalty;
+        }
+    }
+    totals
+}
+
+/// A score that can be shown in the columns of a Markdown table (see [`to_markdown`]).
+pub trait MarkdownScore {
+    /// Headers of the score columns.
+    fn headers() -> Vec<&'static str>;
+    /// Content of the score columns, in the same order as [`headers`](MarkdownScore::headers).
+    fn cells(&self) -> Vec<String>;
+}
+
+impl MarkdownScore for TwoPlayersGameScore {
+    fn headers() -> Vec<&'static str> {
+        vec!["W", "D", "L", "Tie-breaker"]
+    }
+
+    fn cells(&self) -> Vec<String> {
+        [self.num_win, self.num_draw, self.num_lose, self.tie_breaker]
+            .iter()
+            .map(u32::to_string)
+            .collect()
+    }
+}
+
+/// Scores are compared as a whole (see [`SinglePlayerScore`]), so they are all listed.
+impl<S: PartialOrd + std::fmt::Display> MarkdownScore for SinglePlayerScore<S> {
+    fn headers() -> Vec<&'static str> {
+        vec!["Scores", "Games"]
+    }
+
+    fn cells(&self) -> Vec<String> {
+        let scores = self
+            .0
+            .iter()
+            .map(S::to_string)
+            .collect::<Vec<_>>()
+            .join(", ");
+        vec![scores, self.0.len().to_string()]
+    }
+}
+
+impl MarkdownScore for PlacementScore {
+    fn headers() -> Vec<&'static str> {
+        vec!["Points", "Places"]
+    }
+
+    fn cells(&self) -> Vec<String> {
+        let places = self
+            .places
+            .iter()
+            .map(u32::to_string)
+            .collect::<Vec<_>>()
+            .join("/");
+        vec![self.points.to_string(), places]
+    }
+}
+
+/// Formats a ranking (see [`final_ranking`]) as a GitHub-flavored Markdown table, with one row
+/// per place. Tied agents share the same row.
+///
+/// ```
+/// # use std::collections::HashMap;
+/// # use ai_tournament::results::{final_ranking, to_markdown};
+/// # use ai_tournament::tournament_strategy::TwoPlayersGameScore;
+/// let scores = HashMap::from([(
+///     "alice".to_string(),
+///     TwoPlayersGameScore { num_win: 2, num_draw: 1, num_lose: 0, tie_breaker: 0 },
+/// )]);
+/// assert_eq!(
+///     to_markdown(&final_ranking(&scores)),
+///     "| Place | Agent | W | D | L | Tie-breaker |\n\
+///      |---:|---|---:|---:|---:|---:|\n\
+///      | 1 | alice | 2 | 1 | 0 | 0 |\n"
+/// );
+/// ```
+pub fn to_markdown<S: MarkdownScore>(ranking: &[RankedAgent<S>]) -> String {
+    let headers = S::headers();
+    let mut table = format!("| Place | Agent | {} |\n", headers.join(" | "));
+    table += &format!("|---:|---|{}\n", "---:|".repeat(headers.len()));
+    for ranked in ranking {
+        let agents = ranked
+            .agents
+            .iter()
+            .map(|name| escape_markdown(name))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let cells = ranked
+            .score
+            .cells()
+            .iter()
+            .map(|cell| escape_markdown(cell))
+            .collect::<Vec<_>>();
+        table += &format!("| {} | {agents} | {} |\n", ranked.place, cells.join(" | "));
+    }
+    table
+}
+
+/// Escapes characters that would break a table cell
+fn escape_markdown(s: &str) -> String {
+    s.replace('|', "\\|").replace('\n', " ")
+}
+
+/// A score that can be written in the columns of a CSV leaderboard (see [`to_csv`]).
+pub trait LeaderboardRow {
+    /// Headers of the score columns.
+    fn headers() -> Vec<&'static str>;
+    /// Content of the score columns, in the same order as [`headers`](LeaderboardRow::headers).
+    fn row(&self) -> Vec<String>;
+}
+
+impl LeaderboardRow for TwoPlayersGameScore {
+    fn headers() -> Vec<&'static str> {
+        vec!["win", "draw", "lose", "tie_breaker"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        [self.num_win, self.num_draw, self.num_lose, self.tie_breaker]
+            .iter()
+            .map(u32::to_string)
+            .collect()
+    }
+}
+
+/// Statistics of the scores of the agent. Mean, min and max are empty if it played no game.
+impl<S: PartialOrd + Copy + Into<f64>> LeaderboardRow for SinglePlayerScore<S> {
+    fn headers() -> Vec<&'static str> {
+        vec!["count", "sum", "mean", "min", "max"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        let scores = self.0.iter().map(|&s| s.into()).collect::<Vec<f64>>();
+        let count = scores.len();
+        let sum = scores.iter().sum::<f64>();
+        if count == 0 {
+            return vec!["0".into(), "0".into(), "".into(), "".into(), "".into()];
+        }
+        let min = scores.iter().copied().fold(f64::INFINITY, f64::min);
+        let max = scores.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        vec![
+            count.to_string(),
+            sum.to_string(),
+            (sum / count as f64).to_string(),
+            min.to_string(),
+            max.to_string(),
+        ]
+    }
+}
+
+impl LeaderboardRow for PlacementScore {
+    fn headers() -> Vec<&'static str> {
+        vec!["points", "places"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        let places = self
+            .places
+            .iter()
+            .map(u32::to_string)
+            .collect::<Vec<_>>()
+            .join("/");
+        vec![self.points.to_string(), places]
+    }
+}
+
+/// Formats a ranking (see [`final_ranking`]) as CSV, with a header line and one line per agent:
+/// its rank, its name and the columns of its score. Tied agents share the same rank.
+///
+/// ```
+/// # use std::collections::HashMap;
+/// # use ai_tournament::results::{final_ranking, to_csv};
+/// # use ai_tournament::tournament_strategy::TwoPlayersGameScore;
+/// let scores = HashMap::from([(
+///     "alice".to_string(),
+///     TwoPlayersGameScore { num_win: 2, num_draw: 1, num_lose: 0, tie_breaker: 0 },
+/// )]);
+/// assert_eq!(
+///     to_csv(&final_ranking(&scores)),
+///     "rank,agent,win,draw,lose,tie_breaker\n1,alice,2,1,0,0\n"
+/// );
+/// ```
+pub fn to_csv<S: LeaderboardRow>(ranking: &[RankedAgent<S>]) -> String {
+    let mut csv = format!("rank,agent,{}\n", S::headers().join(","));
+    for ranked in ranking {
+        let row = ranked
+            .score
+            .row()
+            .iter()
+            .map(|cell| escape_csv(cell))
+            .collect::<Vec<_>>()
+            .join(",");
+        for name in &ranked.agents {
+            csv += &format!("{},{},{row}\n", ranked.place, escape_csv(name));
+        }
+    }
+    csv
+}
+
+/// Quotes a field containing a separator, a quote or a line break
+fn escape_csv(s: &str) -> String {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+/// Writes the leaderboard of final scores (e.g. returned by
+/// [`Evaluator::evaluate`](crate::server::Evaluator::evaluate)) to `path` as CSV (see
+/// [`to_csv`]), replacing the file if it exists. Pass the strategy's
+/// [`higher_is_better`](crate::tournament_strategy::TournamentStrategy::higher_is_better).
+pub fn export_leaderboard_csv<S: LeaderboardRow + Ord + Clone>(
+    results: &HashMap<String, S>,
+    higher_is_better: bool,
+    path: impl AsRef<Path>,
+) -> anyhow::Result<()> {
+    let path = path.as_ref();
+    let ranking = final_ranking_ordered(results, higher_is_better);
+    std::fs::write(path, to_csv(&ranking))
+        .with_context(|| format!("could not write leaderboard to {}", path.display()))
+}
+
+/// Formats final scores as a JSON object mapping each agent name to its serialized score.
+/// Agents are sorted by name, so that the same results always give the same output.
+///
+/// ```
+/// # use std::collections::HashMap;
+/// # use ai_tournament::results::results_to_json;
+/// # use ai_tournament::tournament_strategy::TwoPlayersGameScore;
+/// let scores = HashMap::from([(
+///     "alice".to_string(),
+///     TwoPlayersGameScore { num_win: 2, num_draw: 1, num_lose: 0, tie_breaker: 0 },
+/// )]);
+/// let json = results_to_json(&scores).unwrap();
+/// assert!(json.contains("\"num_win\": 2"));
+/// ```
+pub fn results_to_json<S: Serialize>(results: &HashMap<String, S>) -> anyhow::Result<String> {
+    let sorted = results.iter().collect::<BTreeMap<_, _>>();
+    let mut json =
+        serde_json::to_string_pretty(&sorted).context("could not serialize results to JSON")?;
+    json.push('\n');
+    Ok(json)
+}
+
+/// Writes final scores (e.g. returned by [`Evaluator::evaluate`](crate::server::Evaluator::evaluate))
+/// to `path` as JSON (see [`results_to_json`]), replacing the file if it exists.
+pub fn export_results_json<S: Serialize>(
+    results: &HashMap<String, S>,
+    path: impl AsRef<Path>,
+) -> anyhow::Result<()> {
+    let path = path.as_ref();
+    std::fs::write(path, results_to_json(results)?)
+        .with_context(|| format!("could not write results to {}", path.display()))
+}
+
+/// Removes duplicate matches (see the [module documentation](self#duplicate-matches)).
+fn deduplicate<S>(runs: Vec<Vec<MatchRecord<S>>>) -> Vec<MatchRecord<S>> {
+    let mut records: Vec<MatchRecord<S>> = vec![];
+    let mut seen = HashSet::new();
+    for record in runs.into_iter().flatten() {
+        if seen.insert((record.round, record.index, record.players.clone())) {
+            records.push(record);
+        }
+    }
+    records
+}
+
+/// Recomputes two-player standings from the union of several runs.
+///
+/// Duplicate matches are counted once. Within a match, the player(s) with the best score win; if
+/// all players have the same score, the match is a draw. This is the same rule as
+/// [`RoundRobinTournament`](crate::tournament_strategy::RoundRobinTournament).
+/// Tie-breakers are left at zero, and match weights are ignored.
+pub fn merge<S: PartialOrd>(
+    runs: Vec<Vec<MatchRecord<S>>>,
+) -> HashMap<String, TwoPlayersGameScore> {
+    let mut standings: HashMap<String, TwoPlayersGameScore> = HashMap::new();
+    for record in deduplicate(runs) {
+        let Some(mut best_score) = record.scores.first() else {
+            continue;
+        };
+        for score in &record.scores[1..] {
+            if best_score < score {
+                best_score = score;
+            }
+        }
+        let is_draw = record.scores.iter().all(|score| score == best_score);
+        for (name, score) in record.players.into_iter().zip(&record.scores) {
+            let entry = standings.entry(name).or_default();
+            if is_draw {
+                entry.num_draw += 1;
+            } else if score == best_score {
+                entry.num_win += 1;
+            } else {
+                entry.num_lose += 1;
+            }
+        }
+    }
+    standings
+}
+
+/// Recomputes single-player standings from the union of several runs.
+///
+/// Duplicate matches are counted once. Scores are listed in record order.
+pub fn merge_single_player<S: PartialOrd>(
+    runs: Vec<Vec<MatchRecord<S>>>,
+) -> HashMap<String, SinglePlayerScore<S>> {
+    let mut standings: HashMap<String, SinglePlayerScore<S>> = HashMap::new();
+    for record in deduplicate(runs) {
+        for (name, score) in record.players.into_iter().zip(record.scores) {
+            standings.entry(name).or_default().0.push(score);
+        }
+    }
+    standings
+}
+
+#[cfg(test)]
+mod results_tests {
+    use super::*;
+
+    fn record(round: usize, index: usize, players: &[&str], scores: &[i32]) -> MatchRecord<i32> {
+        MatchRecord {
+            round,
+            index,
+            players: players.iter().map(|p| p.to_string()).collect(),
+            args: vec![vec![]; players.len()],
+            scores: scores.to_vec(),
+            errors: String::new(),
+            error_details: vec![],
+            startup_durations: vec![None; players.len()],
+            penalties: vec![0; players.len()],
+            peak_memory: vec![None; players.len()],
+            turns: 0,
+            timings: vec![],
+            duration: Duration::ZERO,
+            weight: 1.0,
+            seed: None,
+        }
+    }
+
+    #[test]
+    fn test_agent_timing() {
+        let mut timing = AgentTiming::default();
+        timing.record_turn(Duration::from_millis(30), Duration::from_millis(70));
+        timing.record_turn(Duration::from_millis(50), Duration::from_millis(20));
+        timing.record_turn(Duration::from_millis(20), Duration::ZERO);
+        assert_eq!(
+            timing,
+            AgentTiming {
+                think_time: Duration::from_millis(100),
+                turns: 3,
+                max_turn_time: Duration::from_millis(50),
+                budget_exhausted: true,
+            }
+        );
+    }
+
+    #[test]
+    fn test_record_from_runner_result() {
+        use crate::agent::Agent;
+        use crate::constraints::ConstraintsBuilder;
+        use std::sync::Arc;
+
+        let args = vec!["--mode".to_string(), "aggressive".to_string()];
+        let a = Arc::new(Agent::new(
+            "a-aggressive".into(),
+            None,
+            None,
+            0,
+            Some(args.clone()),
+        ));
+        let b = Arc::new(Agent::new("b".into(), None, None, 1, None));
+        let result = RunnerResult {
+            results: vec![(a, 1), (b, 0)],
+            resources_freed: ConstraintsBuilder::new().build().unwrap(),
+            errors: "b: response timeout after 500ms (timed out) (turn 4), ".into(),
+            error_details: vec![MatchError::Timeout {
+                agent: "b".into(),
+                turn: 4,
+                limit: Duration::from_millis(500),
+                reason: "timed out".into(),
+            }],
+            peak_memory: vec![],
+            round: 1,
+            index: 3,
+            startup_durations: vec![Some(Duration::from_millis(20)), None],
+            transient: false,
+            penalties: vec![],
+            turns: 12,
+            timings: vec![AgentTiming::default(); 2],
+            duration: Duration::from_secs(3),
+            weight: 2.0,
+            seed: Some(17),
+        };
+        let record = MatchRecord::from_runner_result(&result);
+
+        assert_eq!(record.players, vec!["a-aggressive", "b"]);
+        assert_eq!(record.args, vec![args, vec![]]);
+        assert_eq!(record.scores, vec![1, 0]);
+        assert_eq!((record.round, record.index), (1, 3));
+        assert_eq!(record.turns, 12);
+        assert_eq!(record.weight, 2.0);
+        assert_eq!(record.seed, Some(17));
+        assert_eq!(record.error_details[0].agent(), Some("b"));
+        assert_eq!(MatchError::WallClockLimit.agent(), None);
+        // the errors are the details, as displayed
+        assert_eq!(record.errors, format!("{}, ", record.error_details[0]));
+        assert_eq!(
+            record.startup_durations,
+            vec![Some(Duration::from_millis(20)), None]
+        );
+    }
+
+    #[test]
+    fn test_merge_disjoint_runs() {
+        let run_a = vec![record(1, 0, &["a", "b"], &[1, 0])];
+        let run_b = vec![
+            record(1, 1, &["b", "c"], &[1, 1]),
+            record(1, 2, &["a", "c"], &[0, 2]),
+        ];
+        let standings = merge(vec![run_a, run_b]);
+
+        assert_eq!(standings["a"].num_win, 1);
+        assert_eq!(standings["a"].num_lose, 1);
+        assert_eq!(standings["b"].num_lose, 1);
+        assert_eq!(standings["b"].num_draw, 1);
+        assert_eq!(standings["c"].num_win, 1);
+        assert_eq!(standings["c"].num_draw, 1);
+    }
+
+    #[test]
+    fn test_merge_deduplicates() {
+        let run_a = vec![record(1, 0, &["a", "b"], &[1, 0])];
+        // Same match replayed on another machine, with a different outcome: first one is kept
+        let run_b = vec![
+            record(1, 0, &["a", "b"], &[0, 1]),
+            // Same players, other slot: a different match
+            record(1, 1, &["a", "b"], &[1, 0]),
+            // Same slot, swapped order: a different match
+            record(1, 0, &["b", "a"], &[1, 0]),
+        ];
+        let standings = merge(vec![run_a, run_b]);
+
+        assert_eq!(standings["a"].num_win, 2);
+        assert_eq!(standings["a"].num_lose, 1);
+        assert_eq!(standings["b"].num_win, 1);
+        assert_eq!(standings["b"].num_lose, 2);
+    }
+
+    #[test]
+    fn test_final_ranking() {
+        let scores = HashMap::from([
+            ("a".to_string(), 5),
+            ("b".to_string(), 3),
+            ("c".to_string(), 7),
+            ("d".to_string(), 5),
+            ("e".to_string(), 1),
+        ]);
+        let ranking = final_ranking(&scores);
+
+        let places = ranking
+            .iter()
+            .map(|r| (r.place, r.agents.join(","), r.score))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            places,
+            vec![
+                (1, "c".to_string(), 7),
+                (2, "a,d".to_string(), 5),
+                (4, "b".to_string(), 3),
+                (5, "e".to_string(), 1),
+            ]
+        );
+        assert!(final_ranking::<u32>(&HashMap::new()).is_empty());
+
+        // golf: lowest score first, names still sorted within a place
+        let places = final_ranking_ordered(&scores, false)
+            .iter()
+            .map(|r| (r.place, r.agents.join(","), r.score))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            places,
+            vec![
+                (1, "e".to_string(), 1),
+                (2, "b".to_string(), 3),
+                (3, "a,d".to_string(), 5),
+                (5, "c".to_string(), 7),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_to_markdown() {
+        let score = |num_win, num_lose| TwoPlayersGameScore {
+            num_win,
+            num_draw: 1,
+            num_lose,
+            tie_breaker: 0,
+        };
+        let scores = HashMap::from([
+            ("a".to_string(), score(3, 0)),
+            ("b|c".to_string(), score(1, 2)),
+            ("d".to_string(), score(1, 2)),
+        ]);
+        assert_eq!(
+            to_markdown(&final_ranking(&scores)),
+            "| Place | Agent | W | D | L | Tie-breaker |\n\
+             |---:|---|---:|---:|---:|---:|\n\
+             | 1 | a | 3 | 1 | 0 | 0 |\n\
+             | 2 | b\\|c, d | 1 | 1 | 2 | 0 |\n"
+        );
+
+        let scores = HashMap::from([("a".to_string(), SinglePlayerScore(vec![2.5, 1.0]))]);
+        let table = to_markdown(&final_ranking(&scores));
+        assert_eq!(table.lines().nth(2), Some("| 1 | a | 2.5, 1 | 2 |"));
+    }
+
+    #[test]
+    fn test_export_results_json() {
+        let scores = HashMap::from([
+            ("b".to_string(), SinglePlayerScore(vec![2.5, -1.0])),
+            ("a\"".to_string(), SinglePlayerScore(vec![f32::NAN])),
+        ]);
+        assert_eq!(
+            results_to_json(&scores).unwrap(),
+            "{\n  \"a\\\"\": [\n    null\n  ],\n  \"b\": [\n    2.5,\n    -1.0\n  ]\n}\n"
+        );
+        assert_eq!(
+            results_to_json::<PlacementScore>(&HashMap::new()).unwrap(),
+            "{}\n"
+        );
+
+        let path =
+            std::env::temp_dir().join(format!("ai_tournament_results_{}.json", std::process::id()));
+        let scores = HashMap::from([(
+            "a".to_string(),
+            PlacementScore {
+                points: 5,
+                places: vec![1, 0, 2],
+            },
+        )]);
+        export_results_json(&scores, &path).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "{\n  \"a\": {\n    \"points\": 5,\n    \"places\": [\n      1,\n      0,\n      2\n    ]\n  }\n}\n"
+        );
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_export_leaderboard_csv() {
+        let scores = HashMap::from([
+            ("a".to_string(), SinglePlayerScore(vec![3, 1])),
+            ("b,\"c\"".to_string(), SinglePlayerScore(vec![3, 1])),
+            ("d".to_string(), SinglePlayerScore(vec![])),
+        ]);
+        let path = std::env::temp_dir().join(format!(
+            "ai_tournament_leaderboard_{}.csv",
+            std::process::id()
+        ));
+        export_leaderboard_csv(&scores, false, &path).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "rank,agent,count,sum,mean,min,max\n\
+             1,d,0,0,,,\n\
+             2,a,2,4,2,1,3\n\
+             2,\"b,\"\"c\"\"\",2,4,2,1,3\n"
+        );
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_total_penalties() {
+        let mut a = record(1, 0, &["a", "b"], &[1, 0]);
+        a.penalties = vec![0, 2];
+        let mut b = record(1, 1, &["b", "c"], &[0, 1]);
+        b.penalties = vec![3, 1];
+        let c = record(2, 0, &["a", "c"], &[1, 0]);
+        let totals = total_penalties(&[a, b, c]);
+
+        assert_eq!(totals["a"], 0);
+        assert_eq!(totals["b"], 5);
+        assert_eq!(totals["c"], 1);
+    }
+
+    #[test]
+    fn test_merge_single_player() {
+        let run_a = vec![record(1, 0, &["a"], &[3]), record(1, 1, &["a"], &[5])];
+        let run_b = vec![record(1, 1, &["a"], &[5]), record(1, 2, &["b"], &[7])];
+        let standings = merge_single_player(vec![run_a, run_b]);
+
+        assert_eq!(standings["a"], SinglePlayerScore(vec![3, 5]));
+        assert_eq!(standings["b"], SinglePlayerScore(vec![7]));
+    }
+}
@@ -0,0 +1,78 @@
+//! Minimal deterministic pseudo-random number generator.
+//!
+//! Only used where reproducibility from a seed matters (e.g. shuffling matches). Not suitable for
+//! anything security related.
+
+/// SplitMix64 generator (Steele, Lea & Flood, *Fast Splittable Pseudorandom Number Generators*,
+/// OOPSLA 2014).
+#[derive(Debug, Clone)]
+pub(crate) struct SplitMix64 {
+    state: u64,
+}
+
+impl SplitMix64 {
+    pub(crate) fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform integer in `0..bound` (`bound` must be positive).
+    pub(crate) fn below(&mut self, bound: usize) -> usize {
+        // modulo bias is negligible for the small bounds used here
+        (self.next_u64() % bound as u64) as usize
+    }
+
+    /// Fisher-Yates shuffle
+    pub(crate) fn shuffle<T>(&mut self, slice: &mut [T]) {
+        for i in (1..slice.len()).rev() {
+            let j = self.below(i + 1);
+            slice.swap(i, j);
+        }
+    }
+}
+
+/// Seed of match `index` of `round`, derived from the tournament `seed`: distinct for each match,
+/// and the same in every run with the same seed
+pub(crate) fn match_seed(seed: u64, round: usize, index: usize) -> u64 {
+    SplitMix64::new(seed ^ ((round as u64) << 32 | index as u64)).next_u64()
+}
+
+#[cfg(test)]
+mod rng_tests {
+    use super::*;
+
+    #[test]
+    fn test_reference_values() {
+        // first outputs of the reference implementation for seed 1234567
+        let mut rng = SplitMix64::new(1234567);
+        assert_eq!(rng.next_u64(), 6457827717110365317);
+        assert_eq!(rng.next_u64(), 3203168211198807973);
+    }
+
+    #[test]
+    fn test_match_seeds() {
+        let seeds =
+            [(1, 0), (1, 1), (2, 0), (2, 1)].map(|(round, index)| match_seed(7, round, index));
+        for (i, seed) in seeds.iter().enumerate() {
+            assert!(!seeds[i + 1..].contains(seed));
+        }
+        assert_eq!(match_seed(7, 2, 1), seeds[3]);
+        assert_ne!(match_seed(8, 2, 1), seeds[3]);
+    }
+
+    #[test]
+    fn test_shuffle_is_permutation() {
+        let mut v = (0..100).collect::<Vec<_>>();
+        SplitMix64::new(42).shuffle(&mut v);
+        assert_ne!(v, (0..100).collect::<Vec<_>>());
+        v.sort();
+        assert_eq!(v, (0..100).collect::<Vec<_>>());
+    }
+}
@@ -12,7 +12,7 @@
 //!
 //! Behavior is controlled by a [`Configuration`] object:
 //!
-//! - When `config.compile_agents = true`, the evaluator expects agents to be **Rust crates** in the given directory, each containing a YAML config at the root.
+//! - When `config.compile_agents = true`, the evaluator expects agents to be **Rust crates** in the given directory, each containing a YAML config at the root. The binary named `eval` is built, unless the config sets another one with an optional top-level `bin: bin_name` key.
 //! - When `config.compile_agents = false`, the evaluator expects each subdirectory to contain **only two files**:
 //!   - An executable binary (the agent)
 //!   - A `.yaml` or `.yml` config file (see below)
@@ -27,13 +27,87 @@
 //!   - eval_config_name: "--args used for evaluation"  # Used if `test_all_configs = false`
 //! ```
 //!
-//! > ⚠️ This file is manually parsed and supports only basic YAML. Comments are supported, but advanced YAML features (anchors, nesting, multi-line strings) may not parse correctly.
+//! The file is parsed as standard YAML: comments, quoted values with special characters and multi-line values (e.g. folded `>` blocks, joined with spaces) are supported, and arguments are trimmed. `configs` can also be a mapping of config names to arguments. Configs keep the order of the file, e.g. for the agents created with `test_all_configs`. Unknown top-level keys are rejected.
 //!
-//! If `config.test_all_configs = true`, all configs listed under `configs` are tested. Otherwise, only the config named in `eval` is used.
+//! If `config.test_all_configs = true`, all configs listed under `configs` are tested. Otherwise, only the config named in `eval` is used, unless overridden with [`Configuration::with_eval_config`] (or the `EVAL_CONFIG_NAME` environment variable).
+//!
+//! An optional top-level `team: team_name` key puts the agent in a team. Teammates never play each other in a [`RoundRobinTournament`](crate::tournament_strategy::RoundRobinTournament).
+//!
+//! An optional top-level `time_multiplier: factor` key scales the action timeout and time budget of the agent, e.g. `time_multiplier: 3` for an agent written in an interpreted language, to compare algorithms rather than languages. It only affects that agent's own clocks: its opponents keep the constraints of the evaluation. The scaled values are the ones given to the agent on its command line.
+//!
+//! ## Launch Command
+//!
+//! By default, agents are launched as `<binary> <port> <time budget (µs)> <action timeout (µs)> <config args...> <extra args...>`, where extra arguments are given to all agents with [`Configuration::with_extra_agent_args`] (none by default).
+//! With [`Configuration::with_seed`], the seed of the match is inserted after the action timeout: `<binary> <port> <time budget (µs)> <action timeout (µs)> <seed> <config args...> <extra args...>`.
+//! An optional top-level `command:` key replaces this command line with a template, e.g. to launch a script with an interpreter:
+//!
+//! ```yaml
+//! command: python3 {exe} --port {port} --budget {budget_us} {args}
+//! ```
+//!
+//! The following placeholders are substituted:
+//!
+//! - `{exe}`: path to the agent's binary (or script, when agents are not compiled)
+//! - `{port}`: port of the server to connect to (**required**)
+//! - `{budget_us}`: time budget in microseconds
+//! - `{action_us}`: action timeout in microseconds
+//! - `{seed}`: seed of the match (must be a word by itself, removed when no seed is set)
+//! - `{args}`: arguments of the selected config, followed by the extra arguments, as separate words (must be a word by itself)
+//!
+//! The template is split on whitespace, and must not contain quotes. Other placeholders are rejected when agents are collected.
 //!
 //! ## Self-Test Mode
 //!
-//! When `config.self_test = true`, the evaluator ignores the directory parameter and runs a match **using the current working directory** as a single agent. This is useful for debugging or development.
+//! When `config.self_test = true`, the evaluator ignores the directory parameter and runs a match **using the current working directory** (or the directory set with `Configuration::with_self_test_path`) as a single agent. This is useful for debugging or development.
+//!
+//! ## Disqualification
+//!
+//! An agent can be disqualified while the tournament runs, through the [`TournamentControl`]
+//! returned by [`Evaluator::control`]. Its running matches are forfeited: its process is killed and
+//! the game goes on without it, as if it had crashed, then it gets the worst score of the match
+//! (or the fixed score of [`EliminatedScore::Fixed`](crate::configuration::EliminatedScore::Fixed)),
+//! unless a [scoring function](Evaluator::with_scoring) is set. It is never launched again: its
+//! remaining matches are still played, and forfeited the same way. Results recorded before the
+//! disqualification are kept, including the ones of its opponents against it.
+//!
+//! ## Late Agents
+//!
+//! An agent can join a tournament that already started with [`TournamentControl::add_late_agent`],
+//! if the strategy supports it (see [`TournamentStrategy::add_late_agent`]). It is collected (and
+//! compiled) as soon as the next match finishes, which delays the processing of results, then
+//! added to the strategy between two rounds.
+//!
+//! ## Reference Agent
+//!
+//! [`Evaluator::with_reference_agent`] adds an agent implemented in Rust, running inside the
+//! evaluator process (see [`game_interface::Agent`](crate::game_interface::Agent)), to the agents
+//! of the directory. External agents still run as separate processes and play against it through
+//! their socket as usual.
+//!
+//! ## Health Checks
+//!
+//! The [`TournamentControl`] handle also reports whether a tournament is running
+//! ([`TournamentControl::is_running`]) and how far it went ([`TournamentControl::progress`]), e.g.
+//! to answer the health endpoint of a service embedding the evaluator. The handle is `Send` and
+//! `Sync`, and cheap to clone: poll it from any thread while [`Evaluator::evaluate`] runs.
+//!
+//! ## Staged Resources
+//!
+//! [`Evaluator::with_round_constraints`] changes the limits of agents from a given round, e.g. to
+//! screen agents quickly with tight limits in early rounds and play the final rounds with generous
+//! ones. Only per-agent limits are taken from these [`Constraints`]: RAM and CPUs per agent,
+//! timeouts and time budget, disk I/O. The total RAM and CPUs still come from the constraints given
+//! to [`Evaluator::new`]: they form the pool from which each match takes the resources of its
+//! agents, and fewer matches run at the same time in rounds with larger agents.
+//!
+//! The pool itself can be capped while the tournament runs, with
+//! [`TournamentControl::set_resource_cap`], e.g. when the CPU budget allotted on a shared cluster
+//! shrinks. The cap takes effect at the next round: rounds are generated once all the matches of
+//! the previous one finished, so no match holds resources when the pool shrinks. Matches of the
+//! current round keep their resources, and new matches of the round still take them from the
+//! previous pool. The cap can be raised or removed later, but never grows the pool beyond the
+//! constraints given to [`Evaluator::new`]. A cap too small for a single match is raised to the
+//! resources of one match, with a warning.
 //!
 //! ## Uncontained Mode
 //!
@@ -44,20 +118,30 @@
 //!
 //! See crate-level documentation for an example on how to use the `Evaluator`.
 
+use crate::agent::Agent;
 use crate::agent_collector::collect_agents;
 use crate::configuration::Configuration;
 use crate::constraints::Constraints;
-use crate::game_interface::{Game, GameFactory};
+use crate::event_log::EventLog;
+use crate::game_interface::{Agent as GameAgent, Game, GameFactory, MatchContext};
 use crate::logger::init_logger;
 use crate::match_runner::{run_match, MatchSettings, RunnerResult};
+use crate::results::{EvaluationReport, MatchRecord};
+use crate::tournament_control::RunningGuard;
+pub use crate::tournament_control::{Progress, ReferenceFactory, ScoringFn, TournamentControl};
 use crate::tournament_scheduler::TournamentScheduler;
-use crate::tournament_strategy::TournamentStrategy;
+use crate::tournament_strategy::{GauntletReport, GauntletTournament, TournamentStrategy};
 
-use std::collections::HashMap;
+use anyhow::{bail, Context};
+use std::collections::{BTreeMap, HashMap};
 use std::fmt::Display;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::mpsc::Sender;
 use std::sync::{mpsc, Arc, Mutex};
-use tracing::{info, instrument, trace};
+use std::thread::JoinHandle;
+use std::time::Instant;
+use tracing::{info, instrument, trace, warn};
 
 /// The main type for running AI agent tournaments.
 ///
@@ -73,6 +157,12 @@ where
     factory: F,
     constraints: Constraints,
     config: Configuration,
+    control: TournamentControl,
+    scoring: Option<ScoringFn<G>>,
+    /// Name and factory of the in-process reference agent
+    reference: Option<(String, ReferenceFactory<G>)>,
+    /// Limits of the matches from a given round onwards
+    round_constraints: BTreeMap<usize, Constraints>,
     _ff: std::marker::PhantomData<G>,
 }
 
@@ -80,6 +170,7 @@ impl<G: Game + Send + 'static, F: GameFactory<G>> Evaluator<G, F> {
     #[instrument(skip_all)]
     /// Create an [`Evaluator`] with given [`Constraints`] and [`GameFactory`]
     pub fn new(factory: F, config: Configuration, constraints: Constraints) -> Evaluator<G, F> {
+        config.clear_log_dir();
         if let Some(path) = &config.log_dir {
             init_logger(path);
         }
@@ -91,10 +182,64 @@ impl<G: Game + Send + 'static, F: GameFactory<G>> Evaluator<G, F> {
             factory,
             config,
             constraints,
+            control: TournamentControl::default(),
+            scoring: None,
+            reference: None,
+            round_constraints: BTreeMap::new(),
             _ff: std::marker::PhantomData,
         }
     }
 
+    /// Scores players with `scoring` instead of [`Game::get_player_score`], e.g. to add
+    /// contest-specific bonuses or penalties on top of the game's own score.
+    ///
+    /// `scoring` is called at the end of each match with the final game, what happened during the
+    /// match (see [`MatchContext`]) and the player number. It takes precedence over both
+    /// [`Game::get_player_score`] and [`Game::heuristic_score`]: it can call them itself, e.g.
+    /// `heuristic_score` when [`MatchContext::cut_off`] is true.
+    pub fn with_scoring(
+        mut self,
+        scoring: impl Fn(&G, &MatchContext, u32) -> G::Score + Send + Sync + 'static,
+    ) -> Self {
+        self.scoring = Some(Arc::new(scoring));
+        self
+    }
+
+    /// Adds an agent named `name`, running inside the evaluator process, to the agents found in the
+    /// evaluated directory. Useful to benchmark agents against a known baseline without packaging
+    /// it as a crate.
+    ///
+    /// `factory` creates a new instance of the agent for each match (matches run in parallel).
+    /// [`init`](GameAgent::init) is called at the start of the match, then
+    /// [`select_action`](GameAgent::select_action) on each of its turns. It is not constrained:
+    /// it runs on the thread of the match, and neither the time budget nor the action timeout
+    /// apply. A match ends early when no external agent remains.
+    ///
+    /// The reference agent takes part in the tournament like any other agent: to only play
+    /// against it, use a [`GauntletTournament`] (or [`Evaluator::evaluate_gauntlet`]) with `name`
+    /// as hero.
+    pub fn with_reference_agent(
+        mut self,
+        name: impl Into<String>,
+        factory: impl Fn() -> Box<dyn GameAgent<G>> + Send + Sync + 'static,
+    ) -> Self {
+        self.reference = Some((name.into(), Arc::new(factory)));
+        self
+    }
+
+    /// Plays the matches of rounds `first_round` (starting at 1) and later with the per-agent limits
+    /// of `constraints`, until the next round given to this method (see the
+    /// [module documentation](self#staged-resources)).
+    pub fn with_round_constraints(mut self, first_round: usize, constraints: Constraints) -> Self {
+        self.round_constraints.insert(first_round, constraints);
+        self
+    }
+
+    /// Returns a handle to control running tournaments, e.g. to disqualify an agent.
+    pub fn control(&self) -> TournamentControl {
+        self.control.clone()
+    }
+
     /// Executes a tournament between agents found in the specified directory.
     ///
     /// # Parameters
@@ -104,65 +249,397 @@ impl<G: Game + Send + 'static, F: GameFactory<G>> Evaluator<G, F> {
     /// # Returns
     /// Returns a tuple containing:
     /// - A `HashMap` of agent names to their final scores (`T::FinalScore`) for agents that compiled.
-    /// - A `HashMap` of agent names to error messages (`String`) for agents that failed to compile.
+    /// - A `HashMap` of agent names to error messages (`String`) for agents that did not take
+    ///   part: failed to compile, invalid agent directory or config file, failed self test,
+    ///   duplicate agent... Late agents (see [`TournamentControl::add_late_agent`]) are included.
     ///
     /// # Errors
     /// Returns an error if the directory is invalid.
     pub fn evaluate<T: TournamentStrategy<G::Score>>(
         &self,
         directory: impl AsRef<std::path::Path>,
-        mut tournament: T,
+        tournament: T,
     ) -> anyhow::Result<(HashMap<String, T::FinalScore>, HashMap<String, String>)>
     where
         T::FinalScore: 'static,
     {
+        let report = self.run_tournament(directory.as_ref(), tournament, |_| {})?;
+        Ok((report.scores, report.failures))
+    }
+
+    /// Same as [`Evaluator::evaluate`], but returns an [`EvaluationReport`] which also contains
+    /// the number of matches, rounds and byes, and the duration of the evaluation.
+    ///
+    /// # Errors
+    /// Returns an error if the directory is invalid.
+    pub fn evaluate_full<T: TournamentStrategy<G::Score>>(
+        &self,
+        directory: impl AsRef<std::path::Path>,
+        tournament: T,
+    ) -> anyhow::Result<EvaluationReport<T::FinalScore>>
+    where
+        T::FinalScore: 'static,
+    {
+        self.run_tournament(directory.as_ref(), tournament, |_| {})
+    }
+
+    /// Same as [`Evaluator::evaluate`], but also returns the [`MatchRecord`] of every match played,
+    /// in the order in which they finished.
+    ///
+    /// Records can be used to recompute standings, e.g. to merge partial runs from several
+    /// machines with [`results::merge`](crate::results::merge).
+    ///
+    /// # Errors
+    /// Returns an error if the directory is invalid.
+    #[allow(clippy::type_complexity)]
+    pub fn evaluate_with_history<T: TournamentStrategy<G::Score>>(
+        &self,
+        directory: impl AsRef<std::path::Path>,
+        tournament: T,
+    ) -> anyhow::Result<(
+        HashMap<String, T::FinalScore>,
+        HashMap<String, String>,
+        Vec<MatchRecord<G::Score>>,
+    )>
+    where
+        T::FinalScore: 'static,
+        G::Score: Clone,
+    {
+        let mut history = vec![];
+        let report = self.run_tournament(directory.as_ref(), tournament, |result| {
+            history.push(MatchRecord::from_runner_result(result))
+        })?;
+        Ok((report.scores, report.failures, history))
+    }
+
+    /// Runs a gauntlet: the agent named `hero` plays `games_per_opponent` games against every other
+    /// agent found in `directory` (see [`GauntletTournament`]).
+    ///
+    /// # Errors
+    /// Returns an error if the directory is invalid, or if `hero` is not a working agent of
+    /// `directory`.
+    pub fn evaluate_gauntlet(
+        &self,
+        directory: impl AsRef<std::path::Path>,
+        hero: &str,
+        games_per_opponent: usize,
+    ) -> anyhow::Result<GauntletReport> {
+        let (scores, non_compilings) =
+            self.evaluate(directory, GauntletTournament::new(hero, games_per_opponent))?;
+        if let Some(error) = non_compilings.get(hero) {
+            bail!("gauntlet hero '{hero}' is not working: {error}");
+        }
+        GauntletReport::from_scores(hero, &scores)
+            .with_context(|| format!("gauntlet hero '{hero}' not found (or no opponent)"))
+    }
+
+    /// Runs a single match between the agents of `agent_dirs` (one agent directory each, e.g.
+    /// `agents/my_bot`), in that order, and returns its record. Useful to debug a specific
+    /// matchup without setting up a whole tournament.
+    ///
+    /// Agents are collected (and compiled) as in a tournament, with the evaluated config of each
+    /// directory. The same directory can be given several times, e.g. to play an agent against
+    /// itself.
+    ///
+    /// # Errors
+    /// Returns an error if an agent cannot be collected, does not compile, or has several
+    /// configs (with `test_all_configs`), or if the constraints do not have enough CPUs or RAM
+    /// for all the agents of the match.
+    pub fn run_single_match(&self, agent_dirs: &[&Path]) -> anyhow::Result<MatchRecord<G::Score>>
+    where
+        G::Score: Clone,
+    {
+        self.play_single_match(agent_dirs, &self.config)
+    }
+
+    /// Plays a single match again, as [`run_single_match`](Self::run_single_match), giving
+    /// `seed` to the agents instead of the seed derived from
+    /// [`Configuration::with_seed`] (which need not be set).
+    ///
+    /// `seed` is usually the [`seed`](MatchRecord::seed) of a previous record, with the agents of
+    /// that record in the same order. The match is only reproduced if the agents are
+    /// deterministic: an agent must take all its random choices from the seed, and must not
+    /// depend on timings (e.g. iterative deepening until a deadline) or on thread scheduling.
+    /// The game must be deterministic too, as [`GameFactory::new_game`] is not given the seed.
+    ///
+    /// # Errors
+    /// Same as [`run_single_match`](Self::run_single_match).
+    pub fn rerun_match(
+        &self,
+        agent_dirs: &[&Path],
+        seed: u64,
+    ) -> anyhow::Result<MatchRecord<G::Score>>
+    where
+        G::Score: Clone,
+    {
+        let mut config = self.config.clone();
+        config.match_seed = Some(seed);
+        self.play_single_match(agent_dirs, &config)
+    }
+
+    fn play_single_match(
+        &self,
+        agent_dirs: &[&Path],
+        config: &Configuration,
+    ) -> anyhow::Result<MatchRecord<G::Score>>
+    where
+        G::Score: Clone,
+    {
+        let mut next_id = 1;
+        let mut players = vec![];
+        for dir in agent_dirs {
+            let agents = self.collect_agent_dir(dir, &mut next_id)?;
+            let [agent] = &agents[..] else {
+                bail!(
+                    "{} has {} configs, expected one (disable `test_all_configs`)",
+                    dir.display(),
+                    agents.len()
+                );
+            };
+            if !agent.compile {
+                bail!(
+                    "agent {} is not working: {}",
+                    agent.name,
+                    agent.error_message.as_deref().unwrap_or_default()
+                );
+            }
+            players.push(agent.clone());
+        }
+
+        let num_players = players.len();
+        let resources = self
+            .constraints
+            .clone()
+            .try_take(
+                self.constraints.cpus_per_agent * num_players,
+                self.constraints.agent_ram * num_players,
+            )
+            .with_context(|| format!("not enough CPUs or RAM for {num_players} agents"))?;
+        let settings = MatchSettings {
+            ordered_player: players,
+            resources,
+            round: 1,
+            index: 0,
+            weight: 1.0,
+            // no strategy to rank the scores
+            higher_is_better: true,
+        };
+        let result = run_match(
+            settings,
+            config,
+            self.factory.new_game(),
+            &AtomicBool::new(false),
+            &self.control,
+            self.scoring.as_ref(),
+            None,
+        );
+        Ok(MatchRecord::from_runner_result(&result))
+    }
+
+    fn run_tournament<T: TournamentStrategy<G::Score>>(
+        &self,
+        directory: &std::path::Path,
+        mut tournament: T,
+        mut on_result: impl FnMut(&RunnerResult<G::Score>),
+    ) -> anyhow::Result<EvaluationReport<T::FinalScore>>
+    where
+        T::FinalScore: 'static,
+    {
+        let start = Instant::now();
+        let _running = RunningGuard::new(&self.control);
         // 1. Exit on panic otherwise the program would be in a deadlock
         Self::setup_panic_hook(self.config.verbose);
         if self.config.verbose {
             disable_line_wrap();
         }
 
+        let event_log = self
+            .config
+            .event_log
+            .as_deref()
+            .map(EventLog::open)
+            .transpose()?;
+        if let Some(log) = &event_log {
+            log.tournament_start(directory, self.config.shuffle_seed, &self.constraints);
+        }
+
         // 2. get agents name & code in *directory*
-        let agents = collect_agents(directory.as_ref(), &self.config)?;
-        let (compiling_agents, non_compiling_agents) =
+        let mut agents = collect_agents(directory, &self.config)?;
+        let mut next_id = agents.iter().map(|a| a.id).max().unwrap_or(0) + 1;
+        if let Some((name, _)) = &self.reference {
+            if agents.iter().any(|a| &a.name == name) {
+                bail!("reference agent '{name}' has the same name as an agent of {directory:?}");
+            }
+            agents.push(Arc::new(Agent::in_process(name.clone(), next_id)));
+            next_id += 1;
+        }
+        if let Some(log) = &event_log {
+            for agent in &agents {
+                log.agent(agent, false);
+            }
+        }
+        let (compiling_agents, mut non_compiling_agents) =
             agents.into_iter().partition::<Vec<_>, _>(|a| a.compile);
         info!(?compiling_agents);
         tracing::error!(?non_compiling_agents);
+        if compiling_agents.is_empty() {
+            warn!("No working agent: the tournament will not run any match");
+            if self.config.verbose {
+                println!("\x1b[33mNo working agent: the tournament will not run any match\x1b[39m");
+            }
+        }
 
         // 3. add agents to tournament
         tournament.add_agents(compiling_agents);
+        let players = tournament.players_per_match();
+        for (round, limits) in &self.round_constraints {
+            if limits.cpus_per_agent * players > self.constraints.cpus.len()
+                || limits.agent_ram * players > self.constraints.total_ram
+            {
+                bail!("a match of round {round} needs more CPUs or RAM than available in total");
+            }
+        }
 
         // 4. create scheduler and communication channels
-        let mut scheduler = TournamentScheduler::new(self.constraints.clone(), tournament);
+        let mut scheduler = TournamentScheduler::new(self.constraints.clone(), tournament)
+            .with_shuffle_seed(self.config.shuffle_seed)
+            .with_match_retries(self.config.match_retries)
+            .with_max_concurrent_matches(self.config.max_concurrent_matches)
+            .with_round_constraints(self.round_constraints.clone());
+        scheduler.set_resource_cap(self.control.resource_cap());
         let (tx_result, rx_result) = mpsc::channel();
 
         // 5. create running matches shared vector (for printing purpose only)
         let running = Arc::new(Mutex::new(vec![]));
 
-        // 6. Init matches
-        self.launch_initial_matches(&mut scheduler, &tx_result, &running);
+        // 6. Init matches. Running matches are cancelled and joined if we return early
+        let mut threads = MatchThreads::default();
+        self.launch_initial_matches(
+            &mut scheduler,
+            &tx_result,
+            &running,
+            &mut threads,
+            event_log.as_ref(),
+        );
 
         // 7. main loop
+        let mut total_matches = 0;
+        let mut penalties: HashMap<String, u32> = HashMap::new();
         while !scheduler.is_finished() {
+            self.control.set_progress(Progress {
+                matches_done: total_matches,
+                matches_total_estimate: total_matches + scheduler.remaining_matches(),
+                round: scheduler.round(),
+            });
             // not finished <=> match running <=> result to receive
             let result = rx_result.recv().unwrap();
+            for dir in self.control.take_late_agents() {
+                for agent in self.collect_late_agent(&dir, &mut next_id) {
+                    if let Some(log) = &event_log {
+                        log.agent(&agent, true);
+                    }
+                    if agent.compile {
+                        scheduler.add_late_agent(agent);
+                    } else {
+                        non_compiling_agents.push(agent);
+                    }
+                }
+            }
+            // replayed attempts are not part of the tournament results
+            let retried = scheduler.should_retry(&result);
+            if !retried {
+                total_matches += 1;
+                for ((agent, _), penalty) in result.results.iter().zip(&result.penalties) {
+                    *penalties.entry(agent.name.clone()).or_default() += penalty;
+                }
+                on_result(&result);
+            }
+            if let Some(log) = &event_log {
+                // the match is still counted as running by the scheduler, unless it is replayed
+                let remaining = scheduler
+                    .remaining_matches()
+                    .saturating_sub(usize::from(!retried));
+                let progress = Progress {
+                    matches_done: total_matches,
+                    matches_total_estimate: total_matches + remaining,
+                    round: scheduler.round(),
+                };
+                log.match_result(&result, retried, progress);
+            }
+            scheduler.set_resource_cap(self.control.resource_cap());
             for new_match in scheduler.on_result(result) {
-                self.launch_match(new_match, tx_result.clone(), &running);
+                if let Some(log) = &event_log {
+                    log.match_start(&new_match, self.config.seed);
+                }
+                self.launch_match(new_match, tx_result.clone(), &running, &mut threads);
             }
         }
 
+        self.control.set_progress(Progress {
+            matches_done: total_matches,
+            matches_total_estimate: total_matches,
+            round: scheduler.rounds_played(),
+        });
         if self.config.verbose {
             enable_line_wrap();
         }
 
         // format results for output
         let scores = Self::collect_final_scores(&scheduler);
-        let non_compilings = non_compiling_agents
+        if let Some(log) = &event_log {
+            log.standings(&scores, scheduler.higher_is_better());
+            log.tournament_end(
+                total_matches,
+                scheduler.rounds_played(),
+                scheduler.num_byes(),
+            );
+        }
+        let failures = non_compiling_agents
             .into_iter()
             .map(|a| (a.name.clone(), a.error_message.clone().unwrap_or_default()))
             .collect();
 
-        Ok((scores, non_compilings))
+        Ok(EvaluationReport {
+            scores,
+            failures,
+            total_matches,
+            penalties,
+            round_count: scheduler.rounds_played(),
+            byes: scheduler.num_byes(),
+            higher_is_better: scheduler.higher_is_better(),
+            duration: start.elapsed(),
+        })
+    }
+
+    /// Collects the agent in `dir`, with an id not used by other agents
+    fn collect_late_agent(&self, dir: &Path, next_id: &mut u32) -> Vec<Arc<Agent>> {
+        self.collect_agent_dir(dir, next_id)
+            .inspect_err(|e| warn!("Could not collect late agent {}: {e:#}", dir.display()))
+            .unwrap_or_default()
+    }
+
+    /// Collects the agent(s) of a single agent directory, with ids starting at `next_id`
+    fn collect_agent_dir(&self, dir: &Path, next_id: &mut u32) -> anyhow::Result<Vec<Arc<Agent>>> {
+        let config = self
+            .config
+            .clone()
+            .with_self_test(true)
+            .with_self_test_path(dir);
+        let agents = collect_agents(dir, &config)?;
+        Ok(agents
+            .into_iter()
+            .map(|agent| {
+                // ids are only unique within a collection
+                let id = *next_id;
+                *next_id += 1;
+                // Safety: agents are not shared before being returned by `collect_agents`
+                let agent = Arc::try_unwrap(agent).expect("collected agent is shared");
+                // keep every other field of the collected agent
+                let late = Agent { id, ..agent };
+                info!("agent {} collected", late.name);
+                Arc::new(late)
+            })
+            .collect())
     }
 
     fn setup_panic_hook(verbose: bool) {
@@ -181,9 +658,14 @@ impl<G: Game + Send + 'static, F: GameFactory<G>> Evaluator<G, F> {
         scheduler: &mut TournamentScheduler<T, G::Score>,
         tx_result: &Sender<RunnerResult<G::Score>>,
         running: &Arc<Mutex<Vec<MatchSettings>>>,
+        threads: &mut MatchThreads,
+        event_log: Option<&EventLog>,
     ) {
         for m in scheduler.advance() {
-            self.launch_match(m, tx_result.clone(), running);
+            if let Some(log) = event_log {
+                log.match_start(&m, self.config.seed);
+            }
+            self.launch_match(m, tx_result.clone(), running, threads);
         }
     }
 
@@ -202,6 +684,7 @@ impl<G: Game + Send + 'static, F: GameFactory<G>> Evaluator<G, F> {
         match_settings: MatchSettings,
         tx_result: Sender<RunnerResult<G::Score>>,
         running: &Arc<Mutex<Vec<MatchSettings>>>,
+        threads: &mut MatchThreads,
     ) {
         let game = self.factory.new_game();
         let mutex = running.clone();
@@ -214,16 +697,29 @@ impl<G: Game + Send + 'static, F: GameFactory<G>> Evaluator<G, F> {
         drop(guard);
 
         let config = self.config.clone();
-        std::thread::spawn(move || {
-            let result = run_match(match_settings.clone(), &config, game);
+        let cancelled = threads.cancelled.clone();
+        let control = self.control.clone();
+        let scoring = self.scoring.clone();
+        let reference = self.reference.as_ref().map(|(_, factory)| factory.clone());
+        threads.push(std::thread::spawn(move || {
+            let result = run_match(
+                match_settings.clone(),
+                &config,
+                game,
+                &cancelled,
+                &control,
+                scoring.as_ref(),
+                reference.as_ref(),
+            );
 
             if config.verbose {
                 print_runner_result(&match_settings, &result);
             }
             Self::remove_running_match(&mutex, &match_settings);
 
-            tx_result.send(result).unwrap();
-        });
+            // the receiver is gone if the evaluation was stopped
+            let _ = tx_result.send(result);
+        }));
     }
 
     fn remove_running_match(mutex: &Mutex<Vec<MatchSettings>>, running: &MatchSettings) {
@@ -236,6 +732,34 @@ impl<G: Game + Send + 'static, F: GameFactory<G>> Evaluator<G, F> {
     }
 }
 
+/// Threads of the running matches.
+///
+/// On drop, running matches are cancelled and their threads joined, which kills their agent
+/// processes. This ensures that no match outlives the evaluation, even on early return. A match
+/// notices the cancellation between two turns, so dropping waits for the current turn to end
+/// (bounded by the action timeout).
+#[derive(Default)]
+struct MatchThreads {
+    cancelled: Arc<AtomicBool>,
+    handles: Vec<JoinHandle<()>>,
+}
+
+impl MatchThreads {
+    fn push(&mut self, handle: JoinHandle<()>) {
+        self.handles.retain(|h| !h.is_finished());
+        self.handles.push(handle);
+    }
+}
+
+impl Drop for MatchThreads {
+    fn drop(&mut self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+        for handle in self.handles.drain(..) {
+            let _ = handle.join();
+        }
+    }
+}
+
 fn print_runner_result<S: Display + PartialOrd>(
     match_settings: &MatchSettings,
     result: &RunnerResult<S>,
@@ -256,9 +780,25 @@ fn print_runner_result<S: Display + PartialOrd>(
         .collect::<Vec<_>>()
         .join("-");
 
-    // clear line, green match, results, red errors, start of line
+    let args = match_settings
+        .ordered_player
+        .iter()
+        .filter(|player| !player.args().is_empty())
+        .map(|player| format!("{}: {}", player.name, player.args().join(" ")))
+        .collect::<Vec<_>>();
+    let args = if args.is_empty() {
+        String::new()
+    } else {
+        format!("({}) ", args.join(", "))
+    };
+    let seed = result
+        .seed
+        .map(|seed| format!("seed {seed} "))
+        .unwrap_or_default();
+
+    // clear line, green match, results, grey args and seed, red errors, start of line
     println!(
-        "\x1b[2K\x1b[32m{match_settings}: \x1b[39m{ordered_scores} \x1b[31m{}\x1b[39m\x1b[0G",
+        "\x1b[2K\x1b[32m{match_settings}: \x1b[39m{ordered_scores} \x1b[90m{args}{seed}\x1b[31m{}\x1b[39m\x1b[0G",
         result.errors
     );
 }
@@ -283,3 +823,1355 @@ fn disable_line_wrap() {
 fn enable_line_wrap() {
     print!("\x1b[?7h");
 }
+
+#[cfg(all(test, unix))]
+mod server_tests {
+    use std::{path::PathBuf, time::Duration};
+
+    use super::*;
+    use crate::agent::Agent;
+    use crate::constraints::ConstraintsBuilder;
+    use crate::results::MatchError;
+
+    /// A game that never ends
+    #[derive(Clone)]
+    struct EndlessGame;
+
+    impl Game for EndlessGame {
+        type State = u32;
+        type Action = u32;
+        type Score = u32;
+
+        fn parse_action(&self, text: &str) -> Option<u32> {
+            Self::parse_from_str(text)
+        }
+
+        fn apply_action(&mut self, _action: &Option<u32>) -> anyhow::Result<()> {
+            Ok(())
+        }
+
+        fn get_state(&self) -> u32 {
+            0
+        }
+
+        fn get_current_player_number(&self) -> usize {
+            0
+        }
+
+        fn is_finished(&self) -> bool {
+            false
+        }
+
+        fn get_player_score(&self, _player_number: u32) -> u32 {
+            0
+        }
+    }
+
+    impl GameFactory<EndlessGame> for EndlessGame {
+        fn new_game(&self) -> EndlessGame {
+            EndlessGame
+        }
+    }
+
+    /// A single-player game that never ends, although it expects 5 turns at most
+    #[derive(Clone)]
+    struct HintedGame;
+
+    impl Game for HintedGame {
+        type State = u32;
+        type Action = u32;
+        type Score = u32;
+
+        fn parse_action(&self, text: &str) -> Option<u32> {
+            Self::parse_from_str(text)
+        }
+
+        fn apply_action(&mut self, _action: &Option<u32>) -> anyhow::Result<()> {
+            Ok(())
+        }
+
+        fn get_state(&self) -> u32 {
+            0
+        }
+
+        fn get_current_player_number(&self) -> usize {
+            0
+        }
+
+        fn is_finished(&self) -> bool {
+            false
+        }
+
+        fn get_player_score(&self, _player_number: u32) -> u32 {
+            0
+        }
+
+        fn max_turns_hint(&self) -> Option<usize> {
+            Some(5)
+        }
+    }
+
+    impl GameFactory<HintedGame> for HintedGame {
+        fn new_game(&self) -> HintedGame {
+            HintedGame
+        }
+    }
+
+    /// A two-player game that never ends, players playing in turn
+    #[derive(Clone, Default)]
+    struct AlternatingGame {
+        turn: usize,
+    }
+
+    impl Game for AlternatingGame {
+        type State = u32;
+        type Action = u32;
+        type Score = u32;
+
+        fn parse_action(&self, text: &str) -> Option<u32> {
+            Self::parse_from_str(text)
+        }
+
+        fn apply_action(&mut self, _action: &Option<u32>) -> anyhow::Result<()> {
+            self.turn += 1;
+            Ok(())
+        }
+
+        fn get_state(&self) -> u32 {
+            0
+        }
+
+        fn get_current_player_number(&self) -> usize {
+            self.turn % 2
+        }
+
+        fn is_finished(&self) -> bool {
+            false
+        }
+
+        fn get_player_score(&self, _player_number: u32) -> u32 {
+            0
+        }
+    }
+
+    impl GameFactory<AlternatingGame> for AlternatingGame {
+        fn new_game(&self) -> AlternatingGame {
+            AlternatingGame::default()
+        }
+    }
+
+    /// Temporary directory of a test, removed when dropped
+    struct TestDir(PathBuf);
+
+    impl TestDir {
+        /// A fresh directory for the test `name`, or `None` without bash to run the agent
+        /// scripts of the tests
+        fn new(name: &str) -> Option<TestDir> {
+            if std::process::Command::new("bash")
+                .arg("--version")
+                .output()
+                .is_err()
+            {
+                return None;
+            }
+            let dir =
+                std::env::temp_dir().join(format!("ai_tournament_{name}_{}", std::process::id()));
+            let _ = std::fs::remove_dir_all(&dir);
+            std::fs::create_dir_all(&dir).unwrap();
+            Some(TestDir(dir))
+        }
+
+        /// Writes the executable `script` at `file`, relative to the directory
+        fn script(&self, file: &str, script: &str) -> PathBuf {
+            use std::os::unix::fs::PermissionsExt;
+
+            let path = self.0.join(file);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(&path, script).unwrap();
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+            path
+        }
+    }
+
+    impl std::ops::Deref for TestDir {
+        type Target = Path;
+
+        fn deref(&self) -> &Path {
+            &self.0
+        }
+    }
+
+    impl Drop for TestDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    /// An agent answering `0` to every state, forever. Writes its pid next to itself.
+    fn make_endless_agent(dir: &TestDir) -> PathBuf {
+        dir.script(
+            "agent.sh",
+            "#!/bin/bash\n\
+             echo $$ > \"$(dirname \"$0\")/pid\"\n\
+             exec 3<>/dev/tcp/127.0.0.1/$1\n\
+             while read -r -N 1 _ <&3; do printf 0 >&3; done\n",
+        )
+    }
+
+    fn is_alive(pid: &str) -> bool {
+        // a killed process may remain as a zombie until it is reaped
+        std::fs::read_to_string(format!("/proc/{pid}/stat"))
+            .is_ok_and(|stat| !stat.rsplit(')').next().unwrap().trim().starts_with('Z'))
+    }
+
+    /// Quiet, and running the agents without container
+    fn test_config() -> Configuration {
+        Configuration::new()
+            .with_verbose(false)
+            .with_allow_uncontained(true)
+    }
+
+    /// 500ms per action and 5s to start
+    fn test_constraints() -> ConstraintsBuilder {
+        ConstraintsBuilder::new()
+            .with_action_timeout(Duration::from_millis(500))
+            .with_startup_timeout(Duration::from_secs(5))
+    }
+
+    /// First match of the first round between `players`, given a single cpu
+    fn first_match<G: Game, F: GameFactory<G>>(
+        evaluator: &Evaluator<G, F>,
+        players: Vec<Arc<Agent>>,
+    ) -> MatchSettings {
+        let constraints = &evaluator.constraints;
+        MatchSettings {
+            ordered_player: players,
+            resources: constraints.clone().take(1, constraints.agent_ram),
+            round: 1,
+            index: 0,
+            weight: 1.0,
+            higher_is_better: true,
+        }
+    }
+
+    /// Plays a match and waits for its result
+    fn play<G: Game + Send + 'static, F: GameFactory<G>>(
+        evaluator: &Evaluator<G, F>,
+        settings: MatchSettings,
+    ) -> RunnerResult<G::Score> {
+        let (tx_result, rx_result) = mpsc::channel();
+        let running = Arc::new(Mutex::new(vec![]));
+        let mut threads = MatchThreads::default();
+        evaluator.launch_match(settings, tx_result, &running, &mut threads);
+        rx_result
+            .recv_timeout(Duration::from_secs(10))
+            .expect("match did not end")
+    }
+
+    /// Plays the [first match](first_match) between `players` and waits for its result
+    fn play_match<G: Game + Send + 'static, F: GameFactory<G>>(
+        evaluator: &Evaluator<G, F>,
+        players: Vec<Arc<Agent>>,
+    ) -> RunnerResult<G::Score> {
+        play(evaluator, first_match(evaluator, players))
+    }
+
+    #[test]
+    fn test_drop_running_matches() {
+        let Some(dir) = TestDir::new("drop_running") else {
+            return;
+        };
+        let agent = Arc::new(Agent::new(
+            "endless".into(),
+            Some(make_endless_agent(&dir)),
+            None,
+            1,
+            None,
+        ));
+        let evaluator = Evaluator::new(
+            EndlessGame,
+            test_config(),
+            test_constraints().build().unwrap(),
+        );
+
+        let (tx_result, rx_result) = mpsc::channel();
+        let running = Arc::new(Mutex::new(vec![]));
+        let mut threads = MatchThreads::default();
+        let settings = first_match(&evaluator, vec![agent]);
+        evaluator.launch_match(settings, tx_result, &running, &mut threads);
+
+        // wait for the agent to be running
+        let pid_file = dir.join("pid");
+        let deadline = std::time::Instant::now() + Duration::from_secs(5);
+        while !pid_file.exists() && std::time::Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        std::thread::sleep(Duration::from_millis(300));
+        let pid = std::fs::read_to_string(&pid_file).unwrap();
+        let pid = pid.trim();
+        assert!(is_alive(pid), "agent is not running");
+
+        drop(threads);
+
+        assert!(!is_alive(pid), "agent process leaked");
+        let result = rx_result.recv().unwrap();
+        assert!(result.errors.contains("match cancelled"));
+        assert!(running.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_disqualify_running_agent() {
+        let Some(dir) = TestDir::new("disqualify") else {
+            return;
+        };
+        let agent = Arc::new(Agent::new(
+            "cheater".into(),
+            Some(make_endless_agent(&dir)),
+            None,
+            1,
+            None,
+        ));
+        let evaluator = Evaluator::new(
+            EndlessGame,
+            test_config(),
+            test_constraints().build().unwrap(),
+        );
+
+        let (tx_result, rx_result) = mpsc::channel();
+        let running = Arc::new(Mutex::new(vec![]));
+        let mut threads = MatchThreads::default();
+        let settings = first_match(&evaluator, vec![agent]);
+        evaluator.launch_match(settings.clone(), tx_result, &running, &mut threads);
+
+        let pid_file = dir.join("pid");
+        let deadline = std::time::Instant::now() + Duration::from_secs(5);
+        while !pid_file.exists() && std::time::Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        let pid = std::fs::read_to_string(&pid_file).unwrap();
+        let pid = pid.trim().to_string();
+
+        // the endless game only ends once its single player is gone
+        evaluator.control().disqualify("cheater");
+        let result = rx_result
+            .recv_timeout(Duration::from_secs(5))
+            .expect("match did not end");
+        assert!(result.errors.contains("cheater disqualified"));
+        assert!(!result.errors.contains("match cancelled"));
+        assert!(!result.transient);
+        assert!(!is_alive(&pid), "agent process leaked");
+
+        // the agent is not launched anymore
+        std::fs::remove_file(&pid_file).unwrap();
+        let result = play(&evaluator, settings);
+        assert!(result.errors.contains("cheater disqualified"));
+        assert!(!pid_file.exists());
+    }
+
+    #[test]
+    fn test_end_on_elimination() {
+        let Some(dir) = TestDir::new("end_on_elimination") else {
+            return;
+        };
+        let survivor = Arc::new(Agent::new(
+            "survivor".into(),
+            Some(make_endless_agent(&dir)),
+            None,
+            1,
+            None,
+        ));
+        // never launched: only the survivor needs resources
+        let eliminated = Arc::new(Agent::new("eliminated".into(), None, None, 2, None));
+
+        let config = test_config().with_end_on_elimination(true);
+        let evaluator = Evaluator::new(
+            AlternatingGame::default(),
+            config,
+            test_constraints().build().unwrap(),
+        );
+        evaluator.control().disqualify("eliminated");
+
+        let result = play_match(&evaluator, vec![survivor, eliminated]);
+        // the survivor played once, then the eliminated player was given `None`
+        assert_eq!(result.turns, 2);
+        assert!(result.errors.contains("eliminated disqualified"));
+        assert!(!result.errors.contains("match cancelled"));
+    }
+
+    #[test]
+    fn test_enforce_max_turns_hint() {
+        let Some(dir) = TestDir::new("max_turns_hint") else {
+            return;
+        };
+        let agent = Arc::new(Agent::new(
+            "endless".into(),
+            Some(make_endless_agent(&dir)),
+            None,
+            1,
+            None,
+        ));
+        let config = test_config().with_enforce_max_turns_hint(true);
+        let evaluator = Evaluator::new(HintedGame, config, test_constraints().build().unwrap());
+
+        let result = play_match(&evaluator, vec![agent]);
+        assert_eq!(result.turns, 5);
+        assert!(result.errors.contains("max turns hint reached"));
+    }
+
+    #[test]
+    fn test_match_timeout() {
+        let Some(dir) = TestDir::new("match_timeout") else {
+            return;
+        };
+        let agent = Arc::new(Agent::new(
+            "endless".into(),
+            Some(make_endless_agent(&dir)),
+            None,
+            1,
+            None,
+        ));
+        let constraints = test_constraints()
+            .with_match_timeout(Duration::from_millis(300))
+            .build()
+            .unwrap();
+        let evaluator = Evaluator::new(EndlessGame, test_config(), constraints);
+
+        let result = play_match(&evaluator, vec![agent]);
+        assert!(result.turns > 0);
+        assert!(result.errors.contains("match exceeded wall-clock limit"));
+        assert_eq!(result.results[0].1, 0);
+    }
+
+    #[test]
+    fn test_max_turns() {
+        let Some(dir) = TestDir::new("max_turns") else {
+            return;
+        };
+        let agent = Arc::new(Agent::new(
+            "endless".into(),
+            Some(make_endless_agent(&dir)),
+            None,
+            1,
+            None,
+        ));
+        let constraints = test_constraints().with_max_turns(4).build().unwrap();
+        let evaluator = Evaluator::new(EndlessGame, test_config(), constraints);
+
+        let result = play_match(&evaluator, vec![agent]);
+        assert_eq!(result.turns, 4);
+        assert!(result.errors.contains("max turns reached (4 turns)"));
+        assert_eq!(result.error_details, [MatchError::MaxTurns { turns: 4 }]);
+        // not measured without container
+        assert_eq!(result.peak_memory, [None]);
+        assert_eq!(result.results[0].1, 0);
+    }
+
+    #[test]
+    fn test_working_dir() {
+        let Some(dir) = TestDir::new("working_dir") else {
+            return;
+        };
+        make_endless_agent(&dir);
+        // as collected from `run: bash agent.sh`: the script is relative to the agent directory
+        let command = ["bash", "agent.sh", "{port}", "{budget_us}", "{action_us}"];
+        let agent = Arc::new(
+            Agent::new("runner".into(), Some(dir.to_path_buf()), None, 1, None)
+                .with_command(Some(command.map(String::from).to_vec()))
+                .with_working_dir(Some(dir.to_path_buf())),
+        );
+        let constraints = test_constraints().with_max_turns(2).build().unwrap();
+        let evaluator = Evaluator::new(EndlessGame, test_config(), constraints);
+
+        let result = play_match(&evaluator, vec![agent]);
+        assert_eq!(result.turns, 2, "{}", result.errors);
+        assert!(dir.join("pid").exists());
+    }
+
+    #[test]
+    fn test_late_run_agent() {
+        let Some(dir) = TestDir::new("late_run") else {
+            return;
+        };
+        make_endless_agent(&dir);
+        std::fs::write(
+            dir.join("config.yaml"),
+            "run: bash agent.sh\neval: default\nconfigs:\n  - default: \"\"\n",
+        )
+        .unwrap();
+        let constraints = test_constraints().with_max_turns(2).build().unwrap();
+        let evaluator = Evaluator::new(EndlessGame, test_config(), constraints);
+
+        let mut next_id = 7;
+        let agents = evaluator.collect_late_agent(&dir, &mut next_id);
+        assert_eq!(agents.len(), 1);
+        assert_eq!(agents[0].id, 7);
+        assert!(agents[0].compile, "{:?}", agents[0].error_message);
+        // `agent.sh` is relative to the agent directory
+        assert_eq!(
+            agents[0].working_dir.as_ref(),
+            Some(&dir.canonicalize().unwrap())
+        );
+
+        let result = play_match(&evaluator, agents);
+        assert_eq!(result.turns, 2, "{}", result.errors);
+    }
+
+    #[test]
+    fn test_end_notification() {
+        let Some(dir) = TestDir::new("end_notification") else {
+            return;
+        };
+        // plays `0` until told the match is over, then saves its state and exits
+        let path = dir.script(
+            "agent.sh",
+            "#!/bin/bash\n\
+             exec 3<>/dev/tcp/127.0.0.1/$1\n\
+             while read -r -N 1 c <&3; do\n\
+             received=\"$received$c\"\n\
+             if [[ $received == *GAME_OVER* ]]; then echo saved > \"$(dirname \"$0\")/saved\"; exit 0; fi\n\
+             printf 0 >&3\n\
+             done\n",
+        );
+        let agent = Arc::new(Agent::new("saver".into(), Some(path), None, 1, None));
+        let constraints = test_constraints().with_max_turns(3).build().unwrap();
+        let config = test_config().with_end_notification(true);
+        let evaluator = Evaluator::new(EndlessGame, config, constraints);
+
+        let result = play_match(&evaluator, vec![agent]);
+        assert_eq!(result.turns, 3);
+        let timing = &result.timings[0];
+        assert_eq!(timing.turns, 3);
+        assert!(timing.max_turn_time <= timing.think_time);
+        assert!(!timing.budget_exhausted);
+        assert!(result.duration >= timing.think_time);
+        assert_eq!(
+            std::fs::read_to_string(dir.join("saved")).unwrap(),
+            "saved\n"
+        );
+    }
+
+    #[test]
+    fn test_replays() {
+        let Some(dir) = TestDir::new("replays") else {
+            return;
+        };
+        let agent = Arc::new(Agent::new(
+            "endless".into(),
+            Some(make_endless_agent(&dir)),
+            None,
+            1,
+            None,
+        ));
+        let config = test_config()
+            .with_enforce_max_turns_hint(true)
+            .with_replays(dir.join("replays"));
+        let evaluator = Evaluator::new(HintedGame, config, test_constraints().build().unwrap());
+
+        let settings = MatchSettings {
+            round: 2,
+            index: 3,
+            ..first_match(&evaluator, vec![agent])
+        };
+        let result = play(&evaluator, settings);
+        assert_eq!(result.turns, 5, "{}", result.errors);
+
+        let replay = dir.join("replays").join("endless").join("match_2-3.jsonl");
+        let replay = std::fs::read_to_string(replay).unwrap();
+        let turns = replay.lines().collect::<Vec<_>>();
+        assert_eq!(turns.len(), 5);
+        assert_eq!(
+            turns[4],
+            r#"{"turn":5,"player":0,"state":"0","action":"0","error":null}"#
+        );
+    }
+
+    #[test]
+    fn test_extra_agent_args() {
+        use crate::agent_collector::collect_agents;
+
+        let Some(dir) = TestDir::new("extra_agent_args") else {
+            return;
+        };
+        // writes its arguments, then answers 0 forever
+        dir.script(
+            "echo/agent.sh",
+            "#!/bin/bash\n\
+             echo \"$@\" > \"$(dirname \"$0\")/../args\"\n\
+             exec 3<>/dev/tcp/127.0.0.1/$1\n\
+             while read -r -N 1 _ <&3; do printf 0 >&3; done\n",
+        );
+        std::fs::write(
+            dir.join("echo").join("config.yaml"),
+            "eval: default\nconfigs:\n  - default: \"--own\"\n",
+        )
+        .unwrap();
+        let config = test_config()
+            .with_compile_agents(false)
+            .with_enforce_max_turns_hint(true)
+            .with_extra_agent_args(vec!["--depth".into(), "3".into()]);
+        let agents = collect_agents(&*dir, &config).unwrap();
+        assert_eq!(agents.len(), 1);
+        let evaluator = Evaluator::new(HintedGame, config, test_constraints().build().unwrap());
+
+        let result = play_match(&evaluator, agents);
+        assert_eq!(result.turns, 5, "{}", result.errors);
+        // port, time budget and action timeout come first
+        let args = std::fs::read_to_string(dir.join("args")).unwrap();
+        let args = args.split_whitespace().collect::<Vec<_>>();
+        assert_eq!(args[3..], ["--own", "--depth", "3"]);
+    }
+
+    #[test]
+    fn test_match_seed() {
+        let Some(dir) = TestDir::new("match_seed") else {
+            return;
+        };
+        // writes its arguments, then answers 0 forever
+        let path = dir.script(
+            "agent.sh",
+            "#!/bin/bash\n\
+             echo \"$@\" >> \"$(dirname \"$0\")/args\"\n\
+             exec 3<>/dev/tcp/127.0.0.1/$1\n\
+             while read -r -N 1 _ <&3; do printf 0 >&3; done\n",
+        );
+        let config = test_config()
+            .with_enforce_max_turns_hint(true)
+            .with_seed(42);
+        let evaluator = Evaluator::new(HintedGame, config, test_constraints().build().unwrap());
+        let agent = Arc::new(Agent::new(
+            "seeded".into(),
+            Some(path),
+            None,
+            1,
+            Some(vec!["--own".into()]),
+        ));
+
+        for index in [0, 1, 0] {
+            let settings = MatchSettings {
+                index,
+                ..first_match(&evaluator, vec![agent.clone()])
+            };
+            let result = play(&evaluator, settings);
+            assert_eq!(result.turns, 5, "{}", result.errors);
+        }
+
+        // the seed comes after port, time budget and action timeout
+        let args = std::fs::read_to_string(dir.join("args")).unwrap();
+        let seeds = args
+            .lines()
+            .map(|line| {
+                let args = line.split_whitespace().collect::<Vec<_>>();
+                assert_eq!(args[4..], ["--own"]);
+                args[3].to_string()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(seeds[0], crate::rng::match_seed(42, 1, 0).to_string());
+        assert_ne!(seeds[0], seeds[1]);
+        assert_eq!(seeds[0], seeds[2]);
+    }
+
+    #[test]
+    fn test_late_ack() {
+        let Some(dir) = TestDir::new("late_ack") else {
+            return;
+        };
+        // acknowledges the protocol after a slow initialization, then answers 0 forever
+        let path = dir.script(
+            "agent.sh",
+            "#!/bin/bash\n\
+             exec 3<>/dev/tcp/127.0.0.1/$1\n\
+             sleep 0.3\n\
+             printf 'ACK %s\\n' \"$AI_TOURNAMENT_PROTOCOL\" >&3\n\
+             while read -r -N 1 _ <&3; do printf 0 >&3; done\n",
+        );
+        let constraints = test_constraints()
+            .with_action_timeout(Duration::from_millis(1000))
+            .with_max_turns(2)
+            .build()
+            .unwrap();
+        let config = test_config().with_handshake_grace_period(Duration::from_millis(50));
+        let evaluator = Evaluator::new(EndlessGame, config, constraints);
+        let agent = Arc::new(Agent::new("late".into(), Some(path), None, 1, None));
+
+        let result = play_match(&evaluator, vec![agent]);
+        // not read as an invalid action
+        assert!(result.transient);
+        assert!(
+            matches!(
+                &result.error_details[..],
+                [MatchError::StartupFailed { agent, reason }]
+                    if agent == "late" && reason.contains("grace period (50ms)")
+            ),
+            "{}",
+            result.errors
+        );
+    }
+
+    #[test]
+    fn test_startup_failures() {
+        let Some(dir) = TestDir::new("startup_failures") else {
+            return;
+        };
+        let crashing = dir.script("crash.sh", "#!/bin/bash\nexit 1\n");
+        let crashing = Arc::new(Agent::new("crashing".into(), Some(crashing), None, 1, None));
+        let constraints = test_constraints()
+            .with_startup_timeout(Duration::from_millis(500))
+            .build()
+            .unwrap();
+        let evaluator = Evaluator::new(EndlessGame, test_config(), constraints.clone());
+
+        // the agent would crash again: the match is not replayed
+        let result = play_match(&evaluator, vec![crashing]);
+        assert!(!result.transient);
+        assert!(
+            matches!(
+                &result.error_details[..],
+                [MatchError::StartupFailed { agent, .. }] if agent == "crashing"
+            ),
+            "{}",
+            result.errors
+        );
+
+        // no port left for the agent: the host is to blame
+        let taken = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = taken.local_addr().unwrap().port();
+        let config = test_config().with_port_range(port..port + 1);
+        let evaluator = Evaluator::new(EndlessGame, config, constraints);
+        let agent = Arc::new(Agent::new(
+            "endless".into(),
+            Some(make_endless_agent(&dir)),
+            None,
+            1,
+            None,
+        ));
+        let result = play_match(&evaluator, vec![agent]);
+        assert!(result.transient, "{}", result.errors);
+        assert!(result.errors.contains("are in use"), "{}", result.errors);
+    }
+
+    #[test]
+    fn test_rerun_match() {
+        let Some(dir) = TestDir::new("rerun") else {
+            return;
+        };
+        // writes its arguments, then answers 0 forever
+        dir.script(
+            "agent.sh",
+            "echo \"$@\" >> args\n\
+             exec 3<>/dev/tcp/127.0.0.1/$1\n\
+             while read -r -N 1 _ <&3; do printf 0 >&3; done\n",
+        );
+        std::fs::write(
+            dir.join("config.yaml"),
+            "run: bash agent.sh\neval: default\nconfigs:\n  - default: \"\"\n",
+        )
+        .unwrap();
+        let constraints = test_constraints().with_max_turns(2).build().unwrap();
+        let evaluator = Evaluator::new(EndlessGame, test_config().with_seed(42), constraints);
+
+        let record = evaluator.run_single_match(&[&dir]).unwrap();
+        assert_eq!(record.turns, 2, "{}", record.errors);
+        assert_eq!(record.seed, Some(crate::rng::match_seed(42, 1, 0)));
+
+        let rerun = evaluator
+            .rerun_match(&[&dir], record.seed.unwrap())
+            .unwrap();
+        assert_eq!(rerun.turns, 2, "{}", rerun.errors);
+        assert_eq!(rerun.seed, record.seed);
+        let other = evaluator.rerun_match(&[&dir], 7).unwrap();
+        assert_eq!(other.seed, Some(7));
+
+        // the seed comes after port, time budget and action timeout
+        let args = std::fs::read_to_string(dir.join("args")).unwrap();
+        let seeds = args
+            .lines()
+            .map(|line| line.split_whitespace().nth(3).unwrap().to_string())
+            .collect::<Vec<_>>();
+        let expected = record.seed.unwrap().to_string();
+        assert_eq!(seeds, [expected.as_str(), &expected, "7"]);
+    }
+
+    #[test]
+    fn test_time_multiplier() {
+        let Some(dir) = TestDir::new("time_multiplier") else {
+            return;
+        };
+        // answers after 300ms: too slow for the 200ms action timeout, unless doubled
+        let path = dir.script(
+            "agent.sh",
+            "#!/bin/bash\n\
+             exec 3<>/dev/tcp/127.0.0.1/$1\n\
+             while read -r -N 1 _ <&3; do sleep 0.3; printf 0 >&3; done\n",
+        );
+        let constraints = test_constraints()
+            .with_action_timeout(Duration::from_millis(200))
+            .build()
+            .unwrap();
+        let config = test_config().with_enforce_max_turns_hint(true);
+        let evaluator = Evaluator::new(HintedGame, config, constraints);
+
+        let run = |time_multiplier: f32| {
+            let agent = Arc::new(
+                Agent::new("slow".into(), Some(path.clone()), None, 1, None)
+                    .with_time_multiplier(time_multiplier),
+            );
+            play_match(&evaluator, vec![agent])
+        };
+
+        let result = run(2.0);
+        assert_eq!(result.turns, 5);
+        assert!(!result.errors.contains("timeout"), "{}", result.errors);
+
+        let result = run(1.0);
+        assert!(
+            matches!(
+                &result.error_details[..],
+                [MatchError::Timeout { agent, .. }] if agent == "slow"
+            ),
+            "{}",
+            result.errors
+        );
+        assert_eq!(result.errors, format!("{}, ", result.error_details[0]));
+    }
+
+    #[test]
+    fn test_custom_scoring() {
+        let agents = ["absent", "present"]
+            .iter()
+            .enumerate()
+            .map(|(i, name)| Arc::new(Agent::new(name.to_string(), None, None, i as u32, None)))
+            .collect::<Vec<_>>();
+        let constraints = ConstraintsBuilder::new().build().unwrap();
+        let config = Configuration::new().with_verbose(false);
+        let evaluator = Evaluator::new(EndlessGame, config, constraints).with_scoring(
+            |game: &EndlessGame, context, player| {
+                assert_eq!(context.players, ["absent", "present"]);
+                assert_eq!(context.turns, 0);
+                assert!(!context.cut_off);
+                game.get_player_score(player)
+                    + 10 * player
+                    + context.errors.matches("disqualified").count() as u32
+            },
+        );
+        // disqualified agents are not launched: the match ends right away
+        evaluator.control().disqualify("absent");
+        evaluator.control().disqualify("present");
+
+        let result = play_match(&evaluator, agents);
+        let scores = result.results.iter().map(|(_, s)| *s).collect::<Vec<_>>();
+        assert_eq!(scores, [2, 12]);
+    }
+
+    /// Two players alternate, 4 turns. Each player scores the sum of its actions
+    #[derive(Default)]
+    struct SumGame {
+        turn: usize,
+        sums: [u32; 2],
+    }
+
+    impl Game for SumGame {
+        type State = usize;
+        type Action = u32;
+        type Score = u32;
+
+        fn parse_action(&self, text: &str) -> Option<u32> {
+            Self::parse_from_str(text)
+        }
+
+        fn apply_action(&mut self, action: &Option<u32>) -> anyhow::Result<()> {
+            self.sums[self.turn % 2] += action.unwrap_or_default();
+            self.turn += 1;
+            Ok(())
+        }
+
+        fn get_state(&self) -> usize {
+            self.turn
+        }
+
+        fn get_current_player_number(&self) -> usize {
+            self.turn % 2
+        }
+
+        fn is_finished(&self) -> bool {
+            self.turn == 4
+        }
+
+        fn get_player_score(&self, player_number: u32) -> u32 {
+            self.sums[player_number as usize]
+        }
+    }
+
+    struct SumGameFactory;
+
+    impl GameFactory<SumGame> for SumGameFactory {
+        fn new_game(&self) -> SumGame {
+            SumGame::default()
+        }
+    }
+
+    /// Always plays 7
+    struct Seven;
+
+    impl GameAgent<SumGame> for Seven {
+        fn init(&mut self) {}
+
+        fn select_action(&mut self, _state: usize, _deadline: std::time::SystemTime) -> u32 {
+            7
+        }
+    }
+
+    #[test]
+    fn test_reference_agent() {
+        let Some(dir) = TestDir::new("reference") else {
+            return;
+        };
+        let agent = Arc::new(Agent::new(
+            "external".into(),
+            Some(make_endless_agent(&dir)),
+            None,
+            1,
+            None,
+        ));
+        // only the external agent needs resources
+        let reference = Arc::new(Agent::in_process("seven".into(), 2));
+        let evaluator = Evaluator::new(
+            SumGameFactory,
+            test_config(),
+            test_constraints().build().unwrap(),
+        )
+        .with_reference_agent("seven", || Box::new(Seven));
+
+        let result = play_match(&evaluator, vec![agent, reference]);
+        assert_eq!(result.errors, "");
+        let scores = result.results.iter().map(|(_, s)| *s).collect::<Vec<_>>();
+        assert_eq!(scores, [0, 14]);
+    }
+
+    /// Four players take 2 turns each. Each player scores the sum of its actions
+    #[derive(Default)]
+    struct FourPlayerGame {
+        turn: usize,
+        sums: [u32; 4],
+    }
+
+    impl GameFactory<FourPlayerGame> for FourPlayerGame {
+        fn new_game(&self) -> FourPlayerGame {
+            FourPlayerGame::default()
+        }
+    }
+
+    impl Game for FourPlayerGame {
+        type State = usize;
+        type Action = u32;
+        type Score = u32;
+
+        fn parse_action(&self, text: &str) -> Option<u32> {
+            Self::parse_from_str(text)
+        }
+
+        fn apply_action(&mut self, action: &Option<u32>) -> anyhow::Result<()> {
+            self.sums[self.turn % 4] += action.unwrap_or_default();
+            self.turn += 1;
+            Ok(())
+        }
+
+        /// Number of the player to play
+        fn get_state(&self) -> usize {
+            self.turn % 4
+        }
+
+        fn get_current_player_number(&self) -> usize {
+            self.turn % 4
+        }
+
+        fn is_finished(&self) -> bool {
+            self.turn == 8
+        }
+
+        fn get_player_score(&self, player_number: u32) -> u32 {
+            self.sums[player_number as usize]
+        }
+    }
+
+    /// Plays its player number
+    struct PlayerNumber;
+
+    impl GameAgent<FourPlayerGame> for PlayerNumber {
+        fn init(&mut self) {}
+
+        fn select_action(&mut self, state: usize, _deadline: std::time::SystemTime) -> u32 {
+            state as u32
+        }
+    }
+
+    #[test]
+    fn test_free_for_all_match() {
+        use crate::tournament_strategy::FreeForAllTournament;
+
+        let Some(dir) = TestDir::new("free_for_all") else {
+            return;
+        };
+        // the external agent always plays 0, which is its player number. Other players are played
+        // in-process, so that they need no cpu of their own
+        let mut agents = vec![Arc::new(Agent::new(
+            "player_0".into(),
+            Some(make_endless_agent(&dir)),
+            None,
+            0,
+            None,
+        ))];
+        agents.extend((1..4).map(|i| Arc::new(Agent::in_process(format!("player_{i}"), i))));
+        let evaluator = Evaluator::new(
+            FourPlayerGame::default(),
+            test_config(),
+            test_constraints().build().unwrap(),
+        )
+        .with_reference_agent("player", || Box::new(PlayerNumber));
+
+        let mut tournament = FreeForAllTournament::new(4);
+        TournamentStrategy::<u32>::add_agents(&mut tournament, agents);
+        let matchups = TournamentStrategy::<u32>::advance_weighted_round(&mut tournament, vec![]);
+        assert_eq!(matchups.len(), 1);
+
+        let result = play_match(&evaluator, matchups[0].clone());
+        assert_eq!(result.errors, "");
+        assert_eq!(result.turns, 8);
+        let scores = result.results.iter().map(|(_, s)| *s).collect::<Vec<_>>();
+        assert_eq!(scores, [0, 2, 4, 6]);
+
+        assert!(tournament
+            .advance_weighted_round(vec![(result.results, result.weight)])
+            .is_empty());
+        let points = TournamentStrategy::<u32>::get_final_scores(&tournament)
+            .into_iter()
+            .map(|(agent, score)| (agent.name.clone(), score.points))
+            .collect::<HashMap<_, _>>();
+        assert_eq!(points["player_3"], 3);
+        assert_eq!(points["player_0"], 0);
+    }
+
+    #[test]
+    fn test_max_message_size() {
+        let Some(dir) = TestDir::new("max_message_size") else {
+            return;
+        };
+        // plays 7, padded with zeros to 6000 bytes. `cat` sends it with a single write
+        std::fs::write(dir.join("action"), format!("{:06000}", 7)).unwrap();
+        let path = dir.script(
+            "agent.sh",
+            "#!/bin/bash\n\
+             exec 3<>/dev/tcp/127.0.0.1/$1\n\
+             while read -r -N 1 _ <&3; do cat \"$(dirname \"$0\")/action\" >&3; done\n",
+        );
+        let agent = Arc::new(Agent::new("verbose".into(), Some(path), None, 1, None));
+        let reference = Arc::new(Agent::in_process("seven".into(), 2));
+        let config = test_config().with_max_message_size(8192);
+        let evaluator = Evaluator::new(SumGameFactory, config, test_constraints().build().unwrap())
+            .with_reference_agent("seven", || Box::new(Seven));
+
+        let result = play_match(&evaluator, vec![agent, reference]);
+        assert_eq!(result.errors, "");
+        let scores = result.results.iter().map(|(_, s)| *s).collect::<Vec<_>>();
+        assert_eq!(scores, [14, 14]);
+    }
+
+    #[test]
+    fn test_penalties() {
+        use crate::configuration::Penalties;
+
+        let Some(dir) = TestDir::new("penalties") else {
+            return;
+        };
+        // answers an invalid action to the first state
+        let path = dir.script(
+            "agent.sh",
+            "#!/bin/bash\n\
+             exec 3<>/dev/tcp/127.0.0.1/$1\n\
+             read -r -N 1 _ <&3; printf x >&3; sleep 5\n",
+        );
+        let agent = Arc::new(Agent::new("cheater".into(), Some(path), None, 1, None));
+        let config = test_config().with_penalties(Penalties {
+            illegal_action: 5,
+            timeout: 2,
+            crash: 1,
+        });
+        let evaluator = Evaluator::new(EndlessGame, config, test_constraints().build().unwrap())
+            .with_scoring(|_: &EndlessGame, context, player| {
+                100 - context.penalties[player as usize]
+            });
+
+        let result = play_match(&evaluator, vec![agent]);
+        assert!(result.errors.contains("cheater not an action"));
+        assert!(matches!(
+            &result.error_details[..],
+            [MatchError::InvalidAction { agent, .. }] if agent == "cheater"
+        ));
+        assert_eq!(result.penalties, [5]);
+        assert_eq!(result.results[0].1, 95);
+    }
+
+    /// A two-player game of 4 turns, always a draw
+    struct DrawGame(usize);
+
+    impl GameFactory<DrawGame> for DrawGame {
+        fn new_game(&self) -> DrawGame {
+            DrawGame(0)
+        }
+    }
+
+    impl Game for DrawGame {
+        type State = u32;
+        type Action = u32;
+        type Score = u32;
+
+        fn parse_action(&self, text: &str) -> Option<u32> {
+            Self::parse_from_str(text)
+        }
+
+        fn apply_action(&mut self, _action: &Option<u32>) -> anyhow::Result<()> {
+            self.0 += 1;
+            Ok(())
+        }
+
+        fn get_state(&self) -> u32 {
+            0
+        }
+
+        fn get_current_player_number(&self) -> usize {
+            self.0 % 2
+        }
+
+        fn is_finished(&self) -> bool {
+            self.0 >= 4
+        }
+
+        fn get_player_score(&self, _player_number: u32) -> u32 {
+            10
+        }
+    }
+
+    /// Always plays 0
+    struct Zero;
+
+    impl<G: Game<State = u32, Action = u32>> GameAgent<G> for Zero {
+        fn init(&mut self) {}
+
+        fn select_action(&mut self, _state: u32, _deadline: std::time::SystemTime) -> u32 {
+            0
+        }
+    }
+
+    #[test]
+    fn test_penalties_deducted() {
+        use crate::configuration::Penalties;
+
+        let Some(dir) = TestDir::new("penalties_deducted") else {
+            return;
+        };
+        // answers an invalid action to the first state
+        let path = dir.script(
+            "agent.sh",
+            "#!/bin/bash\n\
+             exec 3<>/dev/tcp/127.0.0.1/$1\n\
+             read -r -N 1 _ <&3; printf x >&3; sleep 5\n",
+        );
+        let cheater = Arc::new(Agent::new("cheater".into(), Some(path), None, 1, None));
+        let reference = Arc::new(Agent::in_process("zero".into(), 2));
+        let run = |penalties: Penalties, higher_is_better: bool| {
+            let config = test_config().with_penalties(penalties);
+            let evaluator =
+                Evaluator::new(DrawGame(0), config, test_constraints().build().unwrap())
+                    .with_reference_agent("zero", || Box::new(Zero));
+            let settings = MatchSettings {
+                higher_is_better,
+                ..first_match(&evaluator, vec![cheater.clone(), reference.clone()])
+            };
+            let result = play(&evaluator, settings);
+            assert!(result.errors.contains("cheater not an action"));
+            result
+                .results
+                .iter()
+                .map(|(_, score)| *score)
+                .collect::<Vec<_>>()
+        };
+        let penalties = Penalties {
+            illegal_action: 3,
+            ..Penalties::default()
+        };
+
+        assert_eq!(run(Penalties::default(), true), [10, 10]);
+        // the penalized agent ranks below the agent it tied with
+        assert_eq!(run(penalties, true), [7, 10]);
+        assert_eq!(run(penalties, false), [13, 10]);
+    }
+
+    /// A two-player game of 4 turns, where the second player always scores more
+    struct UnfairGame(usize);
+
+    impl GameFactory<UnfairGame> for UnfairGame {
+        fn new_game(&self) -> UnfairGame {
+            UnfairGame(0)
+        }
+    }
+
+    impl Game for UnfairGame {
+        type State = u32;
+        type Action = u32;
+        type Score = u32;
+
+        fn parse_action(&self, text: &str) -> Option<u32> {
+            Self::parse_from_str(text)
+        }
+
+        fn apply_action(&mut self, _action: &Option<u32>) -> anyhow::Result<()> {
+            self.0 += 1;
+            Ok(())
+        }
+
+        fn get_state(&self) -> u32 {
+            0
+        }
+
+        fn get_current_player_number(&self) -> usize {
+            self.0 % 2
+        }
+
+        fn is_finished(&self) -> bool {
+            self.0 >= 4
+        }
+
+        fn get_player_score(&self, player_number: u32) -> u32 {
+            10 * (player_number + 1)
+        }
+    }
+
+    #[test]
+    fn test_eliminated_score() {
+        use crate::configuration::EliminatedScore;
+
+        let Some(dir) = TestDir::new("eliminated_score") else {
+            return;
+        };
+        // answers an invalid action to the first state
+        let path = dir.script(
+            "agent.sh",
+            "#!/bin/bash\n\
+             exec 3<>/dev/tcp/127.0.0.1/$1\n\
+             read -r -N 1 _ <&3; printf x >&3; sleep 5\n",
+        );
+        let honest = Arc::new(Agent::in_process("zero".into(), 1));
+        let cheater = Arc::new(Agent::new("cheater".into(), Some(path), None, 2, None));
+
+        let run = |policy: EliminatedScore, higher_is_better: bool| {
+            let config = test_config().with_eliminated_score(policy);
+            let evaluator =
+                Evaluator::new(UnfairGame(0), config, test_constraints().build().unwrap())
+                    .with_reference_agent("zero", || Box::new(Zero));
+            let settings = MatchSettings {
+                higher_is_better,
+                ..first_match(&evaluator, vec![honest.clone(), cheater.clone()])
+            };
+            let result = play(&evaluator, settings);
+            assert!(result.errors.contains("cheater not an action"));
+            result
+                .results
+                .iter()
+                .map(|(_, score)| *score)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(run(EliminatedScore::Game, true), [10, 20]);
+        assert_eq!(run(EliminatedScore::Minimum, true), [10, 10]);
+        // the worst score is the highest one
+        assert_eq!(run(EliminatedScore::Minimum, false), [10, 20]);
+        assert_eq!(run(EliminatedScore::Fixed(3), true), [10, 3]);
+    }
+
+    #[test]
+    fn test_disqualified_forfeits() {
+        let Some(dir) = TestDir::new("disqualified_forfeits") else {
+            return;
+        };
+        // answers 0 after a second, leaving time to disqualify it during the match
+        let path = dir.script(
+            "agent.sh",
+            "#!/bin/bash\n\
+             echo $$ > \"$(dirname \"$0\")/pid\"\n\
+             exec 3<>/dev/tcp/127.0.0.1/$1\n\
+             while read -r -N 1 _ <&3; do sleep 1; printf 0 >&3; done\n",
+        );
+        let reference = Arc::new(Agent::in_process("zero".into(), 1));
+        let cheater = Arc::new(Agent::new("cheater".into(), Some(path), None, 2, None));
+        let constraints = test_constraints()
+            .with_action_timeout(Duration::from_secs(3))
+            .build()
+            .unwrap();
+        let evaluator = Evaluator::new(UnfairGame(0), test_config(), constraints)
+            .with_reference_agent("zero", || Box::new(Zero));
+
+        let (tx_result, rx_result) = mpsc::channel();
+        let running = Arc::new(Mutex::new(vec![]));
+        let mut threads = MatchThreads::default();
+        let settings = first_match(&evaluator, vec![reference, cheater]);
+        evaluator.launch_match(settings, tx_result, &running, &mut threads);
+
+        let pid_file = dir.join("pid");
+        let deadline = std::time::Instant::now() + Duration::from_secs(5);
+        while !pid_file.exists() && std::time::Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        evaluator.control().disqualify("cheater");
+        let result = rx_result
+            .recv_timeout(Duration::from_secs(10))
+            .expect("match did not end");
+        assert!(
+            matches!(
+                &result.error_details[..],
+                [MatchError::Disqualified { agent, turn: Some(_) }] if agent == "cheater"
+            ),
+            "{}",
+            result.errors
+        );
+        // the game scores the second player higher, but it forfeited
+        let scores = result.results.iter().map(|(_, s)| *s).collect::<Vec<_>>();
+        assert_eq!(scores, [10, 10]);
+    }
+
+    #[test]
+    fn test_end_on_elimination_forfeit() {
+        let Some(dir) = TestDir::new("end_on_elimination_forfeit") else {
+            return;
+        };
+        let survivor = Arc::new(Agent::new(
+            "survivor".into(),
+            Some(make_endless_agent(&dir)),
+            None,
+            1,
+            None,
+        ));
+        // never launched: only the survivor needs resources
+        let eliminated = Arc::new(Agent::new("eliminated".into(), None, None, 2, None));
+        let run = |higher_is_better: bool| {
+            let config = test_config().with_end_on_elimination(true);
+            let evaluator =
+                Evaluator::new(UnfairGame(0), config, test_constraints().build().unwrap());
+            evaluator.control().disqualify("eliminated");
+            let settings = MatchSettings {
+                higher_is_better,
+                ..first_match(&evaluator, vec![survivor.clone(), eliminated.clone()])
+            };
+            let result = play(&evaluator, settings);
+            assert_eq!(result.turns, 2, "{}", result.errors);
+            result
+                .results
+                .iter()
+                .map(|(_, score)| *score)
+                .collect::<Vec<_>>()
+        };
+
+        // the game scores the eliminated player higher: [10, 20]
+        assert_eq!(run(true), [11, 10]);
+        // the survivor already has the best score
+        assert_eq!(run(false), [10, 20]);
+    }
+}
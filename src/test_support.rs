@@ -0,0 +1,247 @@
+//! Helpers to unit-test a custom [`TournamentStrategy`] without compiling or running agents.
+//!
+//! [`make_agents`] creates stand-in agents, which are never launched. A [`ScriptedRunner`] then
+//! drives the strategy through its rounds, as the evaluator would: the results of each match are
+//! given by a closure instead of being played, so the whole tournament is deterministic and runs
+//! instantly.
+//!
+//! # Example
+//!
+//! ```
+//! use ai_tournament::test_support::{make_agents, names, ScriptedRunner};
+//! use ai_tournament::tournament_strategy::SwissTournament;
+//!
+//! let agents = make_agents(4);
+//! // the player with the highest id wins
+//! let mut runner = ScriptedRunner::new(SwissTournament::new(2, 1), agents, |players| {
+//!     players.iter().map(|agent| agent.id).collect::<Vec<u32>>()
+//! });
+//!
+//! assert_eq!(
+//!     names(runner.matchups()),
+//!     [["agent_2", "agent_3"], ["agent_0", "agent_1"]]
+//! );
+//! // second round: winners meet
+//! let second_round = runner.play_round();
+//! assert!(names(second_round)
+//!     .iter()
+//!     .any(|players| players.contains(&"agent_1") && players.contains(&"agent_3")));
+//!
+//! assert_eq!(runner.run(10), 1);
+//! assert_eq!(runner.final_scores()["agent_3"].num_win, 2);
+//! ```
+//!
+//! Scores can also be scripted match by match, e.g. by taking them from a list in the closure.
+//! Like the evaluator, the runner plays each match once per rotation of its players when the
+//! strategy declares [roles](TournamentStrategy::roles), and gives back the
+//! [weight](TournamentStrategy::match_weight) of each match with its result.
+
+use std::{collections::HashMap, sync::Arc};
+
+use crate::{
+    agent::Agent, match_runner::MatchResult, tournament_scheduler::with_roles,
+    tournament_strategy::TournamentStrategy,
+};
+
+/// `n` stand-in agents named `agent_0`, `agent_1`... with ids `0`, `1`...
+pub fn make_agents(n: u32) -> Vec<Arc<Agent>> {
+    (0..n)
+        .map(|i| make_agent(&format!("agent_{i}"), i))
+        .collect()
+}
+
+/// A stand-in agent, which is never launched. Agents of a tournament must have distinct ids.
+pub fn make_agent(name: &str, id: u32) -> Arc<Agent> {
+    Arc::new(Agent::new(name.to_string(), None, None, id, None))
+}
+
+/// Names of the players of each match, in play order, to assert on pairings.
+pub fn names(matchups: &[Vec<Arc<Agent>>]) -> Vec<Vec<&str>> {
+    matchups
+        .iter()
+        .map(|players| players.iter().map(|agent| agent.name.as_str()).collect())
+        .collect()
+}
+
+/// Scores of the players of a match, in play order
+type PlayFn<S> = Box<dyn FnMut(&[Arc<Agent>]) -> Vec<S>>;
+
+/// Drives a strategy through its rounds with scripted results (see
+/// [module documentation](self)).
+pub struct ScriptedRunner<T, S> {
+    strategy: T,
+    play: PlayFn<S>,
+    matchups: Vec<Vec<Arc<Agent>>>,
+    rounds: Vec<Vec<Vec<Arc<Agent>>>>,
+}
+
+impl<S: PartialOrd, T: TournamentStrategy<S>> ScriptedRunner<T, S> {
+    /// Adds `agents` to `strategy` and generates the first round.
+    ///
+    /// `play` is called for each match, with its players in play order, and returns their scores
+    /// in the same order.
+    pub fn new(
+        mut strategy: T,
+        agents: Vec<Arc<Agent>>,
+        play: impl FnMut(&[Arc<Agent>]) -> Vec<S> + 'static,
+    ) -> Self {
+        strategy.add_agents(agents);
+        let matchups = strategy.advance_weighted_round(vec![]);
+        let matchups = with_roles(&strategy, matchups);
+        Self {
+            strategy,
+            play: Box::new(play),
+            matchups,
+            rounds: vec![],
+        }
+    }
+
+    /// Matches of the current round, empty once the tournament is finished.
+    pub fn matchups(&self) -> &[Vec<Arc<Agent>>] {
+        &self.matchups
+    }
+
+    /// Matches of the rounds played so far.
+    pub fn rounds(&self) -> &[Vec<Vec<Arc<Agent>>>] {
+        &self.rounds
+    }
+
+    /// The tournament is finished: the strategy returned no match.
+    pub fn is_finished(&self) -> bool {
+        self.matchups.is_empty()
+    }
+
+    /// Plays the matches of the current round, gives their results to the strategy, and returns
+    /// the matches of the next round.
+    ///
+    /// # Panics
+    ///
+    /// If a match does not have [`players_per_match`](TournamentStrategy::players_per_match)
+    /// players, or if `play` does not return one score per player.
+    pub fn play_round(&mut self) -> &[Vec<Arc<Agent>>] {
+        let players_per_match = self.strategy.players_per_match();
+        let matchups = std::mem::take(&mut self.matchups);
+        let results = matchups
+            .iter()
+            .map(|players| {
+                assert_eq!(
+                    players.len(),
+                    players_per_match,
+                    "match with {} players instead of {players_per_match}",
+                    players.len()
+                );
+                let scores = (self.play)(players);
+                assert_eq!(scores.len(), players.len(), "not one score per player");
+                let weight = self.strategy.match_weight(players);
+                let result: MatchResult<S> = players.iter().cloned().zip(scores).collect();
+                (result, weight)
+            })
+            .collect();
+        self.rounds.push(matchups);
+        let matchups = self.strategy.advance_weighted_round(results);
+        self.matchups = with_roles(&self.strategy, matchups);
+        &self.matchups
+    }
+
+    /// Plays rounds until the tournament is finished, and returns the number of rounds played by
+    /// this call.
+    ///
+    /// # Panics
+    ///
+    /// If the tournament is not finished after `max_rounds` rounds, e.g. because the strategy
+    /// never stops generating matches.
+    pub fn run(&mut self, max_rounds: usize) -> usize {
+        let mut played = 0;
+        while !self.is_finished() {
+            assert!(
+                played < max_rounds,
+                "tournament not finished after {max_rounds} rounds"
+            );
+            self.play_round();
+            played += 1;
+        }
+        played
+    }
+
+    /// The strategy, e.g. to inspect its state between rounds.
+    pub fn strategy(&self) -> &T {
+        &self.strategy
+    }
+
+    /// Final score of each agent, by agent name.
+    pub fn final_scores(&self) -> HashMap<String, T::FinalScore> {
+        self.strategy
+            .get_final_scores()
+            .into_iter()
+            .map(|(agent, score)| (agent.name.clone(), score))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test_support_tests {
+    use std::collections::VecDeque;
+
+    use super::*;
+    use crate::tournament_strategy::{FreeForAllTournament, RoundRobinTournament, SwissTournament};
+
+    #[test]
+    fn test_scripted_results() {
+        // scores of each match, in the order matches are played
+        let mut script = VecDeque::from([[1, 0], [0, 1], [0, 1], [2, 2]]);
+        let mut runner = ScriptedRunner::new(SwissTournament::new(2, 1), make_agents(4), {
+            move |_: &[Arc<Agent>]| script.pop_front().unwrap().to_vec()
+        });
+        assert_eq!(
+            names(runner.matchups()),
+            [["agent_2", "agent_3"], ["agent_0", "agent_1"]]
+        );
+        let second_round = names(runner.play_round());
+        assert_eq!(second_round.len(), 2);
+        // winners of the first round meet
+        assert!(second_round.contains(&vec!["agent_1", "agent_2"]));
+        assert_eq!(runner.run(10), 1);
+        assert_eq!(runner.rounds().len(), 2);
+
+        let scores = runner.final_scores();
+        assert_eq!(scores["agent_0"].num_lose, 2);
+        assert_eq!(scores["agent_1"].num_win, 1);
+        assert_eq!(scores["agent_1"].num_draw, 1);
+        assert_eq!(scores["agent_3"].num_win, 1);
+    }
+
+    #[test]
+    fn test_weights_are_given_back() {
+        let tournament = FreeForAllTournament::new(2).with_match_weight(|players| {
+            if players[0].id == 0 {
+                2.0
+            } else {
+                1.0
+            }
+        });
+        let mut runner = ScriptedRunner::new(tournament, make_agents(3), |players| {
+            players.iter().map(|agent| agent.id).collect::<Vec<_>>()
+        });
+        assert_eq!(
+            names(runner.matchups()),
+            [
+                ["agent_0", "agent_1"],
+                ["agent_0", "agent_2"],
+                ["agent_1", "agent_2"]
+            ]
+        );
+        assert_eq!(runner.run(1), 1);
+        let scores = runner.final_scores();
+        assert_eq!(scores["agent_1"].points, 2);
+        assert_eq!(scores["agent_2"].points, 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "not finished after 0 rounds")]
+    fn test_max_rounds() {
+        let mut runner = ScriptedRunner::new(RoundRobinTournament::new(false), make_agents(2), {
+            |players: &[Arc<Agent>]| vec![0; players.len()]
+        });
+        runner.run(0);
+    }
+}
@@ -0,0 +1,164 @@
+//! Handle to control running tournaments from another thread, and the callbacks given to the
+//! [`Evaluator`](crate::server::Evaluator) that matches run with.
+
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use tracing::{info, warn};
+
+use crate::constraints::ResourceCap;
+use crate::game_interface::{Agent as GameAgent, Game, MatchContext};
+
+/// Scoring function overriding the scores given by the game, see
+/// [`Evaluator::with_scoring`](crate::server::Evaluator::with_scoring).
+pub type ScoringFn<G> = Arc<dyn Fn(&G, &MatchContext, u32) -> <G as Game>::Score + Send + Sync>;
+
+/// Creates the in-process reference agent of a match, see
+/// [`Evaluator::with_reference_agent`](crate::server::Evaluator::with_reference_agent).
+pub type ReferenceFactory<G> = Arc<dyn Fn() -> Box<dyn GameAgent<G>> + Send + Sync>;
+
+/// Handle to control a running tournament from another thread, obtained with
+/// [`Evaluator::control`](crate::server::Evaluator::control).
+///
+/// The handle is shared by all the tournaments run by the same
+/// [`Evaluator`](crate::server::Evaluator): when several
+/// tournaments run at the same time, [`progress`](Self::progress) reports the last one updated.
+#[derive(Debug, Clone, Default)]
+pub struct TournamentControl {
+    disqualified: Arc<Mutex<HashSet<String>>>,
+    late_agents: Arc<Mutex<Vec<PathBuf>>>,
+    /// Number of tournaments running
+    running: Arc<AtomicUsize>,
+    progress: Arc<Mutex<Progress>>,
+    resource_cap: Arc<Mutex<Option<ResourceCap>>>,
+}
+
+/// Progress of a running tournament, see [`TournamentControl::progress`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Progress {
+    /// Number of matches finished (replayed attempts are counted once).
+    pub matches_done: usize,
+    /// Number of matches known so far: finished ones, plus running and pending ones of the current
+    /// round. Strategies generate rounds one at a time, so this grows with each round.
+    pub matches_total_estimate: usize,
+    /// Current round (starting at 1). 0 before the first round.
+    pub round: usize,
+}
+
+/// Counts the tournament as running while alive
+pub(crate) struct RunningGuard<'a>(&'a TournamentControl);
+
+impl<'a> RunningGuard<'a> {
+    pub(crate) fn new(control: &'a TournamentControl) -> Self {
+        control.running.fetch_add(1, Ordering::Relaxed);
+        *control.progress.lock().unwrap_or_else(|e| e.into_inner()) = Progress::default();
+        RunningGuard(control)
+    }
+}
+
+impl Drop for RunningGuard<'_> {
+    fn drop(&mut self) {
+        self.0.running.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+impl TournamentControl {
+    /// Disqualifies the agent named `agent_name` (see the [module documentation](crate::server#disqualification)).
+    ///
+    /// Takes effect at the next turn of its running matches.
+    pub fn disqualify(&self, agent_name: impl Into<String>) {
+        let agent_name = agent_name.into();
+        warn!("Agent {agent_name} disqualified");
+        self.disqualified
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(agent_name);
+    }
+
+    /// Adds the agent in `agent_dir` (a single agent directory, e.g. `agents/late_bot`) to the
+    /// running tournament (see the [module documentation](crate::server#late-agents)).
+    ///
+    /// Agents added after the tournament finished are ignored.
+    pub fn add_late_agent(&self, agent_dir: impl Into<PathBuf>) {
+        self.late_agents
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(agent_dir.into());
+    }
+
+    pub(crate) fn take_late_agents(&self) -> Vec<PathBuf> {
+        std::mem::take(&mut *self.late_agents.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+
+    /// Returns true while a tournament is running (agents being collected, or matches played),
+    /// until all the tournaments sharing this handle finished.
+    pub fn is_running(&self) -> bool {
+        self.running.load(Ordering::Relaxed) > 0
+    }
+
+    /// Progress of the running tournament, or of the last one once it finished.
+    pub fn progress(&self) -> Progress {
+        *self.progress.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub(crate) fn set_progress(&self, progress: Progress) {
+        *self.progress.lock().unwrap_or_else(|e| e.into_inner()) = progress;
+    }
+
+    /// Caps the CPUs and RAM used by the matches of the next rounds, e.g. when the share of a
+    /// cluster allotted to the tournament shrinks (see the [module
+    /// documentation](crate::server#staged-resources)). `None` removes the cap.
+    pub fn set_resource_cap(&self, cap: Option<ResourceCap>) {
+        info!("Resource cap set to {cap:?}");
+        *self.resource_cap.lock().unwrap_or_else(|e| e.into_inner()) = cap;
+    }
+
+    /// The cap set with [`Self::set_resource_cap`].
+    pub fn resource_cap(&self) -> Option<ResourceCap> {
+        *self.resource_cap.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Returns true if the agent named `agent_name` was disqualified.
+    pub fn is_disqualified(&self, agent_name: &str) -> bool {
+        self.disqualified
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .contains(agent_name)
+    }
+}
+
+#[cfg(test)]
+mod tournament_control_tests {
+    use super::*;
+
+    #[test]
+    fn test_progress() {
+        // evaluations install a panic hook exiting the process: only the control is tested here
+        let control = TournamentControl::default();
+        assert!(!control.is_running());
+        let progress = Progress {
+            matches_done: 3,
+            matches_total_estimate: 8,
+            round: 2,
+        };
+        {
+            let _running = RunningGuard::new(&control);
+            assert!(control.clone().is_running());
+            assert_eq!(control.progress(), Progress::default());
+            control.set_progress(progress);
+        }
+        assert!(!control.is_running());
+        // the progress of the last tournament is kept
+        assert_eq!(control.progress(), progress);
+
+        // the handle is shared by overlapping tournaments
+        let first = RunningGuard::new(&control);
+        let second = RunningGuard::new(&control);
+        drop(first);
+        assert!(control.is_running());
+        drop(second);
+        assert!(!control.is_running());
+    }
+}
@@ -1,10 +1,11 @@
-use tracing::trace;
+use tracing::{error, trace, warn};
 
 use crate::agent::Agent;
-use crate::constraints::Constraints;
+use crate::constraints::{Constraints, ResourceCap};
 use crate::match_runner::{MatchResult, MatchSettings, RunnerResult};
+use crate::rng::SplitMix64;
 use crate::tournament_strategy::TournamentStrategy;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::mem;
 use std::sync::Arc;
 
@@ -13,9 +14,27 @@ where
     S: PartialOrd,
 {
     // pub agents: Vec<Arc<Agent>>,
-    scores: Vec<MatchResult<S>>,
+    /// Results of the current round, with their weight
+    scores: Vec<(MatchResult<S>, f64)>,
+    /// Resources not used by running matches
     resources: Constraints,
-    pending_matches: Vec<Vec<Arc<Agent>>>,
+    /// All the resources given to the scheduler, before the resource cap
+    pool: Constraints,
+    resource_cap: Option<ResourceCap>,
+    pending_matches: Vec<(usize, Vec<Arc<Agent>>)>,
+    /// Weight of each match of the current round, by index
+    weights: Vec<f64>,
+    round: usize,
+    shuffle_seed: Option<u64>,
+    max_retries: usize,
+    /// Maximum number of matches running at the same time, on top of the resources
+    max_concurrent_matches: Option<usize>,
+    /// Number of retries of each match of the current round, by index
+    retries: HashMap<usize, usize>,
+    /// Agents to add to the strategy before the next round
+    late_agents: Vec<Arc<Agent>>,
+    /// Limits of the matches from a given round onwards, see [`Self::with_round_constraints`]
+    round_constraints: BTreeMap<usize, Constraints>,
     strategy: T,
     running_matches: usize,
     is_finished: bool,
@@ -30,21 +49,132 @@ impl<T: TournamentStrategy<S>, S: PartialOrd> TournamentScheduler<T, S> {
         TournamentScheduler {
             // agents,
             scores: vec![],
+            pool: resources.clone(),
             resources,
+            resource_cap: None,
             pending_matches: vec![],
+            weights: vec![],
+            round: 0,
+            shuffle_seed: None,
+            max_retries: 0,
+            max_concurrent_matches: None,
+            retries: HashMap::new(),
+            late_agents: vec![],
+            round_constraints: BTreeMap::new(),
             running_matches: 0,
             strategy,
             is_finished: false,
         }
     }
 
+    /// Shuffle the matches of each round with a seeded generator before scheduling them
+    pub fn with_shuffle_seed(mut self, seed: Option<u64>) -> Self {
+        self.shuffle_seed = seed;
+        self
+    }
+
+    /// Replay matches failing for transient reasons up to `retries` times
+    pub fn with_match_retries(mut self, retries: usize) -> Self {
+        self.max_retries = retries;
+        self
+    }
+
+    /// Runs at most `max` matches at the same time (at least one), even if resources remain
+    pub fn with_max_concurrent_matches(mut self, max: Option<usize>) -> Self {
+        self.max_concurrent_matches = max.map(|max| max.max(1));
+        self
+    }
+
+    /// Uses the limits of `round_constraints` for the matches of the rounds from their key
+    /// onwards, instead of the limits of the pool.
+    ///
+    /// Only per-agent limits are taken from them (RAM and CPUs per agent, timeouts...): the RAM and
+    /// CPUs of each match are still taken from the pool given to [`Self::new`], and given back when
+    /// it ends. A round with larger agents runs fewer matches at the same time.
+    pub fn with_round_constraints(
+        mut self,
+        round_constraints: BTreeMap<usize, Constraints>,
+    ) -> Self {
+        self.round_constraints = round_constraints;
+        self
+    }
+
+    /// Caps the resources used by the matches of the next rounds (`None` to use the whole pool).
+    ///
+    /// The cap is applied when the next round is generated: no match runs at that time, so all
+    /// resources are back in the pool, which is then shrunk (or grown back, up to the pool given
+    /// to [`Self::new`]) to the cap. Matches of the current round keep using the previous pool
+    /// until the round ends. A cap too small for a single match is raised to the resources of one
+    /// match, so that the tournament still progresses.
+    pub fn set_resource_cap(&mut self, cap: Option<ResourceCap>) {
+        self.resource_cap = cap;
+    }
+
+    /// Restricts the pool to the resource cap. Only called between rounds, when no match runs
+    fn apply_resource_cap(&mut self) {
+        let mut resources = self.pool.clone();
+        if let Some(cap) = self.resource_cap {
+            let limits = self.round_limits();
+            let players = self.strategy.players_per_match();
+            let cpus = cap.cpus.max(limits.cpus_per_agent * players);
+            let ram = cap.ram.max(limits.agent_ram * players);
+            if (cpus, ram) != (cap.cpus, cap.ram) {
+                warn!(
+                    "resource cap ({} CPUs, {}MB) too small for a match of round {}: raised to {cpus} CPUs, {}MB",
+                    cap.cpus,
+                    cap.ram / 1_000_000,
+                    self.round,
+                    ram / 1_000_000
+                );
+            }
+            let mut kept = resources.cpus.iter().copied().collect::<Vec<_>>();
+            kept.sort_unstable();
+            kept.truncate(cpus);
+            resources.cpus = kept.into_iter().collect();
+            resources.total_ram = resources.total_ram.min(ram);
+        }
+        self.resources = resources;
+    }
+
+    /// Limits of the matches of the current round
+    fn round_limits(&self) -> &Constraints {
+        self.round_constraints
+            .range(..=self.round)
+            .next_back()
+            .map_or(&self.resources, |(_, limits)| limits)
+    }
+
     pub fn advance(&mut self) -> Vec<MatchSettings> {
         let mut matches_to_run = vec![];
 
         // Generate new round if needed
         if self.running_matches == 0 && self.pending_matches.is_empty() && !self.is_finished {
             trace!("next round");
-            self.pending_matches = self.strategy.advance_round(mem::take(&mut self.scores));
+            self.round += 1;
+            self.retries.clear();
+            self.apply_resource_cap();
+            for agent in self.late_agents.drain(..) {
+                if !self.strategy.add_late_agent(agent.clone()) {
+                    warn!(
+                        "{} not added: strategy does not accept late agents",
+                        agent.name
+                    );
+                }
+            }
+            let matches = self
+                .strategy
+                .advance_weighted_round(mem::take(&mut self.scores));
+            let matches = with_roles(&self.strategy, self.valid_matches(matches));
+            self.weights = matches
+                .iter()
+                .map(|players| self.strategy.match_weight(players))
+                .collect();
+            self.pending_matches = matches.into_iter().enumerate().collect();
+            if let Some(seed) = self.shuffle_seed {
+                // one generator per round: the order of a round does not depend on previous ones
+                SplitMix64::new(seed.wrapping_add(self.round as u64))
+                    .shuffle(&mut self.pending_matches);
+            }
 
             if self.pending_matches.is_empty() {
                 // no more matches from `strategy`
@@ -53,18 +183,32 @@ impl<T: TournamentStrategy<S>, S: PartialOrd> TournamentScheduler<T, S> {
             }
         }
 
-        let cpu_per_match = self.resources.cpus_per_agent * self.strategy.players_per_match(); //FIXME: can be computed for each match
-        let ram_per_match = self.resources.agent_ram * self.strategy.players_per_match();
+        let limits = self.round_limits().clone();
+        let cpu_per_match = limits.cpus_per_agent * self.strategy.players_per_match(); //FIXME: can be computed for each match
+        let ram_per_match = limits.agent_ram * self.strategy.players_per_match();
         // Schedule as many pending matches as long as there is enough resources
         let mut remaining = vec![];
-        for v in self.pending_matches.drain(..) {
-            if let Some(resources) = self.resources.try_take(cpu_per_match, ram_per_match) {
+        for (index, v) in self.pending_matches.drain(..) {
+            let at_cap = self
+                .max_concurrent_matches
+                .is_some_and(|max| self.running_matches + matches_to_run.len() >= max);
+            if at_cap {
+                remaining.push((index, v));
+            } else if let Some(taken) = self.resources.try_take(cpu_per_match, ram_per_match) {
                 matches_to_run.push(MatchSettings {
                     ordered_player: v,
-                    resources,
+                    resources: Constraints {
+                        total_ram: taken.total_ram,
+                        cpus: taken.cpus,
+                        ..limits.clone()
+                    },
+                    round: self.round,
+                    index,
+                    weight: self.weights[index],
+                    higher_is_better: self.strategy.higher_is_better(),
                 });
             } else {
-                remaining.push(v);
+                remaining.push((index, v));
             }
         }
         self.pending_matches = remaining;
@@ -72,8 +216,57 @@ impl<T: TournamentStrategy<S>, S: PartialOrd> TournamentScheduler<T, S> {
         matches_to_run
     }
 
+    /// The match of `result` failed for transient reasons and will be replayed by
+    /// [`Self::on_result`] instead of being recorded
+    pub fn should_retry(&self, result: &RunnerResult<S>) -> bool {
+        result.transient && self.retries.get(&result.index).copied().unwrap_or(0) < self.max_retries
+    }
+
+    /// Adds an agent to the strategy before the next round. Ignored once the tournament is finished
+    pub fn add_late_agent(&mut self, agent: Arc<Agent>) {
+        self.late_agents.push(agent);
+    }
+
+    /// Matches of `matches` that can be played (see [invalid
+    /// matches](crate::tournament_strategy#invalid-matches)): panics on the others in debug builds,
+    /// skips them in release builds
+    fn valid_matches(&self, matches: Vec<Vec<Arc<Agent>>>) -> Vec<Vec<Arc<Agent>>> {
+        let players_per_match = self.strategy.players_per_match();
+        matches
+            .into_iter()
+            .filter(|players| {
+                debug_assert_eq!(
+                    players.len(),
+                    players_per_match,
+                    "not {players_per_match} players match ??"
+                );
+                if players.len() != players_per_match {
+                    let names = players
+                        .iter()
+                        .map(|agent| agent.name.as_str())
+                        .collect::<Vec<_>>()
+                        .join(", ");
+                    error!(
+                        "match skipped: {} players instead of {players_per_match} ({names})",
+                        players.len(),
+                    );
+                }
+                players.len() == players_per_match
+            })
+            .collect()
+    }
+
     pub fn on_result(&mut self, result: RunnerResult<S>) -> Vec<MatchSettings> {
-        self.scores.push(result.results);
+        if self.should_retry(&result) {
+            trace!("retrying match {} of round {}", result.index, result.round);
+            *self.retries.entry(result.index).or_default() += 1;
+            let players = result.results.into_iter().map(|(agent, _)| agent).collect();
+            self.pending_matches.push((result.index, players));
+            self.resources.add(result.resources_freed);
+            self.running_matches -= 1;
+            return self.advance();
+        }
+        self.scores.push((result.results, result.weight));
         self.resources.add(result.resources_freed);
         self.running_matches -= 1;
         self.advance()
@@ -87,4 +280,421 @@ impl<T: TournamentStrategy<S>, S: PartialOrd> TournamentScheduler<T, S> {
     pub fn final_scores(&self) -> HashMap<Arc<Agent>, T::FinalScore> {
         self.strategy.get_final_scores()
     }
+
+    /// Current round (starting at 1 once the first round was generated)
+    pub fn round(&self) -> usize {
+        self.round
+    }
+
+    /// Number of matches of the current round not finished yet (running or pending)
+    pub fn remaining_matches(&self) -> usize {
+        self.pending_matches.len() + self.running_matches
+    }
+
+    /// Number of rounds in which matches were scheduled
+    pub fn rounds_played(&self) -> usize {
+        if self.is_finished {
+            // the last round generated no match
+            self.round.saturating_sub(1)
+        } else {
+            self.round
+        }
+    }
+
+    pub fn num_byes(&self) -> usize {
+        self.strategy.num_byes()
+    }
+
+    pub fn higher_is_better(&self) -> bool {
+        self.strategy.higher_is_better()
+    }
+}
+
+/// Plays each match once per rotation of its players when the strategy declares roles
+pub(crate) fn with_roles<S: PartialOrd, T: TournamentStrategy<S>>(
+    strategy: &T,
+    matches: Vec<Vec<Arc<Agent>>>,
+) -> Vec<Vec<Arc<Agent>>> {
+    let roles = strategy.roles();
+    if roles.is_empty() {
+        return matches;
+    }
+    if roles.len() != strategy.players_per_match() {
+        error!(
+            "{} roles declared for {} players per match: roles are ignored",
+            roles.len(),
+            strategy.players_per_match()
+        );
+        return matches;
+    }
+    matches
+        .into_iter()
+        .flat_map(|players| {
+            (0..players.len()).map(move |shift| {
+                let mut rotated = players.clone();
+                rotated.rotate_left(shift);
+                rotated
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tournament_scheduler_tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::constraints::ConstraintsBuilder;
+    use crate::tournament_strategy::RoundRobinTournament;
+
+    /// Result of a match without errors, each player scoring `score(player)`
+    fn fake_result<S: PartialOrd>(
+        settings: MatchSettings,
+        score: impl Fn(&Agent) -> S,
+    ) -> RunnerResult<S> {
+        RunnerResult {
+            results: settings
+                .ordered_player
+                .iter()
+                .map(|a| (a.clone(), score(a)))
+                .collect(),
+            resources_freed: settings.resources,
+            errors: String::new(),
+            error_details: vec![],
+            peak_memory: vec![],
+            round: settings.round,
+            index: settings.index,
+            startup_durations: vec![],
+            transient: false,
+            penalties: vec![],
+            turns: 0,
+            timings: vec![],
+            duration: Duration::ZERO,
+            weight: settings.weight,
+            seed: None,
+        }
+    }
+
+    /// Plays the matches launched by `scheduler`, the last launched first, until none is left.
+    /// `play` gets the settings of each match and the number of other matches still running, and
+    /// returns its result.
+    fn drain<T: TournamentStrategy<S>, S: PartialOrd>(
+        scheduler: &mut TournamentScheduler<T, S>,
+        mut play: impl FnMut(&mut TournamentScheduler<T, S>, MatchSettings, usize) -> RunnerResult<S>,
+    ) {
+        let mut running = scheduler.advance();
+        while let Some(settings) = running.pop() {
+            let result = play(scheduler, settings, running.len());
+            running.extend(scheduler.on_result(result));
+        }
+    }
+
+    fn scheduled_order(seed: Option<u64>) -> Vec<usize> {
+        let agents = (0..6)
+            .map(|i| Arc::new(Agent::new(format!("agent_{i}"), None, None, i, None)))
+            .collect();
+        let mut strategy = RoundRobinTournament::new(false);
+        TournamentStrategy::<u32>::add_agents(&mut strategy, agents);
+        let resources = ConstraintsBuilder::new()
+            .with_total_cpu_count(8)
+            .with_max_total_ram(8)
+            .with_ram_per_agent(1)
+            .build()
+            .unwrap();
+        let mut scheduler =
+            TournamentScheduler::<_, u32>::new(resources, strategy).with_shuffle_seed(seed);
+
+        let mut order = vec![];
+        drain(&mut scheduler, |_, settings, _| {
+            order.push(settings.index);
+            fake_result(settings, |_| 0)
+        });
+        order
+    }
+
+    #[test]
+    fn test_shuffle_is_reproducible() {
+        let unshuffled = scheduled_order(None);
+        let shuffled = scheduled_order(Some(42));
+
+        assert_eq!(shuffled, scheduled_order(Some(42)));
+        assert_ne!(shuffled, unshuffled);
+        assert_ne!(shuffled, scheduled_order(Some(43)));
+
+        let mut sorted = shuffled.clone();
+        sorted.sort();
+        let mut expected = unshuffled.clone();
+        expected.sort();
+        assert_eq!(sorted, expected);
+    }
+
+    /// Runs a whole round-robin, the first `failures` attempts of match 0 failing transiently.
+    /// Returns the number of attempts of each match, and the number of recorded matches.
+    fn run_with_failures(retries: usize, failures: usize) -> (HashMap<usize, usize>, u32) {
+        let agents = (0..3)
+            .map(|i| Arc::new(Agent::new(format!("agent_{i}"), None, None, i, None)))
+            .collect();
+        let mut strategy = RoundRobinTournament::new(true);
+        TournamentStrategy::<u32>::add_agents(&mut strategy, agents);
+        let resources = ConstraintsBuilder::new()
+            .with_total_cpu_count(2)
+            .with_max_total_ram(2)
+            .with_ram_per_agent(1)
+            .build()
+            .unwrap();
+        let mut scheduler =
+            TournamentScheduler::<_, u32>::new(resources, strategy).with_match_retries(retries);
+
+        let mut attempts: HashMap<usize, usize> = HashMap::new();
+        drain(&mut scheduler, |_, settings, _| {
+            let attempt = attempts.entry(settings.index).or_default();
+            *attempt += 1;
+            let transient = settings.index == 0 && *attempt <= failures;
+            RunnerResult {
+                transient,
+                ..fake_result(settings, |a| a.id)
+            }
+        });
+        assert!(scheduler.is_finished());
+
+        let recorded = scheduler
+            .final_scores()
+            .values()
+            .map(|s| s.num_win + s.num_draw + s.num_lose)
+            .sum::<u32>()
+            / 2;
+        (attempts, recorded)
+    }
+
+    #[test]
+    fn test_transient_failures_are_retried() {
+        // 3 agents, symmetric: 3 matches + 3 self-matches
+        let (attempts, recorded) = run_with_failures(0, 0);
+        assert_eq!(attempts.len(), 6);
+        assert!(attempts.values().all(|&n| n == 1));
+        assert_eq!(recorded, 6);
+
+        let (attempts, recorded) = run_with_failures(3, 2);
+        assert_eq!(attempts[&0], 3);
+        assert_eq!(attempts.values().sum::<usize>(), 8);
+        assert_eq!(recorded, 6);
+
+        // retries exhausted: the failure is recorded
+        let (attempts, recorded) = run_with_failures(1, 2);
+        assert_eq!(attempts[&0], 2);
+        assert_eq!(recorded, 6);
+    }
+
+    /// Single round where every agent meets every other agent once, in an asymmetric game
+    struct RoleStrategy {
+        agents: Vec<Arc<Agent>>,
+        played: Vec<Vec<String>>,
+    }
+
+    impl TournamentStrategy<u32> for RoleStrategy {
+        fn advance_round(&mut self, scores: Vec<MatchResult<u32>>) -> Vec<Vec<Arc<Agent>>> {
+            if !scores.is_empty() {
+                for result in scores {
+                    self.played
+                        .push(result.iter().map(|(a, _)| a.name.clone()).collect());
+                }
+                return vec![];
+            }
+            let mut matches = vec![];
+            for (i, a) in self.agents.iter().enumerate() {
+                for b in &self.agents[i + 1..] {
+                    matches.push(vec![a.clone(), b.clone()]);
+                }
+            }
+            matches
+        }
+
+        fn players_per_match(&self) -> usize {
+            2
+        }
+
+        fn roles(&self) -> Vec<String> {
+            vec!["attacker".into(), "defender".into()]
+        }
+
+        fn add_agents(&mut self, agents: Vec<Arc<Agent>>) {
+            self.agents = agents;
+        }
+
+        type FinalScore = u32;
+
+        fn get_final_scores(&self) -> HashMap<Arc<Agent>, u32> {
+            HashMap::new()
+        }
+    }
+
+    #[test]
+    fn test_roles_are_swapped() {
+        let agents = (0..3)
+            .map(|i| Arc::new(Agent::new(format!("agent_{i}"), None, None, i, None)))
+            .collect();
+        let strategy = RoleStrategy {
+            agents,
+            played: vec![],
+        };
+        let resources = ConstraintsBuilder::new()
+            .with_total_cpu_count(8)
+            .with_max_total_ram(8)
+            .with_ram_per_agent(1)
+            .build()
+            .unwrap();
+        let mut scheduler = TournamentScheduler::<_, u32>::new(resources, strategy);
+
+        let mut first = true;
+        drain(&mut scheduler, |scheduler, settings, _| {
+            if first {
+                assert_eq!(scheduler.remaining_matches(), 6);
+                first = false;
+            }
+            fake_result(settings, |_| 0)
+        });
+        assert!(scheduler.is_finished());
+        assert_eq!(scheduler.remaining_matches(), 0);
+        assert_eq!(scheduler.rounds_played(), 1);
+
+        let mut played = scheduler.strategy.played.clone();
+        played.sort();
+        let expected = [(0, 1), (0, 2), (1, 0), (1, 2), (2, 0), (2, 1)]
+            .iter()
+            .map(|(a, b)| vec![format!("agent_{a}"), format!("agent_{b}")])
+            .collect::<Vec<_>>();
+        assert_eq!(played, expected);
+    }
+
+    #[test]
+    fn test_round_constraints() {
+        use crate::tournament_strategy::SwissTournament;
+        use std::time::Duration;
+
+        let agents = (0..4)
+            .map(|i| Arc::new(Agent::new(format!("agent_{i}"), None, None, i, None)))
+            .collect();
+        let mut strategy = SwissTournament::new(2, 1);
+        TournamentStrategy::<f32>::add_agents(&mut strategy, agents);
+        let pool = ConstraintsBuilder::new()
+            .with_total_cpu_count(4)
+            .with_max_total_ram(4)
+            .with_ram_per_agent(1)
+            .with_action_timeout(Duration::from_millis(10))
+            .build()
+            .unwrap();
+        let final_round = ConstraintsBuilder::new()
+            .with_total_cpu_count(4)
+            .with_max_total_ram(4)
+            .with_ram_per_agent(2)
+            .with_action_timeout(Duration::from_secs(1))
+            .build()
+            .unwrap();
+        let mut scheduler = TournamentScheduler::<_, f32>::new(pool.clone(), strategy)
+            .with_round_constraints(BTreeMap::from([(2, final_round)]));
+
+        // (round, agent RAM, action timeout, matches running at the same time)
+        let mut launched = vec![];
+        drain(&mut scheduler, |_, settings, others| {
+            launched.push((
+                settings.round,
+                settings.resources.agent_ram,
+                settings.resources.action_timeout,
+                others + 1,
+            ));
+            fake_result(settings, |_| 0.0)
+        });
+        assert!(scheduler.is_finished());
+
+        let ms = Duration::from_millis;
+        assert_eq!(
+            launched,
+            [
+                (1, 1_000_000, ms(10), 2),
+                (1, 1_000_000, ms(10), 1),
+                (2, 2_000_000, ms(1000), 1),
+                (2, 2_000_000, ms(1000), 1),
+            ]
+        );
+        // every resource went back to the pool
+        assert_eq!(scheduler.resources, pool);
+    }
+
+    #[test]
+    fn test_resource_cap() {
+        let agents = (0..6)
+            .map(|i| Arc::new(Agent::new(format!("agent_{i}"), None, None, i, None)))
+            .collect();
+        // 10 rounds of 3 matches
+        let mut strategy = RoundRobinTournament::new(false).with_fixed_schedule(true);
+        TournamentStrategy::<u32>::add_agents(&mut strategy, agents);
+        let pool = ConstraintsBuilder::new()
+            .with_total_cpu_count(8)
+            .with_max_total_ram(8)
+            .with_ram_per_agent(1)
+            .build()
+            .unwrap();
+        let mut scheduler = TournamentScheduler::<_, u32>::new(pool.clone(), strategy);
+
+        // most matches running at the same time, by round
+        let mut concurrency = BTreeMap::new();
+        drain(&mut scheduler, |scheduler, settings, others| {
+            let round = settings.round;
+            let most = concurrency.entry(round).or_insert(0);
+            *most = (others + 1).max(*most);
+            match round {
+                // applies from the next round, not to the remaining matches of this one
+                1 => scheduler.set_resource_cap(Some(ResourceCap {
+                    cpus: 4,
+                    ram: pool.total_ram,
+                })),
+                // too small for a match: raised to 2 CPUs
+                3 => scheduler.set_resource_cap(Some(ResourceCap {
+                    cpus: 1,
+                    ram: pool.total_ram,
+                })),
+                4 => scheduler.set_resource_cap(None),
+                _ => {}
+            }
+            fake_result(settings, |_| 0)
+        });
+        assert!(scheduler.is_finished());
+
+        assert_eq!(
+            concurrency.into_values().collect::<Vec<_>>(),
+            [3, 2, 2, 1, 3, 3, 3, 3, 3, 3]
+        );
+        assert_eq!(scheduler.resources, pool);
+    }
+
+    #[test]
+    fn test_max_concurrent_matches() {
+        let agents = (0..6)
+            .map(|i| Arc::new(Agent::new(format!("agent_{i}"), None, None, i, None)))
+            .collect();
+        // 10 rounds of 3 matches
+        let mut strategy = RoundRobinTournament::new(false).with_fixed_schedule(true);
+        TournamentStrategy::<u32>::add_agents(&mut strategy, agents);
+        let pool = ConstraintsBuilder::new()
+            .with_total_cpu_count(8)
+            .with_max_total_ram(8)
+            .with_ram_per_agent(1)
+            .build()
+            .unwrap();
+        let mut scheduler = TournamentScheduler::<_, u32>::new(pool.clone(), strategy)
+            .with_max_concurrent_matches(Some(2));
+
+        let mut most = 0;
+        let mut played = 0;
+        drain(&mut scheduler, |_, settings, others| {
+            most = most.max(others + 1);
+            played += 1;
+            fake_result(settings, |_| 0)
+        });
+        assert!(scheduler.is_finished());
+        // resources allow 3 matches at the same time
+        assert_eq!(most, 2);
+        assert_eq!(played, 30);
+        assert_eq!(scheduler.resources, pool);
+    }
 }
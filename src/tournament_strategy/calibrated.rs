@@ -0,0 +1,256 @@
+//! Calibration rounds before another tournament strategy.
+//!
+//! Strategies such as [`SwissTournament`](super::SwissTournament) pair agents by score, so their
+//! first round is paired blindly: all agents start equal, and are paired in the order they were
+//! collected. [`Calibrated`] first plays a few rounds of random matches, rates agents with Elo
+//! from those matches, and then starts the inner strategy with agents sorted from highest to
+//! lowest rating: agents of similar strength meet from the first round on.
+//!
+//! # Standings
+//!
+//! Calibration matches are **not counted** in the final standings, which are the final scores of
+//! the inner strategy. They only decide the order in which agents are given to the inner
+//! strategy, through [`TournamentStrategy::add_agents`]. The calibration ratings can be read with
+//! [`Calibrated::ratings`].
+//!
+//! # Elo
+//!
+//! Every agent starts at 1500. After each calibration match, each pair of players is updated
+//! with `K = 32`: a player rated `r_a` against `r_b` expects `1 / (1 + 10^((r_b - r_a) / 400))`
+//! points, scores 1 for a win, 0.5 for a draw and 0 for a loss, and gains `K` times the
+//! difference.
+
+use std::{cmp, collections::HashMap, sync::Arc};
+
+use tracing::info;
+
+use super::TournamentStrategy;
+use crate::{agent::Agent, match_runner::MatchResult, rng::SplitMix64};
+
+const INITIAL_RATING: f64 = 1500.0;
+const K_FACTOR: f64 = 32.0;
+
+/// Plays `calibration_rounds` rounds of random matches, then delegates to the inner strategy,
+/// seeded with the calibration ratings (see [module documentation](self)).
+pub struct Calibrated<T> {
+    inner: T,
+    calibration_rounds: usize,
+    round: usize,
+    rng: SplitMix64,
+    agents: Vec<Arc<Agent>>,
+    ratings: HashMap<Arc<Agent>, f64>,
+    is_calibrated: bool,
+}
+
+impl<T> Calibrated<T> {
+    /// Runs `calibration_rounds` rounds of random matches before `inner`. Matches are drawn with a
+    /// generator seeded with `seed`: the same seed gives the same calibration matches.
+    pub fn new(inner: T, calibration_rounds: usize, seed: u64) -> Self {
+        Self {
+            inner,
+            calibration_rounds,
+            round: 0,
+            rng: SplitMix64::new(seed),
+            agents: vec![],
+            ratings: HashMap::new(),
+            is_calibrated: false,
+        }
+    }
+
+    /// Elo ratings computed during calibration, by agent.
+    // Mutable Key safety: mutable AtomicUsize is not used in Hash nor Eq
+    #[allow(clippy::mutable_key_type)]
+    pub fn ratings(&self) -> &HashMap<Arc<Agent>, f64> {
+        &self.ratings
+    }
+
+    /// The inner strategy.
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    fn update_ratings<S: PartialOrd>(&mut self, result: &MatchResult<S>) {
+        let mut deltas = vec![0.0; result.len()];
+        for i in 0..result.len() {
+            for j in i + 1..result.len() {
+                let (a, score_a) = &result[i];
+                let (b, score_b) = &result[j];
+                let expected =
+                    1.0 / (1.0 + 10f64.powf((self.ratings[b] - self.ratings[a]) / 400.0));
+                let actual = match score_a.partial_cmp(score_b) {
+                    Some(cmp::Ordering::Greater) => 1.0,
+                    Some(cmp::Ordering::Less) => 0.0,
+                    _ => 0.5,
+                };
+                deltas[i] += K_FACTOR * (actual - expected);
+                deltas[j] -= K_FACTOR * (actual - expected);
+            }
+        }
+        for ((agent, _), delta) in result.iter().zip(deltas) {
+            *self.ratings.get_mut(agent).unwrap() += delta;
+        }
+    }
+
+    /// Random groups of `players_per_match` agents. Agents left over sit out the round.
+    fn random_matchups(&mut self, players_per_match: usize) -> Vec<Vec<Arc<Agent>>> {
+        let mut agents = self.agents.clone();
+        self.rng.shuffle(&mut agents);
+        agents
+            .chunks_exact(players_per_match)
+            .map(<[_]>::to_vec)
+            .collect()
+    }
+}
+
+impl<S: PartialOrd, T: TournamentStrategy<S>> TournamentStrategy<S> for Calibrated<T> {
+    type FinalScore = T::FinalScore;
+
+    fn add_agents(&mut self, agents: Vec<Arc<Agent>>) {
+        for agent in &agents {
+            self.ratings.insert(agent.clone(), INITIAL_RATING);
+        }
+        self.agents = agents;
+    }
+
+    fn advance_round(&mut self, scores: Vec<MatchResult<S>>) -> Vec<Vec<Arc<Agent>>> {
+        if self.is_calibrated {
+            return self.inner.advance_round(scores);
+        }
+        for result in &scores {
+            self.update_ratings(result);
+        }
+
+        if self.round < self.calibration_rounds {
+            self.round += 1;
+            let matchups = self.random_matchups(self.inner.players_per_match());
+            if !matchups.is_empty() {
+                return matchups;
+            }
+        }
+
+        self.is_calibrated = true;
+        let mut agents = std::mem::take(&mut self.agents);
+        // stable: agents with the same rating keep their order
+        agents.sort_by(|a, b| self.ratings[b].total_cmp(&self.ratings[a]));
+        info!(
+            "Calibration done after {} rounds: {}",
+            self.round,
+            agents
+                .iter()
+                .map(|a| format!("{} ({:.0})", a.name, self.ratings[a]))
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+        self.inner.add_agents(agents);
+        self.inner.advance_round(vec![])
+    }
+
+    fn advance_weighted_round(
+        &mut self,
+        scores: Vec<(MatchResult<S>, f64)>,
+    ) -> Vec<Vec<Arc<Agent>>> {
+        if self.is_calibrated {
+            return self.inner.advance_weighted_round(scores);
+        }
+        self.advance_round(scores.into_iter().map(|(result, _)| result).collect())
+    }
+
+    fn players_per_match(&self) -> usize {
+        self.inner.players_per_match()
+    }
+
+    fn roles(&self) -> Vec<String> {
+        self.inner.roles()
+    }
+
+    fn match_weight(&self, matchup: &[Arc<Agent>]) -> f64 {
+        if self.is_calibrated {
+            self.inner.match_weight(matchup)
+        } else {
+            1.0
+        }
+    }
+
+    fn add_late_agent(&mut self, agent: Arc<Agent>) -> bool {
+        if self.is_calibrated {
+            return self.inner.add_late_agent(agent);
+        }
+        self.ratings.insert(agent.clone(), INITIAL_RATING);
+        self.agents.push(agent);
+        true
+    }
+
+    fn num_byes(&self) -> usize {
+        self.inner.num_byes()
+    }
+
+    fn higher_is_better(&self) -> bool {
+        self.inner.higher_is_better()
+    }
+
+    fn get_final_scores(&self) -> HashMap<Arc<Agent>, Self::FinalScore> {
+        self.inner.get_final_scores()
+    }
+}
+
+#[cfg(test)]
+mod calibrated_tests {
+    use super::*;
+    use crate::test_support::make_agents;
+    use crate::tournament_strategy::SwissTournament;
+
+    /// Simulates a match: higher ID wins.
+    fn simulate_round(matchups: &[Vec<Arc<Agent>>]) -> Vec<MatchResult<u32>> {
+        matchups
+            .iter()
+            .map(|m| m.iter().map(|a| (a.clone(), a.id)).collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_calibration_seeds_inner_strategy() {
+        let agents = make_agents(8);
+        let mut tournament = Calibrated::new(SwissTournament::new(1, 1), 3, 42);
+        TournamentStrategy::<u32>::add_agents(&mut tournament, agents.clone());
+
+        let mut matchups = TournamentStrategy::<u32>::advance_round(&mut tournament, vec![]);
+        for _ in 0..3 {
+            assert_eq!(matchups.len(), 4);
+            let mut players = matchups.concat();
+            players.sort_by_key(|a| a.id);
+            assert_eq!(players, agents);
+            matchups = tournament.advance_round(simulate_round(&matchups));
+        }
+
+        // the Swiss round pairs adjacent seeds: the best rated agents meet first
+        let mut by_rating = agents.clone();
+        by_rating.sort_by(|a, b| tournament.ratings()[b].total_cmp(&tournament.ratings()[a]));
+        assert_eq!(matchups.len(), 4);
+        assert!(matchups[..]
+            .iter()
+            .any(|m| m.contains(&by_rating[0]) && m.contains(&by_rating[1])));
+        assert!(tournament.ratings()[&agents[7]] > INITIAL_RATING);
+        assert!(tournament.ratings()[&agents[0]] < INITIAL_RATING);
+
+        // calibration matches are not counted
+        assert!(tournament
+            .advance_round(simulate_round(&matchups))
+            .is_empty());
+        let scores = TournamentStrategy::<u32>::get_final_scores(&tournament);
+        for score in scores.values() {
+            assert_eq!(score.num_win + score.num_draw + score.num_lose, 1);
+        }
+    }
+
+    #[test]
+    fn test_no_calibration() {
+        let agents = make_agents(4);
+        let mut tournament = Calibrated::new(SwissTournament::new(1, 1), 0, 0);
+        TournamentStrategy::<u32>::add_agents(&mut tournament, agents.clone());
+        // agents are given to the inner strategy in their original order
+        let matchups = TournamentStrategy::<u32>::advance_round(&mut tournament, vec![]);
+        assert_eq!(matchups.len(), 2);
+        assert!(matchups.contains(&vec![agents[0].clone(), agents[1].clone()]));
+        assert!(matchups.contains(&vec![agents[2].clone(), agents[3].clone()]));
+    }
+}
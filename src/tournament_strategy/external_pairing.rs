@@ -0,0 +1,467 @@
+//! Swiss tournament whose pairings are computed by an external pairing engine.
+//!
+//! Established chess pairing programs (e.g. [bbpPairings](https://github.com/BieremaBoyzProgramming/bbpPairings)
+//! or JaVaFo) implement the official FIDE Dutch system. Instead of re-implementing it, the
+//! [`ExternalPairingTournament`] writes the current standings to a TRF file, invokes the
+//! configured [`PairingEngine`] and reads back the pairings it produced.
+//!
+//! # Emitted TRF subset
+//!
+//! Only the lines needed for pairing are written:
+//! - `012 <name>`: tournament name (`ai-tournament`)
+//! - `XXR <n>`: total number of rounds (bbpPairings extension)
+//! - `XXC white1`: the first player by starting rank gets white in round 1 (bbpPairings extension)
+//! - `001 ...`: one line per agent. Only the starting rank (columns 5-8), the name (columns
+//!   15-47), the points (columns 81-84) and the round blocks (from column 92, 10 columns each)
+//!   are filled. Starting ranks follow the order in which agents were added.
+//!
+//! Each round block is `oooo c r` where `oooo` is the opponent's starting rank (`0000` for a
+//! bye), `c` is the color (`w`, `b` or `-`) and `r` is the result (`1`, `=`, `0`, or `U` for a
+//! pairing-allocated bye). Points are `wins + 0.5 * draws`, a bye counting as a win.
+//!
+//! # Parsed pairing subset
+//!
+//! The engine output file must start with the number of pairs, followed by one pair per line:
+//! `<white starting rank> <black starting rank>`. A black starting rank of `0` means the white
+//! player receives a bye. This is the format produced by `bbpPairings -p` and `javafo -p`.
+
+use std::{
+    collections::HashMap,
+    fmt::Write as _,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use anyhow::{bail, Context};
+use tracing::{error, info, instrument, trace};
+
+use super::{MatchOutcome, TournamentStrategy, TwoPlayersGameScore};
+use crate::{agent::Agent, match_runner::MatchResult};
+
+/// Configuration of the external program computing the pairings.
+///
+/// The arguments may contain the `{input}` and `{output}` placeholders, which are replaced by the
+/// path of the TRF file written by the evaluator and the path where the engine must write the
+/// pairings.
+#[derive(Debug, Clone)]
+pub struct PairingEngine {
+    program: PathBuf,
+    args: Vec<String>,
+}
+
+impl PairingEngine {
+    /// Creates a pairing engine configuration for the given binary.
+    ///
+    /// By default, arguments follow the bbpPairings convention: `--dutch {input} -p {output}`.
+    pub fn new(program: impl Into<PathBuf>) -> Self {
+        Self {
+            program: program.into(),
+            args: ["--dutch", "{input}", "-p", "{output}"]
+                .into_iter()
+                .map(String::from)
+                .collect(),
+        }
+    }
+
+    /// Overrides the arguments passed to the engine (see [`PairingEngine`] for placeholders).
+    pub fn with_args<S: AsRef<str>>(mut self, args: &[S]) -> Self {
+        self.args = args.iter().map(|s| s.as_ref().to_string()).collect();
+        self
+    }
+
+    #[instrument(skip(trf))]
+    fn compute_pairings(&self, trf: &str) -> anyhow::Result<Vec<(usize, Option<usize>)>> {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let id = COUNTER.fetch_add(1, Ordering::Relaxed);
+        let dir = std::env::temp_dir();
+        let prefix = format!("ai_tournament_{}_{id}", std::process::id());
+        let input = dir.join(format!("{prefix}.trf"));
+        let output = dir.join(format!("{prefix}_pairings.txt"));
+
+        std::fs::write(&input, trf)
+            .with_context(|| format!("could not write TRF file {}", input.display()))?;
+
+        let args = self
+            .args
+            .iter()
+            .map(|arg| {
+                arg.replace("{input}", &input.to_string_lossy())
+                    .replace("{output}", &output.to_string_lossy())
+            })
+            .collect::<Vec<_>>();
+
+        let result = std::process::Command::new(&self.program)
+            .args(&args)
+            .output()
+            .with_context(|| format!("could not launch pairing engine {:?}", self.program))
+            .and_then(|out| {
+                if !out.status.success() {
+                    bail!(
+                        "pairing engine failed ({}): {}",
+                        out.status,
+                        String::from_utf8_lossy(&out.stderr).trim()
+                    );
+                }
+                std::fs::read_to_string(&output)
+                    .with_context(|| format!("could not read pairings file {}", output.display()))
+            });
+
+        let _ = std::fs::remove_file(&input);
+        let _ = std::fs::remove_file(&output);
+
+        parse_pairings(&result?)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TrfResult {
+    Win,
+    Draw,
+    Loss,
+    PairingBye,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct TrfRound {
+    /// Starting rank of the opponent, `None` for a bye
+    opponent: Option<usize>,
+    white: bool,
+    result: TrfResult,
+}
+
+/// A Swiss tournament for two-player games where pairings are delegated to an external
+/// FIDE-compliant pairing engine (see [module documentation](self)).
+///
+/// Like [`SwissTournament`](super::SwissTournament), each pairing plays `num_match_per_pair`
+/// games with alternating sides, aggregated into a single win/draw/loss outcome. The agent listed
+/// first by the engine is recorded as white.
+///
+/// If the engine fails (missing binary, no valid pairing, malformed output), the error is logged
+/// and the tournament ends early.
+pub struct ExternalPairingTournament {
+    engine: PairingEngine,
+    agents: Vec<Arc<Agent>>,
+    round: usize,
+    max_rounds: usize,
+    num_match_per_pair: usize,
+    scores: HashMap<Arc<Agent>, TwoPlayersGameScore>,
+    history: HashMap<Arc<Agent>, Vec<TrfRound>>,
+    /// (white, black) pairs of the round being played
+    current_pairs: Vec<(Arc<Agent>, Arc<Agent>)>,
+}
+
+impl ExternalPairingTournament {
+    /// Creates a new tournament using `engine` to compute pairings.
+    ///
+    /// If `max_rounds` is `0`, the number of rounds is `ceil(log2(n))` as in
+    /// [`SwissTournament::with_auto_rounds`](super::SwissTournament::with_auto_rounds).
+    pub fn new(engine: PairingEngine, max_rounds: usize, num_match_per_pair: usize) -> Self {
+        assert!(
+            num_match_per_pair >= 1,
+            "Must play at least one match per pairing."
+        );
+        Self {
+            engine,
+            agents: vec![],
+            round: 0,
+            max_rounds,
+            num_match_per_pair,
+            scores: HashMap::new(),
+            history: HashMap::new(),
+            current_pairs: vec![],
+        }
+    }
+
+    fn starting_rank(&self, agent: &Arc<Agent>) -> usize {
+        self.agents
+            .iter()
+            .position(|a| a == agent)
+            .expect("unknown agent")
+            + 1
+    }
+
+    // Mutable Key safety: mutable AtomicUsize is not used in Hash nor Eq
+    #[allow(clippy::mutable_key_type)]
+    fn record_results<S: MatchOutcome>(&mut self, match_results: Vec<MatchResult<S>>) {
+        let mut totals: HashMap<Arc<Agent>, S> = HashMap::new();
+        for result in match_results {
+            for (agent, score) in result {
+                let total = totals.entry(agent).or_default();
+                *total = *total + score;
+            }
+        }
+
+        for (white, black) in std::mem::take(&mut self.current_pairs) {
+            let (Some(score_w), Some(score_b)) = (totals.get(&white), totals.get(&black)) else {
+                error!("missing results for {} VS {}", white.name, black.name);
+                continue;
+            };
+            info!(
+                "Aggregated results {} VS {}: {score_w}-{score_b}",
+                white.name, black.name
+            );
+            let (result_w, result_b) = match S::compare(score_w, score_b) {
+                std::cmp::Ordering::Equal => (TrfResult::Draw, TrfResult::Draw),
+                std::cmp::Ordering::Greater => (TrfResult::Win, TrfResult::Loss),
+                std::cmp::Ordering::Less => (TrfResult::Loss, TrfResult::Win),
+            };
+            let (rank_w, rank_b) = (self.starting_rank(&white), self.starting_rank(&black));
+            self.apply_result(&white, Some(rank_b), true, result_w);
+            self.apply_result(&black, Some(rank_w), false, result_b);
+        }
+    }
+
+    fn apply_result(
+        &mut self,
+        agent: &Arc<Agent>,
+        opponent: Option<usize>,
+        white: bool,
+        result: TrfResult,
+    ) {
+        let score = self.scores.get_mut(agent).unwrap();
+        match result {
+            TrfResult::Win | TrfResult::PairingBye => score.num_win += 1,
+            TrfResult::Draw => score.num_draw += 1,
+            TrfResult::Loss => score.num_lose += 1,
+        }
+        self.history.get_mut(agent).unwrap().push(TrfRound {
+            opponent,
+            white,
+            result,
+        });
+    }
+
+    fn to_trf(&self) -> String {
+        let mut trf = String::new();
+        let _ = writeln!(trf, "012 ai-tournament");
+        let _ = writeln!(trf, "XXR {}", self.max_rounds);
+        let _ = writeln!(trf, "XXC white1");
+        for (i, agent) in self.agents.iter().enumerate() {
+            let score = &self.scores[agent];
+            let points = score.num_win as f32 + score.num_draw as f32 / 2.0;
+            trf.push_str(&trf_player_line(
+                i + 1,
+                &agent.name,
+                points,
+                &self.history[agent],
+            ));
+            trf.push('\n');
+        }
+        trf
+    }
+}
+
+fn trf_player_line(rank: usize, name: &str, points: f32, rounds: &[TrfRound]) -> String {
+    let name = name.chars().take(33).collect::<String>();
+    // columns 1-89: record, rank, sex, title, name, rating, federation, id, birth date, points, rank
+    let mut line = format!(
+        "001 {rank:>4} {:1}{:>3} {name:<33} {:>4} {:>3} {:>11} {:>10} {points:>4.1} {:>4}",
+        "", "", "", "", "", "", ""
+    );
+    for round in rounds {
+        let opponent = round.opponent.unwrap_or(0);
+        let color = match round.opponent {
+            None => '-',
+            Some(_) if round.white => 'w',
+            Some(_) => 'b',
+        };
+        let result = match round.result {
+            TrfResult::Win => '1',
+            TrfResult::Draw => '=',
+            TrfResult::Loss => '0',
+            TrfResult::PairingBye => 'U',
+        };
+        let _ = write!(line, "  {opponent:04} {color} {result}");
+    }
+    line
+}
+
+fn parse_pairings(text: &str) -> anyhow::Result<Vec<(usize, Option<usize>)>> {
+    let mut lines = text.lines().map(str::trim).filter(|l| !l.is_empty());
+    let count: usize = lines
+        .next()
+        .context("empty pairings file")?
+        .parse()
+        .context("first line must be the number of pairs")?;
+
+    let mut pairs = Vec::with_capacity(count);
+    for line in lines {
+        let mut ids = line.split_whitespace().map(str::parse::<usize>);
+        let (Some(Ok(white)), Some(Ok(black)), None) = (ids.next(), ids.next(), ids.next()) else {
+            bail!("invalid pairing line: '{line}'");
+        };
+        if white == 0 {
+            bail!("invalid starting rank 0 for first player: '{line}'");
+        }
+        pairs.push((white, (black != 0).then_some(black)));
+    }
+
+    if pairs.len() != count {
+        bail!("expected {count} pairs, found {}", pairs.len());
+    }
+    Ok(pairs)
+}
+
+impl<S: MatchOutcome> TournamentStrategy<S> for ExternalPairingTournament {
+    fn advance_round(&mut self, scores: Vec<MatchResult<S>>) -> Vec<Vec<Arc<Agent>>> {
+        self.record_results(scores);
+
+        if self.round >= self.max_rounds {
+            return vec![];
+        }
+
+        let trf = self.to_trf();
+        trace!("TRF sent to pairing engine:\n{trf}");
+        let pairs = match self.engine.compute_pairings(&trf) {
+            Ok(pairs) => pairs,
+            Err(e) => {
+                error!("external pairing failed, ending tournament: {e:#}");
+                return vec![];
+            }
+        };
+
+        let mut pending = Vec::with_capacity(pairs.len() * self.num_match_per_pair);
+        for (white, black) in pairs {
+            let Some(a) = self.agents.get(white - 1).cloned() else {
+                error!("pairing engine returned unknown starting rank {white}");
+                continue;
+            };
+            let Some(black) = black else {
+                info!("{} receives a bye", a.name);
+                self.apply_result(&a, None, false, TrfResult::PairingBye);
+                continue;
+            };
+            let Some(b) = self.agents.get(black - 1).cloned() else {
+                error!("pairing engine returned unknown starting rank {black}");
+                continue;
+            };
+            pending.extend((0..self.num_match_per_pair).map(|i| {
+                //permute order for each match
+                if i % 2 == 0 {
+                    vec![a.clone(), b.clone()]
+                } else {
+                    vec![b.clone(), a.clone()]
+                }
+            }));
+            self.current_pairs.push((a, b));
+        }
+
+        self.round += 1;
+        pending
+    }
+
+    fn players_per_match(&self) -> usize {
+        2
+    }
+
+    fn add_agents(&mut self, agents: Vec<Arc<Agent>>) {
+        self.agents = agents;
+        if self.max_rounds == 0 {
+            let n = self.agents.len();
+            self.max_rounds = f32::log2(n as f32).ceil() as usize;
+            info!(
+                "External pairing tournament auto number of rounds: {}",
+                self.max_rounds
+            );
+        }
+        for agent in &self.agents {
+            self.scores
+                .insert(agent.clone(), TwoPlayersGameScore::default());
+            self.history.insert(agent.clone(), vec![]);
+        }
+    }
+
+    type FinalScore = TwoPlayersGameScore;
+
+    fn get_final_scores(&self) -> HashMap<Arc<Agent>, Self::FinalScore> {
+        self.scores.clone()
+    }
+}
+
+#[cfg(test)]
+mod external_pairing_tests {
+    use super::*;
+    use crate::test_support::make_agents;
+
+    #[test]
+    fn test_trf_player_line_columns() {
+        let rounds = [
+            TrfRound {
+                opponent: Some(12),
+                white: true,
+                result: TrfResult::Win,
+            },
+            TrfRound {
+                opponent: None,
+                white: false,
+                result: TrfResult::PairingBye,
+            },
+        ];
+        let line = trf_player_line(3, "my_agent", 2.0, &rounds);
+        assert_eq!(&line[0..3], "001");
+        assert_eq!(&line[4..8], "   3");
+        assert_eq!(line[14..47].trim_end(), "my_agent");
+        assert_eq!(&line[80..84], " 2.0");
+        assert_eq!(&line[91..99], "0012 w 1");
+        assert_eq!(&line[101..109], "0000 - U");
+    }
+
+    #[test]
+    fn test_parse_pairings() {
+        let pairs = parse_pairings("3\n1 4\n3 2\n5 0\n").unwrap();
+        assert_eq!(pairs, vec![(1, Some(4)), (3, Some(2)), (5, None)]);
+
+        assert!(parse_pairings("").is_err());
+        assert!(parse_pairings("2\n1 2\n").is_err());
+        assert!(parse_pairings("1\n1 two\n").is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_with_fake_engine() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let script = std::env::temp_dir().join(format!(
+            "ai_tournament_fake_engine_{}.sh",
+            std::process::id()
+        ));
+        // always pairs 1-2 and gives a bye to 3; `$4` is the output path
+        std::fs::write(&script, "#!/bin/sh\nprintf '2\\n1 2\\n3 0\\n' > \"$4\"\n").unwrap();
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let agents = make_agents(3);
+        let mut tournament = ExternalPairingTournament::new(PairingEngine::new(&script), 2, 2);
+        TournamentStrategy::<f32>::add_agents(&mut tournament, agents.clone());
+
+        let matchups = tournament.advance_round(Vec::<MatchResult<f32>>::new());
+        assert_eq!(matchups.len(), 2);
+        assert_eq!(matchups[0], vec![agents[0].clone(), agents[1].clone()]);
+        assert_eq!(matchups[1], vec![agents[1].clone(), agents[0].clone()]);
+
+        // agent_0 wins both games
+        let results = matchups
+            .iter()
+            .map(|m| {
+                m.iter()
+                    .map(|a| (a.clone(), if a.id == 0 { 1.0 } else { 0.0 }))
+                    .collect()
+            })
+            .collect();
+        let matchups = tournament.advance_round(results);
+        assert_eq!(matchups.len(), 2);
+
+        let trf = tournament.to_trf();
+        assert!(trf.contains("XXR 2"));
+        let line_1 = trf.lines().find(|l| l.starts_with("001    1")).unwrap();
+        assert_eq!(&line_1[91..99], "0002 w 1");
+
+        let _ = tournament.advance_round(Vec::<MatchResult<f32>>::new());
+        let scores = TournamentStrategy::<f32>::get_final_scores(&tournament);
+        assert_eq!(scores[&agents[2]].num_win, 2);
+
+        std::fs::remove_file(&script).unwrap();
+    }
+}
@@ -0,0 +1,217 @@
+//! Predefined matchups, e.g. to replay the schedule of a previous event.
+//!
+//! A [`FixedScheduleTournament`] plays exactly the listed matches, by agent name, in a single
+//! round, instead of computing pairings. Results are scored by another strategy (the "scorer"),
+//! whose own pairings are ignored: e.g. [`RoundRobinTournament`](super::RoundRobinTournament) for
+//! a win, draw or loss per game, or [`FreeForAllTournament`](super::FreeForAllTournament) for
+//! placement points. Strategies whose scoring depends on their own pairings (such as Swiss
+//! tournaments, which aggregate the games of a pairing) should not be used as scorers.
+//!
+//! # Schedule file
+//!
+//! [`FixedScheduleTournament::from_file`] reads one match per line, with the names of its players
+//! in play order, separated by whitespace. Empty lines and lines starting with `#` are ignored:
+//!
+//! ```text
+//! # round 1 of last year's finals
+//! alice bob
+//! carol dave
+//! bob alice
+//! ```
+//!
+//! # Name resolution
+//!
+//! Names are resolved when the agents are added, among the working agents. A match listing an
+//! unknown agent (misspelled, missing from the agent directory, or failing to compile) is not
+//! played, and an error is logged; so is a match whose number of players is not the number of
+//! players per match of the scorer. The names that could not be resolved are available with
+//! [`FixedScheduleTournament::unresolved_names`].
+
+use std::{collections::HashMap, path::Path, sync::Arc};
+
+use anyhow::Context;
+use tracing::error;
+
+use super::TournamentStrategy;
+use crate::{agent::Agent, match_runner::MatchResult};
+
+/// A tournament playing a predefined list of matches, scored by another strategy (see
+/// [module documentation](self)).
+pub struct FixedScheduleTournament<T> {
+    scorer: T,
+    schedule: Vec<Vec<String>>,
+    matchups: Vec<Vec<Arc<Agent>>>,
+    unresolved_names: Vec<String>,
+    is_scheduled: bool,
+}
+
+impl<T> FixedScheduleTournament<T> {
+    /// Plays the matches of `schedule` (names of the players of each match, in play order), and
+    /// scores them with `scorer`.
+    pub fn new(schedule: Vec<Vec<String>>, scorer: T) -> Self {
+        Self {
+            scorer,
+            schedule,
+            matchups: vec![],
+            unresolved_names: vec![],
+            is_scheduled: false,
+        }
+    }
+
+    /// Reads the schedule from a file (see [schedule file](self#schedule-file)).
+    pub fn from_file(path: impl AsRef<Path>, scorer: T) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("could not read schedule {}", path.display()))?;
+        Ok(Self::new(parse_schedule(&content), scorer))
+    }
+
+    /// Names listed in the schedule that did not match any working agent, in order of first
+    /// appearance. Only known once agents are added.
+    pub fn unresolved_names(&self) -> &[String] {
+        &self.unresolved_names
+    }
+
+    /// The strategy scoring the matches.
+    pub fn scorer(&self) -> &T {
+        &self.scorer
+    }
+}
+
+/// One match per non-empty line, skipping `#` comments
+fn parse_schedule(content: &str) -> Vec<Vec<String>> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| line.split_whitespace().map(String::from).collect())
+        .collect()
+}
+
+impl<S: PartialOrd, T: TournamentStrategy<S>> TournamentStrategy<S> for FixedScheduleTournament<T> {
+    type FinalScore = T::FinalScore;
+
+    fn add_agents(&mut self, agents: Vec<Arc<Agent>>) {
+        let by_name = agents
+            .iter()
+            .map(|agent| (agent.name.as_str(), agent))
+            .collect::<HashMap<_, _>>();
+        let players_per_match = self.scorer.players_per_match();
+
+        self.matchups.clear();
+        self.unresolved_names.clear();
+        for (line, names) in self.schedule.iter().enumerate() {
+            let players = names
+                .iter()
+                .map(|name| by_name.get(name.as_str()).map(|&agent| agent.clone()))
+                .collect::<Option<Vec<_>>>();
+            let Some(players) = players else {
+                let unknown = names
+                    .iter()
+                    .filter(|name| !by_name.contains_key(name.as_str()))
+                    .cloned()
+                    .collect::<Vec<_>>();
+                error!(
+                    "match {} ({}) is not played: unknown agent(s) {}",
+                    line + 1,
+                    names.join(" "),
+                    unknown.join(", ")
+                );
+                for name in unknown {
+                    if !self.unresolved_names.contains(&name) {
+                        self.unresolved_names.push(name);
+                    }
+                }
+                continue;
+            };
+            if players.len() != players_per_match {
+                error!(
+                    "match {} ({}) is not played: {} players instead of {players_per_match}",
+                    line + 1,
+                    names.join(" "),
+                    players.len()
+                );
+                continue;
+            }
+            self.matchups.push(players);
+        }
+
+        self.scorer.add_agents(agents);
+    }
+
+    fn advance_round(&mut self, scores: Vec<MatchResult<S>>) -> Vec<Vec<Arc<Agent>>> {
+        if !scores.is_empty() {
+            // the scorer's own pairings are ignored
+            let _ = self.scorer.advance_round(scores);
+        }
+        if self.is_scheduled {
+            return vec![];
+        }
+        self.is_scheduled = true;
+        self.matchups.clone()
+    }
+
+    fn players_per_match(&self) -> usize {
+        self.scorer.players_per_match()
+    }
+
+    fn roles(&self) -> Vec<String> {
+        self.scorer.roles()
+    }
+
+    fn higher_is_better(&self) -> bool {
+        self.scorer.higher_is_better()
+    }
+
+    fn get_final_scores(&self) -> HashMap<Arc<Agent>, Self::FinalScore> {
+        self.scorer.get_final_scores()
+    }
+}
+
+#[cfg(test)]
+mod fixed_schedule_tests {
+    use super::*;
+    use crate::tournament_strategy::RoundRobinTournament;
+
+    #[test]
+    fn test_parse_schedule() {
+        let schedule = parse_schedule("# finals\nalice  bob\n\n  carol\tdave \n");
+        assert_eq!(schedule, [["alice", "bob"], ["carol", "dave"]]);
+    }
+
+    #[test]
+    fn test_fixed_matchups() {
+        let agents: Vec<_> = ["alice", "bob", "carol"]
+            .iter()
+            .enumerate()
+            .map(|(i, name)| Arc::new(Agent::new(name.to_string(), None, None, i as u32, None)))
+            .collect();
+        let schedule = parse_schedule("alice bob\nbob carol\nalice mallory\ncarol\nbob alice");
+        let mut tournament =
+            FixedScheduleTournament::new(schedule, RoundRobinTournament::new(true));
+        TournamentStrategy::<u32>::add_agents(&mut tournament, agents.clone());
+        assert_eq!(tournament.unresolved_names(), ["mallory"]);
+
+        let matchups = TournamentStrategy::<u32>::advance_round(&mut tournament, vec![]);
+        let names = matchups
+            .iter()
+            .map(|m| m.iter().map(|a| a.name.as_str()).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            [["alice", "bob"], ["bob", "carol"], ["bob", "alice"]]
+        );
+
+        // higher ID wins
+        let results = matchups
+            .iter()
+            .map(|m| m.iter().map(|a| (a.clone(), a.id)).collect())
+            .collect();
+        assert!(tournament.advance_round(results).is_empty());
+        let scores = TournamentStrategy::<u32>::get_final_scores(&tournament);
+        assert_eq!(scores[&agents[0]].num_lose, 2);
+        assert_eq!(scores[&agents[1]].num_win, 2);
+        assert_eq!(scores[&agents[1]].num_lose, 1);
+        assert_eq!(scores[&agents[2]].num_win, 1);
+    }
+}
@@ -0,0 +1,299 @@
+//! Free-for-all tournament for games of more than two players, scored by placement.
+//!
+//! Every group of `players_per_match` agents plays one match, like a round robin where matches
+//! are groups instead of pairs. The number of matches is the binomial coefficient `C(n, k)` for
+//! `n` agents and `k` players per match, so it grows quickly: 8 agents in 4-player matches play
+//! 70 matches, 16 agents play 1820.
+//!
+//! In each match, players are ranked by score, and each place awards points (see
+//! [`FreeForAllTournament::with_placement_points`]). Tied players share the best of their places:
+//! with scores `10, 7, 7, 3`, both players scoring 7 are second, and the last one is fourth.
+//!
+//! Matches can be weighted (see [`FreeForAllTournament::with_match_weight`]): the points of a
+//! match are multiplied by its weight and rounded, while the number of places is not weighted.
+
+use std::{cmp, collections::HashMap, fmt::Display, sync::Arc};
+
+use tracing::warn;
+
+use super::TournamentStrategy;
+use crate::{agent::Agent, match_runner::MatchResult};
+
+/// Standing of an agent in a [`FreeForAllTournament`].
+///
+/// Ordered by points, then by number of first places, of second places, and so on.
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize)]
+pub struct PlacementScore {
+    /// Total points awarded for placements.
+    pub points: u32,
+    /// Number of matches finished at each place (`places[0]` is the number of first places).
+    pub places: Vec<u32>,
+}
+
+impl PartialOrd for PlacementScore {
+    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for PlacementScore {
+    fn cmp(&self, other: &Self) -> cmp::Ordering {
+        self.points
+            .cmp(&other.points)
+            .then_with(|| self.places.cmp(&other.places))
+    }
+}
+
+impl Display for PlacementScore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let places = self
+            .places
+            .iter()
+            .map(u32::to_string)
+            .collect::<Vec<_>>()
+            .join("/");
+        write!(f, "points: {}, places: {places}", self.points)
+    }
+}
+
+/// Weight of a match, from its players
+type MatchWeightFn = Box<dyn Fn(&[Arc<Agent>]) -> f64>;
+
+/// A tournament where every group of `players_per_match` agents plays one match, and agents
+/// score points according to their place in each match (see [module documentation](self)).
+pub struct FreeForAllTournament {
+    players_per_match: usize,
+    placement_points: Vec<u32>,
+    agents: Vec<Arc<Agent>>,
+    scores: HashMap<Arc<Agent>, PlacementScore>,
+    is_scheduled: bool,
+    match_weight: Option<MatchWeightFn>,
+}
+
+impl FreeForAllTournament {
+    /// Creates a new free-for-all tournament with `players_per_match` players in each match.
+    ///
+    /// By default, the last place awards no point, and each better place one more point:
+    /// `3, 2, 1, 0` for 4 players.
+    pub fn new(players_per_match: usize) -> Self {
+        assert!(players_per_match >= 2, "Must play at least two per match.");
+        Self {
+            players_per_match,
+            placement_points: (0..players_per_match as u32).rev().collect(),
+            agents: vec![],
+            scores: HashMap::new(),
+            is_scheduled: false,
+            match_weight: None,
+        }
+    }
+
+    /// Sets the points awarded for each place, first place first (e.g. `[10, 6, 3, 0]`).
+    ///
+    /// Missing places award no point.
+    pub fn with_placement_points(mut self, points: &[u32]) -> Self {
+        if points.len() > self.players_per_match {
+            warn!(
+                "{} placement points given for {} players per match: extra points are ignored",
+                points.len(),
+                self.players_per_match
+            );
+        }
+        self.placement_points = points.to_vec();
+        self
+    }
+
+    /// Sets the weight of each match, from its players (e.g. `2.0` for matches between seeded
+    /// agents). Placement points of a match are multiplied by its weight, and rounded to the
+    /// nearest integer, so fine weights need large placement points.
+    ///
+    /// Default weight is `1.0`.
+    pub fn with_match_weight(mut self, weight: impl Fn(&[Arc<Agent>]) -> f64 + 'static) -> Self {
+        self.match_weight = Some(Box::new(weight));
+        self
+    }
+
+    fn record<S: PartialOrd>(&mut self, result: &MatchResult<S>, weight: f64) {
+        for (place, agent) in placements(result).into_iter().zip(result) {
+            let score = self.scores.entry(agent.0.clone()).or_default();
+            let points = self.placement_points.get(place).copied().unwrap_or(0);
+            score.points += (points as f64 * weight).round() as u32;
+            if score.places.len() < self.players_per_match {
+                score.places.resize(self.players_per_match, 0);
+            }
+            score.places[place] += 1;
+        }
+    }
+}
+
+/// Place (starting at 0) of each player of `result`, in the same order. Tied players share the
+/// best of their places.
+pub fn placements<S: PartialOrd>(result: &MatchResult<S>) -> Vec<usize> {
+    result
+        .iter()
+        .map(|(_, score)| result.iter().filter(|(_, other)| other > score).count())
+        .collect()
+}
+
+/// All subsets of `k` elements of `0..n`, in lexicographic order
+fn combinations(n: usize, k: usize) -> Vec<Vec<usize>> {
+    if k > n {
+        return vec![];
+    }
+    let mut all = vec![];
+    let mut current = (0..k).collect::<Vec<_>>();
+    loop {
+        all.push(current.clone());
+        // rightmost index that can still be increased
+        let Some(i) = (0..k).rev().find(|&i| current[i] < n - k + i) else {
+            return all;
+        };
+        current[i] += 1;
+        for j in i + 1..k {
+            current[j] = current[j - 1] + 1;
+        }
+    }
+}
+
+impl<S: PartialOrd> TournamentStrategy<S> for FreeForAllTournament {
+    fn advance_round(&mut self, scores: Vec<MatchResult<S>>) -> Vec<Vec<Arc<Agent>>> {
+        self.advance_weighted_round(scores.into_iter().map(|result| (result, 1.0)).collect())
+    }
+
+    fn advance_weighted_round(
+        &mut self,
+        scores: Vec<(MatchResult<S>, f64)>,
+    ) -> Vec<Vec<Arc<Agent>>> {
+        for (result, weight) in &scores {
+            self.record(result, *weight);
+        }
+
+        if self.is_scheduled {
+            return vec![];
+        }
+        self.is_scheduled = true;
+
+        combinations(self.agents.len(), self.players_per_match)
+            .into_iter()
+            .map(|group| group.into_iter().map(|i| self.agents[i].clone()).collect())
+            .collect()
+    }
+
+    fn players_per_match(&self) -> usize {
+        self.players_per_match
+    }
+
+    fn match_weight(&self, matchup: &[Arc<Agent>]) -> f64 {
+        self.match_weight
+            .as_ref()
+            .map_or(1.0, |weight| weight(matchup))
+    }
+
+    fn add_agents(&mut self, agents: Vec<Arc<Agent>>) {
+        for agent in &agents {
+            self.scores.insert(
+                agent.clone(),
+                PlacementScore {
+                    points: 0,
+                    places: vec![0; self.players_per_match],
+                },
+            );
+        }
+        self.agents = agents;
+    }
+
+    type FinalScore = PlacementScore;
+
+    fn get_final_scores(&self) -> HashMap<Arc<Agent>, Self::FinalScore> {
+        self.scores.clone()
+    }
+}
+
+#[cfg(test)]
+mod free_for_all_tests {
+    use super::*;
+    use crate::test_support::make_agents;
+
+    #[test]
+    fn test_combinations() {
+        assert_eq!(combinations(4, 4), vec![vec![0, 1, 2, 3]]);
+        assert_eq!(combinations(5, 4).len(), 5);
+        assert_eq!(combinations(8, 4).len(), 70);
+        assert_eq!(
+            combinations(4, 2)[..3],
+            [vec![0, 1], vec![0, 2], vec![0, 3]]
+        );
+        assert!(combinations(3, 4).is_empty());
+    }
+
+    #[test]
+    fn test_placement_points() {
+        let agents = make_agents(4);
+        let mut tournament = FreeForAllTournament::new(4).with_placement_points(&[10, 6, 3]);
+        TournamentStrategy::<u32>::add_agents(&mut tournament, agents.clone());
+
+        let matchups = TournamentStrategy::<u32>::advance_round(&mut tournament, vec![]);
+        assert_eq!(matchups, vec![agents.clone()]);
+
+        // agent_2 first, agent_0 second, agent_3 third, agent_1 last
+        let points = [20, 5, 40, 12];
+        let result = agents.iter().map(|a| (a.clone(), points[a.id as usize]));
+        assert!(tournament.advance_round(vec![result.collect()]).is_empty());
+
+        let scores = TournamentStrategy::<u32>::get_final_scores(&tournament);
+        let points = agents.iter().map(|a| scores[a].points).collect::<Vec<_>>();
+        assert_eq!(points, [6, 0, 10, 3]);
+        assert_eq!(scores[&agents[2]].places, [1, 0, 0, 0]);
+        assert_eq!(scores[&agents[1]].places, [0, 0, 0, 1]);
+        assert!(scores[&agents[2]] > scores[&agents[0]]);
+    }
+
+    #[test]
+    fn test_ties_share_best_place() {
+        let agents = make_agents(4);
+        let result = agents
+            .iter()
+            .zip([10, 7, 7, 3])
+            .map(|(a, score)| (a.clone(), score))
+            .collect::<Vec<_>>();
+        assert_eq!(placements(&result), [0, 1, 1, 3]);
+
+        let mut tournament = FreeForAllTournament::new(4);
+        TournamentStrategy::<u32>::add_agents(&mut tournament, agents.clone());
+        tournament.record(&result, 1.0);
+        let scores = TournamentStrategy::<u32>::get_final_scores(&tournament);
+        let points = agents.iter().map(|a| scores[a].points).collect::<Vec<_>>();
+        assert_eq!(points, [3, 2, 2, 0]);
+    }
+
+    #[test]
+    fn test_match_weight() {
+        let agents = make_agents(3);
+        // matches with agent_0 count double
+        let mut tournament = FreeForAllTournament::new(2).with_match_weight(|players| {
+            if players[0].id == 0 {
+                2.0
+            } else {
+                1.0
+            }
+        });
+        TournamentStrategy::<u32>::add_agents(&mut tournament, agents.clone());
+        let matchups = TournamentStrategy::<u32>::advance_round(&mut tournament, vec![]);
+        let weights = matchups
+            .iter()
+            .map(|m| TournamentStrategy::<u32>::match_weight(&tournament, m))
+            .collect::<Vec<_>>();
+        assert_eq!(weights, [2.0, 2.0, 1.0]);
+
+        // higher ID wins
+        let results = matchups
+            .iter()
+            .zip(weights)
+            .map(|(m, w)| (m.iter().map(|a| (a.clone(), a.id)).collect(), w))
+            .collect();
+        assert!(tournament.advance_weighted_round(results).is_empty());
+        let scores = TournamentStrategy::<u32>::get_final_scores(&tournament);
+        let points = agents.iter().map(|a| scores[a].points).collect::<Vec<_>>();
+        assert_eq!(points, [0, 2, 3]);
+        assert_eq!(scores[&agents[2]].places, [2, 0]);
+    }
+}
@@ -0,0 +1,269 @@
+//! Gauntlet: one designated agent (the "hero") against every other agent.
+//!
+//! Typical use is regression testing: put every past version of a bot in a directory, along with
+//! the new one, and check how the new version performs against all of them. Opponents never play
+//! each other, so the number of matches grows linearly with the number of opponents.
+//!
+//! All matches are scheduled in a single round, so that they can all run in parallel.
+
+use std::{collections::HashMap, sync::Arc};
+
+use tracing::error;
+
+use crate::{agent::Agent, match_runner::MatchResult};
+
+use super::{TournamentStrategy, TwoPlayersGameScore};
+
+/// A two-player tournament where a single agent (the hero) plays `games_per_opponent` games
+/// against every other agent.
+///
+/// Sides alternate between games (the hero plays first in even games). Each game counts as a
+/// win, draw or loss, with the same rule as [`RoundRobinTournament`](super::RoundRobinTournament).
+///
+/// Final scores are the hero's record against all opponents, and each opponent's record against
+/// the hero. Use [`GauntletReport::from_scores`] to get the hero's point of view.
+pub struct GauntletTournament {
+    hero_name: String,
+    games_per_opponent: usize,
+    hero: Option<Arc<Agent>>,
+    opponents: Vec<Arc<Agent>>,
+    scores: HashMap<Arc<Agent>, TwoPlayersGameScore>,
+    is_scheduled: bool,
+}
+
+impl GauntletTournament {
+    /// Creates a new gauntlet where the agent named `hero` plays `games_per_opponent` games against
+    /// each other agent.
+    ///
+    /// If no agent is named `hero`, no match is played.
+    pub fn new(hero: impl Into<String>, games_per_opponent: usize) -> Self {
+        Self {
+            hero_name: hero.into(),
+            games_per_opponent,
+            hero: None,
+            opponents: vec![],
+            scores: HashMap::new(),
+            is_scheduled: false,
+        }
+    }
+}
+
+impl<S: PartialOrd> TournamentStrategy<S> for GauntletTournament {
+    fn advance_round(&mut self, scores: Vec<MatchResult<S>>) -> Vec<Vec<Arc<Agent>>> {
+        for match_result in scores {
+            let [(a, score_a), (b, score_b)] = &match_result[..] else {
+                continue;
+            };
+            for (agent, score, other) in [(a, score_a, score_b), (b, score_b, score_a)] {
+                let entry = self.scores.entry(agent.clone()).or_default();
+                if score > other {
+                    entry.num_win += 1;
+                } else if score < other {
+                    entry.num_lose += 1;
+                } else {
+                    entry.num_draw += 1;
+                }
+            }
+        }
+
+        if self.is_scheduled {
+            return vec![];
+        }
+        self.is_scheduled = true;
+
+        let Some(hero) = &self.hero else {
+            return vec![];
+        };
+        let mut pending = vec![];
+        for opponent in &self.opponents {
+            for i in 0..self.games_per_opponent {
+                if i % 2 == 0 {
+                    pending.push(vec![hero.clone(), opponent.clone()]);
+                } else {
+                    pending.push(vec![opponent.clone(), hero.clone()]);
+                }
+            }
+        }
+        pending
+    }
+
+    fn players_per_match(&self) -> usize {
+        2
+    }
+
+    fn add_agents(&mut self, agents: Vec<Arc<Agent>>) {
+        let (hero, opponents) = agents
+            .into_iter()
+            .partition::<Vec<_>, _>(|a| a.name == self.hero_name);
+        self.hero = hero.into_iter().next();
+        if self.hero.is_none() {
+            error!("Gauntlet hero '{}' not found", self.hero_name);
+        }
+        self.opponents = opponents;
+    }
+
+    type FinalScore = TwoPlayersGameScore;
+
+    fn get_final_scores(&self) -> HashMap<Arc<Agent>, Self::FinalScore> {
+        self.scores.clone()
+    }
+}
+
+/// Summary of a gauntlet from the hero's point of view.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GauntletReport {
+    /// Name of the hero.
+    pub hero: String,
+    /// Hero's record against all opponents.
+    pub total: TwoPlayersGameScore,
+    /// Hero's record against each opponent, by opponent name.
+    pub opponents: HashMap<String, TwoPlayersGameScore>,
+}
+
+impl GauntletReport {
+    /// Builds the report from the final scores of a [`GauntletTournament`] (as returned by
+    /// [`Evaluator::evaluate`](crate::server::Evaluator::evaluate)).
+    ///
+    /// Returns `None` if `hero` has no score (not found, or no match played).
+    pub fn from_scores(
+        hero: &str,
+        scores: &HashMap<String, TwoPlayersGameScore>,
+    ) -> Option<GauntletReport> {
+        let total = *scores.get(hero)?;
+        let opponents = scores
+            .iter()
+            .filter(|(name, _)| *name != hero)
+            .map(|(name, score)| {
+                // the opponent's record against the hero, reversed
+                let score = TwoPlayersGameScore {
+                    num_win: score.num_lose,
+                    num_draw: score.num_draw,
+                    num_lose: score.num_win,
+                    tie_breaker: 0,
+                };
+                (name.clone(), score)
+            })
+            .collect();
+        Some(GauntletReport {
+            hero: hero.to_string(),
+            total,
+            opponents,
+        })
+    }
+
+    /// Fraction of games won by the hero (draws excluded), between 0 and 1.
+    pub fn win_rate(&self) -> f64 {
+        rate(&self.total, 0.0)
+    }
+
+    /// Overall strength of the hero: fraction of points scored, a draw being worth half a win.
+    pub fn strength(&self) -> f64 {
+        rate(&self.total, 0.5)
+    }
+
+    /// Hero's strength against each opponent, weakest matchup first.
+    pub fn breakdown(&self) -> Vec<(&str, f64)> {
+        let mut breakdown = self
+            .opponents
+            .iter()
+            .map(|(name, score)| (name.as_str(), rate(score, 0.5)))
+            .collect::<Vec<_>>();
+        breakdown.sort_by(|a, b| a.1.total_cmp(&b.1).then(a.0.cmp(b.0)));
+        breakdown
+    }
+}
+
+fn rate(score: &TwoPlayersGameScore, draw_value: f64) -> f64 {
+    let games = score.num_win + score.num_draw + score.num_lose;
+    if games == 0 {
+        return 0.0;
+    }
+    (score.num_win as f64 + draw_value * score.num_draw as f64) / games as f64
+}
+
+impl std::fmt::Display for GauntletReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "{}: strength {:.1}%, win rate {:.1}% ({})",
+            self.hero,
+            self.strength() * 100.0,
+            self.win_rate() * 100.0,
+            self.total
+        )?;
+        for (name, strength) in self.breakdown() {
+            writeln!(
+                f,
+                "  vs {name}: {:.1}% ({})",
+                strength * 100.0,
+                self.opponents[name]
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod gauntlet_tests {
+    use super::*;
+    use crate::test_support::make_agents;
+
+    /// Simulates a match: higher ID wins.
+    fn simulate_round(matchups: &[Vec<Arc<Agent>>]) -> Vec<MatchResult<u32>> {
+        matchups
+            .iter()
+            .map(|pair| vec![(pair[0].clone(), pair[0].id), (pair[1].clone(), pair[1].id)])
+            .collect()
+    }
+
+    #[test]
+    fn test_pairings() {
+        let agents = make_agents(5);
+        let mut gauntlet = GauntletTournament::new("agent_2", 3);
+        TournamentStrategy::<u32>::add_agents(&mut gauntlet, agents.clone());
+
+        let matchups = TournamentStrategy::<u32>::advance_round(&mut gauntlet, vec![]);
+        assert_eq!(matchups.len(), 4 * 3);
+        for pair in &matchups {
+            assert!(pair.contains(&agents[2]));
+            assert_ne!(pair[0], pair[1]);
+        }
+        let hero_first = matchups.iter().filter(|p| p[0] == agents[2]).count();
+        assert_eq!(hero_first, 4 * 2);
+
+        let results = simulate_round(&matchups);
+        assert!(gauntlet.advance_round(results).is_empty());
+    }
+
+    #[test]
+    fn test_report() {
+        let agents = make_agents(4);
+        let mut gauntlet = GauntletTournament::new("agent_1", 2);
+        TournamentStrategy::<u32>::add_agents(&mut gauntlet, agents.clone());
+        let matchups = TournamentStrategy::<u32>::advance_round(&mut gauntlet, vec![]);
+        let results = simulate_round(&matchups);
+        assert!(gauntlet.advance_round(results).is_empty());
+
+        let scores = TournamentStrategy::<u32>::get_final_scores(&gauntlet)
+            .into_iter()
+            .map(|(agent, score)| (agent.name.clone(), score))
+            .collect();
+        let report = GauntletReport::from_scores("agent_1", &scores).unwrap();
+
+        assert_eq!(report.total.num_win, 2);
+        assert_eq!(report.total.num_lose, 4);
+        assert_eq!(report.opponents["agent_0"].num_win, 2);
+        assert_eq!(report.opponents["agent_3"].num_lose, 2);
+        assert!((report.win_rate() - 1.0 / 3.0).abs() < 1e-9);
+        assert_eq!(report.breakdown()[0], ("agent_2", 0.0));
+        assert_eq!(report.breakdown()[2], ("agent_0", 1.0));
+    }
+
+    #[test]
+    fn test_missing_hero() {
+        let mut gauntlet = GauntletTournament::new("nobody", 2);
+        TournamentStrategy::<u32>::add_agents(&mut gauntlet, make_agents(3));
+        assert!(TournamentStrategy::<u32>::advance_round(&mut gauntlet, vec![]).is_empty());
+        assert!(GauntletReport::from_scores("nobody", &HashMap::new()).is_none());
+    }
+}
@@ -0,0 +1,1809 @@
+//! Tournament strategies used by the evaluator to schedule agent matchups.
+//!
+//! This module defines the [`TournamentStrategy`] trait and several built-in strategies
+//! (e.g., Swiss, Round Robin, Single Player) used by the server-side evaluator to structure
+//! tournaments and process match results.
+//!
+//! Although this trait and types are public to allow advanced users to define custom strategies,
+//! they are not intended for direct use or manual orchestration of tournaments.
+//!
+//! # Provided Strategies
+//! - [`RoundRobinTournament`]: Every agent plays every other agent. Quite slow.
+//! - [`SwissTournament`]: Pairings based on score, with optional tie-breakers. Mush faster than Round Robin
+//! - [`SinglePlayerTournament`]: Each agent plays independently multiple times.
+//! - [`ExternalPairingTournament`]: Swiss pairings computed by an external FIDE pairing engine.
+//! - [`GauntletTournament`]: One agent plays every other agent, e.g. to regression-test a new version.
+//! - [`TrueSkillTournament`]: Bayesian skill rating with uncertainty, pairing agents of similar skill.
+//! - [`FreeForAllTournament`]: Every group of agents plays a multiplayer match, scored by placement.
+//! - [`FixedScheduleTournament`]: A predefined list of matches, e.g. read from a file.
+//!
+//! [`Calibrated`] wraps any of them with a few rounds of random matches, to seed the agents
+//! before the first round.
+//!
+//! # Implementing a Custom Strategy
+//! To implement a new tournament format, define your own type that implements
+//! [`TournamentStrategy`].
+//!
+//! The server will call `add_agents`, then repeatedly call `advance_round`
+//! until it returns an empty list. Once finished, `get_final_scores` is used to produce the ranking.
+//!
+//! [`PlayedPairs`] keeps track of who has played whom, e.g. to avoid rematches.
+//!
+//! [`ScriptedRunner`](crate::test_support::ScriptedRunner) plays a strategy through all its rounds
+//! with scripted results, to unit-test it without agents (see [`test_support`](crate::test_support)).
+//!
+//! [`DrawPolicy`] changes how draws count in Swiss and round-robin tournaments (e.g. as a loss for
+//! both agents, or replayed until decisive).
+//!
+//! [`TieBreaker`] chooses how Swiss tournaments order agents with the same points (e.g.
+//! Sonneborn-Berger, the standard of chess tournaments).
+//!
+//! # Invalid matches
+//!
+//! A custom strategy may return matches the evaluator cannot play, whose number of players is not
+//! [`TournamentStrategy::players_per_match`], and built-in strategies may receive results they
+//! cannot score (e.g. a Swiss match of an agent against itself). In debug builds, these are
+//! assertion failures, to catch the bug early. In release builds, they are logged as errors,
+//! and the match or result is skipped: the rest of the tournament is played, so a long run is not
+//! lost to a single bad match.
+
+use std::{
+    cmp,
+    collections::{BTreeMap, HashMap, HashSet, VecDeque},
+    fmt::Display,
+    ops::Add,
+    sync::Arc,
+};
+
+use tracing::{error, info, warn};
+
+use crate::{agent::Agent, match_runner::MatchResult};
+
+pub mod calibrated;
+pub mod external_pairing;
+pub mod fixed_schedule;
+pub mod free_for_all;
+pub mod gauntlet;
+pub mod trueskill;
+
+pub use calibrated::Calibrated;
+pub use external_pairing::{ExternalPairingTournament, PairingEngine};
+pub use fixed_schedule::FixedScheduleTournament;
+pub use free_for_all::{FreeForAllTournament, PlacementScore};
+pub use gauntlet::{GauntletReport, GauntletTournament};
+pub use trueskill::{TrueSkillRating, TrueSkillTournament};
+
+/// A trait defining how agents are grouped, matched, and scored in a tournament.
+///
+/// Implement this trait to define a custom tournament format. The tournament is responsible for:
+/// - Receiving a set of agents
+/// - Generating matches to run per round
+/// - Processing match results
+/// - Producing a final score for each agent
+pub trait TournamentStrategy<S: PartialOrd> {
+    /// The score type produced at the end of the tournament.
+    type FinalScore: Ord;
+
+    /// Adds a list of agents to the tournament.
+    ///
+    /// Must be called before advancing rounds. This method may be used to initialize internal
+    /// score or pairing state.
+    fn add_agents(&mut self, agents: Vec<Arc<Agent>>);
+
+    /// Returns new matchups for the next round, based on the latest match results.
+    ///
+    /// If the returned list is empty, the tournament is finished.
+    ///
+    /// Each match is a list of agents (usually 2), and will be scored externally.
+    fn advance_round(&mut self, scores: Vec<MatchResult<S>>) -> Vec<Vec<Arc<Agent>>>;
+
+    /// Returns the number of players per match required by this strategy.
+    ///
+    /// This value must match the length of each sub-`Vec` returned by `advance_round`.
+    fn players_per_match(&self) -> usize;
+
+    /// Names of the roles of asymmetric games (e.g. `["attacker", "defender"]`), in play order.
+    ///
+    /// When roles are declared, each match returned by `advance_round` is played once per
+    /// rotation of its players, so that every agent of a match plays every role once: with two
+    /// roles, `[a, b]` is played as `[a, b]` and `[b, a]`. All those matches are given back to
+    /// `advance_round` with the other results of the round.
+    ///
+    /// In every match result (and in [`MatchRecord`](crate::results::MatchRecord)), the player
+    /// at position `i` played the role `roles()[i]`, and its score is the score of that role.
+    ///
+    /// The number of roles must be equal to [`players_per_match`](Self::players_per_match).
+    /// Default is no role: matches are played as returned, in a single order.
+    fn roles(&self) -> Vec<String> {
+        vec![]
+    }
+
+    /// Weight of a match returned by the last call to `advance_round`, e.g. to make later rounds
+    /// or "ranking" matches count more. Called once per match to play (once per rotation when
+    /// [roles](Self::roles) are declared).
+    ///
+    /// The weight is given back with the result of the match to
+    /// [`advance_weighted_round`](Self::advance_weighted_round), and recorded in
+    /// [`MatchRecord::weight`](crate::results::MatchRecord::weight). It is up to the strategy to
+    /// apply it: weights make sense for accumulated points (e.g. the placement points of
+    /// [`FreeForAllTournament`]), not for win/draw/loss counts, where a match is one win whatever
+    /// its weight. Built-in strategies counting wins, draws and losses ignore weights.
+    ///
+    /// Default is `1.0` for every match.
+    fn match_weight(&self, matchup: &[Arc<Agent>]) -> f64 {
+        let _ = matchup;
+        1.0
+    }
+
+    /// Same as [`advance_round`](Self::advance_round), with the weight of each result (see
+    /// [`match_weight`](Self::match_weight)). This is what the evaluator calls.
+    ///
+    /// Default implementation ignores weights and calls `advance_round`.
+    fn advance_weighted_round(
+        &mut self,
+        scores: Vec<(MatchResult<S>, f64)>,
+    ) -> Vec<Vec<Arc<Agent>>> {
+        self.advance_round(scores.into_iter().map(|(result, _)| result).collect())
+    }
+
+    /// Adds an agent to a tournament that already started, between two rounds (see
+    /// [`TournamentControl::add_late_agent`](crate::server::TournamentControl::add_late_agent)).
+    ///
+    /// Returns false if the strategy does not accept late agents, which is the default.
+    fn add_late_agent(&mut self, agent: Arc<Agent>) -> bool {
+        let _ = agent;
+        false
+    }
+
+    /// Number of byes given so far (a round skipped by an agent for lack of opponent). Strategies
+    /// without byes return 0, which is the default.
+    fn num_byes(&self) -> usize {
+        0
+    }
+
+    /// Whether a greater final score is a better one. Default is `true`.
+    ///
+    /// Strategies whose best agents have the lowest final scores (e.g. golf-style tournaments,
+    /// counting strokes) return `false`, so that [`final_ranking_ordered`] and
+    /// [`EvaluationReport::ranking`] rank them first.
+    ///
+    /// [`final_ranking_ordered`]: crate::results::final_ranking_ordered
+    /// [`EvaluationReport::ranking`]: crate::results::EvaluationReport::ranking
+    fn higher_is_better(&self) -> bool {
+        true
+    }
+
+    /// Returns the final scores for all agents once the tournament is complete.
+    fn get_final_scores(&self) -> HashMap<Arc<Agent>, Self::FinalScore>;
+}
+
+/// Score types that two-player strategies can aggregate and compare.
+///
+/// When a pairing plays several games, the scores of each side are summed (`Add`), then the two
+/// sums are compared with [`MatchOutcome::compare`] to decide the pairing's win/draw/loss.
+///
+/// Integer types compare exactly. Float types consider sums closer than their `EPSILON` as a draw.
+///
+/// Other score types can be used with [`SwissTournament`], [`TrueSkillTournament`] and
+/// [`ExternalPairingTournament`] by implementing this trait, e.g. a fixed-point score:
+///
+/// ```
+/// use std::{cmp::Ordering, fmt, ops::Add};
+///
+/// use ai_tournament::tournament_strategy::MatchOutcome;
+///
+/// /// Chips won, in cents
+/// #[derive(Debug, Clone, Copy, Default, PartialEq, PartialOrd)]
+/// struct Chips(i64);
+///
+/// impl Add for Chips {
+///     type Output = Chips;
+///     fn add(self, other: Chips) -> Chips {
+///         Chips(self.0 + other.0)
+///     }
+/// }
+///
+/// impl fmt::Display for Chips {
+///     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+///         write!(f, "{}.{:02}", self.0 / 100, self.0.abs() % 100)
+///     }
+/// }
+///
+/// impl MatchOutcome for Chips {
+///     fn compare(a: &Self, b: &Self) -> Ordering {
+///         a.0.cmp(&b.0)
+///     }
+/// }
+///
+/// assert_eq!(MatchOutcome::compare(&Chips(150), &(Chips(100) + Chips(50))), Ordering::Equal);
+/// ```
+pub trait MatchOutcome: Copy + Default + Add<Output = Self> + PartialOrd + Display {
+    /// Compares the aggregated scores of two agents. `Equal` means a draw.
+    fn compare(a: &Self, b: &Self) -> cmp::Ordering;
+}
+
+macro_rules! impl_exact_match_outcome {
+    ($($t:ty),*) => {
+        $(
+            impl MatchOutcome for $t {
+                fn compare(a: &Self, b: &Self) -> cmp::Ordering {
+                    a.cmp(b)
+                }
+            }
+        )*
+    };
+}
+
+impl_exact_match_outcome!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+
+macro_rules! impl_float_match_outcome {
+    ($($t:ty),*) => {
+        $(
+            impl MatchOutcome for $t {
+                fn compare(a: &Self, b: &Self) -> cmp::Ordering {
+                    if (a - b).abs() < <$t>::EPSILON {
+                        cmp::Ordering::Equal
+                    } else {
+                        a.partial_cmp(b).unwrap_or(cmp::Ordering::Equal)
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_float_match_outcome!(f32, f64);
+
+/// Score summary for agents in two-player tournaments.
+///
+/// Used in `SwissTournament` and `RoundRobinTournament`. This type tracks the total number of wins,
+/// draws, losses, and an optional tie-breaker value.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Default, Debug, Clone, Copy, serde::Serialize)]
+pub struct TwoPlayersGameScore {
+    /// Number of wins.
+    pub num_win: u32,
+    /// Number of draws.
+    pub num_draw: u32,
+    /// Number of losses.
+    pub num_lose: u32,
+    /// Additional tie-breaker value.
+    pub tie_breaker: u32,
+}
+
+impl std::fmt::Display for TwoPlayersGameScore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "win: {}, draw: {}, loose: {}, tie-breaker: {}",
+            self.num_win, self.num_draw, self.num_lose, self.tie_breaker
+        )
+    }
+}
+
+/// How [`SwissTournament`] and [`RoundRobinTournament`] count a draw.
+///
+/// In Swiss tournaments, a draw is a pairing whose aggregated scores are equal (see
+/// [`MatchOutcome::compare`]). In round-robin tournaments, it is a match where all players have the
+/// same score.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DrawPolicy {
+    /// A draw for both agents.
+    #[default]
+    Draw,
+    /// A loss for both agents, to force decisive play.
+    LossForBoth,
+    /// A win for both agents, e.g. in cooperative games.
+    WinForBoth,
+    /// The drawn pairing (or match) is played again, up to `max_replays` times in a row. If it is
+    /// still a draw, it counts as a draw for both agents.
+    ///
+    /// Replays are played before the next pairing, as an extra round of the evaluator.
+    Replay {
+        /// Maximum number of replays of a pairing in a round.
+        max_replays: usize,
+    },
+}
+
+impl DrawPolicy {
+    /// Records a draw that is not replayed in `score`
+    fn record_draw(self, score: &mut TwoPlayersGameScore) {
+        match self {
+            DrawPolicy::Draw | DrawPolicy::Replay { .. } => score.num_draw += 1,
+            DrawPolicy::LossForBoth => score.num_lose += 1,
+            DrawPolicy::WinForBoth => score.num_win += 1,
+        }
+    }
+
+    /// Returns true if a draw replayed `replays` times must be replayed again
+    fn should_replay(self, replays: usize) -> bool {
+        matches!(self, DrawPolicy::Replay { max_replays } if replays < max_replays)
+    }
+}
+
+/// How [`SwissTournament`] computes the tie-breaker of each agent
+/// ([`TwoPlayersGameScore::tie_breaker`]), to order agents with the same points.
+///
+/// Points are counted in half-points (2 for a win or a bye, 1 for a draw), so tie-breakers are
+/// integers: they are meant to be compared, not to match the values of a chess arbiter (which
+/// count 1 point per win).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TieBreaker {
+    /// Sum of the points of the agent's opponents, without the best and the worst one (Median
+    /// Buchholz). Agents with fewer than two opponents have a tie-breaker of `0`.
+    #[default]
+    Median,
+    /// Sum of the points of the agent's opponents.
+    Buchholz,
+    /// Sum, over the agent's opponents, of the points of the opponent multiplied by the points the
+    /// agent scored against it: beaten opponents count fully, drawn opponents for half, and lost
+    /// games not at all.
+    SonnebornBerger,
+    /// No tie-breaker: always `0`.
+    None,
+}
+
+/// Record of the pairs of agents that have played each other, for strategies avoiding rematches.
+///
+/// Pairs are unordered (A vs B is the same pair as B vs A) and identified by agent ID.
+#[derive(Debug, Clone, Default)]
+pub struct PlayedPairs {
+    pairs: HashMap<(u32, u32), usize>,
+}
+
+impl PlayedPairs {
+    /// Creates an empty record.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn key(a: &Agent, b: &Agent) -> (u32, u32) {
+        (a.id.min(b.id), a.id.max(b.id))
+    }
+
+    /// Records that `a` and `b` played each other (once more).
+    pub fn record(&mut self, a: &Agent, b: &Agent) {
+        *self.pairs.entry(Self::key(a, b)).or_default() += 1;
+    }
+
+    /// Returns true if `a` and `b` played each other at least once.
+    pub fn has_played(&self, a: &Agent, b: &Agent) -> bool {
+        self.count(a, b) > 0
+    }
+
+    /// Returns the number of times `a` and `b` played each other.
+    pub fn count(&self, a: &Agent, b: &Agent) -> usize {
+        self.pairs.get(&Self::key(a, b)).copied().unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod played_pairs_tests {
+    use super::*;
+
+    #[test]
+    fn test_played_pairs() {
+        let a = Agent::new("a".into(), None, None, 0, None);
+        let b = Agent::new("b".into(), None, None, 1, None);
+        let c = Agent::new("c".into(), None, None, 2, None);
+        let mut played = PlayedPairs::new();
+        assert!(!played.has_played(&a, &b));
+
+        played.record(&a, &b);
+        played.record(&b, &a);
+        assert!(played.has_played(&a, &b));
+        assert!(played.has_played(&b, &a));
+        assert_eq!(played.count(&b, &a), 2);
+        assert!(!played.has_played(&a, &c));
+        assert_eq!(played.count(&b, &c), 0);
+    }
+}
+
+/// A Swiss-style tournament strategy for two-player games. Does not follow strictly the Swiss
+/// tournament rules.
+///
+/// Works with any game score implementing [`MatchOutcome`] (integers and floats).
+///
+/// Agents are paired based on their current score. The number of rounds can be fixed,
+/// or automatically determined as `ceil(log2(num_players))`.
+///
+/// Two agents never meet twice: each agent is paired with the closest-ranked opponent it has not
+/// played yet. When the remaining pairs do not allow pairing everyone (e.g. with more rounds than
+/// opponents), agents left without a new opponent sit out the round.
+pub struct SwissTournament {
+    agents: Vec<Arc<Agent>>,
+    round: usize,
+    max_rounds: usize,
+    num_match_per_pair: usize,
+    scores: HashMap<Arc<Agent>, TwoPlayersGameScore>,
+    played: PlayedPairs,
+    bye_history: HashSet<Arc<Agent>>,
+    num_byes: usize,
+    draw_policy: DrawPolicy,
+    /// Number of replays of each drawn pair (by ids) in the current round
+    replays: HashMap<(u32, u32), usize>,
+    /// Drawn pairs to play again before the next round
+    pending_replays: Vec<(Arc<Agent>, Arc<Agent>)>,
+    early_termination: bool,
+    tie_breaker: TieBreaker,
+    /// Points (in half-points) scored by the first agent against the second one, by ids
+    points_against: HashMap<(u32, u32), u32>,
+}
+
+impl SwissTournament {
+    /// Creates a new Swiss tournament with the number of matches per pair and automatic number of rounds.
+    ///
+    /// The number of rounds is determined automatically based on the number of agents,
+    /// using the formula `ceil(log2(n))`, where `n` is the number of players.
+    ///
+    /// Each pair of agents will play `num_match_per_pair` games per round. If the game is
+    /// asymmetric, this number should be even to ensure fairness.
+    /// The order of players will alternate between games to account for side asymmetry.
+    /// The results of these games are aggregated into a single win/loss/draw outcome
+    /// for Swiss pairing and scoring purposes.
+    pub fn with_auto_rounds(num_match_per_pair: usize) -> Self {
+        Self::new(0, num_match_per_pair)
+    }
+
+    /// Creates a new Swiss tournament with a specified number of rounds and matches per pair.
+    ///
+    /// If `max_rounds` is set to `0`, this is equivalent to using
+    /// [`with_auto_rounds`](Self::with_auto_rounds).
+    ///
+    /// Each pair of agents will play `num_match_per_pair` games per round.
+    /// The order of players will alternate between games to account for side asymmetry.
+    /// The results of these games are aggregated into a single win/loss/draw outcome
+    /// for Swiss pairing and scoring purposes.
+    pub fn new(max_rounds: usize, num_match_per_pair: usize) -> Self {
+        assert!(
+            num_match_per_pair >= 1,
+            "Must play at least one match per pairing."
+        );
+        Self {
+            agents: vec![],
+            round: 0,
+            max_rounds,
+            num_match_per_pair,
+            scores: HashMap::new(),
+            played: PlayedPairs::new(),
+            bye_history: HashSet::new(),
+            num_byes: 0,
+            draw_policy: DrawPolicy::Draw,
+            replays: HashMap::new(),
+            pending_replays: vec![],
+            early_termination: false,
+            tie_breaker: TieBreaker::Median,
+            points_against: HashMap::new(),
+        }
+    }
+
+    /// Sets how drawn pairings are counted (see [`DrawPolicy`]). Default is
+    /// [`DrawPolicy::Draw`].
+    pub fn with_draw_policy(mut self, draw_policy: DrawPolicy) -> Self {
+        self.draw_policy = draw_policy;
+        self
+    }
+
+    /// Sets how tie-breakers are computed (see [`TieBreaker`]). Default is
+    /// [`TieBreaker::Median`].
+    pub fn with_tie_breaker(mut self, tie_breaker: TieBreaker) -> Self {
+        self.tie_breaker = tie_breaker;
+        self
+    }
+
+    /// Stops the tournament before `max_rounds` when the leader can no longer be caught: no other
+    /// agent could reach the leader's points, even by winning all remaining rounds.
+    ///
+    /// This saves the rounds that cannot change the winner, but the remaining places are decided
+    /// on fewer games, and tie-breakers are computed on fewer opponents. Default is `false`.
+    pub fn with_early_termination(mut self, early_termination: bool) -> Self {
+        self.early_termination = early_termination;
+        self
+    }
+
+    /// Whether the leader has more points than any other agent can reach in the remaining rounds
+    /// (a win or a bye being worth 2 points, a draw 1).
+    fn is_leader_uncatchable(&self) -> bool {
+        let mut points = self
+            .scores
+            .values()
+            .map(|score| score.num_win * 2 + score.num_draw)
+            .collect::<Vec<_>>();
+        points.sort_unstable_by(|a, b| b.cmp(a));
+        let [leader, second, ..] = points[..] else {
+            return false;
+        };
+        let remaining_rounds = self.max_rounds.saturating_sub(self.round) as u32;
+        leader > second + 2 * remaining_rounds
+    }
+
+    /// Adds an agent to the tournament after it started, with the given starting score. It is
+    /// paired from the next round on.
+    ///
+    /// A late agent missed the previous rounds, so its standing is only as fair as
+    /// `initial_score`: a typical choice is a draw for each round played so far (what the
+    /// [`TournamentStrategy::add_late_agent`] implementation gives). Its tie-breaker only
+    /// considers the opponents it actually met, and it may receive a bye although another agent
+    /// already received one. The number of rounds is not changed, even when computed
+    /// automatically.
+    pub fn add_late_agent(&mut self, agent: Arc<Agent>, initial_score: TwoPlayersGameScore) {
+        info!(
+            "{} joins the tournament after round {} ({initial_score})",
+            agent.name, self.round
+        );
+        self.scores.insert(agent.clone(), initial_score);
+        self.agents.push(agent);
+    }
+
+    fn recursive_pairing_search(
+        &self,
+        ordered_players: &[Arc<Agent>],
+        out_pairs: &mut Vec<(Arc<Agent>, Arc<Agent>)>,
+        out_bye: &mut Option<Arc<Agent>>,
+    ) -> bool {
+        if ordered_players.is_empty() {
+            return true;
+        }
+
+        // Bye required when number of players is odd. Do it first to try from lowest score to highest (in swiss tournaments,
+        // byes are typically assigned to the lowest-ranked eligible player)
+        if ordered_players.len() % 2 == 1 {
+            assert!(out_bye.is_none());
+            // ordered from lowest to highest
+            for i in (0..ordered_players.len()).rev() {
+                let p = &ordered_players[i];
+                if !self.bye_history.contains(p) {
+                    let mut rest = ordered_players.to_vec();
+                    rest.remove(i); // no swap_remove! We want to preserve ordering
+                    if self.recursive_pairing_search(&rest, out_pairs, out_bye) {
+                        *out_bye = Some(p.clone());
+                        return true;
+                    }
+                }
+            }
+            return false;
+        }
+
+        // Else: even number of players, no bye allowed (max 1 bye/round)
+        for i in 1..ordered_players.len() {
+            let a = &ordered_players[0];
+            let b = &ordered_players[i];
+
+            // no double pairing
+            if self.played.has_played(a, b) {
+                continue;
+            }
+
+            let mut rest = Vec::with_capacity(ordered_players.len() - 2);
+            rest.extend_from_slice(&ordered_players[1..i]);
+            rest.extend_from_slice(&ordered_players[i + 1..]);
+
+            if self.recursive_pairing_search(&rest, out_pairs, out_bye) {
+                out_pairs.push((a.clone(), b.clone()));
+                return true;
+            }
+        }
+
+        false
+    }
+
+    fn update_tie_breakers(&mut self) {
+        // https://en.wikipedia.org/wiki/Tie-breaking_in_Swiss-system_tournaments
+        for agent in &self.agents {
+            let mut adv_scores = vec![];
+            let mut sonneborn_berger = 0;
+            for adv in &self.agents {
+                if !self.played.has_played(agent, adv) {
+                    continue;
+                }
+                let adv_score = &self.scores[adv];
+                let adv_score = adv_score.num_win * 2 + adv_score.num_draw;
+                adv_scores.push(adv_score);
+                let points = self
+                    .points_against
+                    .get(&(agent.id, adv.id))
+                    .copied()
+                    .unwrap_or(0);
+                sonneborn_berger += points * adv_score;
+            }
+            self.scores.get_mut(agent).unwrap().tie_breaker = match self.tie_breaker {
+                TieBreaker::Median => {
+                    let min = *adv_scores.iter().min().unwrap_or(&0);
+                    let max = *adv_scores.iter().max().unwrap_or(&0);
+                    if adv_scores.len() <= 1 {
+                        0
+                    } else {
+                        adv_scores.iter().sum::<u32>() - min - max
+                    }
+                }
+                TieBreaker::Buchholz => adv_scores.iter().sum(),
+                TieBreaker::SonnebornBerger => sonneborn_berger,
+                TieBreaker::None => 0,
+            };
+        }
+    }
+
+    fn update_scores<S: MatchOutcome>(&mut self, match_results: Vec<MatchResult<S>>) {
+        let mut pair_results: HashMap<_, Vec<_>> =
+            HashMap::with_capacity(match_results.len() / self.num_match_per_pair);
+
+        // 1. aggregate score per pair
+        for result in match_results {
+            debug_assert!(result.len() == 2, "not two players match ??");
+            if result.len() != 2 {
+                error!("result of a {}-player match ignored", result.len());
+                continue;
+            }
+
+            let (a, score_a) = &result[0];
+            let (b, score_b) = &result[1];
+
+            debug_assert!(
+                !Arc::ptr_eq(a, b) && a.id != b.id,
+                "should not be able to play against yourself"
+            );
+            if Arc::ptr_eq(a, b) || a.id == b.id {
+                error!("result of {} against itself ignored", a.name);
+                continue;
+            }
+
+            let key = if a.id < b.id {
+                (a.clone(), b.clone())
+            } else {
+                (b.clone(), a.clone())
+            };
+
+            let score = if a.id < b.id {
+                (*score_a, *score_b)
+            } else {
+                (*score_b, *score_a)
+            };
+
+            pair_results.entry(key).or_default().push(score);
+        }
+
+        // 2. update swiss score
+        for ((a, b), scores) in pair_results.into_iter() {
+            let (score_a, score_b) = scores
+                .into_iter()
+                .fold((S::default(), S::default()), |acu, (score_a, score_b)| {
+                    (acu.0 + score_a, acu.1 + score_b)
+                });
+            info!(
+                "Aggregated results {} VS {}: {score_a}-{score_b}",
+                a.name, b.name
+            );
+            let points = |score: &TwoPlayersGameScore| score.num_win * 2 + score.num_draw;
+            let points_before = (points(&self.scores[&a]), points(&self.scores[&b]));
+            match S::compare(&score_a, &score_b) {
+                cmp::Ordering::Equal => {
+                    let replays = self.replays.entry((a.id, b.id)).or_default();
+                    if self.draw_policy.should_replay(*replays) {
+                        *replays += 1;
+                        info!("{} VS {} is a draw, replayed", a.name, b.name);
+                        self.pending_replays.push((a, b));
+                        continue;
+                    }
+                    self.draw_policy
+                        .record_draw(self.scores.get_mut(&a).unwrap());
+                    self.draw_policy
+                        .record_draw(self.scores.get_mut(&b).unwrap());
+                }
+                cmp::Ordering::Greater => {
+                    self.scores.get_mut(&a).unwrap().num_win += 1;
+                    self.scores.get_mut(&b).unwrap().num_lose += 1;
+                }
+                cmp::Ordering::Less => {
+                    self.scores.get_mut(&a).unwrap().num_lose += 1;
+                    self.scores.get_mut(&b).unwrap().num_win += 1;
+                }
+            }
+
+            *self.points_against.entry((a.id, b.id)).or_default() +=
+                points(&self.scores[&a]) - points_before.0;
+            *self.points_against.entry((b.id, a.id)).or_default() +=
+                points(&self.scores[&b]) - points_before.1;
+            self.played.record(&a, &b);
+        }
+    }
+
+    fn create_pair_matches(&self, a: &Arc<Agent>, b: &Arc<Agent>) -> Vec<Vec<Arc<Agent>>> {
+        (0..self.num_match_per_pair)
+            .map(|i| {
+                //permute order for each match
+                if i % 2 == 0 {
+                    vec![a.clone(), b.clone()]
+                } else {
+                    vec![b.clone(), a.clone()]
+                }
+            })
+            .collect()
+    }
+
+    // The greedy search may end up with more than one bye, but is significantly faster and is sure
+    // to find something
+    fn greedy_pairing(
+        &self,
+        out_byes: &mut Vec<Arc<Agent>>,
+        out_pairs: &mut Vec<(Arc<Agent>, Arc<Agent>)>,
+    ) {
+        assert!(out_byes.is_empty(), "out_byes is an output");
+        assert!(out_pairs.is_empty(), "out_pairs is an output");
+
+        // Group by score
+        // BTreeMap is used to auto-group by sorted scores
+        let mut score_groups: BTreeMap<_, Vec<_>> = BTreeMap::new();
+        for agent in &self.agents {
+            let score = self.scores[agent].num_win * 2 + self.scores[agent].num_draw;
+            score_groups.entry(score as i32).or_default().push(agent);
+        }
+
+        let mut leftovers = vec![];
+
+        for (_score, group) in score_groups.iter_mut().rev() {
+            // append leftovers from previous group to the next one
+            // BUT priority to same-group pairing (because `append` concatenate at the end)
+            group.append(&mut leftovers);
+
+            let mut i = 0;
+            while i + 1 < group.len() {
+                let a = group[i];
+                let mut paired = false;
+
+                // greedy pairing: pair with the first valid opponent
+                for j in (i + 1)..group.len() {
+                    let b = group[j];
+                    if !self.played.has_played(a, b) {
+                        out_pairs.push((a.clone(), b.clone()));
+                        // DO NOT SWAP the 2 following lines! (j > i)
+                        group.swap_remove(j); // remove b
+                        group.swap_remove(i); // remove a
+                        paired = true;
+                        break;
+                    }
+                }
+
+                // only increase i if no pair found, because otherwise 'current' group[i] was removed
+                if !paired {
+                    i += 1; // couldn't find a partner yet
+                }
+            }
+
+            // Any unpaired agent gets floated to the next (lower) group
+            leftovers.append(group);
+        }
+
+        //NOTE: at this point, all pair within leftovers have already been tested
+
+        // Assign bye to ALL unpaired player
+        for agent in leftovers {
+            // Give a bye
+            out_byes.push(agent.clone());
+        }
+    }
+
+    fn apply_bye(&mut self, a: Arc<Agent>) {
+        if self.bye_history.contains(&a) {
+            warn!(
+                "{} already received a bye — assigning second bye due to no valid opponents",
+                a.name
+            );
+            // println!(
+            //     "{} already received a bye — assigning second bye due to no valid opponents",
+            //     a.name
+            // )
+        } else {
+            info!("{} receives a bye", a.name);
+            // println!("{} receives a bye", a.name);
+        }
+        self.scores.get_mut(&a).unwrap().num_win += 1;
+        self.bye_history.insert(a);
+        self.num_byes += 1;
+    }
+
+    fn create_next_round_pairings(&mut self) -> Vec<(Arc<Agent>, Arc<Agent>)> {
+        let mut ordered_agents = self.agents.clone();
+        ordered_agents.sort_by(|a, b| {
+            let sa = &self.scores[a];
+            let sb = &self.scores[b];
+            let score_a = sa.num_win * 2 + sa.num_draw;
+            let score_b = sb.num_win * 2 + sb.num_draw;
+            score_b
+                .cmp(&score_a)
+                .then(sb.tie_breaker.cmp(&sa.tie_breaker))
+        });
+
+        let mut pairs = Vec::with_capacity(self.agents.len() / 2);
+        let mut bye = None;
+
+        // See swiss_tests::test_advance_round_scaling for bench tests
+        // let start = std::time::Instant::now();
+        let success = self.recursive_pairing_search(&ordered_agents, &mut pairs, &mut bye);
+        // println!("Recursive pairing took {:?}", start.elapsed());
+
+        if success {
+            if let Some(agent) = bye {
+                self.apply_bye(agent);
+            }
+            pairs
+        } else {
+            // fallback to greedy pairing
+            println!("Recursive pairing failed. Using greedy fallback");
+
+            let mut byes = vec![];
+
+            assert!(pairs.is_empty());
+            // pairs.clear();
+
+            self.greedy_pairing(&mut byes, &mut pairs);
+
+            if byes.len() > 1 {
+                println!(
+                    "Greedy pairing could not pair those: {:?}",
+                    byes.iter().map(|a| a.name.clone()).collect::<Vec<_>>()
+                );
+            }
+
+            pairs
+        }
+    }
+}
+
+impl<S: MatchOutcome> TournamentStrategy<S> for SwissTournament {
+    fn advance_round(&mut self, scores: Vec<MatchResult<S>>) -> Vec<Vec<Arc<Agent>>> {
+        self.update_scores(scores);
+        self.update_tie_breakers();
+
+        // the round goes on until replays are decisive
+        if !self.pending_replays.is_empty() {
+            let replays = std::mem::take(&mut self.pending_replays);
+            return replays
+                .iter()
+                .flat_map(|(a, b)| self.create_pair_matches(a, b))
+                .collect();
+        }
+        self.replays.clear();
+
+        if self.round >= self.max_rounds {
+            return vec![];
+        }
+        if self.early_termination && self.is_leader_uncatchable() {
+            info!(
+                "Swiss tournament stopped after {}/{} rounds: the leader cannot be caught",
+                self.round, self.max_rounds
+            );
+            self.max_rounds = self.round;
+            return vec![];
+        }
+
+        let pairs = self.create_next_round_pairings();
+        let mut pending = Vec::with_capacity(pairs.len() * self.num_match_per_pair);
+        for (a, b) in pairs {
+            pending.extend(self.create_pair_matches(&a, &b));
+        }
+
+        self.round += 1;
+        pending
+    }
+
+    fn players_per_match(&self) -> usize {
+        2
+    }
+
+    fn add_agents(&mut self, agents: Vec<Arc<Agent>>) {
+        self.agents = agents;
+        if self.max_rounds == 0 {
+            let n = self.agents.len();
+            self.max_rounds = f32::log2(n as f32).ceil() as usize;
+            info!(
+                "Swiss tournament auto number of rounds: {}",
+                self.max_rounds
+            );
+        }
+        for agent in &self.agents {
+            self.scores
+                .insert(agent.clone(), TwoPlayersGameScore::default());
+        }
+    }
+
+    fn add_late_agent(&mut self, agent: Arc<Agent>) -> bool {
+        // a draw for each missed round
+        let initial_score = TwoPlayersGameScore {
+            num_draw: self.round as u32,
+            ..Default::default()
+        };
+        SwissTournament::add_late_agent(self, agent, initial_score);
+        true
+    }
+
+    fn num_byes(&self) -> usize {
+        self.num_byes
+    }
+
+    type FinalScore = TwoPlayersGameScore;
+
+    fn get_final_scores(&self) -> HashMap<Arc<Agent>, Self::FinalScore> {
+        //NOTE: Tie-breakers should already be up-to-date
+        self.scores.clone()
+    }
+}
+
+#[cfg(test)]
+mod swiss_tests {
+    use std::{collections::HashSet, sync::Arc, time::Instant};
+
+    use crate::{
+        agent::Agent,
+        match_runner::MatchResult,
+        test_support::{make_agents, ScriptedRunner},
+        tournament_strategy::{DrawPolicy, SwissTournament, TieBreaker, TournamentStrategy},
+    };
+
+    /// Simulates a match: higher ID wins.
+    fn simulate_round(matchups: &[Vec<Arc<Agent>>]) -> Vec<MatchResult<f32>> {
+        matchups
+            .iter()
+            .map(|pair| {
+                let a = &pair[0];
+                let b = &pair[1];
+
+                let (score_a, score_b) = if a.id > b.id {
+                    (1.0, 0.0)
+                } else if a.id < b.id {
+                    (0.0, 1.0)
+                } else {
+                    (0.5, 0.5)
+                };
+
+                vec![(a.clone(), score_a), (b.clone(), score_b)]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_basic_swiss_tournament_progression() {
+        let agents = make_agents(63);
+
+        let mut swiss = SwissTournament::new(8, 1);
+        TournamentStrategy::<f32>::add_agents(&mut swiss, agents.clone());
+
+        let mut all_matchups = HashSet::new();
+        let mut round_count = 0;
+        let mut results = vec![];
+
+        loop {
+            let matchups = swiss.advance_round(results.clone());
+            if matchups.is_empty() {
+                println!("Tournament finished.");
+                break;
+            }
+            round_count += 1;
+
+            println!("\n=== Round {round_count} ===");
+            for pair in &matchups {
+                println!("{} VS {}", pair[0].name, pair[1].name);
+                let ids = (pair[0].id.min(pair[1].id), pair[0].id.max(pair[1].id));
+                assert!(
+                    !all_matchups.contains(&ids),
+                    "Repeated matchup detected: {:?}",
+                    ids
+                );
+                all_matchups.insert(ids);
+            }
+
+            results = simulate_round(&matchups);
+        }
+
+        let scores = TournamentStrategy::<f32>::get_final_scores(&swiss);
+        println!(
+            "\n== Final Scores ({round_count}/{} rounds) ==",
+            swiss.max_rounds
+        );
+        let mut leaderboard: Vec<_> = scores.iter().collect();
+        leaderboard.sort_by_key(|(_, score)| -(score.num_win as i32 * 2 + score.num_draw as i32));
+
+        for (index, (agent, score)) in leaderboard.iter().enumerate() {
+            let _rank = agents.len() - index - 1;
+            // println!("{_rank}, {_rank}, {}", &agent.name["agent_".len()..]); // csv output
+            println!(
+                "{}: {}-{}-{} (tiebreaker: {})",
+                agent.name, score.num_win, score.num_draw, score.num_lose, score.tie_breaker
+            );
+        }
+    }
+
+    /// Runs the full Swiss tournament with increasing player count,
+    /// and prints the total time taken for each size.
+    ///
+    /// Results: on a average machine, takes <1s for 1024 players. Conclusion: this is negligible
+    #[test]
+    fn test_advance_round_scaling() {
+        let player_counts = 2..=128;
+
+        for n in player_counts {
+            let agents = make_agents(n);
+            let mut swiss = SwissTournament::with_auto_rounds(1);
+            TournamentStrategy::<f32>::add_agents(&mut swiss, agents.clone());
+
+            let start = Instant::now();
+
+            let mut round = 0;
+            let mut matchups = swiss.advance_round(Vec::<MatchResult<f32>>::new());
+            while !matchups.is_empty() {
+                let results = simulate_round(&matchups);
+                matchups = swiss.advance_round(results);
+                round += 1;
+            }
+
+            let elapsed = start.elapsed();
+            println!("Swiss tournament with {n:>3} players ({round:>2} rounds), took {elapsed:?}");
+            // println!("{n:>3}, {}", elapsed.as_micros()); //csv ouput
+        }
+    }
+
+    #[test]
+    fn test_integer_scores() {
+        let agents = make_agents(2);
+        let mut swiss = SwissTournament::new(1, 2);
+        TournamentStrategy::<i64>::add_agents(&mut swiss, agents.clone());
+
+        let matchups = swiss.advance_round(Vec::<MatchResult<i64>>::new());
+        assert_eq!(matchups.len(), 2);
+
+        // agent_1 wins by 2 points in total, which is lost when summed as f32
+        let results = matchups
+            .iter()
+            .map(|m| {
+                m.iter()
+                    .map(|a| (a.clone(), 1_000_000_000 + a.id as i64))
+                    .collect()
+            })
+            .collect();
+        assert!(swiss.advance_round(results).is_empty());
+
+        let scores = TournamentStrategy::<i64>::get_final_scores(&swiss);
+        assert_eq!(scores[&agents[1]].num_win, 1);
+        assert_eq!(scores[&agents[0]].num_lose, 1);
+        assert_eq!(scores[&agents[0]].num_draw, 0);
+    }
+
+    /// Pairs (lowest id first) of the matches played by `runner`
+    fn played_pairs(runner: &ScriptedRunner<SwissTournament, f32>) -> Vec<(u32, u32)> {
+        let mut pairs = runner
+            .rounds()
+            .concat()
+            .iter()
+            .map(|m| (m[0].id.min(m[1].id), m[0].id.max(m[1].id)))
+            .collect::<Vec<_>>();
+        pairs.sort();
+        pairs
+    }
+
+    /// 4 agents, 3 rounds: the two strongest agents meet once, and every pair meets exactly once
+    #[test]
+    fn test_no_repeated_pairing() {
+        // higher ID wins, so that the same agents keep leading
+        let mut runner =
+            ScriptedRunner::new(SwissTournament::new(3, 1), make_agents(4), |players| {
+                players.iter().map(|a| a.id as f32).collect()
+            });
+        assert_eq!(runner.run(3), 3);
+        assert_eq!(
+            played_pairs(&runner),
+            [(0, 1), (0, 2), (0, 3), (1, 2), (1, 3), (2, 3)]
+        );
+
+        // more rounds than opponents: pairs never meet twice
+        for n in 5..=8 {
+            let swiss = SwissTournament::new(n as usize, 1);
+            let mut runner = ScriptedRunner::new(swiss, make_agents(n), |players| {
+                players.iter().map(|a| a.id as f32).collect()
+            });
+            runner.run(n as usize);
+            let mut pairs = played_pairs(&runner);
+            let count = pairs.len();
+            pairs.dedup();
+            assert_eq!(pairs.len(), count, "repeated pairing with {n} agents");
+        }
+    }
+
+    #[test]
+    fn test_tie_breakers() {
+        let agents = make_agents(4);
+        let result = |winner: usize, loser: usize, draw: bool| {
+            let loser_score = if draw { 1.0 } else { 0.0 };
+            vec![
+                (agents[winner].clone(), 1.0),
+                (agents[loser].clone(), loser_score),
+            ]
+        };
+        // agent_0 and agent_2 both have a win and a draw (3 half-points)
+        let results = vec![
+            result(0, 1, false),
+            result(0, 2, true),
+            result(2, 3, false),
+            result(1, 3, false),
+        ];
+        let expected = [
+            (TieBreaker::Median, [0, 0, 0, 0]),
+            (TieBreaker::Buchholz, [5, 3, 3, 5]),
+            (TieBreaker::SonnebornBerger, [7, 0, 3, 0]),
+            (TieBreaker::None, [0, 0, 0, 0]),
+        ];
+        for (tie_breaker, expected) in expected {
+            let mut swiss = SwissTournament::new(1, 1).with_tie_breaker(tie_breaker);
+            TournamentStrategy::<f32>::add_agents(&mut swiss, agents.clone());
+            swiss.advance_round(results.clone());
+            let scores = TournamentStrategy::<f32>::get_final_scores(&swiss);
+            let tie_breakers = agents.iter().map(|a| scores[a].tie_breaker);
+            assert_eq!(
+                tie_breakers.collect::<Vec<_>>(),
+                expected,
+                "{tie_breaker:?}"
+            );
+        }
+    }
+
+    /// Asserts in debug builds, skips the result in release builds
+    #[test]
+    #[cfg_attr(debug_assertions, should_panic(expected = "not two players match"))]
+    fn test_invalid_result() {
+        let agents = make_agents(4);
+        let mut swiss = SwissTournament::new(2, 1);
+        TournamentStrategy::<f32>::add_agents(&mut swiss, agents.clone());
+        TournamentStrategy::<f32>::advance_round(&mut swiss, vec![]);
+
+        let result = agents[..3]
+            .iter()
+            .map(|a| (a.clone(), a.id as f32))
+            .collect();
+        swiss.advance_round(vec![result]);
+        let scores = TournamentStrategy::<f32>::get_final_scores(&swiss);
+        assert!(scores
+            .values()
+            .all(|s| s.num_win + s.num_draw + s.num_lose == 0));
+    }
+
+    #[test]
+    fn test_late_agent() {
+        let agents = make_agents(5);
+        let mut swiss = SwissTournament::new(3, 1);
+        TournamentStrategy::<f32>::add_agents(&mut swiss, agents[..4].to_vec());
+
+        let matchups = TournamentStrategy::<f32>::advance_round(&mut swiss, vec![]);
+        assert_eq!(matchups.len(), 2);
+        let results = simulate_round(&matchups);
+
+        // joins between round 1 and 2, with a draw for the missed round
+        assert!(TournamentStrategy::<f32>::add_late_agent(
+            &mut swiss,
+            agents[4].clone()
+        ));
+        let matchups = swiss.advance_round(results);
+        assert_eq!(matchups.len(), 2, "5 agents: 2 pairs and a bye");
+        assert!(matchups.iter().flatten().any(|a| a == &agents[4]));
+        assert_eq!(TournamentStrategy::<f32>::num_byes(&swiss), 1);
+
+        let scores = TournamentStrategy::<f32>::get_final_scores(&swiss);
+        assert_eq!(scores[&agents[4]].num_draw, 1);
+    }
+
+    #[test]
+    fn test_draw_policies() {
+        let agents = make_agents(2);
+        let draws = |matchups: &[Vec<Arc<Agent>>]| -> Vec<MatchResult<u32>> {
+            matchups
+                .iter()
+                .map(|m| m.iter().map(|a| (a.clone(), 0)).collect())
+                .collect()
+        };
+        // (win, draw, lose) of both agents after a single drawn pairing
+        let records = [
+            (DrawPolicy::Draw, (0, 1, 0)),
+            (DrawPolicy::LossForBoth, (0, 0, 1)),
+            (DrawPolicy::WinForBoth, (1, 0, 0)),
+        ];
+        for (policy, record) in records {
+            let mut swiss = SwissTournament::new(1, 2).with_draw_policy(policy);
+            TournamentStrategy::<u32>::add_agents(&mut swiss, agents.clone());
+            let matchups = swiss.advance_round(Vec::<MatchResult<u32>>::new());
+            assert!(swiss.advance_round(draws(&matchups)).is_empty());
+            let scores = TournamentStrategy::<u32>::get_final_scores(&swiss);
+            for agent in &agents {
+                let score = scores[agent];
+                assert_eq!((score.num_win, score.num_draw, score.num_lose), record);
+            }
+        }
+
+        // replayed twice, then counted as a draw
+        let mut swiss =
+            SwissTournament::new(1, 2).with_draw_policy(DrawPolicy::Replay { max_replays: 2 });
+        TournamentStrategy::<u32>::add_agents(&mut swiss, agents.clone());
+        let mut matchups = swiss.advance_round(Vec::<MatchResult<u32>>::new());
+        for _ in 0..2 {
+            matchups = swiss.advance_round(draws(&matchups));
+            assert_eq!(matchups.len(), 2);
+        }
+        assert!(swiss.advance_round(draws(&matchups)).is_empty());
+        let scores = TournamentStrategy::<u32>::get_final_scores(&swiss);
+        assert_eq!(scores[&agents[0]].num_draw, 1);
+
+        // decisive replay
+        let mut swiss =
+            SwissTournament::new(1, 2).with_draw_policy(DrawPolicy::Replay { max_replays: 2 });
+        TournamentStrategy::<u32>::add_agents(&mut swiss, agents.clone());
+        let matchups = swiss.advance_round(Vec::<MatchResult<u32>>::new());
+        let matchups = swiss.advance_round(draws(&matchups));
+        let results = matchups
+            .iter()
+            .map(|m| m.iter().map(|a| (a.clone(), a.id)).collect())
+            .collect();
+        assert!(swiss.advance_round(results).is_empty());
+        let scores = TournamentStrategy::<u32>::get_final_scores(&swiss);
+        assert_eq!(scores[&agents[1]].num_win, 1);
+        assert_eq!(scores[&agents[0]].num_lose, 1);
+        assert_eq!(scores[&agents[0]].num_draw, 0);
+    }
+
+    #[test]
+    fn test_early_termination() {
+        // agent_0 wins all its matches, all other matches are draws
+        let dominated = |matchups: &[Vec<Arc<Agent>>]| -> Vec<MatchResult<u32>> {
+            matchups
+                .iter()
+                .map(|m| m.iter().map(|a| (a.clone(), (a.id == 0) as u32)).collect())
+                .collect()
+        };
+        let play = |early_termination| {
+            let agents = make_agents(8);
+            let mut swiss = SwissTournament::new(6, 1).with_early_termination(early_termination);
+            TournamentStrategy::<u32>::add_agents(&mut swiss, agents.clone());
+            let mut rounds = 0;
+            let mut matchups = swiss.advance_round(Vec::<MatchResult<u32>>::new());
+            while !matchups.is_empty() {
+                rounds += 1;
+                matchups = swiss.advance_round(dominated(&matchups));
+            }
+            let scores = TournamentStrategy::<u32>::get_final_scores(&swiss);
+            (rounds, scores[&agents[0]].num_win)
+        };
+
+        assert_eq!(play(false), (6, 6));
+        // after 5 rounds, agent_0 has 10 points, and the others at most 5 + 2
+        assert_eq!(play(true), (5, 5));
+    }
+}
+
+/// A round-robin tournament where each agent plays against every other agent.
+///
+/// If `symmetric` is false, each pair is evaluated in both directions (A vs B and B vs A).
+///
+/// Agents sharing the same team (`team:` key of their config file) never play each other: the
+/// standings then only reflect results against other teams. Agents without a team play everyone.
+///
+/// By default, all matches are given in a single round, and the scheduler runs them as resources
+/// allow. With [`with_fixed_schedule`](Self::with_fixed_schedule), matches are split into proper
+/// rounds, in which each agent plays at most once.
+///
+/// # Per-game and per-pairing scoring
+///
+/// By default, each game counts as a win, draw or loss. If the tournament is not symmetric, a
+/// pairing where each agent wins on one side is then a win and a loss for both agents. With
+/// [`with_pairing_scores`](Self::with_pairing_scores), the games of a pairing are aggregated
+/// into a single outcome, as in [`SwissTournament`]: the agent winning more games than the other
+/// wins the pairing, and a split is a draw.
+pub struct RoundRobinTournament {
+    scores: HashMap<Arc<Agent>, TwoPlayersGameScore>,
+    agents: Vec<Arc<Agent>>,
+    symmetric: bool,
+    is_scheduled: bool,
+    draw_policy: DrawPolicy,
+    /// Number of replays of each drawn match, by ids of its players
+    replays: HashMap<Vec<u32>, usize>,
+    fixed_schedule: bool,
+    /// Rounds not played yet, with a fixed schedule
+    rounds: VecDeque<Vec<Vec<Arc<Agent>>>>,
+    pairing_scores: bool,
+    /// Pairings with games left to play, with per-pairing scores: (games won by the agent with the
+    /// lowest id minus games won by the other, games played), by ids
+    pairings: HashMap<(u32, u32), (i32, usize)>,
+}
+
+impl RoundRobinTournament {
+    /// Creates a new Round Robin tournament.
+    ///
+    /// Set `symmetric = true` if A vs B is equivalent to B vs A.
+    pub fn new(symmetric: bool) -> Self {
+        Self {
+            symmetric,
+            agents: vec![],
+            scores: HashMap::new(),
+            is_scheduled: false,
+            draw_policy: DrawPolicy::Draw,
+            replays: HashMap::new(),
+            fixed_schedule: false,
+            rounds: VecDeque::new(),
+            pairing_scores: false,
+            pairings: HashMap::new(),
+        }
+    }
+
+    /// Sets how drawn matches are counted (see [`DrawPolicy`]). Default is [`DrawPolicy::Draw`].
+    pub fn with_draw_policy(mut self, draw_policy: DrawPolicy) -> Self {
+        self.draw_policy = draw_policy;
+        self
+    }
+
+    /// Splits the matches into rounds with the circle method: each agent plays at most once per
+    /// round, so the load is balanced between rounds, and standings after each round are
+    /// meaningful.
+    ///
+    /// `n` agents play `n - 1` rounds (`n` if `n` is odd, each agent sitting out one round). If
+    /// the tournament is not symmetric, the matches with swapped sides are played in as many
+    /// additional rounds. Agents do not play themselves, and drawn matches to replay (see
+    /// [`DrawPolicy::Replay`]) form their own round, right after the round they were drawn in.
+    ///
+    /// Default is `false`: all matches are given at once, and the scheduler runs them as
+    /// resources allow.
+    pub fn with_fixed_schedule(mut self, value: bool) -> Self {
+        self.fixed_schedule = value;
+        self
+    }
+
+    /// Counts one outcome per pairing instead of one per game (see
+    /// [per-pairing scoring](Self#per-game-and-per-pairing-scoring)). Only changes the standings
+    /// if the tournament is not symmetric.
+    ///
+    /// The draw policy then applies to drawn pairings: a replayed pairing plays all its games
+    /// again, in the same round.
+    ///
+    /// Default is `false`: each game is counted.
+    pub fn with_pairing_scores(mut self, value: bool) -> Self {
+        self.pairing_scores = value;
+        self
+    }
+
+    /// Adds a game to its pairing, and counts the pairing once all its games are played. Returns
+    /// the games to play again if the pairing is a draw to replay.
+    fn record_pairing_game<S: PartialOrd>(
+        &mut self,
+        result: &MatchResult<S>,
+    ) -> Vec<Vec<Arc<Agent>>> {
+        let (a, score_a) = &result[0];
+        let (b, score_b) = &result[1];
+        let won = match score_a.partial_cmp(score_b) {
+            Some(cmp::Ordering::Greater) => 1,
+            Some(cmp::Ordering::Less) => -1,
+            _ => 0,
+        };
+        let (first, second, won) = if a.id <= b.id {
+            (a, b, won)
+        } else {
+            (b, a, -won)
+        };
+
+        let key = (first.id, second.id);
+        let games = if self.symmetric { 1 } else { 2 };
+        let pairing = self.pairings.entry(key).or_default();
+        pairing.0 += won;
+        pairing.1 += 1;
+        if pairing.1 < games {
+            return vec![];
+        }
+        let (won, _) = self.pairings.remove(&key).unwrap();
+
+        match won.cmp(&0) {
+            cmp::Ordering::Equal => {
+                let replays = self.replays.entry(vec![key.0, key.1]).or_default();
+                if self.draw_policy.should_replay(*replays) {
+                    *replays += 1;
+                    let mut games = vec![vec![first.clone(), second.clone()]];
+                    if !self.symmetric {
+                        games.push(vec![second.clone(), first.clone()]);
+                    }
+                    return games;
+                }
+                for agent in [first, second] {
+                    self.draw_policy
+                        .record_draw(self.scores.entry(agent.clone()).or_default());
+                }
+            }
+            cmp::Ordering::Greater => {
+                self.scores.entry(first.clone()).or_default().num_win += 1;
+                self.scores.entry(second.clone()).or_default().num_lose += 1;
+            }
+            cmp::Ordering::Less => {
+                self.scores.entry(first.clone()).or_default().num_lose += 1;
+                self.scores.entry(second.clone()).or_default().num_win += 1;
+            }
+        }
+        vec![]
+    }
+
+    /// Rounds of the circle method: the first agent stays in place, while the others rotate.
+    fn circle_rounds(&self) -> Vec<Vec<Vec<Arc<Agent>>>> {
+        let mut circle = self.agents.iter().cloned().map(Some).collect::<Vec<_>>();
+        if circle.len() % 2 == 1 {
+            // bye
+            circle.push(None);
+        }
+        let m = circle.len();
+        let mut rounds = vec![];
+        for round in 0..m.saturating_sub(1) {
+            let mut matchups = vec![];
+            for i in 0..m / 2 {
+                let (Some(a), Some(b)) = (&circle[i], &circle[m - 1 - i]) else {
+                    continue;
+                };
+                if a.team.is_some() && a.team == b.team {
+                    continue;
+                }
+                // the fixed agent alternates sides
+                if i == 0 && round % 2 == 1 {
+                    matchups.push(vec![b.clone(), a.clone()]);
+                } else {
+                    matchups.push(vec![a.clone(), b.clone()]);
+                }
+            }
+            rounds.push(matchups);
+            circle[1..].rotate_right(1);
+        }
+        if !self.symmetric {
+            let return_rounds = rounds
+                .iter()
+                .map(|matchups| {
+                    matchups
+                        .iter()
+                        .map(|pair| vec![pair[1].clone(), pair[0].clone()])
+                        .collect()
+                })
+                .collect::<Vec<_>>();
+            rounds.extend(return_rounds);
+        }
+        rounds
+    }
+}
+
+impl<S: PartialOrd> TournamentStrategy<S> for RoundRobinTournament {
+    fn advance_round(&mut self, scores: Vec<MatchResult<S>>) -> Vec<Vec<Arc<Agent>>> {
+        let mut pending_replays = vec![];
+        for match_result in scores {
+            if self.pairing_scores {
+                pending_replays.extend(self.record_pairing_game(&match_result));
+                continue;
+            }
+            let mut best_score = &match_result[0].1;
+            for result in match_result.iter().skip(1) {
+                if best_score < &result.1 {
+                    best_score = &result.1;
+                }
+            }
+            let is_draw = match_result
+                .iter()
+                .all(|(_agent, score)| *score == *best_score);
+            if is_draw {
+                let players = match_result.iter().map(|(agent, _)| agent.clone());
+                let replays = self
+                    .replays
+                    .entry(players.clone().map(|agent| agent.id).collect())
+                    .or_default();
+                if self.draw_policy.should_replay(*replays) {
+                    *replays += 1;
+                    pending_replays.push(players.collect());
+                    continue;
+                }
+            }
+            for (agent, score) in &match_result {
+                if is_draw {
+                    self.draw_policy
+                        .record_draw(self.scores.entry(agent.clone()).or_default());
+                } else if *score == *best_score {
+                    self.scores.entry(agent.clone()).or_default().num_win += 1;
+                } else
+                /* *score != best_score */
+                {
+                    self.scores.entry(agent.clone()).or_default().num_lose += 1;
+                }
+            }
+        }
+        //TODO: tie-breakers
+        // Not quite an official source, but that will do: https://mtgoldframe.com/the-round-robin-tournament-system-rules-scoring-and-tiebreakers/
+
+        if self.fixed_schedule {
+            if !self.is_scheduled {
+                self.is_scheduled = true;
+                self.rounds = self.circle_rounds().into();
+            }
+            if !pending_replays.is_empty() {
+                return pending_replays;
+            }
+            // rounds can be empty if all their matches are between teammates
+            while let Some(round) = self.rounds.pop_front() {
+                if !round.is_empty() {
+                    return round;
+                }
+            }
+            return vec![];
+        }
+
+        if self.is_scheduled {
+            // first (and only) round was already ran, drawn matches are replayed until decisive
+            return pending_replays;
+        }
+        self.is_scheduled = true;
+
+        let n = self.agents.len();
+        let mut pending = vec![];
+        for i in 0..n {
+            for j in i..n {
+                let (team_i, team_j) = (&self.agents[i].team, &self.agents[j].team);
+                if i != j && team_i.is_some() && team_i == team_j {
+                    continue;
+                }
+                pending.push(vec![self.agents[i].clone(), self.agents[j].clone()]);
+                if !self.symmetric {
+                    pending.push(vec![self.agents[j].clone(), self.agents[i].clone()]);
+                }
+            }
+        }
+
+        pending
+    }
+
+    fn players_per_match(&self) -> usize {
+        2
+    }
+
+    fn add_agents(&mut self, agents: Vec<Arc<Agent>>) {
+        self.agents = agents;
+    }
+
+    type FinalScore = TwoPlayersGameScore;
+
+    fn get_final_scores(&self) -> HashMap<Arc<Agent>, Self::FinalScore> {
+        self.scores.clone()
+    }
+}
+
+#[cfg(test)]
+mod round_robin_tests {
+    use super::*;
+
+    #[test]
+    fn test_teams_skip_intra_team_matches() {
+        let agents: Vec<_> = ["red", "red", "blue", "blue"]
+            .iter()
+            .enumerate()
+            .map(|(i, team)| {
+                let agent = Agent::new(format!("agent_{i}"), None, None, i as u32, None);
+                Arc::new(agent.with_team(Some(team.to_string())))
+            })
+            .collect();
+        let mut round_robin = RoundRobinTournament::new(true);
+        TournamentStrategy::<u32>::add_agents(&mut round_robin, agents.clone());
+
+        let matchups = TournamentStrategy::<u32>::advance_round(&mut round_robin, vec![]);
+        let inter_team = matchups
+            .iter()
+            .filter(|pair| pair[0] != pair[1])
+            .collect::<Vec<_>>();
+        assert_eq!(inter_team.len(), 4);
+        for pair in inter_team {
+            assert_ne!(pair[0].team, pair[1].team);
+        }
+
+        // higher ID wins: agent_2 and agent_3 beat both red agents
+        let results = matchups
+            .iter()
+            .map(|pair| vec![(pair[0].clone(), pair[0].id), (pair[1].clone(), pair[1].id)])
+            .collect();
+        assert!(round_robin.advance_round(results).is_empty());
+        let scores = TournamentStrategy::<u32>::get_final_scores(&round_robin);
+        assert_eq!(scores[&agents[0]].num_lose, 2);
+        assert_eq!(scores[&agents[2]].num_win, 2);
+        assert_eq!(scores[&agents[3]].num_win, 2);
+    }
+
+    #[test]
+    fn test_draw_policies() {
+        let agents: Vec<_> = (0..2)
+            .map(|i| Arc::new(Agent::new(format!("agent_{i}"), None, None, i, None)))
+            .collect();
+        let draws = |matchups: &[Vec<Arc<Agent>>]| -> Vec<MatchResult<u32>> {
+            matchups
+                .iter()
+                .map(|m| m.iter().map(|a| (a.clone(), 0)).collect())
+                .collect()
+        };
+
+        // 3 matches, all drawn: agent_0 plays itself (counted twice) and agent_1
+        for (policy, record) in [
+            (DrawPolicy::Draw, (0, 3, 0)),
+            (DrawPolicy::LossForBoth, (0, 0, 3)),
+            (DrawPolicy::WinForBoth, (3, 0, 0)),
+        ] {
+            let mut round_robin = RoundRobinTournament::new(true).with_draw_policy(policy);
+            TournamentStrategy::<u32>::add_agents(&mut round_robin, agents.clone());
+            let matchups = TournamentStrategy::<u32>::advance_round(&mut round_robin, vec![]);
+            assert_eq!(matchups.len(), 3);
+            assert!(round_robin.advance_round(draws(&matchups)).is_empty());
+            let scores = TournamentStrategy::<u32>::get_final_scores(&round_robin);
+            let score = scores[&agents[0]];
+            assert_eq!((score.num_win, score.num_draw, score.num_lose), record);
+        }
+
+        let mut round_robin =
+            RoundRobinTournament::new(true).with_draw_policy(DrawPolicy::Replay { max_replays: 1 });
+        TournamentStrategy::<u32>::add_agents(&mut round_robin, agents.clone());
+        let matchups = TournamentStrategy::<u32>::advance_round(&mut round_robin, vec![]);
+        // every match is replayed once
+        let replays = round_robin.advance_round(draws(&matchups));
+        assert_eq!(replays, matchups);
+        // decisive (higher ID wins), except the self-matches which stay drawn
+        let results = replays
+            .iter()
+            .map(|m| m.iter().map(|a| (a.clone(), a.id)).collect())
+            .collect();
+        assert!(round_robin.advance_round(results).is_empty());
+        let scores = TournamentStrategy::<u32>::get_final_scores(&round_robin);
+        assert_eq!(scores[&agents[1]].num_win, 1);
+        assert_eq!(scores[&agents[0]].num_lose, 1);
+        assert_eq!(scores[&agents[0]].num_draw, 2);
+    }
+
+    #[test]
+    fn test_fixed_schedule() {
+        for (n, symmetric, num_rounds) in [(6, true, 5), (5, true, 5), (4, false, 6)] {
+            let agents: Vec<_> = (0..n)
+                .map(|i| Arc::new(Agent::new(format!("agent_{i}"), None, None, i, None)))
+                .collect();
+            let mut round_robin = RoundRobinTournament::new(symmetric).with_fixed_schedule(true);
+            TournamentStrategy::<u32>::add_agents(&mut round_robin, agents.clone());
+
+            let mut played = HashSet::new();
+            let mut rounds = 0;
+            let mut matchups = TournamentStrategy::<u32>::advance_round(&mut round_robin, vec![]);
+            while !matchups.is_empty() {
+                rounds += 1;
+                assert_eq!(matchups.len(), n as usize / 2);
+                let mut seen = HashSet::new();
+                for pair in &matchups {
+                    assert_ne!(pair[0], pair[1]);
+                    for agent in pair {
+                        assert!(seen.insert(agent.id), "agent_{} plays twice", agent.id);
+                    }
+                    assert!(played.insert((pair[0].id, pair[1].id)));
+                }
+                // higher ID wins
+                let results = matchups
+                    .iter()
+                    .map(|m| m.iter().map(|a| (a.clone(), a.id)).collect())
+                    .collect();
+                matchups = round_robin.advance_round(results);
+            }
+            assert_eq!(rounds, num_rounds);
+
+            let pairs = played
+                .iter()
+                .map(|&(a, b)| (a.min(b), a.max(b)))
+                .collect::<HashSet<_>>();
+            let legs = if symmetric { 1 } else { 2 };
+            assert_eq!(played.len(), pairs.len() * legs);
+            assert_eq!(pairs.len(), (n * (n - 1) / 2) as usize);
+            let scores = TournamentStrategy::<u32>::get_final_scores(&round_robin);
+            assert_eq!(
+                scores[&agents[n as usize - 1]].num_win,
+                (n - 1) * legs as u32
+            );
+        }
+    }
+
+    #[test]
+    fn test_pairing_scores() {
+        let agents: Vec<_> = (0..2)
+            .map(|i| Arc::new(Agent::new(format!("agent_{i}"), None, None, i, None)))
+            .collect();
+        // the first player always wins: each agent wins on one side
+        let play = |pairing_scores: bool| {
+            let mut round_robin = RoundRobinTournament::new(false)
+                .with_fixed_schedule(true)
+                .with_pairing_scores(pairing_scores);
+            TournamentStrategy::<u32>::add_agents(&mut round_robin, agents.clone());
+            let mut matchups = TournamentStrategy::<u32>::advance_round(&mut round_robin, vec![]);
+            while !matchups.is_empty() {
+                let results = matchups
+                    .iter()
+                    .map(|m| vec![(m[0].clone(), 1), (m[1].clone(), 0)])
+                    .collect();
+                matchups = round_robin.advance_round(results);
+            }
+            let scores = TournamentStrategy::<u32>::get_final_scores(&round_robin);
+            let score = scores[&agents[0]];
+            (score.num_win, score.num_draw, score.num_lose)
+        };
+
+        assert_eq!(play(false), (1, 0, 1));
+        assert_eq!(play(true), (0, 1, 0));
+    }
+}
+
+/// Holds a list of scores for an agent in a single-player tournament.
+///
+/// Implements ordering by comparison.
+#[derive(PartialEq, Debug, Clone, serde::Serialize)]
+pub struct SinglePlayerScore<S: PartialOrd>(pub Vec<S>);
+
+impl<S: PartialOrd> Default for SinglePlayerScore<S> {
+    fn default() -> Self {
+        Self(vec![])
+    }
+}
+
+impl<S: PartialOrd> Eq for SinglePlayerScore<S> {} // That's it ??
+
+impl<S: PartialOrd> PartialOrd for SinglePlayerScore<S> {
+    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<S: PartialOrd> Ord for SinglePlayerScore<S> {
+    fn cmp(&self, other: &Self) -> cmp::Ordering {
+        self.0.partial_cmp(&other.0).unwrap()
+    }
+}
+
+/// A tournament where each agent plays independently across multiple games.
+///
+/// Each agent is evaluated in isolation, and scores are stored as lists of `f32`.
+pub struct SinglePlayerTournament<S: PartialOrd> {
+    game_per_agent: usize,
+    agents: Vec<Arc<Agent>>,
+    scores: HashMap<Arc<Agent>, SinglePlayerScore<S>>,
+}
+
+impl<S: PartialOrd> SinglePlayerTournament<S> {
+    /// Creates a new single-player tournament.
+    ///
+    /// `game_per_agent` determines how many games each agent will play.
+    pub fn new(game_per_agent: usize) -> Self {
+        Self {
+            game_per_agent,
+            agents: vec![],
+            scores: HashMap::new(),
+        }
+    }
+}
+
+impl<S: PartialOrd + Clone> TournamentStrategy<S> for SinglePlayerTournament<S> {
+    fn advance_round(&mut self, match_results: Vec<MatchResult<S>>) -> Vec<Vec<Arc<Agent>>> {
+        for match_result in match_results {
+            for (agent, score) in match_result {
+                self.scores.entry(agent).or_default().0.push(score);
+            }
+        }
+
+        // the first and only round
+        let mut pending = vec![];
+        for agent in self.agents.drain(..) {
+            // drain so that no more matches are returned after first round
+            pending.append(&mut vec![vec![agent.clone()]; self.game_per_agent]);
+        }
+        pending
+    }
+
+    fn players_per_match(&self) -> usize {
+        1
+    }
+
+    fn add_agents(&mut self, agents: Vec<Arc<Agent>>) {
+        self.agents = agents;
+    }
+
+    type FinalScore = SinglePlayerScore<S>;
+
+    fn get_final_scores(&self) -> HashMap<Arc<Agent>, Self::FinalScore> {
+        self.scores.clone()
+    }
+}
+
+//TODO: knockout AKA single elimination tournament
@@ -0,0 +1,404 @@
+//! TrueSkill rating tournament for two-player games.
+//!
+//! Each agent has a Gaussian belief `N(μ, σ²)` on its skill. After each match, both beliefs are
+//! updated with the two-player TrueSkill equations (Herbrich, Minka & Graepel, *TrueSkill™: A
+//! Bayesian Skill Rating System*, NIPS 2006, section 3 and table 1):
+//!
+//! ```text
+//! σ_i² ← σ_i² + τ²                                  (dynamics, before the match)
+//! c²   = 2β² + σ_w² + σ_l²
+//! t    = (μ_w − μ_l) / c          ε = draw margin / c
+//! μ_w  ← μ_w + σ_w² / c · v(t, ε)
+//! μ_l  ← μ_l − σ_l² / c · v(t, ε)
+//! σ_i² ← σ_i² · (1 − σ_i² / c² · w(t, ε))
+//! ```
+//!
+//! With, for a win (`N` and `Φ` are the standard normal density and cumulative distribution):
+//!
+//! ```text
+//! v(t, ε) = N(t − ε) / Φ(t − ε)
+//! w(t, ε) = v(t, ε) · (v(t, ε) + t − ε)
+//! ```
+//!
+//! And for a draw:
+//!
+//! ```text
+//! v(t, ε) = (N(−ε − t) − N(ε − t)) / (Φ(ε − t) − Φ(−ε − t))
+//! w(t, ε) = v(t, ε)² + ((ε − t) · N(ε − t) + (ε + t) · N(ε + t)) / (Φ(ε − t) − Φ(−ε − t))
+//! ```
+//!
+//! The draw margin is derived from the draw probability `p`: `Φ⁻¹((p + 1) / 2) · √2 · β`.
+//!
+//! Default parameters are the ones of the original paper: `μ₀ = 25`, `σ₀ = 25/3`, `β = σ₀/2`,
+//! `τ = σ₀/100` and a draw probability of 10%.
+
+use std::{cmp, collections::HashMap, fmt::Display, sync::Arc};
+
+use tracing::{error, info};
+
+use super::{MatchOutcome, TournamentStrategy};
+use crate::{agent::Agent, match_runner::MatchResult};
+
+const DEFAULT_MU: f64 = 25.0;
+const DEFAULT_SIGMA: f64 = DEFAULT_MU / 3.0;
+const DEFAULT_BETA: f64 = DEFAULT_SIGMA / 2.0;
+const DEFAULT_TAU: f64 = DEFAULT_SIGMA / 100.0;
+const DEFAULT_DRAW_PROBABILITY: f64 = 0.1;
+
+/// TrueSkill rating of an agent: mean skill `mu` and its uncertainty `sigma`.
+///
+/// Ratings are ordered by their conservative estimate `μ − 3σ` (then by `μ`), which is the value
+/// the agent's skill is higher than with ~99% confidence.
+#[derive(Debug, Clone, Copy)]
+pub struct TrueSkillRating {
+    /// Mean of the skill belief (μ).
+    pub mu: f64,
+    /// Standard deviation of the skill belief (σ).
+    pub sigma: f64,
+}
+
+impl TrueSkillRating {
+    /// Conservative skill estimate: `μ − 3σ`.
+    pub fn conservative(&self) -> f64 {
+        self.mu - 3.0 * self.sigma
+    }
+}
+
+impl Default for TrueSkillRating {
+    fn default() -> Self {
+        Self {
+            mu: DEFAULT_MU,
+            sigma: DEFAULT_SIGMA,
+        }
+    }
+}
+
+impl PartialEq for TrueSkillRating {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == cmp::Ordering::Equal
+    }
+}
+
+impl Eq for TrueSkillRating {}
+
+impl PartialOrd for TrueSkillRating {
+    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for TrueSkillRating {
+    fn cmp(&self, other: &Self) -> cmp::Ordering {
+        self.conservative()
+            .total_cmp(&other.conservative())
+            .then(self.mu.total_cmp(&other.mu))
+    }
+}
+
+impl Display for TrueSkillRating {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "μ: {:.3}, σ: {:.3}, conservative: {:.3}",
+            self.mu,
+            self.sigma,
+            self.conservative()
+        )
+    }
+}
+
+/// A tournament rating agents with TrueSkill (see [module documentation](self) for the math).
+///
+/// Each round, agents are sorted by conservative rating (`μ − 3σ`) and adjacent agents are paired,
+/// so that agents of similar estimated skill play each other. With an odd number of agents, the
+/// lowest rated one sits out the round without rating change. Ratings are updated after each
+/// match, in the order results are received.
+pub struct TrueSkillTournament {
+    agents: Vec<Arc<Agent>>,
+    ratings: HashMap<Arc<Agent>, TrueSkillRating>,
+    round: usize,
+    num_rounds: usize,
+    beta: f64,
+    tau: f64,
+    draw_probability: f64,
+}
+
+impl TrueSkillTournament {
+    /// Creates a new TrueSkill tournament running `num_rounds` rounds.
+    pub fn new(num_rounds: usize) -> Self {
+        Self {
+            agents: vec![],
+            ratings: HashMap::new(),
+            round: 0,
+            num_rounds,
+            beta: DEFAULT_BETA,
+            tau: DEFAULT_TAU,
+            draw_probability: DEFAULT_DRAW_PROBABILITY,
+        }
+    }
+
+    /// Sets the probability of a draw between two agents of equal skill (default 10%).
+    ///
+    /// Should be close to the draw frequency observed in the game.
+    pub fn with_draw_probability(mut self, draw_probability: f64) -> Self {
+        assert!(
+            (0.0..1.0).contains(&draw_probability),
+            "draw probability must be in [0, 1)"
+        );
+        self.draw_probability = draw_probability;
+        self
+    }
+
+    fn draw_margin(&self) -> f64 {
+        inverse_cdf((self.draw_probability + 1.0) / 2.0) * 2f64.sqrt() * self.beta
+    }
+
+    /// Updates the ratings of `a` and `b` after a match. `ordering` is `a`'s outcome.
+    fn update(&mut self, a: &Arc<Agent>, b: &Arc<Agent>, ordering: cmp::Ordering) {
+        let (winner, loser) = match ordering {
+            cmp::Ordering::Less => (b, a),
+            _ => (a, b),
+        };
+        let mut rating_w = self.ratings[winner];
+        let mut rating_l = self.ratings[loser];
+
+        let var_w = rating_w.sigma.powi(2) + self.tau.powi(2);
+        let var_l = rating_l.sigma.powi(2) + self.tau.powi(2);
+        let c2 = 2.0 * self.beta.powi(2) + var_w + var_l;
+        let c = c2.sqrt();
+        let t = (rating_w.mu - rating_l.mu) / c;
+        let e = self.draw_margin() / c;
+
+        let (v, w) = if ordering == cmp::Ordering::Equal {
+            (v_draw(t, e), w_draw(t, e))
+        } else {
+            (v_win(t, e), w_win(t, e))
+        };
+
+        rating_w.mu += var_w / c * v;
+        rating_l.mu -= var_l / c * v;
+        rating_w.sigma = (var_w * (1.0 - var_w / c2 * w)).sqrt();
+        rating_l.sigma = (var_l * (1.0 - var_l / c2 * w)).sqrt();
+
+        self.ratings.insert(winner.clone(), rating_w);
+        self.ratings.insert(loser.clone(), rating_l);
+    }
+}
+
+impl<S: MatchOutcome> TournamentStrategy<S> for TrueSkillTournament {
+    fn advance_round(&mut self, scores: Vec<MatchResult<S>>) -> Vec<Vec<Arc<Agent>>> {
+        for result in scores {
+            debug_assert!(result.len() == 2, "not two players match ??");
+            if result.len() != 2 {
+                error!("result of a {}-player match ignored", result.len());
+                continue;
+            }
+            let (a, score_a) = &result[0];
+            let (b, score_b) = &result[1];
+            self.update(a, b, S::compare(score_a, score_b));
+        }
+
+        if self.round >= self.num_rounds {
+            return vec![];
+        }
+        self.round += 1;
+
+        let mut ordered = self.agents.clone();
+        ordered.sort_by(|a, b| self.ratings[b].cmp(&self.ratings[a]));
+        if ordered.len() % 2 == 1 {
+            let bye = ordered.pop().unwrap();
+            info!("{} sits out round {}", bye.name, self.round);
+        }
+
+        ordered
+            .chunks_exact(2)
+            .enumerate()
+            .map(|(i, pair)| {
+                // alternate sides between pairs to avoid favoring higher rated agents
+                if (i + self.round) % 2 == 0 {
+                    vec![pair[0].clone(), pair[1].clone()]
+                } else {
+                    vec![pair[1].clone(), pair[0].clone()]
+                }
+            })
+            .collect()
+    }
+
+    fn players_per_match(&self) -> usize {
+        2
+    }
+
+    fn add_agents(&mut self, agents: Vec<Arc<Agent>>) {
+        for agent in &agents {
+            self.ratings
+                .insert(agent.clone(), TrueSkillRating::default());
+        }
+        self.agents = agents;
+    }
+
+    type FinalScore = TrueSkillRating;
+
+    fn get_final_scores(&self) -> HashMap<Arc<Agent>, Self::FinalScore> {
+        self.ratings.clone()
+    }
+}
+
+/// Complementary error function (Numerical Recipes, fractional error < 1.2e-7)
+fn erfc(x: f64) -> f64 {
+    let z = x.abs();
+    let t = 1.0 / (1.0 + z / 2.0);
+    let r = t
+        * (-z * z - 1.26551223
+            + t * (1.00002368
+                + t * (0.37409196
+                    + t * (0.09678418
+                        + t * (-0.18628806
+                            + t * (0.27886807
+                                + t * (-1.13520398
+                                    + t * (1.48851587 + t * (-0.82215223 + t * 0.17087277)))))))))
+            .exp();
+    if x >= 0.0 {
+        r
+    } else {
+        2.0 - r
+    }
+}
+
+/// Inverse of [`erfc`], refined with two Newton steps
+fn erfc_inverse(y: f64) -> f64 {
+    if y >= 2.0 {
+        return -100.0;
+    }
+    if y <= 0.0 {
+        return 100.0;
+    }
+    let zero_point = y < 1.0;
+    let y = if zero_point { y } else { 2.0 - y };
+    let t = (-2.0 * (y / 2.0).ln()).sqrt();
+    let mut x = -std::f64::consts::FRAC_1_SQRT_2
+        * ((2.30753 + t * 0.27061) / (1.0 + t * (0.99229 + t * 0.04481)) - t);
+    for _ in 0..2 {
+        let err = erfc(x) - y;
+        x += err / (std::f64::consts::FRAC_2_SQRT_PI * (-x * x).exp() - x * err);
+    }
+    if zero_point {
+        x
+    } else {
+        -x
+    }
+}
+
+/// Standard normal probability density function `N`
+fn pdf(x: f64) -> f64 {
+    (-x * x / 2.0).exp() / (2.0 * std::f64::consts::PI).sqrt()
+}
+
+/// Standard normal cumulative distribution function `Φ`
+fn cdf(x: f64) -> f64 {
+    0.5 * erfc(-x / std::f64::consts::SQRT_2)
+}
+
+/// Inverse of the standard normal cumulative distribution function `Φ⁻¹`
+fn inverse_cdf(p: f64) -> f64 {
+    -std::f64::consts::SQRT_2 * erfc_inverse(2.0 * p)
+}
+
+fn v_win(t: f64, e: f64) -> f64 {
+    let x = t - e;
+    let denom = cdf(x);
+    if denom > 0.0 {
+        pdf(x) / denom
+    } else {
+        -x
+    }
+}
+
+fn w_win(t: f64, e: f64) -> f64 {
+    let v = v_win(t, e);
+    v * (v + t - e)
+}
+
+fn v_draw(t: f64, e: f64) -> f64 {
+    let abs_t = t.abs();
+    let (a, b) = (e - abs_t, -e - abs_t);
+    let denom = cdf(a) - cdf(b);
+    let v = if denom > 0.0 {
+        (pdf(b) - pdf(a)) / denom
+    } else {
+        a
+    };
+    if t < 0.0 {
+        -v
+    } else {
+        v
+    }
+}
+
+fn w_draw(t: f64, e: f64) -> f64 {
+    let abs_t = t.abs();
+    let (a, b) = (e - abs_t, -e - abs_t);
+    let denom = cdf(a) - cdf(b);
+    let v = v_draw(abs_t, e);
+    v * v + (a * pdf(a) - b * pdf(b)) / denom
+}
+
+#[cfg(test)]
+mod trueskill_tests {
+    use super::*;
+    use crate::test_support::make_agents;
+
+    fn assert_close(a: f64, b: f64) {
+        assert!((a - b).abs() < 1e-3, "{a} != {b}");
+    }
+
+    #[test]
+    fn test_one_vs_one_updates() {
+        // Reference values from the `trueskill` Python package (`rate_1vs1` with defaults)
+        let agents = make_agents(2);
+        let mut tournament = TrueSkillTournament::new(1);
+        TournamentStrategy::<f32>::add_agents(&mut tournament, agents.clone());
+        tournament.update(&agents[0], &agents[1], cmp::Ordering::Greater);
+        assert_close(tournament.ratings[&agents[0]].mu, 29.396);
+        assert_close(tournament.ratings[&agents[0]].sigma, 7.171);
+        assert_close(tournament.ratings[&agents[1]].mu, 20.604);
+        assert_close(tournament.ratings[&agents[1]].sigma, 7.171);
+
+        let mut tournament = TrueSkillTournament::new(1);
+        TournamentStrategy::<f32>::add_agents(&mut tournament, agents.clone());
+        tournament.update(&agents[0], &agents[1], cmp::Ordering::Equal);
+        assert_close(tournament.ratings[&agents[0]].mu, 25.0);
+        assert_close(tournament.ratings[&agents[0]].sigma, 6.458);
+        assert_close(tournament.ratings[&agents[1]].mu, 25.0);
+        assert_close(tournament.ratings[&agents[1]].sigma, 6.458);
+    }
+
+    #[test]
+    fn test_ranking_converges() {
+        // higher id always wins
+        let agents = make_agents(5);
+        let mut tournament = TrueSkillTournament::new(10);
+        TournamentStrategy::<u32>::add_agents(&mut tournament, agents.clone());
+
+        let mut results = vec![];
+        loop {
+            let matchups = tournament.advance_round(results);
+            if matchups.is_empty() {
+                break;
+            }
+            for m in &matchups {
+                assert_eq!(m.len(), 2);
+                assert_ne!(m[0], m[1]);
+            }
+            results = matchups
+                .iter()
+                .map(|m| m.iter().map(|a| (a.clone(), a.id)).collect())
+                .collect();
+        }
+
+        let scores = TournamentStrategy::<u32>::get_final_scores(&tournament);
+        let mut ranking = agents.clone();
+        ranking.sort_by(|a, b| scores[b].cmp(&scores[a]));
+        assert_eq!(ranking[0], agents[4]);
+        assert!(scores[&agents[4]].sigma < DEFAULT_SIGMA);
+    }
+}
@@ -18,6 +18,10 @@ impl Game for DummyGame {
 
     type Score = u32;
 
+    fn parse_action(&self, text: &str) -> Option<Self::Action> {
+        Self::parse_from_str(text)
+    }
+
     fn apply_action(&mut self, _action: &Option<Self::Action>) -> anyhow::Result<()> {
         self.got_none |= _action.is_none();
         Ok(())
@@ -291,6 +295,10 @@ impl Game for RPSWrapper {
 
     type Score = f32;
 
+    fn parse_action(&self, text: &str) -> Option<Self::Action> {
+        Self::parse_from_str(text)
+    }
+
     fn apply_action(&mut self, action: &Option<Self::Action>) -> anyhow::Result<()> {
         self.actions_buffer.push(*action);
         self.current_player += 1;
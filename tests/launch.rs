@@ -116,6 +116,27 @@ fn launch_rock_paper_scissors() {
     // dbg!(scores);
 }
 
+#[test]
+fn run_single_rock_paper_scissors_match() {
+    let params = ConstraintsBuilder::new()
+        .with_time_budget(Duration::from_secs(10))
+        .build()
+        .unwrap();
+    let config = Configuration::new()
+        .with_verbose(false)
+        .with_allow_uncontained(true);
+
+    let evaluator = Evaluator::new(RPSWrapper::default(), config, params);
+    let paper = std::path::Path::new("tests/rock_paper_scissors_agents/always_paper");
+    let rock = std::path::Path::new("tests/rock_paper_scissors_agents/always_rock");
+    let record = evaluator.run_single_match(&[paper, rock]).unwrap();
+
+    assert_eq!(record.players, ["always_paper", "always_rock"]);
+    assert_eq!(record.errors, "");
+    assert!(record.turns > 0);
+    assert!(record.scores[0] > record.scores[1]);
+}
+
 #[test]
 fn test_from_str() -> Result<(), String> {
     let _state = crate::games::PlayerState::from_str("0\n")?;